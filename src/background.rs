@@ -0,0 +1,88 @@
+use std::rc::Rc;
+
+use gtk::{
+    cairo::Context,
+    gdk::prelude::GdkCairoContextExt,
+    gdk_pixbuf::Pixbuf,
+    gio::{Cancellable, MemoryInputStream},
+    glib::Bytes,
+};
+
+use crate::common::Vec2;
+
+/// Decoded image file used as background, the original file content is kept for saving
+pub struct BackgroundImage {
+    pub data: Vec<u8>,
+    pixbuf: Pixbuf,
+}
+
+impl BackgroundImage {
+    /// decodes a PNG or JPEG file
+    pub fn from_data(data: Vec<u8>) -> Option<Self> {
+        let stream = MemoryInputStream::from_bytes(&Bytes::from(&data));
+        match Pixbuf::from_stream(&stream, Cancellable::NONE) {
+            Ok(pixbuf) => Some(Self { data, pixbuf }),
+            Err(e) => {
+                println!("Unable to load background image: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn size(&self) -> Vec2<f64> {
+        Vec2 {
+            x: self.pixbuf.width() as f64,
+            y: self.pixbuf.height() as f64,
+        }
+    }
+}
+
+/// Placement of the background image in world coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BackgroundSettings {
+    /// top left corner of the unrotated image
+    pub pos: Vec2<f64>,
+    /// world units per image pixel
+    pub scale: f64,
+    /// rotation around the image center in degrees, clockwise
+    pub rotation: f64,
+    pub opacity: f64,
+    pub in_gm_export: bool,
+    pub in_player_export: bool,
+}
+
+impl BackgroundSettings {
+    pub fn new() -> Self {
+        Self {
+            pos: Vec2 { x: 0.0, y: 0.0 },
+            scale: 1.0,
+            rotation: 0.0,
+            opacity: 0.5,
+            in_gm_export: true,
+            in_player_export: false,
+        }
+    }
+}
+
+/// Image drawn below the dungeon, e.g. to trace a scanned map
+#[derive(Clone)]
+pub struct Background {
+    pub image: Rc<BackgroundImage>,
+    pub settings: BackgroundSettings,
+}
+
+impl Background {
+    pub fn draw(&self, ctx: &Context) {
+        let s = &self.settings;
+        let center = 0.5 * self.image.size();
+        ctx.save().unwrap();
+        ctx.translate(s.pos.x, s.pos.y);
+        ctx.scale(s.scale, s.scale);
+        ctx.translate(center.x, center.y);
+        ctx.rotate(s.rotation.to_radians());
+        ctx.translate(-center.x, -center.y);
+        ctx.set_source_pixbuf(&self.image.pixbuf, 0.0, 0.0);
+        ctx.paint_with_alpha(s.opacity).unwrap();
+        ctx.restore().unwrap();
+    }
+}
@@ -3,7 +3,9 @@ use core::f64;
 use crate::{
     common::{BBox, Line, Rgb, Vec2},
     config::{DEFAULT_CHAMBER_COLOR, WALL_WIDTH},
-    view::primitives::{self, Polygon, Primitive, Text},
+    geometry::{self, Shape},
+    print_theme::WallStyle,
+    view::primitives::{self, Polygon, Polyline, Primitive, Text},
 };
 pub type ChamberId = u32;
 pub type WallId = u32;
@@ -29,6 +31,8 @@ pub struct NextVert {
 pub struct ChamberDrawOptions {
     pub color: Option<Rgb>,
     pub fill: Option<bool>,
+    /// walls and floor as printed, otherwise as in the editor
+    pub wall_style: Option<WallStyle>,
 }
 
 /// A Chamber is part of a Dungeon
@@ -38,8 +42,12 @@ pub struct Chamber {
     pub id: ChamberId,
     pub name: String,
     pub notes: String,
+    /// boxed text read to the players, separate from the GM notes
+    pub read_aloud: String,
     pub hidden: bool,
     walls: Vec<Wall>,
+    /// inner boundaries of the chamber, e.g. pillars or pits
+    holes: Vec<Vec<Wall>>,
     first_vert: Option<Vec2<i32>>,
     color: Rgb,
 }
@@ -50,37 +58,47 @@ impl Chamber {
             id: 1,
             name: "New Chamber".to_owned(),
             notes: String::new(),
+            read_aloud: String::new(),
             hidden: false,
             walls: vec![],
+            holes: vec![],
             first_vert: None,
             color: DEFAULT_CHAMBER_COLOR,
         }
     }
 
+    /// Draws the chamber.
+    /// Walls are left open where they lie inside of `openings`, e.g. the outlines of corridors.
     pub fn draw(
         &self,
         next_vert: Option<NextVert>,
         options: Option<ChamberDrawOptions>,
+        openings: &[Shape],
     ) -> Vec<Box<dyn Primitive>> {
         let mut walls = self.walls.clone();
+        let mut holes = self.holes.clone();
         let mut show_chamber_number = true;
 
         let color = match options {
-            Some(ChamberDrawOptions {
-                color: Some(c),
-                fill: _,
-            }) => c,
+            Some(ChamberDrawOptions { color: Some(c), .. }) => c,
             _ => self.color,
         };
+        let wall_style = options.as_ref().and_then(|o| o.wall_style);
+        let wall_width = wall_style.map_or(WALL_WIDTH, |s| s.width);
+        let dashed = self.hidden && wall_style.is_none_or(|s| s.dashed_hidden);
 
         match next_vert {
             Some(v) => match v.in_wall_id {
                 Some(wall_id) => {
-                    let idx = walls.iter().position(|w| w.id == wall_id).unwrap().clone();
-                    let wall = walls[idx];
+                    let ring = std::iter::once(&mut walls)
+                        .chain(holes.iter_mut())
+                        .find(|r| r.iter().any(|w| w.id == wall_id))
+                        .unwrap();
+                    let idx = ring.iter().position(|w| w.id == wall_id).unwrap();
+                    let wall = ring[idx];
                     let (w1, w2) = wall.split(v.pos);
-                    walls[idx] = w1;
-                    walls.insert(idx + 1, w2);
+                    ring[idx] = w1;
+                    ring.insert(idx + 1, w2);
                     show_chamber_number = false;
                 }
                 None => {
@@ -114,63 +132,62 @@ impl Chamber {
         }
 
         let mut prims = Vec::<Box<dyn Primitive>>::new();
+        let rings: Vec<Vec<Vec2<f64>>> = std::iter::once(&walls)
+            .chain(holes.iter())
+            .map(|r| r.iter().map(|w| w.p1.into()).collect())
+            .collect();
+        let gaps: Vec<Vec<Vec<(f64, f64)>>> = rings
+            .iter()
+            .map(|r| {
+                (0..r.len())
+                    .map(|i| geometry::segment_inside(openings, r[i], r[(i + 1) % r.len()]))
+                    .collect()
+            })
+            .collect();
+        let has_openings = gaps.iter().flatten().any(|g| !g.is_empty());
+
         let poly = Box::new(Polygon {
             points: walls
                 .iter()
                 .map(|p| Into::<Vec2<f64>>::into(p.p1))
                 .collect(),
-            fill_color: color,
+            holes: holes
+                .iter()
+                .map(|h| h.iter().map(|w| w.p1.into()).collect())
+                .collect(),
+            fill_color: wall_style.map_or(color, |s| s.fill),
             fill_opacity: match options {
                 Some(ChamberDrawOptions {
-                    color: _,
-                    fill: Some(false),
+                    fill: Some(false), ..
                 }) => 0.0,
-                _ => 0.3,
+                _ => wall_style.map_or(0.3, |s| s.fill_opacity),
             },
             stroke_color: color,
-            stroke_width: WALL_WIDTH,
-            dashed: self.hidden,
+            stroke_width: match has_openings {
+                true => 0.0,
+                false => wall_width,
+            },
+            dashed,
         });
-        let bbox = poly.bbox();
         prims.push(poly);
 
-        if show_chamber_number {
-            // get bbox of polygon
-            // iterate over each cell in BBox
-            // if in polygon
-            // calc min distance to polygon
-            // take cell with max min dist which is in polygon
-            let mut max_min_dist = f64::NEG_INFINITY;
-            let mut best_p = None;
-
-            let grid = 50.0;
-            let x_steps: u32 = ((bbox.max.x - bbox.min.x) / grid).ceil() as u32;
-            let y_steps: u32 = ((bbox.max.y - bbox.min.y) / grid).ceil() as u32;
-            for x_i in 0..x_steps {
-                for y_i in 0..y_steps {
-                    let p = bbox.min
-                        + Vec2::<f64> {
-                            x: (x_i as f64) * grid + 25.0,
-                            y: (y_i as f64) * grid + 25.0,
-                        };
-                    if self.contains_point(p) {
-                        let d = self
-                            .walls()
-                            .iter()
-                            .map(|w| w.distance(p))
-                            .reduce(f64::min)
-                            .unwrap_or(f64::NEG_INFINITY);
-                        if d > max_min_dist {
-                            max_min_dist = d;
-                            best_p = Some(p);
-                        }
-                    }
+        if has_openings {
+            for (ring, ring_gaps) in rings.iter().zip(gaps.iter()) {
+                for run in geometry::ring_runs(ring, |i| ring_gaps[i].clone()) {
+                    prims.push(Box::new(Polyline {
+                        points: run,
+                        color,
+                        width: wall_width,
+                        dashed,
+                    }));
                 }
             }
+        }
 
-            if let Some(p) = best_p {
+        if show_chamber_number {
+            if let Some(p) = self.number_pos() {
                 prims.push(Box::new(Text {
-                    at: Into::<Vec2<f64>>::into(p),
+                    at: p,
                     text: self.id.to_string(),
                     color: color,
                     size: 25.0,
@@ -181,8 +198,59 @@ impl Chamber {
         prims
     }
 
+    /// Position of the chamber number, the grid cell center farthest from the walls
+    pub fn number_pos(&self) -> Option<Vec2<f64>> {
+        // get bbox of polygon
+        // iterate over each cell in BBox
+        // if in polygon
+        // calc min distance to polygon
+        // take cell with max min dist which is in polygon
+        let bbox = self.bbox();
+        let mut max_min_dist = f64::NEG_INFINITY;
+        let mut best_p = None;
+
+        let grid = 50.0;
+        let x_steps: u32 = ((bbox.max.x - bbox.min.x) / grid).ceil() as u32;
+        let y_steps: u32 = ((bbox.max.y - bbox.min.y) / grid).ceil() as u32;
+        for x_i in 0..x_steps {
+            for y_i in 0..y_steps {
+                let p = bbox.min
+                    + Vec2::<f64> {
+                        x: (x_i as f64) * grid + 25.0,
+                        y: (y_i as f64) * grid + 25.0,
+                    };
+                if self.contains_point(p) {
+                    let d = self
+                        .walls()
+                        .iter()
+                        .map(|w| w.distance(p))
+                        .reduce(f64::min)
+                        .unwrap_or(f64::NEG_INFINITY);
+                    if d > max_min_dist {
+                        max_min_dist = d;
+                        best_p = Some(p);
+                    }
+                }
+            }
+        }
+        best_p
+    }
+
     fn next_wall_id(&self) -> WallId {
-        self.walls.iter().map(|w| w.id).max().unwrap_or(0) + 1
+        self.walls().iter().map(|w| w.id).max().unwrap_or(0) + 1
+    }
+
+    /// the outer ring or hole containing the wall
+    fn ring(&self, wall_id: WallId) -> Option<&Vec<Wall>> {
+        std::iter::once(&self.walls)
+            .chain(self.holes.iter())
+            .find(|r| r.iter().any(|w| w.id == wall_id))
+    }
+
+    fn ring_mut(&mut self, wall_id: WallId) -> Option<&mut Vec<Wall>> {
+        std::iter::once(&mut self.walls)
+            .chain(self.holes.iter_mut())
+            .find(|r| r.iter().any(|w| w.id == wall_id))
     }
 
     pub fn append(&mut self, vert: Vec2<i32>) {
@@ -210,16 +278,56 @@ impl Chamber {
         }
     }
 
-    pub fn walls(&self) -> &Vec<Wall> {
-        &self.walls
+    /// all walls of the chamber, including the walls of holes
+    pub fn walls(&self) -> Vec<Wall> {
+        self.walls
+            .iter()
+            .chain(self.holes.iter().flatten())
+            .copied()
+            .collect()
     }
 
     pub fn wall(&self, id: WallId) -> Option<&Wall> {
-        self.walls.iter().find(|w| w.id == id)
+        self.walls
+            .iter()
+            .chain(self.holes.iter().flatten())
+            .find(|w| w.id == id)
+    }
+
+    pub fn holes(&self) -> &Vec<Vec<Wall>> {
+        &self.holes
+    }
+
+    /// corners of the chamber in drawing order
+    pub fn vertices(&self) -> Vec<Vec2<i32>> {
+        self.walls.iter().map(|w| w.p1).collect()
+    }
+
+    pub fn shape(&self) -> Shape {
+        Shape {
+            outer: self.vertices(),
+            holes: self
+                .holes
+                .iter()
+                .map(|h| h.iter().map(|w| w.p1).collect())
+                .collect(),
+        }
+    }
+
+    /// Replaces the walls of the chamber by the outline and holes of the shape.
+    /// Walls are renumbered, doors on the chamber have to be relocated by the caller.
+    pub(crate) fn set_shape(&mut self, shape: &Shape) {
+        self.walls = ring_walls(self.id, 1, &shape.outer);
+        self.holes = vec![];
+        for hole in shape.holes.iter() {
+            let first_id = self.next_wall_id();
+            self.holes.push(ring_walls(self.id, first_id, hole));
+        }
+        self.first_vert = shape.outer.first().copied();
     }
 
     fn lines(&self) -> Vec<Line> {
-        self.walls
+        self.walls()
             .iter()
             .map(|w| Line {
                 a: w.p1.into(),
@@ -248,6 +356,7 @@ impl Chamber {
             }
         }
         // uneven crossing == inside
+        // holes are part of the lines, points inside a hole have an even count
         crossings % 2 == 1
     }
 
@@ -270,20 +379,21 @@ impl Chamber {
     /// The corner will always be wall_1.p2 == wall_2.p1
     pub fn nearest_corner(&self, pos: Vec2<f64>) -> Option<(Wall, Wall)> {
         // get the first wall
-        let wall_1 = self.walls.iter().min_by(|a, b| {
+        let wall_1 = self.walls().into_iter().min_by(|a, b| {
             (a.p2 - pos.into())
                 .sqr_len()
                 .total_cmp(&(b.p2 - pos.into()).sqr_len())
         });
         match wall_1 {
             Some(wall_1) => {
-                let p = self.walls.iter().position(|w| w.id == wall_1.id).unwrap();
-                let wall_2 = if p != self.walls.len() - 1 {
-                    self.walls[p + 1]
+                let ring = self.ring(wall_1.id).unwrap();
+                let p = ring.iter().position(|w| w.id == wall_1.id).unwrap();
+                let wall_2 = if p != ring.len() - 1 {
+                    ring[p + 1]
                 } else {
-                    self.walls[0]
+                    ring[0]
                 };
-                Some((*wall_1, wall_2))
+                Some((wall_1, wall_2))
             }
             None => None,
         }
@@ -297,43 +407,55 @@ impl Chamber {
         bbox
     }
 
+    /// moves all walls of the chamber
+    pub fn translate(&mut self, by: Vec2<i32>) {
+        for wall in self.walls.iter_mut().chain(self.holes.iter_mut().flatten()) {
+            wall.p1 += by;
+            wall.p2 += by;
+        }
+        if let Some(v) = self.first_vert.as_mut() {
+            *v += by;
+        }
+    }
+
     pub(crate) fn split(&mut self, wall_id: WallId, pos: Vec2<i32>) {
-        let idx = self
-            .walls
-            .iter()
-            .position(|w| w.id == wall_id)
-            .unwrap()
-            .clone();
+        let new_id = self.next_wall_id();
+        let walls = self.ring_mut(wall_id).unwrap();
+        let idx = walls.iter().position(|w| w.id == wall_id).unwrap();
 
-        let (w1, mut w2) = self.walls[idx].split(pos);
-        w2.id = self.next_wall_id();
+        let (w1, mut w2) = walls[idx].split(pos);
+        w2.id = new_id;
 
-        self.walls[idx] = w1;
-        self.walls.insert(idx + 1, w2);
+        walls[idx] = w1;
+        walls.insert(idx + 1, w2);
     }
 
     pub(crate) fn collapse(&mut self, wall_id: WallId) -> WallId {
-        let idx = self
-            .walls
-            .iter()
-            .position(|w| w.id == wall_id)
-            .unwrap()
-            .clone();
-        let next_idx = if idx == self.walls.len() - 1 {
-            0
-        } else {
-            idx + 1
-        };
-        let removed_id = self.walls[next_idx].id;
-        let mut n_wall = self.walls[idx];
-        n_wall.p2 = self.walls[next_idx].p2;
-        self.walls[idx] = n_wall;
-        self.walls.remove(next_idx);
+        let walls = self.ring_mut(wall_id).unwrap();
+        let idx = walls.iter().position(|w| w.id == wall_id).unwrap();
+        let next_idx = if idx == walls.len() - 1 { 0 } else { idx + 1 };
+        let removed_id = walls[next_idx].id;
+        let mut n_wall = walls[idx];
+        n_wall.p2 = walls[next_idx].p2;
+        walls[idx] = n_wall;
+        walls.remove(next_idx);
 
         removed_id
     }
 }
 
+/// closed ring of walls through the vertices with consecutive ids
+fn ring_walls(chamber_id: ChamberId, first_id: WallId, verts: &[Vec2<i32>]) -> Vec<Wall> {
+    (0..verts.len())
+        .map(|i| Wall {
+            id: first_id + i as WallId,
+            chamber_id,
+            p1: verts[i],
+            p2: verts[(i + 1) % verts.len()],
+        })
+        .collect()
+}
+
 impl Wall {
     pub fn distance(&self, p: Vec2<f64>) -> f64 {
         (p - self.nearest_point(p)).len()
@@ -363,6 +485,32 @@ impl Wall {
         (on_wall - self.p1.into()).len() / (self.p2 - self.p1).len()
     }
 
+    /// Returns the part of this wall lying on `other`.
+    /// The walls have to be collinear within `tolerance` and overlap by more than `tolerance`.
+    pub fn overlap(&self, other: &Wall, tolerance: f64) -> Option<(Vec2<f64>, Vec2<f64>)> {
+        if self.p1 == self.p2 {
+            return None;
+        }
+        let origin: Vec2<f64> = self.p1.into();
+        let t = self.tangent();
+        let mut params = vec![];
+        for q in [other.p1, other.p2] {
+            let d = Into::<Vec2<f64>>::into(q) - origin;
+            // distance of q to the line through this wall
+            if (d.x * t.y - d.y * t.x).abs() > tolerance {
+                return None;
+            }
+            params.push(d.dot(t));
+        }
+        let len = Into::<Vec2<f64>>::into(self.p2 - self.p1).len();
+        let lo = params[0].min(params[1]).max(0.0);
+        let hi = params[0].max(params[1]).min(len);
+        if hi - lo <= tolerance {
+            return None;
+        }
+        Some((origin + lo * t, origin + hi * t))
+    }
+
     fn split(&self, vert: Vec2<i32>) -> (Wall, Wall) {
         (
             Wall {
@@ -394,10 +542,29 @@ impl Wall {
 
 #[cfg(test)]
 mod tests {
-    use crate::common::Vec2;
+    use crate::{common::Vec2, geometry::Shape};
 
     use super::{Chamber, Wall};
 
+    fn ring_chamber() -> Chamber {
+        let mut r = Chamber::new();
+        r.set_shape(&Shape {
+            outer: vec![
+                Vec2 { x: 0, y: 0 },
+                Vec2 { x: 300, y: 0 },
+                Vec2 { x: 300, y: 300 },
+                Vec2 { x: 0, y: 300 },
+            ],
+            holes: vec![vec![
+                Vec2 { x: 100, y: 100 },
+                Vec2 { x: 100, y: 200 },
+                Vec2 { x: 200, y: 200 },
+                Vec2 { x: 200, y: 100 },
+            ]],
+        });
+        r
+    }
+
     #[test]
     fn test_walls_now_verts() {
         let r = Chamber::new();
@@ -521,4 +688,76 @@ mod tests {
 
         assert_eq!(r.contains_point(Vec2 { x: 100., y: 50. }), true);
     }
+
+    #[test]
+    fn contains_hole() {
+        let r = ring_chamber();
+        assert!(r.contains_point(Vec2 { x: 50., y: 150. }));
+        assert!(!r.contains_point(Vec2 { x: 150., y: 150. }));
+        assert!(r.contains_point(Vec2 { x: 250., y: 150. }));
+    }
+
+    #[test]
+    fn hole_walls() {
+        let r = ring_chamber();
+        let walls = r.walls();
+        assert_eq!(walls.len(), 8);
+        // wall ids are unique across outer walls and holes
+        for (i, w) in walls.iter().enumerate() {
+            assert_eq!(w.id, i as u32 + 1);
+        }
+        assert_eq!(r.wall(5).unwrap().p1, Vec2 { x: 100, y: 100 });
+        assert_eq!(r.shape().holes.len(), 1);
+    }
+
+    #[test]
+    fn split_hole_wall() {
+        let mut r = ring_chamber();
+        r.split(5, Vec2 { x: 100, y: 150 });
+        assert_eq!(r.walls().len(), 9);
+        assert_eq!(r.holes()[0].len(), 5);
+        assert_eq!(r.wall(9).unwrap().p1, Vec2 { x: 100, y: 150 });
+        assert_eq!(r.collapse(5), 9);
+        assert_eq!(r.holes()[0].len(), 4);
+        let (w1, w2) = r.nearest_corner(Vec2 { x: 110.0, y: 190.0 }).unwrap();
+        assert_eq!(w1.id, 5);
+        assert_eq!(w2.id, 6);
+    }
+
+    #[test]
+    fn overlap_of_collinear_walls() {
+        let a = Wall {
+            id: 1,
+            chamber_id: 1,
+            p1: Vec2 { x: 0, y: 0 },
+            p2: Vec2 { x: 100, y: 0 },
+        };
+        let b = Wall {
+            id: 1,
+            chamber_id: 2,
+            p1: Vec2 { x: 150, y: 0 },
+            p2: Vec2 { x: 50, y: 0 },
+        };
+        let (from, to) = a.overlap(&b, 1.0).unwrap();
+        assert_eq!(from, Vec2 { x: 50.0, y: 0.0 });
+        assert_eq!(to, Vec2 { x: 100.0, y: 0.0 });
+
+        // touching in a single point
+        let c = Wall {
+            id: 1,
+            chamber_id: 2,
+            p1: Vec2 { x: 100, y: 0 },
+            p2: Vec2 { x: 200, y: 0 },
+        };
+        assert!(a.overlap(&c, 1.0).is_none());
+
+        // parallel, but not on the same line
+        let d = Wall {
+            id: 1,
+            chamber_id: 2,
+            p1: Vec2 { x: 0, y: 5 },
+            p2: Vec2 { x: 100, y: 5 },
+        };
+        assert!(a.overlap(&d, 1.0).is_none());
+    }
 }
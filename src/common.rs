@@ -1,6 +1,6 @@
 use std::ops::{Add, AddAssign, BitAnd, BitAndAssign, Div, Mul, Sub};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Vec2<T> {
     pub x: T,
     pub y: T,
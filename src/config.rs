@@ -30,3 +30,56 @@ pub const DEFAULT_CHAMBER_COLOR: Rgb = Rgb {
 };
 pub const WALL_WIDTH: f64 = 5.0;
 pub const GRID_SIZE: i32 = 50;
+/// maximal distance of the cursor to a corner or wall to snap to it
+pub const SNAP_DISTANCE: f64 = 10.0;
+
+/// chamber colors of the session view
+pub const SESSION_UNEXPLORED_COLOR: Rgb = Rgb {
+    r: 0.6,
+    g: 0.6,
+    b: 0.6,
+};
+pub const SESSION_EXPLORED_COLOR: Rgb = Rgb {
+    r: 1.0,
+    g: 0.95,
+    b: 0.6,
+};
+pub const SESSION_CLEARED_COLOR: Rgb = Rgb {
+    r: 0.6,
+    g: 0.9,
+    b: 0.6,
+};
+/// door colors of the session view
+pub const SESSION_OPENED_COLOR: Rgb = Rgb {
+    r: 0.3,
+    g: 0.8,
+    b: 0.3,
+};
+pub const SESSION_BROKEN_COLOR: Rgb = Rgb {
+    r: 0.9,
+    g: 0.2,
+    b: 0.2,
+};
+/// object color of the session view
+pub const SESSION_LOOTED_COLOR: Rgb = Rgb {
+    r: 0.5,
+    g: 0.5,
+    b: 0.5,
+};
+
+/// highlights of the changes to a compared dungeon
+pub const DIFF_ADDED_COLOR: Rgb = Rgb {
+    r: 0.2,
+    g: 0.8,
+    b: 0.2,
+};
+pub const DIFF_REMOVED_COLOR: Rgb = Rgb {
+    r: 0.9,
+    g: 0.1,
+    b: 0.1,
+};
+pub const DIFF_CHANGED_COLOR: Rgb = Rgb {
+    r: 1.0,
+    g: 0.8,
+    b: 0.0,
+};
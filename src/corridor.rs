@@ -0,0 +1,267 @@
+use crate::{
+    common::{Rgb, Vec2},
+    config::{DEFAULT_CHAMBER_COLOR, GRID_SIZE, WALL_WIDTH},
+    geometry::{self, BooleanOp, Shape},
+    print_theme::WallStyle,
+    view::primitives::{Point, Polygon, Polyline, Primitive, Text},
+};
+
+pub type CorridorId = u32;
+
+pub struct CorridorDrawOptions {
+    pub color: Option<Rgb>,
+    pub fill: Option<bool>,
+    pub show_number: bool,
+    /// walls and floor as printed, otherwise as in the editor
+    pub wall_style: Option<WallStyle>,
+}
+
+/// A Corridor connects chambers.
+/// It is defined by a centerline and a width in grid cells.
+/// Walls are drawn on both sides of the centerline, except inside of chambers.
+#[derive(Clone)]
+pub struct Corridor {
+    pub id: CorridorId,
+    pub name: String,
+    pub notes: String,
+    pub hidden: bool,
+    /// numbered corridors are labeled and listed in the GM export
+    pub numbered: bool,
+    points: Vec<Vec2<i32>>,
+    width: i32,
+    /// cached outline, derived from points and width
+    outline: Vec<Shape>,
+}
+
+impl Corridor {
+    pub fn new() -> Self {
+        Self {
+            id: 1,
+            name: "New Corridor".to_owned(),
+            notes: String::new(),
+            hidden: false,
+            numbered: false,
+            points: vec![],
+            width: 1,
+            outline: vec![],
+        }
+    }
+
+    pub fn points(&self) -> &Vec<Vec2<i32>> {
+        &self.points
+    }
+
+    /// width in grid cells
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn append(&mut self, point: Vec2<i32>) {
+        if self.points.last() == Some(&point) {
+            return;
+        }
+        self.points.push(point);
+        self.update_outline();
+    }
+
+    pub fn translate(&mut self, by: Vec2<i32>) {
+        for p in self.points.iter_mut() {
+            *p += by;
+        }
+        self.update_outline();
+    }
+
+    pub fn set_width(&mut self, width: i32) {
+        self.width = width.max(1);
+        self.update_outline();
+    }
+
+    /// area covered by the corridor, including the parts inside of chambers
+    pub fn outline(&self) -> &Vec<Shape> {
+        &self.outline
+    }
+
+    pub fn contains_point(&self, pos: Vec2<f64>) -> bool {
+        self.outline.iter().any(|s| s.contains(pos))
+    }
+
+    /// snaps a point to the grid so that the walls of the corridor lie on grid lines
+    pub fn snap(&self, pos: Vec2<f64>) -> Vec2<i32> {
+        let offset = match self.width % 2 {
+            0 => 0.0,
+            _ => GRID_SIZE as f64 / 2.0,
+        };
+        let snap = |v: f64| ((v - offset) / GRID_SIZE as f64).round() as i32 * GRID_SIZE;
+        Vec2 {
+            x: snap(pos.x) + offset as i32,
+            y: snap(pos.y) + offset as i32,
+        }
+    }
+
+    /// union of all segments, each extended by half the width at both ends
+    fn update_outline(&mut self) {
+        let half = (self.width * GRID_SIZE) as f64 / 2.0;
+        let mut outline: Vec<Shape> = vec![];
+        for w in self.points.windows(2) {
+            let a: Vec2<f64> = w[0].into();
+            let b: Vec2<f64> = w[1].into();
+            let d = b - a;
+            let d = (half / d.len()) * d;
+            let n = Vec2 { x: -d.y, y: d.x };
+            let round = |v: Vec2<f64>| Vec2 {
+                x: v.x.round() as i32,
+                y: v.y.round() as i32,
+            };
+            let segment = Shape {
+                outer: vec![
+                    round(a - d - n),
+                    round(b + d - n),
+                    round(b + d + n),
+                    round(a - d + n),
+                ],
+                holes: vec![],
+            };
+            let mut touching = vec![];
+            let mut merged = segment;
+            for shape in outline.into_iter() {
+                let union = geometry::boolean(&merged, &shape, BooleanOp::Union);
+                if union.len() == 1 {
+                    merged = union.into_iter().next().unwrap();
+                } else {
+                    touching.push(shape);
+                }
+            }
+            touching.push(merged);
+            outline = touching;
+        }
+        self.outline = outline;
+    }
+
+    /// Draws the corridor.
+    /// The floor is clipped by `chambers`, walls are left out inside of `chambers` and `corridors`.
+    pub fn draw(
+        &self,
+        chambers: &[Shape],
+        corridors: &[Shape],
+        options: CorridorDrawOptions,
+    ) -> Vec<Box<dyn Primitive>> {
+        let color = options.color.unwrap_or(DEFAULT_CHAMBER_COLOR);
+        if self.points.len() == 1 {
+            return vec![Box::new(Point {
+                at: self.points[0].into(),
+                color,
+            })];
+        }
+
+        let mut prims = Vec::<Box<dyn Primitive>>::new();
+
+        // floor
+        let mut floor = self.outline.clone();
+        for chamber in chambers {
+            floor = floor
+                .iter()
+                .flat_map(|s| geometry::boolean(s, chamber, BooleanOp::Difference))
+                .collect();
+        }
+        for shape in floor {
+            prims.push(Box::new(Polygon {
+                points: shape.outer.iter().map(|p| (*p).into()).collect(),
+                holes: shape
+                    .holes
+                    .iter()
+                    .map(|h| h.iter().map(|p| (*p).into()).collect())
+                    .collect(),
+                fill_color: options.wall_style.map_or(color, |s| s.fill),
+                fill_opacity: match options.fill {
+                    Some(false) => 0.0,
+                    _ => options.wall_style.map_or(0.3, |s| s.fill_opacity),
+                },
+                stroke_color: color,
+                stroke_width: 0.0,
+                dashed: false,
+            }));
+        }
+
+        // walls
+        let obstacles: Vec<Shape> = chambers.iter().chain(corridors.iter()).cloned().collect();
+        for shape in self.outline.iter() {
+            for ring in shape.rings() {
+                let ring: Vec<Vec2<f64>> = ring.iter().map(|p| (*p).into()).collect();
+                let runs = geometry::ring_runs(&ring, |i| {
+                    geometry::segment_inside(&obstacles, ring[i], ring[(i + 1) % ring.len()])
+                });
+                for run in runs {
+                    prims.push(Box::new(Polyline {
+                        points: run,
+                        color,
+                        width: options.wall_style.map_or(WALL_WIDTH, |s| s.width),
+                        dashed: self.hidden && options.wall_style.is_none_or(|s| s.dashed_hidden),
+                    }));
+                }
+            }
+        }
+
+        if options.show_number && self.numbered {
+            if let Some(at) = self.label_pos() {
+                prims.push(Box::new(Text {
+                    at,
+                    text: self.label(),
+                    color,
+                    size: 25.0,
+                }));
+            }
+        }
+
+        prims
+    }
+
+    /// short label used on maps, e.g. "C3"
+    pub fn label(&self) -> String {
+        format!("C{}", self.id)
+    }
+
+    /// center of the longest segment
+    pub fn label_pos(&self) -> Option<Vec2<f64>> {
+        self.points
+            .windows(2)
+            .max_by(|a, b| (a[1] - a[0]).sqr_len().total_cmp(&(b[1] - b[0]).sqr_len()))
+            .map(|w| 0.5 * (Into::<Vec2<f64>>::into(w[0]) + w[1].into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::Vec2;
+
+    use super::Corridor;
+
+    #[test]
+    fn outline_straight() {
+        let mut c = Corridor::new();
+        c.append(Vec2 { x: 25, y: 25 });
+        c.append(Vec2 { x: 225, y: 25 });
+        assert_eq!(c.outline().len(), 1);
+        assert!(c.contains_point(Vec2 { x: 100.0, y: 10.0 }));
+        assert!(c.contains_point(Vec2 { x: 240.0, y: 40.0 }));
+        assert!(!c.contains_point(Vec2 { x: 100.0, y: 60.0 }));
+    }
+
+    #[test]
+    fn outline_corner() {
+        let mut c = Corridor::new();
+        c.append(Vec2 { x: 25, y: 25 });
+        c.append(Vec2 { x: 225, y: 25 });
+        c.append(Vec2 { x: 225, y: 225 });
+        assert_eq!(c.outline().len(), 1);
+        assert_eq!(c.outline()[0].outer.len(), 6);
+        assert!(c.contains_point(Vec2 { x: 240.0, y: 10.0 }));
+    }
+
+    #[test]
+    fn snap_to_cells() {
+        let mut c = Corridor::new();
+        assert_eq!(c.snap(Vec2 { x: 60.0, y: 90.0 }), Vec2 { x: 75, y: 75 });
+        c.set_width(2);
+        assert_eq!(c.snap(Vec2 { x: 60.0, y: 90.0 }), Vec2 { x: 50, y: 100 });
+    }
+}
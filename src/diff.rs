@@ -0,0 +1,439 @@
+use crate::dungeon::Dungeon;
+
+/// Kind of entity a change applies to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntityKind {
+    Dungeon,
+    Chamber,
+    Door,
+    Object,
+    Corridor,
+}
+
+impl EntityKind {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            EntityKind::Dungeon => "Dungeon",
+            EntityKind::Chamber => "Chamber",
+            EntityKind::Door => "Door",
+            EntityKind::Object => "Object",
+            EntityKind::Corridor => "Corridor",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// the outline of a chamber or corridor changed
+    Reshaped,
+    /// position, size or rotation of a door or object changed
+    Moved,
+    /// renamed, with the previous name
+    Renamed(String),
+    NotesChanged,
+    /// the boxed text read to the players changed
+    ReadAloudChanged,
+    HiddenChanged(bool),
+    /// a door leads to another chamber, with the names of the previous and the new chamber
+    Retargeted(Option<String>, Option<String>),
+    /// style of an object changed, with the previous and the new style
+    StyleChanged(String, String),
+}
+
+/// A difference between two versions of a dungeon.
+/// Entities are matched by their ids, which are kept across versions of a dungeon file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub entity: EntityKind,
+    pub id: u32,
+    /// name in the new version, or in the old version for removed entities
+    pub name: String,
+    pub kind: ChangeKind,
+}
+
+impl Change {
+    fn new(entity: EntityKind, id: u32, name: &str, kind: ChangeKind) -> Self {
+        Self {
+            entity,
+            id,
+            name: name.to_owned(),
+            kind,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let what = match self.kind.clone() {
+            ChangeKind::Added => "added".to_owned(),
+            ChangeKind::Removed => "removed".to_owned(),
+            ChangeKind::Reshaped => "reshaped".to_owned(),
+            ChangeKind::Moved => "moved".to_owned(),
+            ChangeKind::Renamed(old) => format!("renamed from \"{}\"", old),
+            ChangeKind::NotesChanged => "notes changed".to_owned(),
+            ChangeKind::ReadAloudChanged => "read-aloud text changed".to_owned(),
+            ChangeKind::HiddenChanged(true) => "hidden".to_owned(),
+            ChangeKind::HiddenChanged(false) => "unhidden".to_owned(),
+            ChangeKind::Retargeted(old, new) => format!(
+                "leads to {} instead of {}",
+                new.unwrap_or("nowhere".to_owned()),
+                old.unwrap_or("nowhere".to_owned())
+            ),
+            ChangeKind::StyleChanged(old, new) => format!("style changed from {} to {}", old, new),
+        };
+        match (self.entity, self.name.is_empty()) {
+            (EntityKind::Dungeon, _) => format!("Dungeon: {}", what),
+            (entity, true) => format!("{} {}: {}", entity.to_str(), self.id, what),
+            (entity, false) => format!(
+                "{} {} \"{}\": {}",
+                entity.to_str(),
+                self.id,
+                self.name,
+                what
+            ),
+        }
+    }
+}
+
+/// changes of the properties every entity has
+fn common_changes(
+    changes: &mut Vec<Change>,
+    entity: EntityKind,
+    id: u32,
+    old: (&str, &str, bool),
+    new: (&str, &str, bool),
+) {
+    let (old_name, old_notes, old_hidden) = old;
+    let (name, notes, hidden) = new;
+    if old_name != name {
+        changes.push(Change::new(
+            entity,
+            id,
+            name,
+            ChangeKind::Renamed(old_name.to_owned()),
+        ));
+    }
+    if old_notes != notes {
+        changes.push(Change::new(entity, id, name, ChangeKind::NotesChanged));
+    }
+    if old_hidden != hidden {
+        changes.push(Change::new(
+            entity,
+            id,
+            name,
+            ChangeKind::HiddenChanged(hidden),
+        ));
+    }
+}
+
+/// Compares two versions of a dungeon at the level of chambers, doors, objects and corridors
+pub fn diff(old: &Dungeon, new: &Dungeon) -> Vec<Change> {
+    let mut changes = vec![];
+    if old.name != new.name {
+        changes.push(Change::new(
+            EntityKind::Dungeon,
+            0,
+            &new.name,
+            ChangeKind::Renamed(old.name.clone()),
+        ));
+    }
+    if old.notes != new.notes {
+        changes.push(Change::new(
+            EntityKind::Dungeon,
+            0,
+            &new.name,
+            ChangeKind::NotesChanged,
+        ));
+    }
+
+    let entity = EntityKind::Chamber;
+    for chamber in new.chambers.iter() {
+        match old.chamber(chamber.id) {
+            None => changes.push(Change::new(
+                entity,
+                chamber.id,
+                &chamber.name,
+                ChangeKind::Added,
+            )),
+            Some(o) => {
+                if o.shape() != chamber.shape() {
+                    changes.push(Change::new(
+                        entity,
+                        chamber.id,
+                        &chamber.name,
+                        ChangeKind::Reshaped,
+                    ));
+                }
+                if o.read_aloud != chamber.read_aloud {
+                    changes.push(Change::new(
+                        entity,
+                        chamber.id,
+                        &chamber.name,
+                        ChangeKind::ReadAloudChanged,
+                    ));
+                }
+                common_changes(
+                    &mut changes,
+                    entity,
+                    chamber.id,
+                    (&o.name, &o.notes, o.hidden),
+                    (&chamber.name, &chamber.notes, chamber.hidden),
+                );
+            }
+        }
+    }
+    for o in old.chambers.iter().filter(|c| new.chamber(c.id).is_none()) {
+        changes.push(Change::new(entity, o.id, &o.name, ChangeKind::Removed));
+    }
+
+    let entity = EntityKind::Door;
+    let chamber_name = |dungeon: &Dungeon, id: Option<u32>| -> Option<String> {
+        id.and_then(|id| dungeon.chamber(id))
+            .map(|c| c.name.clone())
+    };
+    for door in new.doors.iter() {
+        match old.door(door.id) {
+            None => changes.push(Change::new(entity, door.id, &door.name, ChangeKind::Added)),
+            Some(o) => {
+                if old.door_world_pos(o) != new.door_world_pos(door) || o.width != door.width {
+                    changes.push(Change::new(entity, door.id, &door.name, ChangeKind::Moved));
+                }
+                if o.leads_to != door.leads_to {
+                    changes.push(Change::new(
+                        entity,
+                        door.id,
+                        &door.name,
+                        ChangeKind::Retargeted(
+                            chamber_name(old, o.leads_to),
+                            chamber_name(new, door.leads_to),
+                        ),
+                    ));
+                }
+                if o.read_aloud != door.read_aloud {
+                    changes.push(Change::new(
+                        entity,
+                        door.id,
+                        &door.name,
+                        ChangeKind::ReadAloudChanged,
+                    ));
+                }
+                common_changes(
+                    &mut changes,
+                    entity,
+                    door.id,
+                    (&o.name, &o.notes, o.hidden),
+                    (&door.name, &door.notes, door.hidden),
+                );
+            }
+        }
+    }
+    for o in old.doors.iter().filter(|d| new.door(d.id).is_none()) {
+        changes.push(Change::new(entity, o.id, &o.name, ChangeKind::Removed));
+    }
+
+    let entity = EntityKind::Object;
+    for object in new.objects.iter() {
+        match old.object(object.id) {
+            None => changes.push(Change::new(
+                entity,
+                object.id,
+                &object.name,
+                ChangeKind::Added,
+            )),
+            Some(o) => {
+                if o.pos != object.pos || o.size != object.size || o.rotation != object.rotation {
+                    changes.push(Change::new(
+                        entity,
+                        object.id,
+                        &object.name,
+                        ChangeKind::Moved,
+                    ));
+                }
+                if o.style.to_str() != object.style.to_str() {
+                    changes.push(Change::new(
+                        entity,
+                        object.id,
+                        &object.name,
+                        ChangeKind::StyleChanged(o.style.to_str(), object.style.to_str()),
+                    ));
+                }
+                if o.read_aloud != object.read_aloud {
+                    changes.push(Change::new(
+                        entity,
+                        object.id,
+                        &object.name,
+                        ChangeKind::ReadAloudChanged,
+                    ));
+                }
+                common_changes(
+                    &mut changes,
+                    entity,
+                    object.id,
+                    (&o.name, &o.notes, o.hidden),
+                    (&object.name, &object.notes, object.hidden),
+                );
+            }
+        }
+    }
+    for o in old.objects.iter().filter(|o| new.object(o.id).is_none()) {
+        changes.push(Change::new(entity, o.id, &o.name, ChangeKind::Removed));
+    }
+
+    let entity = EntityKind::Corridor;
+    for corridor in new.corridors.iter() {
+        match old.corridor(corridor.id) {
+            None => changes.push(Change::new(
+                entity,
+                corridor.id,
+                &corridor.name,
+                ChangeKind::Added,
+            )),
+            Some(o) => {
+                if o.points() != corridor.points() || o.width() != corridor.width() {
+                    changes.push(Change::new(
+                        entity,
+                        corridor.id,
+                        &corridor.name,
+                        ChangeKind::Reshaped,
+                    ));
+                }
+                common_changes(
+                    &mut changes,
+                    entity,
+                    corridor.id,
+                    (&o.name, &o.notes, o.hidden),
+                    (&corridor.name, &corridor.notes, corridor.hidden),
+                );
+            }
+        }
+    }
+    for o in old
+        .corridors
+        .iter()
+        .filter(|c| new.corridor(c.id).is_none())
+    {
+        changes.push(Change::new(entity, o.id, &o.name, ChangeKind::Removed));
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::Vec2;
+    use crate::door::Door;
+    use crate::dungeon::tests::add_rect;
+    use crate::state::{State, StateCommand, StateController};
+
+    use super::{diff, ChangeKind, EntityKind};
+
+    fn add_chamber(state: &mut State, name: &str, min: (i32, i32), max: (i32, i32)) {
+        let chamber_id = add_rect(&mut state.dungeon, min.0, min.1, max.0, max.1);
+        StateCommand::ChangeChamberName(chamber_id, name.to_owned()).execute(state);
+    }
+
+    fn version_1() -> State {
+        let mut state = State::new();
+        add_chamber(&mut state, "Hall", (0, 0), (100, 100));
+        add_chamber(&mut state, "Crypt", (100, 0), (200, 100));
+        add_chamber(&mut state, "Vault", (0, 100), (100, 200));
+        let wall = state.dungeon.chamber(1).unwrap().walls()[1];
+        StateCommand::AddDoor(Door::new(1, Some(2), 50.0, wall.id, 0.5)).execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn no_changes() {
+        let state = version_1();
+        assert!(diff(&state.dungeon, &state.dungeon).is_empty());
+    }
+
+    #[test]
+    fn changes_follow_the_dungeon() {
+        let mut control = StateController::new();
+        control.state = version_1();
+        control.state.set_compare(Some(version_1().dungeon));
+        assert!(control.state.changes.is_empty());
+
+        control.apply(StateCommand::ChangeChamberName(1, "Great Hall".to_owned()));
+        assert_eq!(control.state.changes.len(), 1);
+        assert_eq!(control.state.changes[0].entity, EntityKind::Chamber);
+
+        // editor state is no change of the dungeon
+        control.apply(StateCommand::SelectChamber(Some(2)));
+        assert_eq!(control.state.changes.len(), 1);
+
+        control.state.set_compare(None);
+        assert!(control.state.changes.is_empty());
+    }
+
+    #[test]
+    fn model_level_changes() {
+        let old = version_1();
+        let mut new = version_1();
+        StateCommand::ChangeChamberName(1, "Great Hall".to_owned()).execute(&mut new);
+        StateCommand::ChangeChamberNotes(2, "Undead".to_owned()).execute(&mut new);
+        StateCommand::DeleteChamber(2).execute(&mut new);
+        let wall = new.dungeon.chamber(3).unwrap().walls()[0];
+        StateCommand::SplitWall(3, wall.id, Vec2 { x: 50, y: 100 }).execute(&mut new);
+        add_chamber(&mut new, "Shrine", (200, 0), (300, 100));
+        StateCommand::ChangeDoorLeadsTo(1, Some(4)).execute(&mut new);
+
+        let changes = diff(&old.dungeon, &new.dungeon);
+        let kinds: Vec<(EntityKind, u32, ChangeKind)> = changes
+            .iter()
+            .map(|c| (c.entity, c.id, c.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    EntityKind::Chamber,
+                    1,
+                    ChangeKind::Renamed("Hall".to_owned())
+                ),
+                (EntityKind::Chamber, 3, ChangeKind::Reshaped),
+                (EntityKind::Chamber, 4, ChangeKind::Added),
+                (EntityKind::Chamber, 2, ChangeKind::Removed),
+                (
+                    EntityKind::Door,
+                    1,
+                    ChangeKind::Retargeted(Some("Crypt".to_owned()), Some("Shrine".to_owned()))
+                ),
+            ]
+        );
+        assert_eq!(
+            changes[0].describe(),
+            "Chamber 1 \"Great Hall\": renamed from \"Hall\""
+        );
+        assert_eq!(
+            changes[4].describe(),
+            "Door 1: leads to Shrine instead of Crypt"
+        );
+    }
+
+    #[test]
+    fn read_aloud_changes() {
+        let old = version_1();
+        let mut new = version_1();
+        StateCommand::ChangeChamberReadAloud(2, "Bones everywhere.".to_owned()).execute(&mut new);
+        StateCommand::ChangeDoorReadAloud(1, "A heavy door.".to_owned()).execute(&mut new);
+
+        let changes = diff(&old.dungeon, &new.dungeon);
+        let kinds: Vec<(EntityKind, u32, ChangeKind)> = changes
+            .iter()
+            .map(|c| (c.entity, c.id, c.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (EntityKind::Chamber, 2, ChangeKind::ReadAloudChanged),
+                (EntityKind::Door, 1, ChangeKind::ReadAloudChanged),
+            ]
+        );
+        assert_eq!(
+            changes[0].describe(),
+            "Chamber 2 \"Crypt\": read-aloud text changed"
+        );
+    }
+}
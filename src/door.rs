@@ -21,6 +21,8 @@ pub struct Door {
     pub id: DoorId,
     pub name: String,
     pub notes: String,
+    /// boxed text read to the players, separate from the GM notes
+    pub read_aloud: String,
     pub hidden: bool,
     pub part_of: ChamberId,
     pub leads_to: Option<ChamberId>,
@@ -41,6 +43,7 @@ impl Door {
             id: 1,
             name: "".to_owned(),
             notes: "".to_owned(),
+            read_aloud: "".to_owned(),
             hidden: false,
             part_of: part_of,
             leads_to: leads_to,
@@ -1,18 +1,68 @@
 use crate::{
-    chamber::{Chamber, ChamberId, Wall},
-    common::Vec2,
+    background::Background,
+    chamber::{Chamber, ChamberId, Wall, WallId},
+    common::{BBox, Vec2},
+    corridor::{Corridor, CorridorId},
     door::{Door, DoorId},
+    export_settings::ExportSettings,
+    geometry::Shape,
     object::{Object, ObjectId},
+    selection::Selection,
 };
 
+/// maximal distance of a door to a wall to be considered on that wall
+const DOOR_WALL_TOLERANCE: f64 = 1.0;
+
+/// maximal distance between walls of different chambers to be considered the same wall
+const SHARED_WALL_TOLERANCE: f64 = 1.0;
+
+/// Part of a wall which coincides with a wall of another chamber, see `Dungeon::shared_walls`
+#[derive(Clone, Copy, Debug)]
+pub struct SharedWall {
+    pub wall: Wall,
+    pub other: Wall,
+    pub from: Vec2<f64>,
+    pub to: Vec2<f64>,
+}
+
+/// What a position was snapped to, see `Dungeon::snap_to_walls`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnapTarget {
+    Grid,
+    Vertex,
+    Wall,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snap {
+    pub pos: Vec2<i32>,
+    pub target: SnapTarget,
+}
+
+/// Result of replacing chambers by new shapes, see `Dungeon::replace_chambers`
+#[derive(Default)]
+pub struct ChamberReplacement {
+    pub added: Vec<ChamberId>,
+    pub modified: Vec<ChamberId>,
+    pub removed: Vec<ChamberId>,
+    pub modified_doors: Vec<DoorId>,
+    pub removed_doors: Vec<DoorId>,
+    pub modified_objects: Vec<ObjectId>,
+}
+
 /// A Dungeon is the main object we care about
 /// It consists of multiple chambers
+#[derive(Clone)]
 pub struct Dungeon {
     pub chambers: Vec<Chamber>,
     pub doors: Vec<Door>,
     pub objects: Vec<Object>,
+    pub corridors: Vec<Corridor>,
     pub name: String,
     pub notes: String,
+    pub background: Option<Background>,
+    /// page setup of the PDF exports
+    pub export_settings: ExportSettings,
 }
 
 impl Dungeon {
@@ -21,8 +71,11 @@ impl Dungeon {
             chambers: vec![],
             doors: vec![],
             objects: vec![],
+            corridors: vec![],
             name: "".to_owned(),
             notes: "".to_owned(),
+            background: None,
+            export_settings: ExportSettings::new(),
         }
     }
 
@@ -157,10 +210,15 @@ impl Dungeon {
         self.doors.iter().map(|r| r.id).max().unwrap_or(0) + 1
     }
 
+    /// Doors of the chamber, including doors on walls shared with other chambers
     pub fn chamber_doors(&self, chamber_id: ChamberId) -> Vec<&Door> {
         self.doors
             .iter()
-            .filter(|d| d.part_of == chamber_id || d.leads_to == Some(chamber_id))
+            .filter(|d| {
+                d.part_of == chamber_id
+                    || d.leads_to == Some(chamber_id)
+                    || self.door_shared_with(d).contains(&chamber_id)
+            })
             .collect()
     }
 
@@ -190,13 +248,55 @@ impl Dungeon {
     pub fn walls(&self) -> Vec<Wall> {
         let mut all_walls = Vec::<Wall>::new();
         for chamber in self.chambers.iter() {
-            for wall in chamber.walls() {
-                all_walls.push(wall.clone())
-            }
+            all_walls.extend(chamber.walls())
         }
         all_walls
     }
 
+    /// Snaps a position to the corners and walls of all chambers except `skip`.
+    /// Corners take priority over walls.
+    /// Returns None if no corner or wall is within `tolerance`.
+    pub fn snap_to_walls(
+        &self,
+        pos: Vec2<f64>,
+        tolerance: f64,
+        skip: Option<ChamberId>,
+    ) -> Option<Snap> {
+        let walls: Vec<Wall> = self
+            .chambers
+            .iter()
+            .filter(|c| Some(c.id) != skip)
+            .flat_map(|c| c.walls())
+            .collect();
+
+        let vertex = walls
+            .iter()
+            .map(|w| w.p1)
+            .map(|v| (v, (pos - v.into()).len()))
+            .filter(|(_, d)| *d <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((v, _)) = vertex {
+            return Some(Snap {
+                pos: v,
+                target: SnapTarget::Vertex,
+            });
+        }
+
+        walls
+            .iter()
+            .map(|w| w.nearest_point(pos))
+            .map(|p| (p, (pos - p).len()))
+            .filter(|(_, d)| *d <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(p, _)| Snap {
+                pos: Vec2 {
+                    x: p.x.round() as i32,
+                    y: p.y.round() as i32,
+                },
+                target: SnapTarget::Wall,
+            })
+    }
+
     pub fn object_at(&self, pos: Vec2<f64>) -> Option<ObjectId> {
         for object in self.objects.iter() {
             if object.contains(pos) {
@@ -232,4 +332,845 @@ impl Dungeon {
     pub fn object_mut(&mut self, id: ObjectId) -> Option<&mut Object> {
         self.objects.iter_mut().find(|d| d.id == id)
     }
+
+    pub fn add_corridor(&mut self, mut corridor: Corridor) -> CorridorId {
+        let id = self.next_corridor_id();
+        corridor.id = id;
+        self.corridors.push(corridor);
+        id
+    }
+
+    fn next_corridor_id(&self) -> CorridorId {
+        self.corridors.iter().map(|c| c.id).max().unwrap_or(0) + 1
+    }
+
+    pub fn corridor(&self, id: CorridorId) -> Option<&Corridor> {
+        self.corridors.iter().find(|c| c.id == id)
+    }
+
+    pub fn corridor_mut(&mut self, id: CorridorId) -> Option<&mut Corridor> {
+        self.corridors.iter_mut().find(|c| c.id == id)
+    }
+
+    pub fn corridor_at(&self, pos: Vec2<f64>) -> Option<CorridorId> {
+        self.corridors
+            .iter()
+            .find(|c| c.contains_point(pos))
+            .map(|c| c.id)
+    }
+
+    pub fn remove_corridor(&mut self, corridor_id: CorridorId) {
+        let idx = self.corridors.iter().position(|c| c.id == corridor_id);
+        match idx {
+            Some(i) => {
+                self.corridors.remove(i);
+            }
+            None => {
+                println!("corridor Id not found for deletion")
+            }
+        };
+    }
+
+    /// shapes of all chambers, used to clip corridors
+    pub fn chamber_shapes(&self, include_hidden: bool) -> Vec<Shape> {
+        self.chambers
+            .iter()
+            .filter(|c| include_hidden || !c.hidden)
+            .map(|c| c.shape())
+            .collect()
+    }
+
+    /// outlines of all corridors except `skip`, used to open walls where corridors meet
+    pub fn corridor_outlines(&self, include_hidden: bool, skip: Option<CorridorId>) -> Vec<Shape> {
+        self.corridors
+            .iter()
+            .filter(|c| (include_hidden || !c.hidden) && Some(c.id) != skip)
+            .flat_map(|c| c.outline().iter().cloned())
+            .collect()
+    }
+
+    /// The entity at the position, as selected by clicking on it.
+    /// Objects are preferred over doors, doors over chambers and chambers over corridors.
+    pub fn entity_at(&self, pos: Vec2<f64>) -> Selection {
+        let mut hit = Selection::new();
+        if let Some(id) = self.object_at(pos) {
+            hit.objects.push(id);
+        } else if let Some(id) = self.door_at(pos) {
+            hit.doors.push(id);
+        } else if let Some(id) = self.chamber_at(pos) {
+            hit.chambers.push(id);
+        } else if let Some(id) = self.corridor_at(pos) {
+            hit.corridors.push(id);
+        }
+        hit
+    }
+
+    /// All entities lying completely inside of the area
+    pub fn entities_in(&self, area: BBox) -> Selection {
+        let inside = |p: Vec2<f64>| {
+            p.x >= area.min.x && p.y >= area.min.y && p.x <= area.max.x && p.y <= area.max.y
+        };
+        Selection {
+            chambers: self
+                .chambers
+                .iter()
+                .filter(|c| !c.walls().is_empty())
+                .filter(|c| {
+                    let bbox = c.bbox();
+                    inside(bbox.min) && inside(bbox.max)
+                })
+                .map(|c| c.id)
+                .collect(),
+            doors: self
+                .doors
+                .iter()
+                .filter(|d| inside(self.door_world_pos(d)))
+                .map(|d| d.id)
+                .collect(),
+            objects: self
+                .objects
+                .iter()
+                .filter(|o| {
+                    let bbox = o.bbox();
+                    inside(bbox.min) && inside(bbox.max)
+                })
+                .map(|o| o.id)
+                .collect(),
+            corridors: self
+                .corridors
+                .iter()
+                .filter(|c| !c.points().is_empty())
+                .filter(|c| c.points().iter().all(|p| inside((*p).into())))
+                .map(|c| c.id)
+                .collect(),
+        }
+    }
+
+    /// Moves the selected chambers, objects and corridors.
+    /// Doors and objects of moved chambers are moved with them,
+    /// objects moved on their own become part of the chamber they are moved into.
+    /// Returns the ids of all moved objects.
+    pub fn move_entities(&mut self, selection: &Selection, by: Vec2<i32>) -> Vec<ObjectId> {
+        for chamber in self.chambers.iter_mut() {
+            if selection.chambers.contains(&chamber.id) {
+                chamber.translate(by);
+            }
+        }
+        for corridor in self.corridors.iter_mut() {
+            if selection.corridors.contains(&corridor.id) {
+                corridor.translate(by);
+            }
+        }
+        let mut moved = vec![];
+        for obj in self.objects.iter_mut() {
+            let with_chamber = obj
+                .part_of
+                .map(|id| selection.chambers.contains(&id))
+                .unwrap_or(false);
+            if with_chamber || selection.objects.contains(&obj.id) {
+                obj.pos += by;
+                moved.push(obj.id);
+            }
+        }
+        let placed: Vec<(ObjectId, Option<ChamberId>)> = self
+            .objects
+            .iter()
+            .filter(|o| selection.objects.contains(&o.id))
+            .filter(|o| !o.part_of.is_some_and(|id| selection.chambers.contains(&id)))
+            .map(|o| (o.id, self.chamber_at(o.center())))
+            .collect();
+        for (object_id, part_of) in placed {
+            self.object_mut(object_id).unwrap().part_of = part_of;
+        }
+        moved
+    }
+
+    /// Wall segments of the chamber coinciding with walls of other chambers.
+    pub fn shared_walls(&self, chamber_id: ChamberId) -> Vec<SharedWall> {
+        let walls = match self.chamber(chamber_id) {
+            Some(chamber) => chamber.walls(),
+            None => return vec![],
+        };
+        let others: Vec<Wall> = self
+            .chambers
+            .iter()
+            .filter(|c| c.id != chamber_id)
+            .flat_map(|c| c.walls())
+            .collect();
+        let mut shared = vec![];
+        for wall in walls.iter() {
+            for other in others.iter() {
+                if let Some((from, to)) = wall.overlap(other, SHARED_WALL_TOLERANCE) {
+                    shared.push(SharedWall {
+                        wall: *wall,
+                        other: *other,
+                        from,
+                        to,
+                    });
+                }
+            }
+        }
+        shared
+    }
+
+    /// Walls of other chambers coinciding with the wall `wall_id` at `pos`.
+    pub fn shared_walls_at(
+        &self,
+        chamber_id: ChamberId,
+        wall_id: WallId,
+        pos: Vec2<f64>,
+    ) -> Vec<Wall> {
+        self.shared_walls(chamber_id)
+            .into_iter()
+            .filter(|s| s.wall.id == wall_id && on_segment(s.from, s.to, pos))
+            .map(|s| s.other)
+            .collect()
+    }
+
+    /// Other chambers sharing the wall of the door at the door position.
+    /// Doors on shared walls belong to all of these chambers.
+    pub fn door_shared_with(&self, door: &Door) -> Vec<ChamberId> {
+        let pos = self.door_world_pos(door);
+        let mut ids: Vec<ChamberId> = self
+            .shared_walls_at(door.part_of, door.on_wall, pos)
+            .iter()
+            .map(|w| w.chamber_id)
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Walls of other chambers which have to be split as well,
+    /// when the shared wall `wall_id` is split at `pos`.
+    pub fn linked_splits(
+        &self,
+        chamber_id: ChamberId,
+        wall_id: WallId,
+        pos: Vec2<i32>,
+    ) -> Vec<(ChamberId, WallId)> {
+        self.shared_walls_at(chamber_id, wall_id, pos.into())
+            .iter()
+            .filter(|w| w.p1 != pos && w.p2 != pos)
+            .map(|w| (w.chamber_id, w.id))
+            .collect()
+    }
+
+    /// Corners of other chambers which have to be removed as well,
+    /// when the `corner` of the chamber is removed.
+    /// Only corners connected by a shared wall are considered.
+    /// Each corner is returned as the wall ending in it, as used by `StateCommand::CollapseWall`.
+    pub fn linked_corners(
+        &self,
+        chamber_id: ChamberId,
+        corner: Vec2<i32>,
+    ) -> Vec<(ChamberId, WallId)> {
+        let mut linked: Vec<(ChamberId, WallId)> = vec![];
+        for shared in self.shared_walls(chamber_id) {
+            if shared.wall.p1 != corner && shared.wall.p2 != corner {
+                continue;
+            }
+            let other_id = shared.other.chamber_id;
+            if linked.iter().any(|(id, _)| *id == other_id) {
+                continue;
+            }
+            if let Some(other) = self.chamber(other_id) {
+                if let Some((w1, _)) = other.nearest_corner(corner.into()) {
+                    if w1.p2 == corner {
+                        linked.push((other_id, w1.id));
+                    }
+                }
+            }
+        }
+        linked
+    }
+
+    /// position of the door center in world coordinates
+    pub fn door_world_pos(&self, door: &Door) -> Vec2<f64> {
+        self.chamber(door.part_of)
+            .unwrap()
+            .wall(door.on_wall)
+            .unwrap()
+            .rel_to_world(door.position)
+    }
+
+    /// Chambers the corridor starts, ends or passes through
+    pub fn corridor_chambers(&self, corridor: &Corridor) -> Vec<ChamberId> {
+        self.chambers
+            .iter()
+            .filter(|c| {
+                corridor.points().iter().any(|p| {
+                    let p: Vec2<f64> = (*p).into();
+                    c.contains_point(p)
+                        || c.nearest_wall(p)
+                            .is_some_and(|w| w.distance(p) <= SHARED_WALL_TOLERANCE)
+                })
+            })
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// Other chambers connected to the chamber by a door or a corridor
+    pub fn connected_chambers(&self, chamber_id: ChamberId) -> Vec<ChamberId> {
+        let mut ids: Vec<ChamberId> = vec![];
+        for door in self.chamber_doors(chamber_id) {
+            ids.push(door.part_of);
+            ids.extend(door.leads_to);
+            ids.append(&mut self.door_shared_with(door));
+        }
+        for corridor in self.corridors.iter() {
+            let chambers = self.corridor_chambers(corridor);
+            if chambers.contains(&chamber_id) {
+                ids.extend(chambers);
+            }
+        }
+        ids.retain(|id| *id != chamber_id && self.chamber(*id).is_some());
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Chambers reachable from `start` passing at most `steps` doors or corridors,
+    /// in the order they are reached, starting with `start`
+    pub fn reachable_chambers(&self, start: ChamberId, steps: u32) -> Vec<ChamberId> {
+        if self.chamber(start).is_none() {
+            return vec![];
+        }
+        let mut reached = vec![start];
+        let mut front = vec![start];
+        for _ in 0..steps {
+            let mut next = vec![];
+            for id in front {
+                for other in self.connected_chambers(id) {
+                    if !reached.contains(&other) {
+                        reached.push(other);
+                        next.push(other);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            front = next;
+        }
+        reached
+    }
+
+    /// Copy of the dungeon with only the chambers `ids`, their doors and objects
+    /// and the corridors leading to them.
+    /// Doors of other chambers leading into the subset are moved onto the shared wall.
+    pub fn subset(&self, ids: &[ChamberId]) -> Dungeon {
+        Dungeon {
+            chambers: self
+                .chambers
+                .iter()
+                .filter(|c| ids.contains(&c.id))
+                .cloned()
+                .collect(),
+            doors: self
+                .doors
+                .iter()
+                .filter_map(|d| {
+                    if ids.contains(&d.part_of) {
+                        return Some(d.clone());
+                    }
+                    let chamber = self.chamber(d.leads_to.filter(|id| ids.contains(id))?)?;
+                    let pos = self
+                        .chamber(d.part_of)?
+                        .wall(d.on_wall)?
+                        .rel_to_world(d.position);
+                    let wall = chamber
+                        .nearest_wall(pos)
+                        .filter(|w| w.distance(pos) <= SHARED_WALL_TOLERANCE)?;
+                    Some(Door {
+                        part_of: chamber.id,
+                        leads_to: Some(d.part_of),
+                        on_wall: wall.id,
+                        position: wall.nearest_relative_pos(pos),
+                        ..d.clone()
+                    })
+                })
+                .collect(),
+            objects: self
+                .objects
+                .iter()
+                .filter(|o| o.part_of.is_some_and(|id| ids.contains(&id)))
+                .cloned()
+                .collect(),
+            corridors: self
+                .corridors
+                .iter()
+                .filter(|c| self.corridor_chambers(c).iter().any(|id| ids.contains(id)))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Replaces the chambers `old` by new shapes.
+    /// The shapes are assigned to the ids in `old` in order. Further shapes are added as new chambers,
+    /// copying the name and visibility of the first chamber. Chambers without a shape are removed,
+    /// their notes are appended to the notes of the first chamber.
+    ///
+    /// Doors and objects of the old chambers are moved to the chamber they are located in afterwards.
+    /// Doors no longer located on a wall are removed.
+    pub(crate) fn replace_chambers(
+        &mut self,
+        old: &[ChamberId],
+        shapes: &[Shape],
+    ) -> ChamberReplacement {
+        let mut result = ChamberReplacement::default();
+        if old.is_empty() {
+            return result;
+        }
+
+        // remember door locations before changing any geometry
+        let door_positions: Vec<(DoorId, Vec2<f64>)> = self
+            .doors
+            .iter()
+            .filter(|d| old.contains(&d.part_of) || d.leads_to.is_some_and(|c| old.contains(&c)))
+            .map(|d| (d.id, self.door_world_pos(d)))
+            .collect();
+
+        let template = self.chamber(old[0]).unwrap().clone();
+        let mut new_ids = vec![];
+        for (i, shape) in shapes.iter().enumerate() {
+            let chamber_id = match old.get(i) {
+                Some(id) => {
+                    result.modified.push(*id);
+                    *id
+                }
+                None => {
+                    let mut chamber = template.clone();
+                    chamber.notes = String::new();
+                    let id = self.add_chamber(chamber);
+                    result.added.push(id);
+                    id
+                }
+            };
+            self.chamber_mut(chamber_id).unwrap().set_shape(shape);
+            new_ids.push(chamber_id);
+        }
+        let removed: Vec<ChamberId> = old.iter().skip(shapes.len()).copied().collect();
+
+        for (door_id, pos) in door_positions {
+            let part_of = self.door(door_id).unwrap().part_of;
+            let target = new_ids
+                .iter()
+                .filter_map(|id| self.chamber(*id).unwrap().nearest_wall(pos))
+                .filter(|w| w.distance(pos) < DOOR_WALL_TOLERANCE)
+                .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)));
+            let door = self.door_mut(door_id).unwrap();
+            if old.contains(&part_of) {
+                match target {
+                    Some(wall) => {
+                        door.part_of = wall.chamber_id;
+                        door.on_wall = wall.id;
+                        door.position = wall.nearest_relative_pos(pos);
+                    }
+                    None => {
+                        result.removed_doors.push(door_id);
+                        continue;
+                    }
+                }
+            }
+            if let Some(leads_to) = door.leads_to {
+                if old.contains(&leads_to) {
+                    door.leads_to = match target {
+                        Some(wall) if wall.chamber_id != door.part_of => Some(wall.chamber_id),
+                        _ if removed.contains(&leads_to) => Some(new_ids[0]),
+                        _ => Some(leads_to),
+                    };
+                }
+            }
+            if door.leads_to == Some(door.part_of) {
+                door.leads_to = None;
+            }
+            result.modified_doors.push(door_id);
+        }
+        self.doors.retain(|d| !result.removed_doors.contains(&d.id));
+
+        let object_targets: Vec<(ObjectId, Option<ChamberId>)> = self
+            .objects
+            .iter()
+            .filter(|o| o.part_of.is_some_and(|c| old.contains(&c)))
+            .map(|o| {
+                let center = o.center();
+                let target = new_ids
+                    .iter()
+                    .find(|id| self.chamber(**id).unwrap().contains_point(center))
+                    .copied();
+                (o.id, target)
+            })
+            .collect();
+        for (object_id, target) in object_targets {
+            self.object_mut(object_id).unwrap().part_of = target;
+            result.modified_objects.push(object_id);
+        }
+
+        for chamber_id in removed.iter() {
+            let notes = self.chamber(*chamber_id).unwrap().notes.clone();
+            if notes.is_empty() {
+                continue;
+            }
+            let first = self.chamber_mut(old[0]).unwrap();
+            if !first.notes.is_empty() {
+                first.notes.push_str("\n\n");
+            }
+            first.notes.push_str(&notes);
+        }
+        self.chambers.retain(|c| !removed.contains(&c.id));
+        result.removed = removed;
+        result
+    }
+}
+
+/// checks if `p` lies on the segment from `a` to `b`
+fn on_segment(a: Vec2<f64>, b: Vec2<f64>, p: Vec2<f64>) -> bool {
+    let d = b - a;
+    let t = (p - a).dot(d) / d.sqr_len();
+    (0.0..=1.0).contains(&t) && (a + t * d - p).len() <= SHARED_WALL_TOLERANCE
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{
+        chamber::Chamber,
+        common::{BBox, Vec2},
+        corridor::Corridor,
+        door::Door,
+        geometry::{self, BooleanOp},
+        object::Object,
+        selection::Selection,
+    };
+
+    use super::{Dungeon, SnapTarget};
+
+    /// adds a rectangular chamber, shared by the tests of all modules
+    pub(crate) fn add_rect(dungeon: &mut Dungeon, x0: i32, y0: i32, x1: i32, y1: i32) -> u32 {
+        let id = dungeon.add_chamber(Chamber::new());
+        let chamber = dungeon.chamber_mut(id).unwrap();
+        chamber.append(Vec2 { x: x0, y: y0 });
+        chamber.append(Vec2 { x: x1, y: y0 });
+        chamber.append(Vec2 { x: x1, y: y1 });
+        chamber.append(Vec2 { x: x0, y: y1 });
+        id
+    }
+
+    #[test]
+    fn merge_moves_outer_doors_and_removes_inner_doors() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        // door between a and b (second wall of a is x = 100)
+        let inner = dungeon.add_door(Door::new(a, Some(b), 50.0, 2, 0.5));
+        // door on the right wall of b
+        let outer = dungeon.add_door(Door::new(b, None, 50.0, 2, 0.5));
+
+        let shapes = geometry::boolean(
+            &dungeon.chamber(a).unwrap().shape(),
+            &dungeon.chamber(b).unwrap().shape(),
+            BooleanOp::Union,
+        );
+        let r = dungeon.replace_chambers(&[a, b], &shapes);
+
+        assert_eq!(r.removed, vec![b]);
+        assert_eq!(r.removed_doors, vec![inner]);
+        assert!(dungeon.chamber(b).is_none());
+        assert!(dungeon.door(inner).is_none());
+        let door = dungeon.door(outer).unwrap();
+        assert_eq!(door.part_of, a);
+        assert_eq!(dungeon.door_world_pos(door), Vec2 { x: 200.0, y: 50.0 });
+    }
+
+    #[test]
+    fn merge_appends_notes() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        dungeon.chamber_mut(a).unwrap().notes = "Goblins".to_owned();
+        dungeon.chamber_mut(b).unwrap().notes = "Treasure".to_owned();
+        let shapes = geometry::boolean(
+            &dungeon.chamber(a).unwrap().shape(),
+            &dungeon.chamber(b).unwrap().shape(),
+            BooleanOp::Union,
+        );
+        dungeon.replace_chambers(&[a, b], &shapes);
+        assert_eq!(dungeon.chamber(a).unwrap().notes, "Goblins\n\nTreasure");
+    }
+
+    #[test]
+    fn split_adds_chamber() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 200, 100);
+        let (left, right) = geometry::split(
+            &dungeon.chamber(a).unwrap().shape(),
+            Vec2 { x: 100, y: 0 },
+            Vec2 { x: 100, y: 100 },
+        )
+        .unwrap();
+        let r = dungeon.replace_chambers(&[a], &[left, right]);
+        assert_eq!(r.added.len(), 1);
+        assert_eq!(dungeon.chambers().len(), 2);
+    }
+
+    #[test]
+    fn subtract_inner_chamber_creates_hole() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 300, 300);
+        let b = add_rect(&mut dungeon, 100, 100, 200, 200);
+        let shapes = geometry::boolean(
+            &dungeon.chamber(a).unwrap().shape(),
+            &dungeon.chamber(b).unwrap().shape(),
+            BooleanOp::Difference,
+        );
+        dungeon.replace_chambers(&[a], &shapes);
+        let chamber = dungeon.chamber(a).unwrap();
+        assert_eq!(chamber.holes().len(), 1);
+        assert_eq!(chamber.walls().len(), 8);
+        // doors can be placed on the walls of the hole
+        let (chamber_id, wall) = dungeon.nearest_wall(Vec2 { x: 150.0, y: 95.0 }).unwrap();
+        assert_eq!(chamber_id, a);
+        assert!(chamber.holes()[0].iter().any(|w| w.id == wall.id));
+    }
+
+    #[test]
+    fn snap_prefers_vertices_over_walls() {
+        let mut dungeon = Dungeon::new();
+        add_rect(&mut dungeon, 0, 0, 100, 100);
+        let snap = dungeon
+            .snap_to_walls(Vec2 { x: 103.0, y: 96.0 }, 10.0, None)
+            .unwrap();
+        assert_eq!(snap.pos, Vec2 { x: 100, y: 100 });
+        assert_eq!(snap.target, SnapTarget::Vertex);
+    }
+
+    #[test]
+    fn snap_to_nearest_point_on_wall() {
+        let mut dungeon = Dungeon::new();
+        add_rect(&mut dungeon, 0, 0, 100, 100);
+        let snap = dungeon
+            .snap_to_walls(Vec2 { x: 104.0, y: 37.0 }, 10.0, None)
+            .unwrap();
+        assert_eq!(snap.pos, Vec2 { x: 100, y: 37 });
+        assert_eq!(snap.target, SnapTarget::Wall);
+    }
+
+    #[test]
+    fn snap_ignores_far_and_skipped_chambers() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        assert!(dungeon
+            .snap_to_walls(Vec2 { x: 120.0, y: 50.0 }, 10.0, None)
+            .is_none());
+        assert!(dungeon
+            .snap_to_walls(Vec2 { x: 104.0, y: 50.0 }, 10.0, Some(a))
+            .is_none());
+    }
+
+    #[test]
+    fn shared_walls_of_adjacent_chambers() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 50, 200, 150);
+        let shared = dungeon.shared_walls(a);
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].wall.id, 2);
+        assert_eq!(shared[0].other.chamber_id, b);
+        assert_eq!(shared[0].from, Vec2 { x: 100.0, y: 50.0 });
+        assert_eq!(shared[0].to, Vec2 { x: 100.0, y: 100.0 });
+    }
+
+    #[test]
+    fn door_on_shared_wall_belongs_to_both_chambers() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 50, 200, 150);
+        let shared = dungeon.add_door(Door::new(a, None, 20.0, 2, 0.75));
+        let outside = dungeon.add_door(Door::new(a, None, 20.0, 2, 0.25));
+
+        let door = dungeon.door(shared).unwrap();
+        assert_eq!(dungeon.door_shared_with(door), vec![b]);
+        let door = dungeon.door(outside).unwrap();
+        assert!(dungeon.door_shared_with(door).is_empty());
+
+        let b_doors: Vec<_> = dungeon.chamber_doors(b).iter().map(|d| d.id).collect();
+        assert_eq!(b_doors, vec![shared]);
+        assert_eq!(dungeon.chamber_doors(a).len(), 2);
+    }
+
+    #[test]
+    fn linked_splits_and_corners() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        // wall 2 of a is x = 100, wall 4 of b is x = 100 (reversed)
+        assert_eq!(
+            dungeon.linked_splits(a, 2, Vec2 { x: 100, y: 40 }),
+            vec![(b, 4)]
+        );
+        // splitting at an existing corner of b does not split b
+        assert!(dungeon
+            .linked_splits(a, 2, Vec2 { x: 100, y: 100 })
+            .is_empty());
+
+        dungeon
+            .chamber_mut(a)
+            .unwrap()
+            .split(2, Vec2 { x: 100, y: 40 });
+        dungeon
+            .chamber_mut(b)
+            .unwrap()
+            .split(4, Vec2 { x: 100, y: 40 });
+        let linked = dungeon.linked_corners(a, Vec2 { x: 100, y: 40 });
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].0, b);
+        let wall = *dungeon.chamber(b).unwrap().wall(linked[0].1).unwrap();
+        assert_eq!(wall.p2, Vec2 { x: 100, y: 40 });
+    }
+
+    #[test]
+    fn entities_in_area() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        add_rect(&mut dungeon, 100, 0, 300, 100);
+        let door = dungeon.add_door(Door::new(a, None, 20.0, 4, 0.5));
+        let obj = dungeon.add_object(Object::new(Vec2 { x: 50, y: 50 }, Some(a)));
+
+        let mut area = BBox::new();
+        area += Vec2 { x: -10.0, y: -10.0 };
+        area += Vec2 { x: 150.0, y: 110.0 };
+        let found = dungeon.entities_in(area);
+        assert_eq!(found.chambers, vec![a]);
+        assert_eq!(found.doors, vec![door]);
+        assert_eq!(found.objects, vec![obj]);
+        assert!(found.corridors.is_empty());
+    }
+
+    #[test]
+    fn move_chamber_with_its_objects() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 200, 0, 300, 100);
+        let inside = dungeon.add_object(Object::new(Vec2 { x: 50, y: 50 }, Some(a)));
+        let other = dungeon.add_object(Object::new(Vec2 { x: 250, y: 50 }, Some(b)));
+
+        let selection = Selection {
+            chambers: vec![a],
+            ..Selection::new()
+        };
+        let moved = dungeon.move_entities(&selection, Vec2 { x: 0, y: 50 });
+        assert_eq!(moved, vec![inside]);
+        assert_eq!(
+            dungeon.chamber(a).unwrap().wall(1).unwrap().p1,
+            Vec2 { x: 0, y: 50 }
+        );
+        assert_eq!(
+            dungeon.chamber(b).unwrap().wall(1).unwrap().p1,
+            Vec2 { x: 200, y: 0 }
+        );
+        assert_eq!(dungeon.object(inside).unwrap().pos, Vec2 { x: 50, y: 100 });
+        assert_eq!(dungeon.object(other).unwrap().pos, Vec2 { x: 250, y: 50 });
+    }
+
+    #[test]
+    fn reachable_chambers() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        let c = add_rect(&mut dungeon, 300, 0, 400, 100);
+        let d = add_rect(&mut dungeon, 500, 0, 600, 100);
+        // a and b share a door, b and c are joined by a corridor, d is unconnected
+        dungeon.add_door(Door::new(a, Some(b), 50.0, 2, 0.5));
+        let mut corridor = Corridor::new();
+        corridor.append(Vec2 { x: 150, y: 50 });
+        corridor.append(Vec2 { x: 350, y: 50 });
+        dungeon.add_corridor(corridor);
+
+        assert_eq!(dungeon.connected_chambers(b), vec![a, c]);
+        assert_eq!(dungeon.reachable_chambers(a, 0), vec![a]);
+        assert_eq!(dungeon.reachable_chambers(a, 1), vec![a, b]);
+        assert_eq!(dungeon.reachable_chambers(a, 5), vec![a, b, c]);
+        assert_eq!(dungeon.reachable_chambers(d, 5), vec![d]);
+        assert!(dungeon.reachable_chambers(42, 1).is_empty());
+    }
+
+    #[test]
+    fn subset_keeps_doors_objects_and_corridors_of_chambers() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        let c = add_rect(&mut dungeon, 300, 0, 400, 100);
+        let door = dungeon.add_door(Door::new(a, Some(b), 50.0, 2, 0.5));
+        dungeon.add_door(Door::new(b, None, 50.0, 1, 0.5));
+        let obj = dungeon.add_object(Object::new(Vec2 { x: 50, y: 50 }, Some(a)));
+        dungeon.add_object(Object::new(Vec2 { x: 350, y: 50 }, Some(c)));
+        let mut corridor = Corridor::new();
+        corridor.append(Vec2 { x: 150, y: 50 });
+        corridor.append(Vec2 { x: 350, y: 50 });
+        dungeon.add_corridor(corridor);
+
+        let subset = dungeon.subset(&[a]);
+        assert_eq!(subset.chambers().len(), 1);
+        assert_eq!(
+            subset.doors.iter().map(|d| d.id).collect::<Vec<_>>(),
+            vec![door]
+        );
+        assert_eq!(
+            subset.objects.iter().map(|o| o.id).collect::<Vec<_>>(),
+            vec![obj]
+        );
+        assert!(subset.corridors.is_empty());
+        assert_eq!(dungeon.subset(&[c]).corridors.len(), 1);
+    }
+
+    #[test]
+    fn subset_keeps_doors_leading_into_it() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        let c = add_rect(&mut dungeon, 300, 0, 400, 100);
+        // on the left wall of b, shared with a
+        let wall = dungeon.chamber(b).unwrap().walls()[3];
+        let door = dungeon.add_door(Door::new(b, Some(a), 50.0, wall.id, 0.25));
+        // c does not share a wall with a
+        let wall = dungeon.chamber(c).unwrap().walls()[3];
+        dungeon.add_door(Door::new(c, Some(a), 50.0, wall.id, 0.5));
+
+        let subset = dungeon.subset(&[a]);
+        assert_eq!(subset.doors.len(), 1);
+        let moved = &subset.doors[0];
+        assert_eq!(moved.id, door);
+        assert_eq!((moved.part_of, moved.leads_to), (a, Some(b)));
+        let pos = |dungeon: &Dungeon, door: &Door| {
+            let wall = *dungeon
+                .chamber(door.part_of)
+                .unwrap()
+                .wall(door.on_wall)
+                .unwrap();
+            wall.rel_to_world(door.position)
+        };
+        let old_pos = pos(&dungeon, dungeon.door(door).unwrap());
+        let new_pos = pos(&subset, moved);
+        assert!((new_pos.x - old_pos.x).abs() < 1e-9 && (new_pos.y - old_pos.y).abs() < 1e-9);
+        assert_eq!(new_pos.x, 100.0);
+    }
+
+    #[test]
+    fn moved_object_changes_chamber() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 200, 0, 300, 100);
+        let obj = dungeon.add_object(Object::new(Vec2 { x: 50, y: 50 }, Some(a)));
+
+        let selection = Selection {
+            objects: vec![obj],
+            ..Selection::new()
+        };
+        dungeon.move_entities(&selection, Vec2 { x: 200, y: 0 });
+        assert_eq!(dungeon.object(obj).unwrap().part_of, Some(b));
+
+        dungeon.move_entities(&selection, Vec2 { x: 0, y: 200 });
+        assert_eq!(dungeon.object(obj).unwrap().part_of, None);
+    }
 }
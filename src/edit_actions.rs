@@ -1,10 +1,16 @@
+use crate::common::Vec2;
+use crate::config::GRID_SIZE;
+use crate::generator::{generate, GeneratorSettings, GeneratorStyle};
 use crate::observers::HistoryObserver;
+use crate::state::events::StateEvent;
 use crate::state::{EditMode, StateCommand, StateController};
 use cairo::glib::clone;
 use gtk::gio::{ActionEntry, SimpleActionGroup};
 use gtk::prelude::*;
+use gtk::{Button, DropDown, Entry, Label, MessageDialog, SpinButton};
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn change_mode_action(
     control: Rc<RefCell<StateController>>,
@@ -20,6 +26,110 @@ fn change_mode_action(
         .build()
 }
 
+/// asks for a name pattern and renames all selected entities
+fn rename_dialog(control: Rc<RefCell<StateController>>) {
+    let selection = control.borrow().state.selected();
+    if selection.is_empty() {
+        return;
+    }
+    let dialog = MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .buttons(gtk::ButtonsType::OkCancel)
+        .text(format!("Rename {} selected entities", selection.len()))
+        .secondary_text("{n} is replaced by a running number, {name} by the current name.")
+        .modal(true)
+        .build();
+    let entry = Entry::builder().text("{name} {n}").build();
+    dialog
+        .message_area()
+        .downcast::<gtk::Box>()
+        .unwrap()
+        .append(&entry);
+    dialog.connect_response(clone!(@strong control => move |dialog, r| {
+        if r == gtk::ResponseType::Ok {
+            let pattern = entry.text().to_string();
+            control
+                .borrow_mut()
+                .apply(StateCommand::RenameEntities(selection.clone(), pattern));
+        }
+        dialog.close();
+    }));
+    dialog.show();
+}
+
+/// asks for the generator settings and adds a generated dungeon at the top left of the view
+fn generate_dialog(control: Rc<RefCell<StateController>>) {
+    let defaults = GeneratorSettings::new();
+    let dialog = MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .buttons(gtk::ButtonsType::OkCancel)
+        .text("Generate Dungeon")
+        .secondary_text("The same settings and seed always generate the same dungeon.")
+        .modal(true)
+        .build();
+
+    let styles = [GeneratorStyle::Rooms, GeneratorStyle::Caves];
+    let style = DropDown::from_strings(&styles.map(|s| s.to_str()));
+    let width = SpinButton::with_range(10.0, 200.0, 1.0);
+    width.set_value(defaults.size.x as f64);
+    let height = SpinButton::with_range(10.0, 200.0, 1.0);
+    height.set_value(defaults.size.y as f64);
+    let rooms = SpinButton::with_range(1.0, 100.0, 1.0);
+    rooms.set_value(defaults.rooms as f64);
+    let seed = SpinButton::with_range(0.0, u32::MAX as f64, 1.0);
+    seed.set_value(defaults.seed as f64);
+    let random_seed = Button::builder().label("Random Seed").build();
+    random_seed.connect_clicked(clone!(@strong seed => move |_| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        seed.set_value(nanos as f64);
+    }));
+    let loopiness = SpinButton::with_range(0.0, 1.0, 0.05);
+    loopiness.set_digits(2);
+    loopiness.set_value(defaults.loopiness);
+
+    let area = dialog.message_area().downcast::<gtk::Box>().unwrap();
+    area.append(&Label::new(Some("Style")));
+    area.append(&style);
+    area.append(&Label::new(Some("Width (cells)")));
+    area.append(&width);
+    area.append(&Label::new(Some("Height (cells)")));
+    area.append(&height);
+    area.append(&Label::new(Some("Rooms")));
+    area.append(&rooms);
+    area.append(&Label::new(Some("Seed")));
+    area.append(&seed);
+    area.append(&random_seed);
+    area.append(&Label::new(Some("Loopiness")));
+    area.append(&loopiness);
+
+    dialog.connect_response(clone!(@strong control => move |dialog, r| {
+        if r == gtk::ResponseType::Ok {
+            let settings = GeneratorSettings {
+                style: styles[style.selected() as usize],
+                size: Vec2 {
+                    x: width.value_as_int(),
+                    y: height.value_as_int(),
+                },
+                rooms: rooms.value_as_int() as u32,
+                seed: seed.value() as u64,
+                loopiness: loopiness.value(),
+            };
+            let world_min = control.borrow().state.view.world_min();
+            let origin = Vec2 {
+                x: (world_min.x.div_euclid(GRID_SIZE) + 1) * GRID_SIZE,
+                y: (world_min.y.div_euclid(GRID_SIZE) + 1) * GRID_SIZE,
+            };
+            let cmds = generate(control.borrow().dungeon(), &settings, origin);
+            control.borrow_mut().apply_group(cmds);
+        }
+        dialog.close();
+    }));
+    dialog.show();
+}
+
 pub fn edit_actions(
     control: Rc<RefCell<StateController>>,
     history: Rc<RefCell<HistoryObserver>>,
@@ -38,7 +148,10 @@ pub fn edit_actions(
     let edit_action_undo = ActionEntry::builder("undo")
         .activate(
             clone!(@strong control, @strong history => move |_window: &SimpleActionGroup, _, _| {
+                // the session is not part of the history
+                let session = control.borrow().state.session.clone();
                 control.borrow_mut().reset();
+                control.borrow_mut().state.session = session;
                 let cmds = {
                     history.borrow_mut().undo();
                     history.borrow_mut().get_stack()
@@ -48,6 +161,9 @@ pub fn edit_actions(
                     control.borrow_mut().apply_silent(cmd)
                 }
                 control.borrow_mut().reload();
+                history
+                    .borrow_mut()
+                    .set_session(control.borrow().state.session.clone());
             }),
         )
         .build();
@@ -56,6 +172,11 @@ pub fn edit_actions(
         .activate(
             clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
                 let mut control = control.borrow_mut();
+                if !control.state.selection.is_empty() {
+                    let selection = control.state.selection.clone();
+                    control.apply(StateCommand::DeleteEntities(selection));
+                    return;
+                }
                 if let Some(door_id) = control.state.active_door_id {
                     control
                         .apply(StateCommand::DeleteDoor(door_id))
@@ -68,6 +189,10 @@ pub fn edit_actions(
                     control
                         .apply(StateCommand::DeleteObject(object_id))
                 }
+                if let Some(corridor_id) = control.state.active_corridor_id {
+                    control
+                        .apply(StateCommand::DeleteCorridor(corridor_id))
+                }
             }),
         )
         .build();
@@ -80,11 +205,127 @@ pub fn edit_actions(
         )
         .build();
 
+    let edit_action_add_corridor = ActionEntry::builder("add_corridor")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                control.borrow_mut().apply(StateCommand::AddCorridor);
+            }),
+        )
+        .build();
+
+    let edit_action_hide_selected = ActionEntry::builder("hide_selected")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                let mut control = control.borrow_mut();
+                let selection = control.state.selected();
+                if !selection.is_empty() {
+                    control.apply(StateCommand::ChangeEntitiesHidden(selection, true));
+                }
+            }),
+        )
+        .build();
+
+    let edit_action_show_selected = ActionEntry::builder("show_selected")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                let mut control = control.borrow_mut();
+                let selection = control.state.selected();
+                if !selection.is_empty() {
+                    control.apply(StateCommand::ChangeEntitiesHidden(selection, false));
+                }
+            }),
+        )
+        .build();
+
+    let edit_action_reveal_selected = ActionEntry::builder("reveal_selected")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                let mut control = control.borrow_mut();
+                let selection = control.state.selected();
+                if !selection.is_empty() {
+                    control.apply(StateCommand::ChangeEntitiesRevealed(selection, true));
+                }
+            }),
+        )
+        .build();
+
+    let edit_action_unreveal_selected = ActionEntry::builder("unreveal_selected")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                let mut control = control.borrow_mut();
+                let selection = control.state.selected();
+                if !selection.is_empty() {
+                    control.apply(StateCommand::ChangeEntitiesRevealed(selection, false));
+                }
+            }),
+        )
+        .build();
+
+    let edit_action_rename_selected = ActionEntry::builder("rename_selected")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                rename_dialog(control.clone());
+            }),
+        )
+        .build();
+
+    let edit_action_generate = ActionEntry::builder("generate")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                generate_dialog(control.clone());
+            }),
+        )
+        .build();
+
+    let edit_action_link_shared_walls = ActionEntry::builder("link_shared_walls")
+        .state(false.to_variant())
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, action, _| {
+                let link = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+                action.set_state(&link.to_variant());
+                control.borrow_mut().state.link_shared_walls = link;
+            }),
+        )
+        .build();
+
+    let edit_action_session_view = ActionEntry::builder("session_view")
+        .state(false.to_variant())
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, action, _| {
+                let show = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+                action.set_state(&show.to_variant());
+                let mut control = control.borrow_mut();
+                control.state.session_view = show;
+                control.notify(StateEvent::SessionChanged);
+            }),
+        )
+        .build();
+
+    let edit_action_hide_changes = ActionEntry::builder("hide_changes")
+        .activate(
+            clone!(@strong control => move |_window: &SimpleActionGroup, _, _| {
+                let mut control = control.borrow_mut();
+                control.state.set_compare(None);
+                control.notify(StateEvent::CompareChanged);
+            }),
+        )
+        .build();
+
     edit_actions.add_action_entries([
         edit_action_unselect,
         edit_action_undo,
         edit_action_delete,
         edit_action_add_chamber,
+        edit_action_add_corridor,
+        edit_action_link_shared_walls,
+        edit_action_generate,
+        edit_action_session_view,
+        edit_action_hide_changes,
+        edit_action_hide_selected,
+        edit_action_show_selected,
+        edit_action_rename_selected,
+        edit_action_reveal_selected,
+        edit_action_unreveal_selected,
         change_mode_action(control.clone(), EditMode::Select, "mode_select"),
         change_mode_action(
             control.clone(),
@@ -93,6 +334,26 @@ pub fn edit_actions(
         ),
         change_mode_action(control.clone(), EditMode::SplitEdge, "mode_split_edge"),
         change_mode_action(control.clone(), EditMode::AddDoor, "mode_add_door"),
+        change_mode_action(
+            control.clone(),
+            EditMode::MergeChamber,
+            "mode_merge_chamber",
+        ),
+        change_mode_action(
+            control.clone(),
+            EditMode::SubtractChamber,
+            "mode_subtract_chamber",
+        ),
+        change_mode_action(
+            control.clone(),
+            EditMode::SplitChamber,
+            "mode_split_chamber",
+        ),
+        change_mode_action(
+            control.clone(),
+            EditMode::AppendCorridor,
+            "mode_append_corridor",
+        ),
     ]);
 
     edit_actions
@@ -0,0 +1,150 @@
+use crate::{
+    chamber::{ChamberId, Wall},
+    common::Vec2,
+    door::{Door, DoorId},
+    dungeon::Dungeon,
+    object::{Object, ObjectId},
+    state::{events::StateEvent, EditMode, State, StateCommand},
+};
+
+/// Applies generated commands to a copy of the dungeon to learn the ids they create.
+/// Used by the generator and to place templates and imports.
+pub(crate) struct Emitter {
+    pub state: State,
+    pub cmds: Vec<StateCommand>,
+}
+
+impl Emitter {
+    pub fn new(dungeon: &Dungeon) -> Self {
+        let mut state = State::new();
+        state.dungeon = dungeon.clone();
+        Self {
+            state,
+            cmds: vec![],
+        }
+    }
+
+    pub fn apply(&mut self, cmd: StateCommand) -> Vec<StateEvent> {
+        let events = cmd.execute(&mut self.state);
+        self.cmds.push(cmd);
+        events
+    }
+
+    /// adds a chamber with the outer ring and holes, returns its id
+    pub fn add_chamber(&mut self, outer: &[Vec2<i32>], holes: &[Vec<Vec2<i32>>]) -> ChamberId {
+        let chamber_id = self.add_outline(outer);
+        for hole in holes.iter() {
+            let hole_id = self.add_outline(hole);
+            self.apply(StateCommand::SubtractChamber(chamber_id, hole_id));
+            self.apply(StateCommand::DeleteChamber(hole_id));
+        }
+        chamber_id
+    }
+
+    fn add_outline(&mut self, vertices: &[Vec2<i32>]) -> ChamberId {
+        self.apply(StateCommand::AddChamber);
+        let chamber_id = self.state.active_chamber_id.unwrap();
+        for v in vertices {
+            self.apply(StateCommand::AddVertexToChamber(chamber_id, *v));
+        }
+        chamber_id
+    }
+
+    /// wall of the chamber the position lies on
+    pub fn wall_at(&self, chamber_id: ChamberId, pos: Vec2<f64>) -> Option<Wall> {
+        self.state
+            .dungeon
+            .chamber(chamber_id)?
+            .nearest_wall(pos)
+            .filter(|w| w.distance(pos) < 1.0)
+    }
+
+    /// Adds a door, returns its id.
+    /// `AddDoor` only stores the position of the door when saved,
+    /// so name, notes, read-aloud text, visibility and target are set by their own commands.
+    pub fn add_door(&mut self, door: Door) -> Option<DoorId> {
+        let events = self.apply(StateCommand::AddDoor(Door::new(
+            door.part_of,
+            None,
+            door.width,
+            door.on_wall,
+            door.position,
+        )));
+        let door_id = events.iter().find_map(|e| match e {
+            StateEvent::DoorAdded(id) => Some(*id),
+            _ => None,
+        })?;
+        if !door.name.is_empty() {
+            self.apply(StateCommand::ChangeDoorName(door_id, door.name.clone()));
+        }
+        if !door.notes.is_empty() {
+            self.apply(StateCommand::ChangeDoorNotes(door_id, door.notes.clone()));
+        }
+        if !door.read_aloud.is_empty() {
+            self.apply(StateCommand::ChangeDoorReadAloud(
+                door_id,
+                door.read_aloud.clone(),
+            ));
+        }
+        if door.hidden {
+            self.apply(StateCommand::ChangeDoorHidden(door_id, true));
+        }
+        let leads_to = self.state.dungeon.door(door_id)?.leads_to;
+        if door.leads_to.is_some() && door.leads_to != leads_to {
+            self.apply(StateCommand::ChangeDoorLeadsTo(door_id, door.leads_to));
+        }
+        Some(door_id)
+    }
+
+    /// Adds an object, returns its id.
+    /// Like doors, the properties of the object are set by their own commands.
+    pub fn add_object(&mut self, object: &Object) -> Option<ObjectId> {
+        let events = self.apply(StateCommand::AddObject(object.pos, object.part_of));
+        let object_id = events.iter().find_map(|e| match e {
+            StateEvent::ObjectAdded(id) => Some(*id),
+            _ => None,
+        })?;
+        self.apply(StateCommand::ChangeObjectStyle(
+            object_id,
+            object.style.clone(),
+        ));
+        if object.size != (Vec2 { x: 1, y: 1 }) {
+            self.apply(StateCommand::ChangeObjectSize(object_id, object.size));
+        }
+        if object.rotation != 0.0 {
+            self.apply(StateCommand::ChangeObjectRotation(
+                object_id,
+                object.rotation,
+            ));
+        }
+        if !object.name.is_empty() {
+            self.apply(StateCommand::ChangeObjectName(
+                object_id,
+                object.name.clone(),
+            ));
+        }
+        if !object.notes.is_empty() {
+            self.apply(StateCommand::ChangeObjectNotes(
+                object_id,
+                object.notes.clone(),
+            ));
+        }
+        if !object.read_aloud.is_empty() {
+            self.apply(StateCommand::ChangeObjectReadAloud(
+                object_id,
+                object.read_aloud.clone(),
+            ));
+        }
+        if object.hidden {
+            self.apply(StateCommand::ChangeObjectHidden(object_id, true));
+        }
+        Some(object_id)
+    }
+
+    /// leaves the editor in select mode without active entity
+    pub fn finish(mut self) -> Vec<StateCommand> {
+        self.apply(StateCommand::SelectChamber(None));
+        self.apply(StateCommand::ChangeMode(EditMode::Select));
+        self.cmds
+    }
+}
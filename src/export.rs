@@ -1,36 +1,67 @@
-use std::f64;
+use std::{cell::RefCell, collections::HashMap, f64, rc::Rc};
 
-use cairo::Context;
+use cairo::{
+    Content, Context, FillRule, FontSlant, FontWeight, LineJoin, Operator, Pattern, PdfOutline,
+    PdfSurface, RecordingSurface, Rectangle, CAIRO_TAG_DEST, CAIRO_TAG_LINK, PDF_OUTLINE_ROOT,
+};
 use pango::ffi::PANGO_SCALE;
 use pangocairo::functions::{show_layout, show_layout_line};
 
 use crate::{
-    chamber::{Chamber, ChamberDrawOptions},
+    chamber::{Chamber, ChamberDrawOptions, ChamberId},
     common::{BBox, Rgb, Vec2},
+    corridor::{Corridor, CorridorDrawOptions},
     door::{Door, DoorDrawOptions},
     dungeon::Dungeon,
+    export_settings::ExportSettings,
+    geometry::Shape,
+    markdown::{self, Block},
     object::{Object, ObjectDrawOptions},
+    packing::{pack, Piece},
+    print_theme::{GridStyle, PrintTheme, ThemeStyle},
+    selection::Selection,
     view::{grid::Grid, primitives::Primitive},
 };
 
-const PAGE_W: f64 = 595.0;
-const PAGE_H: f64 = 842.0;
-const EDGE_SPACING: f64 = 15.0;
 const SIZE_PAGE_NUMBER: f64 = 12.0;
-const HEIGHT_PAGE_NUMBER: f64 = PAGE_H - EDGE_SPACING * 2.0;
-const START_H: f64 = EDGE_SPACING * 2.0;
-const END_H: f64 = PAGE_H - (EDGE_SPACING * 2.0) - SIZE_PAGE_NUMBER;
-const LEFT_SPACE: f64 = EDGE_SPACING;
-const RIGHT_END: f64 = PAGE_W - EDGE_SPACING;
-const TEXT_WIDTH: f64 = RIGHT_END - LEFT_SPACE;
 const HEADLINE_IMAGE_SPACING: f64 = 12.0;
 const IMAGE_NOTES_SPACEING: f64 = 24.0;
 const TITLE_SPACING: f64 = 16.0;
 const TEXT_SPACING: f64 = 12.0;
 const IMAGE_SIZE: f64 = 120.0;
 
-const TEXT_FONT_SIZE: i32 = 10;
+/// font size the sizes of titles and headlines are given for
+const DEFAULT_FONT_SIZE: f64 = 10.0;
 const TEXT_LINE_SPACING: f64 = 1.5;
+/// indentation per level of lists, at the default font size
+const LIST_INDENT: f64 = 16.0;
+const LIST_MARKER_SPACING: f64 = 4.0;
+/// indentation of quotes, at the default font size
+const QUOTE_INDENT: f64 = 12.0;
+const TABLE_PADDING: f64 = 3.0;
+/// radius of the dots of `GridStyle::Dots` in world units
+const GRID_DOT_RADIUS: f64 = 2.5;
+/// distance of the hatching lines in world units
+const HATCH_SPACING: f64 = 8.0;
+/// width of the hatching outside of the walls in world units
+const HATCH_WIDTH: f64 = 20.0;
+/// space between the border of a read-aloud box and its text
+const READ_ALOUD_PADDING: f64 = 6.0;
+/// depth of the fold-over tabs at the doors of the cutouts, in world units
+const TAB_DEPTH: f64 = 20.0;
+/// space between the cutouts on a page
+const CUTOUT_SPACING: f64 = 12.0;
+
+/// size around chamber and corridor numbers on the GM map linking to their notes, in world units
+const NUMBER_LINK_SIZE: f64 = 25.0;
+
+/// named destinations of the GM PDF besides chambers and corridors
+const DEST_MAP: &str = "map";
+const DEST_CONTENTS: &str = "contents";
+const DEST_DUNGEON: &str = "dungeon";
+const DEST_INDEX: &str = "index";
+const DEST_HIDDEN: &str = "hidden";
+const DEST_DOORS: &str = "doors";
 
 const HEADLINE_COLOR: Rgb = Rgb {
     r: 0.0,
@@ -42,121 +73,278 @@ const NOTES_COLOR: Rgb = Rgb {
     g: 0.0,
     b: 0.0,
 };
+const LINK_COLOR: Rgb = Rgb {
+    r: 0.0,
+    g: 0.2,
+    b: 0.6,
+};
+const READ_ALOUD_COLOR: Rgb = Rgb {
+    r: 0.9,
+    g: 0.9,
+    b: 0.9,
+};
 
-fn title_font() -> pango::FontDescription {
-    let mut font = pango::FontDescription::default();
-    font.set_size(24 * PANGO_SCALE);
-    font.set_weight(pango::Weight::Bold);
-    font
+/// Size, margins and font size of the exported pages, see `ExportSettings`
+#[derive(Clone, Copy)]
+struct Page {
+    width: f64,
+    height: f64,
+    margin: f64,
+    font_size: f64,
+    style: ThemeStyle,
 }
 
-fn text_font() -> pango::FontDescription {
-    let mut font = pango::FontDescription::default();
-    font.set_size(TEXT_FONT_SIZE * PANGO_SCALE);
-    font
+impl Page {
+    fn new(settings: &ExportSettings, size: Vec2<f64>) -> Self {
+        Self {
+            width: size.x,
+            height: size.y,
+            margin: settings.margin_pt(),
+            font_size: settings.font_size,
+            style: settings.theme.style(),
+        }
+    }
+
+    /// factor of the font size to the default font size
+    fn font_scale(&self) -> f64 {
+        self.font_size / DEFAULT_FONT_SIZE
+    }
+
+    fn size(&self) -> Vec2<f64> {
+        Vec2 {
+            x: self.width,
+            y: self.height,
+        }
+    }
+
+    fn start_h(&self) -> f64 {
+        self.margin * 2.0
+    }
+
+    /// end of the content, above the page number
+    fn end_h(&self) -> f64 {
+        self.height - self.margin * 2.0 - SIZE_PAGE_NUMBER * self.font_scale()
+    }
+
+    fn page_number_h(&self) -> f64 {
+        self.height - self.margin * 2.0
+    }
+
+    fn left(&self) -> f64 {
+        self.margin
+    }
+
+    fn right_end(&self) -> f64 {
+        self.width - self.margin
+    }
+
+    fn text_width(&self) -> f64 {
+        self.right_end() - self.left()
+    }
+
+    /// font of the given size at the default font size
+    fn font(&self, size: f64, bold: bool) -> pango::FontDescription {
+        let mut font = pango::FontDescription::default();
+        font.set_family(self.style.font);
+        font.set_size((size * self.font_scale() * PANGO_SCALE as f64) as i32);
+        if bold {
+            font.set_weight(pango::Weight::Bold);
+        }
+        font
+    }
+
+    fn layout(&self, font: pango::FontDescription) -> (pango::Context, pango::Layout) {
+        let p_ctx = pango::Context::new();
+        p_ctx.set_font_map(Some(&pangocairo::FontMap::default()));
+        let layout = pango::Layout::new(&p_ctx);
+        layout.set_width(self.text_width() as i32 * PANGO_SCALE);
+        layout.set_font_description(Some(&font));
+
+        (p_ctx, layout)
+    }
 }
 
-fn headline_font() -> pango::FontDescription {
-    let mut font = pango::FontDescription::default();
-    font.set_size(12 * PANGO_SCALE);
-    font.set_weight(pango::Weight::Bold);
-    font
+/// Page of an export recorded for the print preview
+pub struct PreviewPage {
+    pub pattern: Pattern,
+    /// size in points
+    pub size: Vec2<f64>,
 }
 
-fn secondary_headline_font() -> pango::FontDescription {
-    let mut font = pango::FontDescription::default();
-    font.set_size(10 * PANGO_SCALE);
-    font.set_weight(pango::Weight::Bold);
-    font
+/// Where the pages of an export are drawn to
+pub enum Target {
+    /// PDF file at the path
+    Pdf(String),
+    /// the pages are added to the recorded pages, for the print preview
+    Preview(Rc<RefCell<Vec<PreviewPage>>>),
 }
 
-fn page_number_font() -> pango::FontDescription {
-    let mut font = pango::FontDescription::default();
-    font.set_size(8 * PANGO_SCALE);
-    font
+/// Pages of an export with the context to draw them.
+/// Preview pages are recorded as groups, the last page is added when the document is dropped.
+struct Document {
+    ctx: Context,
+    pdf: Option<PdfSurface>,
+    preview: Option<Rc<RefCell<Vec<PreviewPage>>>>,
+    size: Vec2<f64>,
 }
 
-fn layout_title() -> (pango::Context, pango::Layout) {
-    let p_ctx = pango::Context::new();
-    p_ctx.set_font_map(Some(&pangocairo::FontMap::default()));
-    let layout = pango::Layout::new(&p_ctx);
-    layout.set_width(TEXT_WIDTH as i32 * PANGO_SCALE);
-    let font = title_font();
-    layout.set_font_description(Some(&font));
-    layout.set_alignment(pango::Alignment::Center);
+impl Document {
+    fn new(target: Target, size: Vec2<f64>) -> Self {
+        match target {
+            Target::Pdf(path) => {
+                let pdf = PdfSurface::new(size.x, size.y, path).unwrap();
+                Self {
+                    ctx: Context::new(&pdf).unwrap(),
+                    pdf: Some(pdf),
+                    preview: None,
+                    size,
+                }
+            }
+            Target::Preview(pages) => {
+                let surface = RecordingSurface::create(
+                    Content::ColorAlpha,
+                    Some(Rectangle::new(0.0, 0.0, size.x, size.y)),
+                )
+                .unwrap();
+                let ctx = Context::new(&surface).unwrap();
+                ctx.push_group();
+                Self {
+                    ctx,
+                    pdf: None,
+                    preview: Some(pages),
+                    size,
+                }
+            }
+        }
+    }
 
-    (p_ctx, layout)
+    /// finishes the current page and starts the next one
+    fn show_page(&self) {
+        match &self.preview {
+            Some(pages) => {
+                let pattern = self.ctx.pop_group().unwrap();
+                pages.borrow_mut().push(PreviewPage {
+                    pattern,
+                    size: self.size,
+                });
+                self.ctx.push_group();
+            }
+            None => self.ctx.show_page().unwrap(),
+        }
+    }
+
+    /// adds an entry to the outline of the PDF, the preview has no outline
+    fn add_outline(&self, parent: i32, title: &str, dest: &str, flags: PdfOutline) -> i32 {
+        match &self.pdf {
+            Some(pdf) => pdf
+                .add_outline(parent, title, &format!("dest='{}'", dest), flags)
+                .unwrap(),
+            None => PDF_OUTLINE_ROOT,
+        }
+    }
 }
 
-fn layout_text() -> (pango::Context, pango::Layout) {
-    let p_ctx = pango::Context::new();
-    p_ctx.set_font_map(Some(&pangocairo::FontMap::default()));
-    let layout = pango::Layout::new(&p_ctx);
-    layout.set_width(TEXT_WIDTH as i32 * PANGO_SCALE);
-    let font = text_font();
-    layout.set_font_description(Some(&font));
+impl Drop for Document {
+    fn drop(&mut self) {
+        if let Some(pages) = &self.preview {
+            if let Ok(pattern) = self.ctx.pop_group() {
+                pages.borrow_mut().push(PreviewPage {
+                    pattern,
+                    size: self.size,
+                });
+            }
+        }
+    }
+}
 
+fn layout_title(page: &Page) -> (pango::Context, pango::Layout) {
+    let (p_ctx, layout) = page.layout(page.font(24.0, true));
+    layout.set_alignment(pango::Alignment::Center);
     (p_ctx, layout)
 }
 
-fn layout_headline() -> (pango::Context, pango::Layout) {
-    let p_ctx = pango::Context::new();
-    p_ctx.set_font_map(Some(&pangocairo::FontMap::default()));
-    let layout = pango::Layout::new(&p_ctx);
-    layout.set_width(TEXT_WIDTH as i32 * PANGO_SCALE);
-    let font = headline_font();
-    layout.set_font_description(Some(&font));
-
-    (p_ctx, layout)
+fn layout_text(page: &Page) -> (pango::Context, pango::Layout) {
+    page.layout(page.font(DEFAULT_FONT_SIZE, false))
 }
 
-fn layout_secondary_headline() -> (pango::Context, pango::Layout) {
-    let p_ctx = pango::Context::new();
-    p_ctx.set_font_map(Some(&pangocairo::FontMap::default()));
-    let layout = pango::Layout::new(&p_ctx);
-    layout.set_width(TEXT_WIDTH as i32 * PANGO_SCALE);
-    let font = secondary_headline_font();
-    layout.set_font_description(Some(&font));
+fn layout_headline(page: &Page) -> (pango::Context, pango::Layout) {
+    page.layout(page.font(12.0, true))
+}
 
-    (p_ctx, layout)
+fn layout_secondary_headline(page: &Page) -> (pango::Context, pango::Layout) {
+    page.layout(page.font(10.0, true))
 }
 
-fn layout_page_number() -> (pango::Context, pango::Layout) {
-    let p_ctx = pango::Context::new();
-    p_ctx.set_font_map(Some(&pangocairo::FontMap::default()));
-    let layout = pango::Layout::new(&p_ctx);
-    layout.set_width(TEXT_WIDTH as i32 * PANGO_SCALE);
-    let font = page_number_font();
-    layout.set_font_description(Some(&font));
+fn layout_page_number(page: &Page) -> (pango::Context, pango::Layout) {
+    let (p_ctx, layout) = page.layout(page.font(8.0, false));
     layout.set_alignment(pango::Alignment::Right);
-
     (p_ctx, layout)
 }
 
-fn dungeon_to_primitives(dungeon: &Dungeon, include_hidden: bool) -> Vec<Box<dyn Primitive>> {
+/// Primitives of the dungeon as printed.
+/// With `revealed` only the chambers, doors and corridors revealed to the players are included,
+/// even if they are hidden, e.g. a secret door the players found.
+fn dungeon_to_primitives(
+    dungeon: &Dungeon,
+    style: &ThemeStyle,
+    include_hidden: bool,
+    revealed: Option<&Selection>,
+) -> Vec<Box<dyn Primitive>> {
     let mut all_prims = vec![];
+    let corridor_outlines = dungeon.corridor_outlines(include_hidden, None);
     for chamber in dungeon.chambers() {
-        if include_hidden == false && chamber.hidden {
+        let shown = match revealed {
+            Some(r) => r.chambers.contains(&chamber.id),
+            None => include_hidden || !chamber.hidden,
+        };
+        if !shown {
             continue;
         }
 
         let mut prims = chamber.draw(
             None,
             Some(ChamberDrawOptions {
-                color: Some(Rgb {
-                    r: 0.0,
-                    g: 0.0,
-                    b: 0.0,
-                }),
+                color: Some(style.ink),
                 fill: Some(true),
+                wall_style: Some(style.walls),
             }),
+            &corridor_outlines,
+        );
+        all_prims.append(&mut prims)
+    }
+
+    // draw corridors
+    let chamber_shapes = dungeon.chamber_shapes(include_hidden);
+    for corridor in dungeon.corridors.iter() {
+        let shown = match revealed {
+            Some(r) => r.corridors.contains(&corridor.id),
+            None => include_hidden || !corridor.hidden,
+        };
+        if !shown {
+            continue;
+        }
+
+        let mut prims = corridor.draw(
+            &chamber_shapes,
+            &dungeon.corridor_outlines(include_hidden, Some(corridor.id)),
+            CorridorDrawOptions {
+                color: Some(style.ink),
+                fill: Some(true),
+                // numbers are only shown on the GM map
+                show_number: include_hidden,
+                wall_style: Some(style.walls),
+            },
         );
         all_prims.append(&mut prims)
     }
 
     // draw doors
     for door in dungeon.doors.iter() {
-        if include_hidden == false && door.hidden {
+        let shown = match revealed {
+            Some(r) => r.doors.contains(&door.id),
+            None => include_hidden || !door.hidden,
+        };
+        if !shown {
             continue;
         }
 
@@ -167,11 +355,7 @@ fn dungeon_to_primitives(dungeon: &Dungeon, include_hidden: bool) -> Vec<Box<dyn
                 .wall(door.on_wall)
                 .unwrap(),
             DoorDrawOptions {
-                color: Some(Rgb {
-                    r: 0.0,
-                    g: 0.0,
-                    b: 0.0,
-                }),
+                color: Some(style.ink),
             },
         );
         all_prims.append(&mut prims)
@@ -183,19 +367,19 @@ fn dungeon_to_primitives(dungeon: &Dungeon, include_hidden: bool) -> Vec<Box<dyn
         if include_hidden == false && object.hidden {
             continue;
         }
-        // hide if chamber of object is hidden
+        // hide if chamber of object is hidden, unless it is revealed to the players
         if let Some(chamber_id) = object.part_of {
-            if dungeon.chamber(chamber_id).unwrap().hidden {
+            if revealed.is_none() && dungeon.chamber(chamber_id).unwrap().hidden {
                 continue;
             }
         }
+        // objects are revealed together with their chamber
+        if revealed.is_some_and(|r| !object.part_of.is_some_and(|id| r.chambers.contains(&id))) {
+            continue;
+        }
 
         let mut prims = object.draw(ObjectDrawOptions {
-            color: Some(Rgb {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-            }),
+            color: Some(style.ink),
         });
         all_prims.append(&mut prims)
     }
@@ -203,6 +387,20 @@ fn dungeon_to_primitives(dungeon: &Dungeon, include_hidden: bool) -> Vec<Box<dyn
     all_prims
 }
 
+/// Primitives shown in the player window and the area of the full player map,
+/// which keeps the view stable while chambers are revealed.
+pub fn player_view_primitives(
+    dungeon: &Dungeon,
+    revealed: &Selection,
+) -> (Vec<Box<dyn Primitive>>, BBox) {
+    let style = PrintTheme::Editor.style();
+    let bbox = prims_to_bbox(&dungeon_to_primitives(dungeon, &style, false, None));
+    (
+        dungeon_to_primitives(dungeon, &style, false, Some(revealed)),
+        bbox,
+    )
+}
+
 fn prims_to_bbox(prims: &Vec<Box<dyn Primitive>>) -> BBox {
     // determine size of dungeon
     let mut bbox = BBox {
@@ -226,28 +424,21 @@ fn prims_to_bbox(prims: &Vec<Box<dyn Primitive>>) -> BBox {
     bbox
 }
 
-fn draw_chamber(
+/// Primitives of the chamber with its doors and objects
+fn chamber_primitives(
     dungeon: &Dungeon,
+    style: &ThemeStyle,
     chamber: &Chamber,
-    cur_h: f64,
-    max_size: Vec2<f64>,
-    ctx: &Context,
     include_hidden: bool,
-) -> f64 {
-    if include_hidden == false && chamber.hidden {
-        return 0.0;
-    }
-    // Draw Image
+) -> Vec<Box<dyn Primitive>> {
     let mut prims = chamber.draw(
         None,
         Some(ChamberDrawOptions {
-            color: Some(Rgb {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-            }),
+            color: Some(style.ink),
             fill: Some(true),
+            wall_style: Some(style.walls),
         }),
+        &dungeon.corridor_outlines(include_hidden, None),
     );
 
     // draw doors
@@ -262,11 +453,7 @@ fn draw_chamber(
                 .wall(door.on_wall)
                 .unwrap(),
             DoorDrawOptions {
-                color: Some(Rgb {
-                    r: 0.0,
-                    g: 0.0,
-                    b: 0.0,
-                }),
+                color: Some(style.ink),
             },
         );
         prims.append(&mut door_prims)
@@ -278,14 +465,27 @@ fn draw_chamber(
             continue;
         }
         let mut door_prims = obj.draw(ObjectDrawOptions {
-            color: Some(Rgb {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-            }),
+            color: Some(style.ink),
         });
         prims.append(&mut door_prims)
     }
+    prims
+}
+
+fn draw_chamber(
+    page: &Page,
+    dungeon: &Dungeon,
+    chamber: &Chamber,
+    cur_h: f64,
+    max_size: Vec2<f64>,
+    ctx: &Context,
+    include_hidden: bool,
+) -> f64 {
+    if include_hidden == false && chamber.hidden {
+        return 0.0;
+    }
+    // Draw Image
+    let prims = chamber_primitives(dungeon, &page.style, chamber, include_hidden);
 
     if !prims.is_empty() {
         let mut bbox = prims[0].bbox();
@@ -299,35 +499,18 @@ fn draw_chamber(
             let size = bbox.max - bbox.min;
             let scale = f64::min(max_size.x / size.x, max_size.y / size.y);
             ctx.translate(
-                -bbox.min.x * scale + LEFT_SPACE,
+                -bbox.min.x * scale + page.left(),
                 -bbox.min.y * scale + cur_h,
             );
             ctx.scale(scale, scale);
 
-            let mut grid = Grid::new();
-            grid.color = Rgb {
-                r: 0.5,
-                g: 0.5,
-                b: 0.5,
-            };
-            grid.width = 1.0;
-
             // set clipping
             ctx.rectangle(bbox.min.x, bbox.min.y, size.x, size.y);
             ctx.clip();
             ctx.new_path();
 
-            // draw grid
-            ctx.set_dash(&vec![10.0, 10.0], 0.0);
-            for prim in grid.draw(bbox.min.into(), bbox.max.into()) {
-                prim.draw(&ctx)
-            }
-            ctx.set_dash(&vec![], 0.0);
-
-            // draw chamber
-            for prim in prims.iter() {
-                prim.draw(&ctx)
-            }
+            draw_paper(ctx, &page.style, &bbox);
+            draw_themed(ctx, &page.style, &prims, &bbox, &[chamber.shape()]);
 
             ctx.reset_clip();
             ctx.identity_matrix();
@@ -339,92 +522,281 @@ fn draw_chamber(
     return 0.0;
 }
 
-fn draw_full_dungeon(dungeon: &Dungeon, ctx: &Context, include_hidden: bool) {
-    let all_prims = dungeon_to_primitives(dungeon, include_hidden);
+/// draws the background image, if it is enabled for GM or player exports
+fn draw_background(dungeon: &Dungeon, ctx: &Context, gm: bool) {
+    if let Some(background) = &dungeon.background {
+        let enabled = match gm {
+            true => background.settings.in_gm_export,
+            false => background.settings.in_player_export,
+        };
+        if enabled {
+            background.draw(ctx);
+        }
+    }
+}
+
+/// Fills the map area with the paper color of the theme
+fn draw_paper(ctx: &Context, style: &ThemeStyle, bbox: &BBox) {
+    if let Some(paper) = style.paper {
+        let size = bbox.max - bbox.min;
+        ctx.set_source_rgb(paper.r, paper.g, paper.b);
+        ctx.rectangle(bbox.min.x, bbox.min.y, size.x, size.y);
+        ctx.fill().unwrap();
+    }
+}
+
+fn draw_grid(ctx: &Context, style: &ThemeStyle, bbox: &BBox) {
+    let mut grid = Grid::new();
+    grid.color = style.grid_color;
+    grid.width = 1.0;
+    match style.grid {
+        GridStyle::Dashed | GridStyle::Lines => {
+            grid.dashed = style.grid == GridStyle::Dashed;
+            for prim in grid.draw(bbox.min.into(), bbox.max.into()) {
+                prim.draw(ctx)
+            }
+        }
+        GridStyle::Dots => {
+            let min = grid.cell(bbox.min.into());
+            ctx.set_source_rgb(grid.color.r, grid.color.g, grid.color.b);
+            for x in (min.x..=bbox.max.x as i32).step_by(grid.size.x as usize) {
+                for y in (min.y..=bbox.max.y as i32).step_by(grid.size.y as usize) {
+                    ctx.arc(
+                        x as f64,
+                        y as f64,
+                        GRID_DOT_RADIUS,
+                        0.0,
+                        2.0 * f64::consts::PI,
+                    );
+                    ctx.fill().unwrap();
+                }
+            }
+        }
+        GridStyle::None => (),
+    }
+}
+
+/// Cross-hatching in a band along the walls of the shapes.
+/// Its inner half is covered by the floors drawn afterwards.
+fn draw_hatching(ctx: &Context, style: &ThemeStyle, shapes: &[Shape], bbox: &BBox) {
+    let size = bbox.max - bbox.min;
+    ctx.push_group();
+    ctx.set_source_rgb(style.ink.r, style.ink.g, style.ink.b);
+    ctx.set_line_width(1.0);
+    let mut offset = -size.y;
+    while offset < size.x {
+        let x = bbox.min.x + offset;
+        ctx.move_to(x, bbox.min.y);
+        ctx.line_to(x + size.y, bbox.max.y);
+        ctx.move_to(x, bbox.max.y);
+        ctx.line_to(x + size.y, bbox.min.y);
+        offset += HATCH_SPACING;
+    }
+    ctx.stroke().unwrap();
+
+    // keep the lines only where the walls are stroked with the width of the band
+    ctx.set_operator(Operator::DestIn);
+    ctx.set_line_width(2.0 * HATCH_WIDTH);
+    ctx.set_line_join(LineJoin::Round);
+    for ring in shapes.iter().flat_map(|s| s.rings()) {
+        for (i, p) in ring.iter().enumerate() {
+            match i {
+                0 => ctx.move_to(p.x as f64, p.y as f64),
+                _ => ctx.line_to(p.x as f64, p.y as f64),
+            }
+        }
+        ctx.close_path();
+    }
+    ctx.stroke().unwrap();
+    ctx.set_operator(Operator::Over);
+    ctx.pop_group_to_source().unwrap();
+    ctx.paint().unwrap();
+}
+
+/// Draws the primitives with grid and hatching of the theme, in world coordinates.
+/// `shapes` are the floors of the chambers and corridors.
+fn draw_themed(
+    ctx: &Context,
+    style: &ThemeStyle,
+    prims: &[Box<dyn Primitive>],
+    bbox: &BBox,
+    shapes: &[Shape],
+) {
+    ctx.select_font_face(style.font, FontSlant::Normal, FontWeight::Normal);
+    if !style.grid_on_floors {
+        draw_grid(ctx, style, bbox);
+    }
+    if style.hatching {
+        draw_hatching(ctx, style, shapes, bbox);
+    }
+
+    // draw chamber
+    for prim in prims.iter() {
+        prim.draw(ctx)
+    }
+
+    if style.grid_on_floors {
+        for shape in shapes {
+            let mut shape_bbox = BBox::new();
+            ctx.save().unwrap();
+            for ring in shape.rings() {
+                for (i, p) in ring.iter().enumerate() {
+                    shape_bbox += (*p).into();
+                    match i {
+                        0 => ctx.move_to(p.x as f64, p.y as f64),
+                        _ => ctx.line_to(p.x as f64, p.y as f64),
+                    }
+                }
+                ctx.close_path();
+            }
+            ctx.set_fill_rule(FillRule::EvenOdd);
+            ctx.clip();
+            ctx.set_fill_rule(FillRule::Winding);
+            draw_grid(ctx, style, &shape_bbox);
+            ctx.restore().unwrap();
+        }
+    }
+}
+
+/// Draws background, grid and primitives clipped to `bbox`, in world coordinates
+fn draw_map(
+    dungeon: &Dungeon,
+    ctx: &Context,
+    style: &ThemeStyle,
+    prims: &[Box<dyn Primitive>],
+    bbox: &BBox,
+    include_hidden: bool,
+) {
+    let size = bbox.max - bbox.min;
+
+    // set clipping
+    ctx.rectangle(bbox.min.x, bbox.min.y, size.x, size.y);
+    ctx.clip();
+    ctx.new_path();
+
+    draw_paper(ctx, style, bbox);
+    draw_background(dungeon, ctx, include_hidden);
+
+    let mut shapes = dungeon.chamber_shapes(include_hidden);
+    shapes.append(&mut dungeon.corridor_outlines(include_hidden, None));
+    draw_themed(ctx, style, prims, bbox, &shapes);
+}
+
+/// Draws the map on its own page, `false` if the dungeon is empty.
+/// On the GM map the chamber and corridor numbers link to their notes.
+fn draw_full_dungeon(page: &Page, dungeon: &Dungeon, doc: &Document, include_hidden: bool) -> bool {
+    let ctx = &doc.ctx;
+    let all_prims = dungeon_to_primitives(dungeon, &page.style, include_hidden, None);
     let bbox = prims_to_bbox(&all_prims);
     // early abort on empty dungeon
     if !bbox.is_valid() {
-        return;
+        return false;
     }
 
-    let mut cur_h = START_H;
-    let (_, tl) = layout_title();
+    let mut cur_h = page.start_h();
+    let (_, tl) = layout_title(page);
     tl.set_text(&dungeon.name);
     ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
-    ctx.move_to(LEFT_SPACE, cur_h);
-    show_layout(&ctx, &tl);
+    ctx.move_to(page.left(), cur_h);
+    show_dest(ctx, &tl, DEST_MAP);
     cur_h += (tl.extents().0.height() as f64 / PANGO_SCALE as f64) + TITLE_SPACING;
 
     let size = bbox.max - bbox.min;
-    let max_scale_x = (RIGHT_END - LEFT_SPACE) / size.x;
-    let max_scale_y = (END_H - cur_h) / size.y;
+    let max_scale_x = (page.right_end() - page.left()) / size.x;
+    let max_scale_y = (page.end_h() - cur_h) / size.y;
     let scale = f64::min(max_scale_x, max_scale_y);
     ctx.translate(
-        -bbox.min.x * scale + LEFT_SPACE,
-        -bbox.min.y * scale + (((END_H - cur_h) - (size.y * scale)) / 2.0),
+        -bbox.min.x * scale + page.left(),
+        -bbox.min.y * scale + (((page.end_h() - cur_h) - (size.y * scale)) / 2.0),
     );
     ctx.scale(scale, scale);
 
-    let mut grid = Grid::new();
-    grid.color = Rgb {
-        r: 0.5,
-        g: 0.5,
-        b: 0.5,
-    };
-    grid.width = 1.0;
-
-    // set clipping
-    ctx.rectangle(bbox.min.x, bbox.min.y, size.x, size.y);
-    ctx.clip();
-    ctx.new_path();
+    draw_map(dungeon, ctx, &page.style, &all_prims, &bbox, include_hidden);
 
-    // draw grid
-    ctx.set_dash(&vec![10.0, 10.0], 0.0);
-    for prim in grid.draw(bbox.min.into(), bbox.max.into()) {
-        prim.draw(&ctx)
-    }
-    ctx.set_dash(&vec![], 0.0);
-
-    // draw chamber
-    for prim in all_prims.iter() {
-        prim.draw(&ctx)
+    // link areas around the numbers, in page coordinates
+    let mut links = vec![];
+    if include_hidden {
+        for chamber in dungeon.chambers() {
+            if let Some(p) = chamber.number_pos() {
+                links.push((chamber_dest(chamber.id), ctx.user_to_device(p.x, p.y)));
+            }
+        }
+        for corridor in dungeon.corridors.iter().filter(|c| c.numbered) {
+            if let Some(p) = corridor.label_pos() {
+                links.push((corridor_dest(corridor), ctx.user_to_device(p.x, p.y)));
+            }
+        }
     }
 
     ctx.reset_clip();
     ctx.identity_matrix();
-    ctx.show_page().unwrap();
+    let r = f64::max(NUMBER_LINK_SIZE * scale, 6.0);
+    for (dest, (x, y)) in links {
+        ctx.tag_begin(
+            CAIRO_TAG_LINK,
+            &format!(
+                "dest='{}' rect=[{} {} {} {}]",
+                dest,
+                x - r,
+                y - r,
+                2.0 * r,
+                2.0 * r
+            ),
+        );
+        ctx.tag_end(CAIRO_TAG_LINK);
+    }
+    doc.show_page();
+    true
+}
+
+fn chamber_dest(chamber_id: ChamberId) -> String {
+    format!("chamber-{}", chamber_id)
+}
+
+fn corridor_dest(corridor: &Corridor) -> String {
+    format!("corridor-{}", corridor.id)
+}
+
+/// Draws the layout as a named destination for links and the outline
+fn show_dest(ctx: &Context, layout: &pango::Layout, dest: &str) {
+    ctx.tag_begin(CAIRO_TAG_DEST, &format!("name='{}'", dest));
+    show_layout(ctx, layout);
+    ctx.tag_end(CAIRO_TAG_DEST);
 }
 
 struct PdfElement {
     pub height: f64,
-    pub draw: Box<dyn Fn(&Context, f64, &Dungeon, &Chamber)>,
+    pub draw: Box<dyn Fn(&Context, f64, &Dungeon)>,
 }
 
 /**
  * Combination of chamber headline and image
  * This is combined to avoid a Headline add the end of the page without further info.
  */
-fn chamber_headline(chamber: &Chamber) -> PdfElement {
-    let (_, hl) = layout_headline();
+fn chamber_headline(page: Page, chamber: &Chamber) -> PdfElement {
+    let (_, hl) = layout_headline(&page);
     hl.set_text(&format!("{}: {}", chamber.id, &chamber.name));
 
     let headline_height =
         (hl.extents().0.height() as f64 / PANGO_SCALE as f64) + HEADLINE_IMAGE_SPACING;
     let image_height = IMAGE_SIZE + IMAGE_NOTES_SPACEING;
+    let dest = chamber_dest(chamber.id);
+    let chamber = chamber.clone();
 
     PdfElement {
         height: headline_height + image_height,
-        draw: Box::new(move |ctx, start_h, dungeon, chamber| {
+        draw: Box::new(move |ctx, start_h, dungeon| {
             let mut cur_h = start_h;
             // Draw Headline
             ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
-            ctx.move_to(LEFT_SPACE, start_h);
-            show_layout(&ctx, &hl);
+            ctx.move_to(page.left(), start_h);
+            show_dest(ctx, &hl, &dest);
 
             cur_h += headline_height;
             draw_chamber(
+                &page,
                 dungeon,
-                chamber,
+                &chamber,
                 cur_h,
                 Vec2 {
                     x: IMAGE_SIZE,
@@ -437,247 +809,1047 @@ fn chamber_headline(chamber: &Chamber) -> PdfElement {
     }
 }
 
-fn str_to_pdf_elements(str: String) -> Vec<PdfElement> {
-    let (_, tl) = layout_text();
-    tl.set_text(&str);
-    let lines = tl.lines();
-    lines
+/// One element per line of the layout, so text may continue on the next page
+fn layout_lines(page: Page, layout: pango::Layout, indent: f64, color: Rgb) -> Vec<PdfElement> {
+    layout
+        .lines()
         .into_iter()
         .map(move |l| PdfElement {
-            height: (l.extents().0.height() as f64 / PANGO_SCALE as f64).max(TEXT_FONT_SIZE as f64)
+            height: (l.extents().0.height() as f64 / PANGO_SCALE as f64).max(page.font_size)
                 * TEXT_LINE_SPACING,
-            draw: Box::new(move |ctx, start_h, _, _| {
-                ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
-                ctx.move_to(LEFT_SPACE, start_h);
+            draw: Box::new(move |ctx, start_h, _| {
+                ctx.set_source_rgba(color.r, color.g, color.b, 1.0);
+                ctx.move_to(page.left() + indent, start_h);
                 show_layout_line(&ctx, &l);
             }),
         })
         .collect()
 }
 
-fn chamber_notes(chamber: &Chamber) -> Vec<PdfElement> {
-    str_to_pdf_elements(chamber.notes.clone())
+/// Draws `decoration` along with the element
+fn decorate(elem: PdfElement, decoration: impl Fn(&Context, f64, f64) + 'static) -> PdfElement {
+    let draw = elem.draw;
+    let height = elem.height;
+    PdfElement {
+        height,
+        draw: Box::new(move |ctx, start_h, dungeon| {
+            draw(ctx, start_h, dungeon);
+            decoration(ctx, start_h, height);
+        }),
+    }
+}
+
+/// Rows of a table with columns of equal width, the header is printed bold on gray
+fn table_elements(page: Page, header: Vec<String>, rows: Vec<Vec<String>>) -> Vec<PdfElement> {
+    let columns = header
+        .len()
+        .max(rows.iter().map(|r| r.len()).max().unwrap_or(0));
+    if columns == 0 {
+        return vec![];
+    }
+    let column_width = page.text_width() / columns as f64;
+    let padding = TABLE_PADDING * page.font_scale();
+    let with_header = !header.is_empty();
+    std::iter::once(header)
+        .filter(|_| with_header)
+        .chain(rows)
+        .enumerate()
+        .map(|(i, row)| {
+            let is_header = with_header && i == 0;
+            let cells: Vec<pango::Layout> = (0..columns)
+                .map(|col| {
+                    let (_, cl) = layout_text(&page);
+                    cl.set_width(((column_width - 2.0 * padding) as i32) * PANGO_SCALE);
+                    let markup = markdown::inline_markup(row.get(col).map_or("", |c| c));
+                    match is_header {
+                        true => cl.set_markup(&format!("<b>{}</b>", markup)),
+                        false => cl.set_markup(&markup),
+                    }
+                    cl
+                })
+                .collect();
+            let height = cells
+                .iter()
+                .map(|cl| cl.extents().1.height() as f64 / PANGO_SCALE as f64)
+                .fold(page.font_size, f64::max)
+                + 2.0 * padding;
+            PdfElement {
+                height,
+                draw: Box::new(move |ctx, start_h, _| {
+                    // aligned with the lines of text, which are drawn on their baseline
+                    let top = start_h - page.font_size;
+                    for (col, cl) in cells.iter().enumerate() {
+                        let x = page.left() + col as f64 * column_width;
+                        ctx.rectangle(x, top, column_width, height);
+                        if is_header {
+                            ctx.set_source_rgba(0.9, 0.9, 0.9, 1.0);
+                            ctx.fill_preserve().unwrap();
+                        }
+                        ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
+                        ctx.set_line_width(0.5);
+                        ctx.stroke().unwrap();
+                        ctx.move_to(x + padding, top + padding);
+                        show_layout(ctx, cl);
+                    }
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Elements of notes written in the Markdown subset of `markdown::parse`
+fn str_to_pdf_elements(page: Page, str: String) -> Vec<PdfElement> {
+    let mut elems = vec![];
+    for block in markdown::parse(&str) {
+        match block {
+            Block::Heading(level, text) => {
+                let size = match level {
+                    1 => 12.0,
+                    2 => 11.0,
+                    _ => DEFAULT_FONT_SIZE,
+                };
+                let (_, hl) = page.layout(page.font(size, true));
+                hl.set_markup(&markdown::inline_markup(&text));
+                elems.append(&mut layout_lines(page, hl, 0.0, HEADLINE_COLOR));
+            }
+            Block::Paragraph(text) => {
+                let (_, tl) = layout_text(&page);
+                tl.set_markup(&markdown::inline_markup(&text));
+                elems.append(&mut layout_lines(page, tl, 0.0, NOTES_COLOR));
+            }
+            Block::Blank => elems.push(spacing(page.font_size * TEXT_LINE_SPACING)),
+            Block::ListItem {
+                number,
+                depth,
+                text,
+            } => {
+                let indent = LIST_INDENT * page.font_scale() * (depth + 1) as f64;
+                let (_, tl) = layout_text(&page);
+                tl.set_width(((page.text_width() - indent) as i32) * PANGO_SCALE);
+                tl.set_markup(&markdown::inline_markup(&text));
+                let (_, ml) = layout_text(&page);
+                ml.set_width(-1);
+                ml.set_text(&markdown::list_marker(number));
+                let marker_width = ml.extents().1.width() as f64 / PANGO_SCALE as f64;
+                let marker_x = page.left() + indent - marker_width - LIST_MARKER_SPACING;
+                for (i, e) in layout_lines(page, tl, indent, NOTES_COLOR)
+                    .into_iter()
+                    .enumerate()
+                {
+                    match (i, ml.line(0)) {
+                        (0, Some(marker)) => elems.push(decorate(e, move |ctx, start_h, _| {
+                            ctx.move_to(marker_x, start_h);
+                            show_layout_line(ctx, &marker);
+                        })),
+                        _ => elems.push(e),
+                    }
+                }
+            }
+            Block::Quote(text) => {
+                let indent = QUOTE_INDENT * page.font_scale();
+                let mut font = page.font(DEFAULT_FONT_SIZE, false);
+                font.set_style(pango::Style::Italic);
+                let (_, ql) = page.layout(font);
+                ql.set_width(((page.text_width() - indent) as i32) * PANGO_SCALE);
+                ql.set_markup(&markdown::inline_markup(&text));
+                for e in layout_lines(page, ql, indent, NOTES_COLOR) {
+                    // bar in front of the quote, continuous across the lines
+                    elems.push(decorate(e, move |ctx, start_h, height| {
+                        let top = start_h - page.font_size;
+                        ctx.set_source_rgba(0.6, 0.6, 0.6, 1.0);
+                        ctx.rectangle(page.left(), top, 2.0, height);
+                        ctx.fill().unwrap();
+                    }));
+                }
+            }
+            Block::Table { header, rows } => {
+                elems.append(&mut table_elements(page, header, rows));
+            }
+        }
+    }
+    elems
+}
+
+/// Draws the element on a shaded box across the text width
+fn shade(page: Page, elem: PdfElement) -> PdfElement {
+    let draw = elem.draw;
+    let height = elem.height;
+    PdfElement {
+        height,
+        draw: Box::new(move |ctx, start_h, dungeon| {
+            // aligned with the lines of text, which are drawn on their baseline,
+            // slightly overlapping the next element to avoid gaps between the lines
+            ctx.set_source_rgba(
+                READ_ALOUD_COLOR.r,
+                READ_ALOUD_COLOR.g,
+                READ_ALOUD_COLOR.b,
+                1.0,
+            );
+            ctx.rectangle(
+                page.left(),
+                start_h - page.font_size,
+                page.text_width(),
+                height + 0.5,
+            );
+            ctx.fill().unwrap();
+            draw(ctx, start_h, dungeon);
+        }),
+    }
+}
+
+/// Text read to the players, on a shaded box to set it apart from the GM notes
+fn read_aloud_box(page: Page, text: &str) -> Vec<PdfElement> {
+    if text.trim().is_empty() {
+        return vec![];
+    }
+    let padding = READ_ALOUD_PADDING * page.font_scale();
+    // only narrows the text, the elements are placed on the pages of `page`
+    let inner = Page {
+        margin: page.margin + padding,
+        ..page
+    };
+    let mut elems = vec![spacing(padding)];
+    elems.append(&mut str_to_pdf_elements(inner, text.to_owned()));
+    elems.push(spacing(padding));
+    let mut elems: Vec<PdfElement> = elems.into_iter().map(|e| shade(page, e)).collect();
+    elems.push(spacing(TEXT_SPACING));
+    elems
+}
+
+fn chamber_notes(page: Page, chamber: &Chamber) -> Vec<PdfElement> {
+    str_to_pdf_elements(page, chamber.notes.clone())
 }
 
-fn chamber_door(door: &Door) -> PdfElement {
+/// Notes of a door, the chamber on the other side links to its notes
+fn chamber_door(page: Page, dungeon: &Dungeon, chamber: &Chamber, door: &Door) -> Vec<PdfElement> {
+    let other = match door.leads_to {
+        Some(id) if id == chamber.id => Some(door.part_of),
+        leads_to => leads_to,
+    }
+    .and_then(|id| dungeon.chamber(id));
+
     // pointless ot add empty doors to the pdf
-    if door.name.is_empty() && door.notes.is_empty() {
-        return PdfElement {
-            height: 0.0,
-            draw: Box::new(move |_, _, _, _| {}),
-        };
+    if door.name.is_empty()
+        && door.notes.is_empty()
+        && door.read_aloud.is_empty()
+        && other.is_none()
+    {
+        return vec![];
     }
 
-    let (_, hl) = layout_secondary_headline();
+    let (_, hl) = layout_secondary_headline(&page);
     match door.name.is_empty() {
         true => hl.set_text(&format!("Door: {}", door.id)),
         false => hl.set_text(&format!("Door: {}", door.name)),
     };
-    let (_, tl) = layout_text();
-    tl.set_text(&door.notes);
-    PdfElement {
+    let (_, ll) = layout_text(&page);
+    let link = other.map(|c| {
+        ll.set_text(&format!("Leads to {}: {}", c.id, c.name));
+        chamber_dest(c.id)
+    });
+    let link_height = match link {
+        Some(_) => ll.extents().0.height() as f64 / PANGO_SCALE as f64,
+        None => 0.0,
+    };
+    let mut elems = vec![PdfElement {
+        // the notes below are drawn on their baseline
         height: ((hl.extents().0.height() as f64 / PANGO_SCALE as f64) * 1.5)
-            + (tl.extents().0.height() as f64 / PANGO_SCALE as f64)
-            + HEADLINE_IMAGE_SPACING,
-        draw: Box::new(move |ctx, start_h, _, _| {
+            + link_height
+            + page.font_size,
+        draw: Box::new(move |ctx, start_h, _| {
             let mut cur_h = start_h;
             ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
-            ctx.move_to(LEFT_SPACE, cur_h);
+            ctx.move_to(page.left(), cur_h);
             show_layout(&ctx, &hl);
 
             cur_h += (hl.extents().0.height() as f64 / PANGO_SCALE as f64) * 1.5;
 
-            ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
-            ctx.move_to(LEFT_SPACE, cur_h);
-            show_layout(&ctx, &tl);
+            if let Some(dest) = &link {
+                ctx.set_source_rgba(LINK_COLOR.r, LINK_COLOR.g, LINK_COLOR.b, 1.0);
+                ctx.move_to(page.left(), cur_h);
+                ctx.tag_begin(CAIRO_TAG_LINK, &format!("dest='{}'", dest));
+                show_layout(ctx, &ll);
+                ctx.tag_end(CAIRO_TAG_LINK);
+            }
         }),
-    }
+    }];
+    elems.append(&mut read_aloud_box(page, &door.read_aloud));
+    elems.append(&mut str_to_pdf_elements(page, door.notes.clone()));
+    elems.push(spacing(HEADLINE_IMAGE_SPACING));
+    elems
 }
 
-fn chamber_object(object: &Object) -> PdfElement {
+fn chamber_object(page: Page, object: &Object) -> Vec<PdfElement> {
     // pointless ot add empty objects to the pdf
-    if object.name.is_empty() && object.notes.is_empty() {
-        return PdfElement {
-            height: 0.0,
-            draw: Box::new(move |_, _, _, _| {}),
-        };
+    if object.name.is_empty() && object.notes.is_empty() && object.read_aloud.is_empty() {
+        return vec![];
     }
 
-    let (_, hl) = layout_secondary_headline();
+    let (_, hl) = layout_secondary_headline(&page);
     match object.name.is_empty() {
         true => hl.set_text(&format!("Object: {}", object.id)),
         false => hl.set_text(&format!("Object: {}", object.name)),
     };
-    let (_, tl) = layout_text();
-    tl.set_text(&object.notes);
-    PdfElement {
-        height: ((hl.extents().0.height() as f64 / PANGO_SCALE as f64) * 1.5)
-            + (tl.extents().0.height() as f64 / PANGO_SCALE as f64)
-            + HEADLINE_IMAGE_SPACING,
-        draw: Box::new(move |ctx, start_h, _, _| {
-            let mut cur_h = start_h;
+    let mut elems = vec![PdfElement {
+        // the notes below are drawn on their baseline
+        height: ((hl.extents().0.height() as f64 / PANGO_SCALE as f64) * 1.5) + page.font_size,
+        draw: Box::new(move |ctx, start_h, _| {
             ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
-            ctx.move_to(LEFT_SPACE, cur_h);
+            ctx.move_to(page.left(), start_h);
             show_layout(&ctx, &hl);
-
-            cur_h += (hl.extents().0.height() as f64 / PANGO_SCALE as f64) * 1.5;
-
-            ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
-            ctx.move_to(LEFT_SPACE, cur_h);
-            show_layout(&ctx, &tl);
         }),
+    }];
+    elems.append(&mut read_aloud_box(page, &object.read_aloud));
+    elems.append(&mut str_to_pdf_elements(page, object.notes.clone()));
+    elems.push(spacing(HEADLINE_IMAGE_SPACING));
+    elems
+}
+
+fn spacing(height: f64) -> PdfElement {
+    PdfElement {
+        height,
+        draw: Box::new(move |_, _, _| {}),
     }
 }
 
-fn separator() -> PdfElement {
+fn separator(page: Page) -> PdfElement {
     PdfElement {
         height: 42.0,
-        draw: Box::new(|ctx, start_h, _, _| {
+        draw: Box::new(move |ctx, start_h, _| {
             let mut cur_h = start_h;
             cur_h += 20.0;
-            ctx.move_to(LEFT_SPACE, cur_h);
+            ctx.move_to(page.left(), cur_h);
             ctx.set_line_width(2.0);
-            ctx.line_to(RIGHT_END, cur_h);
+            ctx.line_to(page.right_end(), cur_h);
             ctx.stroke().unwrap();
         }),
     }
 }
 
-fn chamber_elems(dungeon: &Dungeon, chamber: &Chamber) -> Vec<PdfElement> {
-    let mut elems = vec![chamber_headline(chamber)];
-    elems.append(&mut chamber_notes(chamber));
-    for e in dungeon
-        .chamber_doors(chamber.id)
-        .iter()
-        .map(|d| chamber_door(d))
-    {
-        elems.push(e)
+fn chamber_elems(page: Page, dungeon: &Dungeon, chamber: &Chamber) -> Vec<PdfElement> {
+    let mut elems = vec![chamber_headline(page, chamber)];
+    elems.append(&mut read_aloud_box(page, &chamber.read_aloud));
+    elems.append(&mut chamber_notes(page, chamber));
+    for door in dungeon.chamber_doors(chamber.id) {
+        elems.append(&mut chamber_door(page, dungeon, chamber, door));
     }
-    for e in dungeon
-        .chamber_objects(chamber.id)
-        .iter()
-        .map(|o| chamber_object(o))
-    {
-        elems.push(e)
+    for object in dungeon.chamber_objects(chamber.id) {
+        elems.append(&mut chamber_object(page, object));
+    }
+
+    elems.push(separator(page));
+    elems
+}
+
+fn corridor_headline(page: Page, corridor: &Corridor) -> PdfElement {
+    let (_, hl) = layout_headline(&page);
+    hl.set_text(&format!("{}: {}", corridor.label(), &corridor.name));
+    let dest = corridor_dest(corridor);
+
+    PdfElement {
+        height: (hl.extents().0.height() as f64 / PANGO_SCALE as f64) + HEADLINE_IMAGE_SPACING,
+        draw: Box::new(move |ctx, start_h, _| {
+            ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
+            ctx.move_to(page.left(), start_h);
+            show_dest(ctx, &hl, &dest);
+        }),
     }
+}
 
-    elems.push(separator());
+fn corridor_elems(page: Page, corridor: &Corridor) -> Vec<PdfElement> {
+    let mut elems = vec![corridor_headline(page, corridor)];
+    elems.append(&mut str_to_pdf_elements(page, corridor.notes.clone()));
+    elems.push(separator(page));
     elems
 }
 
-fn finalize_page(ctx: &Context, cur_page_number: i32) {
+fn finalize_page(page: &Page, ctx: &Context, cur_page_number: i32) {
     // add page number to page
-    let (_, pl) = layout_page_number();
+    let (_, pl) = layout_page_number(page);
     pl.set_text(&format!("{}", cur_page_number));
     ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
-    ctx.move_to(LEFT_SPACE, HEIGHT_PAGE_NUMBER);
+    ctx.move_to(page.left(), page.page_number_h());
     show_layout(&ctx, &pl);
 }
 
-pub fn to_pdf(dungeon: &Dungeon, path: String) {
-    let pdf = gtk::cairo::PdfSurface::new(PAGE_W, PAGE_H, path).unwrap();
-    let ctx = Context::new(pdf).unwrap();
-
-    let mut cur_h = START_H;
-    let mut cur_page_number = 1;
+/// Part of the GM notes with its own entry in the contents and the outline
+struct Section {
+    title: String,
+    dest: String,
+    elems: Vec<PdfElement>,
+}
 
-    // Draw entire dungeon
-    draw_full_dungeon(dungeon, &ctx, true);
-
-    let (_, hl) = layout_headline();
+fn dungeon_headline(page: Page, dungeon: &Dungeon) -> PdfElement {
+    let (_, hl) = layout_headline(&page);
     hl.set_text(&dungeon.name);
-    ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
-    ctx.move_to(LEFT_SPACE, cur_h);
-    show_layout(&ctx, &hl);
-    cur_h += (hl.extents().0.height() as f64 / PANGO_SCALE as f64) + TEXT_SPACING;
+    PdfElement {
+        height: (hl.extents().0.height() as f64 / PANGO_SCALE as f64) + TEXT_SPACING,
+        draw: Box::new(move |ctx, start_h, _| {
+            ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
+            ctx.move_to(page.left(), start_h);
+            show_dest(ctx, &hl, DEST_DUNGEON);
+        }),
+    }
+}
 
-    let mut dungeon_elems = str_to_pdf_elements(dungeon.notes.clone());
-    dungeon_elems.push(separator());
+/// The dungeon notes, every chamber and every numbered corridor
+fn notes_sections(page: Page, dungeon: &Dungeon) -> Vec<Section> {
+    let mut dungeon_elems = vec![dungeon_headline(page, dungeon)];
+    dungeon_elems.append(&mut str_to_pdf_elements(page, dungeon.notes.clone()));
+    dungeon_elems.push(separator(page));
 
-    for e in dungeon_elems {
-        let next_h = cur_h + (e.height);
-        if next_h > END_H {
-            finalize_page(&ctx, cur_page_number);
-            cur_page_number += 1;
+    let mut sections = vec![Section {
+        title: match dungeon.name.is_empty() {
+            true => "Dungeon".to_owned(),
+            false => dungeon.name.clone(),
+        },
+        dest: DEST_DUNGEON.to_owned(),
+        elems: dungeon_elems,
+    }];
+    for chamber in dungeon.chambers() {
+        sections.push(Section {
+            title: format!("{}: {}", chamber.id, chamber.name),
+            dest: chamber_dest(chamber.id),
+            elems: chamber_elems(page, dungeon, chamber),
+        });
+    }
+    for corridor in dungeon.corridors.iter().filter(|c| c.numbered) {
+        sections.push(Section {
+            title: format!("{}: {}", corridor.label(), corridor.name),
+            dest: corridor_dest(corridor),
+            elems: corridor_elems(page, corridor),
+        });
+    }
+    sections
+}
 
-            // start new page
-            ctx.show_page().unwrap();
-            cur_h = START_H;
-        }
-        (e.draw)(&ctx, cur_h, dungeon, &Chamber::new()); // TODO this is hacky. Chamber not needed
-        cur_h = cur_h + (e.height);
+fn appendix_headline(page: Page, title: &str, dest: &'static str) -> PdfElement {
+    let (_, hl) = layout_headline(&page);
+    hl.set_text(title);
+    PdfElement {
+        // the table below is drawn on the baseline of its text
+        height: (hl.extents().0.height() as f64 / PANGO_SCALE as f64)
+            + TEXT_SPACING
+            + page.font_size,
+        draw: Box::new(move |ctx, start_h, _| {
+            ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
+            ctx.move_to(page.left(), start_h);
+            show_dest(ctx, &hl, dest);
+        }),
     }
+}
 
-    for chamber in dungeon.chambers() {
-        // for each chamber
-        // emit list of unseparable elements
-        // each element has an associated height
-        // if element no longer fits on page: start new page
-        let elems = chamber_elems(dungeon, chamber);
-        for e in elems {
-            let next_h = cur_h + (e.height);
-            if next_h > END_H {
-                finalize_page(&ctx, cur_page_number);
-                cur_page_number += 1;
+fn appendix_section(
+    page: Page,
+    title: &str,
+    dest: &'static str,
+    header: &[&str],
+    rows: Vec<Vec<String>>,
+) -> Section {
+    let mut elems = vec![appendix_headline(page, title, dest)];
+    elems.append(&mut table_elements(
+        page,
+        header.iter().map(|h| h.to_string()).collect(),
+        rows,
+    ));
+    elems.push(separator(page));
+    Section {
+        title: title.to_owned(),
+        dest: dest.to_owned(),
+        elems,
+    }
+}
+
+/// Number and name of the chamber with the page of its notes, empty without a chamber
+fn chamber_columns(
+    dungeon: &Dungeon,
+    chamber_id: Option<ChamberId>,
+    chamber_pages: &HashMap<ChamberId, i32>,
+) -> [String; 2] {
+    match chamber_id.and_then(|id| dungeon.chamber(id)) {
+        Some(c) => [
+            format!("{}: {}", c.id, c.name),
+            chamber_pages
+                .get(&c.id)
+                .map_or(String::new(), |n| n.to_string()),
+        ],
+        None => [String::new(), String::new()],
+    }
+}
+
+fn door_title(door: &Door) -> String {
+    match door.name.is_empty() {
+        true => format!("Door {}", door.id),
+        false => door.name.clone(),
+    }
+}
+
+fn object_title(object: &Object) -> String {
+    match object.name.is_empty() {
+        true => format!("Object {}", object.id),
+        false => object.name.clone(),
+    }
+}
+
+/// Appendices of the GM PDF enabled in the settings, referring to the pages of the chambers
+fn appendix_sections(
+    page: Page,
+    dungeon: &Dungeon,
+    settings: &ExportSettings,
+    chamber_pages: &HashMap<ChamberId, i32>,
+) -> Vec<Section> {
+    let mut sections = vec![];
+    if settings.index_appendix {
+        let mut rows: Vec<Vec<String>> = dungeon
+            .objects
+            .iter()
+            .filter(|o| !o.name.is_empty())
+            .map(|o| ("Object", &o.name, o.part_of))
+            .chain(
+                dungeon
+                    .doors
+                    .iter()
+                    .filter(|d| !d.name.is_empty())
+                    .map(|d| ("Door", &d.name, Some(d.part_of))),
+            )
+            .map(|(kind, name, chamber_id)| {
+                let [chamber, page_number] = chamber_columns(dungeon, chamber_id, chamber_pages);
+                vec![name.clone(), kind.to_owned(), chamber, page_number]
+            })
+            .collect();
+        rows.sort_by_key(|r| r[0].to_lowercase());
+        sections.push(appendix_section(
+            page,
+            "Index of Objects and Doors",
+            DEST_INDEX,
+            &["Name", "Type", "Chamber", "Page"],
+            rows,
+        ));
+    }
+    if settings.hidden_appendix {
+        let chambers = dungeon
+            .chambers()
+            .iter()
+            .filter(|c| c.hidden)
+            .map(|c| ("Chamber", format!("{}: {}", c.id, c.name), Some(c.id)));
+        let doors = dungeon
+            .doors
+            .iter()
+            .filter(|d| d.hidden)
+            .map(|d| ("Door", door_title(d), Some(d.part_of)));
+        let objects = dungeon
+            .objects
+            .iter()
+            .filter(|o| o.hidden)
+            .map(|o| ("Object", object_title(o), o.part_of));
+        let rows = chambers
+            .chain(doors)
+            .chain(objects)
+            .map(|(kind, name, chamber_id)| {
+                let [chamber, page_number] = chamber_columns(dungeon, chamber_id, chamber_pages);
+                // an empty box to tick off during play
+                vec![format!("\u{2610} {}", kind), name, chamber, page_number]
+            })
+            .collect();
+        sections.push(appendix_section(
+            page,
+            "Hidden Features",
+            DEST_HIDDEN,
+            &["Found", "Name", "Chamber", "Page"],
+            rows,
+        ));
+    }
+    if settings.doors_appendix {
+        let rows = dungeon
+            .doors
+            .iter()
+            .map(|d| {
+                let [from, page_number] = chamber_columns(dungeon, Some(d.part_of), chamber_pages);
+                let [to, _] = chamber_columns(dungeon, d.leads_to, chamber_pages);
+                vec![door_title(d), from, to, page_number]
+            })
+            .collect();
+        sections.push(appendix_section(
+            page,
+            "Door Connections",
+            DEST_DOORS,
+            &["Door", "From", "To", "Page"],
+            rows,
+        ));
+    }
+    sections
+}
+
+fn contents_headline(page: Page) -> PdfElement {
+    let (_, hl) = layout_headline(&page);
+    hl.set_text("Contents");
+    PdfElement {
+        height: (hl.extents().0.height() as f64 / PANGO_SCALE as f64) + TEXT_SPACING,
+        draw: Box::new(move |ctx, start_h, _| {
+            ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
+            ctx.move_to(page.left(), start_h);
+            show_dest(ctx, &hl, DEST_CONTENTS);
+        }),
+    }
+}
 
-                // start new page
-                ctx.show_page().unwrap();
-                cur_h = START_H;
+/// Line of the contents linking to a section
+fn contents_entry(page: Page, section: &Section, page_number: i32) -> PdfElement {
+    let (_, tl) = layout_text(&page);
+    tl.set_width(((page.text_width() * 0.85) as i32) * PANGO_SCALE);
+    tl.set_ellipsize(pango::EllipsizeMode::End);
+    tl.set_text(&section.title);
+    let (_, nl) = layout_text(&page);
+    nl.set_alignment(pango::Alignment::Right);
+    nl.set_text(&format!("{}", page_number));
+    let dest = section.dest.clone();
+    PdfElement {
+        height: (tl.extents().0.height() as f64 / PANGO_SCALE as f64).max(page.font_size)
+            * TEXT_LINE_SPACING,
+        draw: Box::new(move |ctx, start_h, _| {
+            ctx.set_source_rgba(LINK_COLOR.r, LINK_COLOR.g, LINK_COLOR.b, 1.0);
+            ctx.tag_begin(CAIRO_TAG_LINK, &format!("dest='{}'", dest));
+            ctx.move_to(page.left(), start_h);
+            show_layout(ctx, &tl);
+            ctx.move_to(page.left(), start_h);
+            show_layout(ctx, &nl);
+            ctx.tag_end(CAIRO_TAG_LINK);
+        }),
+    }
+}
+
+/// Page number and height each element is drawn at.
+/// An element not fitting on the current page starts a new one.
+fn paginate(page: &Page, heights: impl Iterator<Item = f64>, first_page: i32) -> Vec<(i32, f64)> {
+    let mut cur_h = page.start_h();
+    let mut cur_page_number = first_page;
+    heights
+        .map(|height| {
+            if cur_h + height > page.end_h() {
+                cur_page_number += 1;
+                cur_h = page.start_h();
             }
-            (e.draw)(&ctx, cur_h, dungeon, chamber);
-            cur_h = cur_h + (e.height);
+            let pos = (cur_page_number, cur_h);
+            cur_h += height;
+            pos
+        })
+        .collect()
+}
+
+/// Draws the elements on pages starting with `first_page`, returns the number of the last page
+fn draw_pages(
+    page: &Page,
+    doc: &Document,
+    dungeon: &Dungeon,
+    elems: &[&PdfElement],
+    first_page: i32,
+) -> i32 {
+    let ctx = &doc.ctx;
+    let positions = paginate(page, elems.iter().map(|e| e.height), first_page);
+    let mut cur_page_number = first_page;
+    for (e, (page_number, h)) in elems.iter().zip(positions) {
+        if page_number != cur_page_number {
+            finalize_page(page, ctx, cur_page_number);
+            cur_page_number = page_number;
+
+            // start new page
+            doc.show_page();
         }
+        (e.draw)(ctx, h, dungeon);
     }
     // add page number to last page
-    finalize_page(&ctx, cur_page_number);
+    finalize_page(page, ctx, cur_page_number);
+    cur_page_number
 }
 
-pub fn to_player_cutout_pdf(dungeon: &Dungeon, path: String) {
-    // find max bbox size
-    let max_size = dungeon.chambers().iter().fold(
-        Vec2 {
-            x: f64::MIN,
-            y: f64::MIN,
-        },
-        |prev, chamber| {
+/// GM notes with the map, a table of contents, an outline and the appendices of the settings.
+/// Chamber numbers on the map, the contents and doors link to the notes of the chambers.
+pub fn to_pdf(dungeon: &Dungeon, settings: &ExportSettings, target: Target) {
+    let doc = Document::new(target, settings.page_size());
+    draw_notes(&doc, dungeon, settings);
+}
+
+/// Draws the pages of `to_pdf` into the document
+fn draw_notes(doc: &Document, dungeon: &Dungeon, settings: &ExportSettings) {
+    let page = Page::new(settings, settings.page_size());
+
+    // Draw entire dungeon
+    let has_map = draw_full_dungeon(&page, dungeon, doc, true);
+
+    let mut sections = notes_sections(page, dungeon);
+    let notes_count = sections.len();
+    sections.append(&mut appendix_sections(
+        page,
+        dungeon,
+        settings,
+        &HashMap::new(),
+    ));
+    let contents = |sections: &[Section], page_numbers: &[i32]| -> Vec<PdfElement> {
+        let mut elems = vec![contents_headline(page)];
+        for (section, n) in sections.iter().zip(page_numbers) {
+            elems.push(contents_entry(page, section, *n));
+        }
+        elems
+    };
+    // the contents list the pages of the sections following them,
+    // the number of contents pages does not depend on these
+    let contents_pages = paginate(
+        &page,
+        contents(&sections, &vec![0; sections.len()])
+            .iter()
+            .map(|e| e.height),
+        1,
+    )
+    .last()
+    .map_or(1, |(n, _)| *n);
+    let positions = paginate(
+        &page,
+        sections
+            .iter()
+            .flat_map(|s| s.elems.iter().map(|e| e.height)),
+        contents_pages + 1,
+    );
+    let mut first_elem = 0;
+    let page_numbers: Vec<i32> = sections
+        .iter()
+        .map(|s| {
+            let n = positions
+                .get(first_elem)
+                .map_or(contents_pages + 1, |(n, _)| *n);
+            first_elem += s.elems.len();
+            n
+        })
+        .collect();
+    // the appendices follow the chambers, their height does not depend on the page numbers
+    let chamber_pages: HashMap<ChamberId, i32> = dungeon
+        .chambers()
+        .iter()
+        .zip(&page_numbers[1..])
+        .map(|(c, n)| (c.id, *n))
+        .collect();
+    sections.truncate(notes_count);
+    sections.append(&mut appendix_sections(
+        page,
+        dungeon,
+        settings,
+        &chamber_pages,
+    ));
+
+    let contents_elems = contents(&sections, &page_numbers);
+    let elems: Vec<&PdfElement> = contents_elems.iter().collect();
+    draw_pages(&page, doc, dungeon, &elems, 1);
+    doc.show_page();
+
+    let elems: Vec<&PdfElement> = sections.iter().flat_map(|s| s.elems.iter()).collect();
+    draw_pages(&page, doc, dungeon, &elems, contents_pages + 1);
+
+    // outline with the chambers and corridors below the dungeon, followed by the appendices
+    let outline = |parent: i32, title: &str, dest: &str, flags: PdfOutline| -> i32 {
+        doc.add_outline(parent, title, dest, flags)
+    };
+    if has_map {
+        outline(PDF_OUTLINE_ROOT, "Map", DEST_MAP, PdfOutline::empty());
+    }
+    outline(
+        PDF_OUTLINE_ROOT,
+        "Contents",
+        DEST_CONTENTS,
+        PdfOutline::empty(),
+    );
+    let root = outline(
+        PDF_OUTLINE_ROOT,
+        &sections[0].title,
+        &sections[0].dest,
+        PdfOutline::OPEN,
+    );
+    for section in &sections[1..notes_count] {
+        outline(root, &section.title, &section.dest, PdfOutline::empty());
+    }
+    for section in &sections[notes_count..] {
+        outline(
+            PDF_OUTLINE_ROOT,
+            &section.title,
+            &section.dest,
+            PdfOutline::empty(),
+        );
+    }
+}
+
+/// Headline of a handout, followed by a read-aloud box
+fn handout_headline(page: Page, hl: pango::Layout) -> PdfElement {
+    PdfElement {
+        // the box below starts above the baseline of its first line
+        height: (hl.extents().0.height() as f64 / PANGO_SCALE as f64)
+            + HEADLINE_IMAGE_SPACING
+            + page.font_size,
+        draw: Box::new(move |ctx, start_h, _| {
+            ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
+            ctx.move_to(page.left(), start_h);
+            show_layout(ctx, &hl);
+        }),
+    }
+}
+
+/// Read-aloud texts of a chamber and its doors and objects, hidden ones are left out
+fn chamber_handouts(page: Page, dungeon: &Dungeon, chamber: &Chamber) -> Vec<PdfElement> {
+    let mut elems = vec![];
+    if chamber.hidden {
+        return elems;
+    }
+    if !chamber.read_aloud.trim().is_empty() {
+        let (_, hl) = layout_headline(&page);
+        hl.set_text(&format!("{}: {}", chamber.id, chamber.name));
+        elems.push(handout_headline(page, hl));
+        elems.append(&mut read_aloud_box(page, &chamber.read_aloud));
+    }
+    for door in dungeon.chamber_doors(chamber.id) {
+        if door.hidden || door.read_aloud.trim().is_empty() {
+            continue;
+        }
+        let (_, hl) = layout_secondary_headline(&page);
+        match door.name.is_empty() {
+            true => hl.set_text(&format!(
+                "{}: {} - Door {}",
+                chamber.id, chamber.name, door.id
+            )),
+            false => hl.set_text(&format!("{}: {} - {}", chamber.id, chamber.name, door.name)),
+        };
+        elems.push(handout_headline(page, hl));
+        elems.append(&mut read_aloud_box(page, &door.read_aloud));
+    }
+    for object in dungeon.chamber_objects(chamber.id) {
+        if object.hidden || object.read_aloud.trim().is_empty() {
+            continue;
+        }
+        let (_, hl) = layout_secondary_headline(&page);
+        match object.name.is_empty() {
+            true => hl.set_text(&format!(
+                "{}: {} - Object {}",
+                chamber.id, chamber.name, object.id
+            )),
+            false => hl.set_text(&format!(
+                "{}: {} - {}",
+                chamber.id, chamber.name, object.name
+            )),
+        };
+        elems.push(handout_headline(page, hl));
+        elems.append(&mut read_aloud_box(page, &object.read_aloud));
+    }
+    elems
+}
+
+/// The read-aloud texts alone, to be handed to the players
+pub fn to_handouts_pdf(dungeon: &Dungeon, settings: &ExportSettings, target: Target) {
+    let page = Page::new(settings, settings.page_size());
+    let doc = Document::new(target, page.size());
+
+    let handouts: Vec<PdfElement> = dungeon
+        .chambers()
+        .iter()
+        .flat_map(|chamber| chamber_handouts(page, dungeon, chamber))
+        .collect();
+    let elems: Vec<&PdfElement> = handouts.iter().collect();
+    draw_pages(&page, &doc, dungeon, &elems, 1);
+}
+
+/// Chamber of the cutout sheets with its area in world coordinates, including the door tabs
+struct Cutout<'a> {
+    chamber: &'a Chamber,
+    bbox: BBox,
+    label: pango::Layout,
+}
+
+/// Fold-over tabs outside the walls at the doors of the chamber,
+/// cut along the solid lines and folded at the dashed line.
+fn draw_door_tabs(ctx: &Context, style: &ThemeStyle, dungeon: &Dungeon, chamber: &Chamber) {
+    ctx.set_source_rgb(style.ink.r, style.ink.g, style.ink.b);
+    ctx.set_line_width(1.0);
+    for door in dungeon.chamber_doors(chamber.id) {
+        if door.hidden {
+            continue;
+        }
+        let Some(wall) = dungeon
+            .chamber(door.part_of)
+            .and_then(|c| c.wall(door.on_wall))
+        else {
+            continue;
+        };
+        let pos = wall.rel_to_world(door.position);
+        let tangent = wall.tangent();
+        let mut normal = Vec2 {
+            x: -tangent.y,
+            y: tangent.x,
+        };
+        if chamber.contains_point(pos + TAB_DEPTH * normal) {
+            normal = -1.0 * normal;
+        }
+        let half = door.width / 2.0;
+        let inset = f64::min(TAB_DEPTH / 2.0, half / 2.0);
+        let (from, to) = (pos - half * tangent, pos + half * tangent);
+        let outer_from = pos + TAB_DEPTH * normal - (half - inset) * tangent;
+        let outer_to = pos + TAB_DEPTH * normal + (half - inset) * tangent;
+
+        ctx.move_to(from.x, from.y);
+        ctx.line_to(outer_from.x, outer_from.y);
+        ctx.line_to(outer_to.x, outer_to.y);
+        ctx.line_to(to.x, to.y);
+        ctx.stroke().unwrap();
+
+        ctx.set_dash(&[4.0, 4.0], 0.0);
+        ctx.move_to(from.x, from.y);
+        ctx.line_to(to.x, to.y);
+        ctx.stroke().unwrap();
+        ctx.set_dash(&[], 0.0);
+    }
+}
+
+/// Back of a cutout sheet, the number and name of each chamber behind its cutout.
+/// Pieces are mirrored horizontally for printing on both sides along the long edge.
+fn draw_cutout_backs(page: &Page, ctx: &Context, cutouts: &[(&Cutout, Vec2<f64>, Vec2<f64>)]) {
+    for (cutout, pos, size) in cutouts {
+        let x = page.width - pos.x - size.x;
+        let (_, nl) = page.layout(page.font(48.0, true));
+        nl.set_width((size.x * PANGO_SCALE as f64) as i32);
+        nl.set_alignment(pango::Alignment::Center);
+        nl.set_text(&format!("{}", cutout.chamber.id));
+        let (_, tl) = page.layout(page.font(DEFAULT_FONT_SIZE, false));
+        tl.set_width((size.x * PANGO_SCALE as f64) as i32);
+        tl.set_alignment(pango::Alignment::Center);
+        tl.set_text(&cutout.chamber.name);
+        let nh = nl.extents().1.height() as f64 / PANGO_SCALE as f64;
+        let th = tl.extents().1.height() as f64 / PANGO_SCALE as f64;
+
+        let y = pos.y + (size.y - nh - th) / 2.0;
+        ctx.set_source_rgb(page.style.ink.r, page.style.ink.g, page.style.ink.b);
+        ctx.move_to(x, y);
+        show_layout(ctx, &nl);
+        ctx.move_to(x, y + nh);
+        show_layout(ctx, &tl);
+    }
+}
+
+/// Chambers at true scale, `ExportSettings::cell_size` per grid cell, cut out and
+/// laid on the table as the players explore. The chambers are packed onto as few pages
+/// as possible and labeled with their number, optionally also on back pages.
+pub fn to_player_cutout_pdf(dungeon: &Dungeon, settings: &ExportSettings, target: Target) {
+    let page = Page::new(settings, settings.page_size());
+    let doc = Document::new(target, page.size());
+    let ctx = &doc.ctx;
+    let scale = settings.poster_scale();
+    // the grid stays on the pieces, the paper would be cut away
+    let style = ThemeStyle {
+        paper: None,
+        grid_on_floors: true,
+        hatching: false,
+        ..page.style
+    };
+
+    let padding = Vec2 {
+        x: TAB_DEPTH + page.style.walls.width,
+        y: TAB_DEPTH + page.style.walls.width,
+    };
+    let cutouts: Vec<Cutout> = dungeon
+        .chambers()
+        .iter()
+        .filter(|c| !c.hidden && c.bbox().is_valid())
+        .map(|chamber| {
+            let (_, label) = layout_text(&page);
+            label.set_width(-1);
+            label.set_text(&format!("{}: {}", chamber.id, chamber.name));
             let bbox = chamber.bbox();
-            Vec2 {
-                x: prev.x.max(bbox.max.x - bbox.min.x),
-                y: prev.y.max(bbox.max.y - bbox.min.y),
+            Cutout {
+                chamber,
+                bbox: BBox {
+                    min: bbox.min - padding,
+                    max: bbox.max + padding,
+                },
+                label,
             }
-        },
-    );
-    let pdf = gtk::cairo::PdfSurface::new(PAGE_W, PAGE_H, path).unwrap();
-    let scale = (PAGE_W - (2. * EDGE_SPACING)) / max_size.x;
-    let ctx = Context::new(pdf).unwrap();
+        })
+        .collect();
+    let label_height = |c: &Cutout| c.label.extents().1.height() as f64 / PANGO_SCALE as f64;
+    let pieces: Vec<Piece> = cutouts
+        .iter()
+        .map(|c| {
+            let size = scale * c.bbox.size();
+            let lh = label_height(c);
+            Piece {
+                size: Vec2 {
+                    x: size.x,
+                    y: size.y + lh,
+                },
+                turned: settings.cutout_rotation.then_some(Vec2 {
+                    x: size.y,
+                    y: size.x + lh,
+                }),
+            }
+        })
+        .collect();
+    let area = Vec2 {
+        x: page.text_width(),
+        y: page.end_h() - page.start_h(),
+    };
+    let placements = pack(&pieces, area, CUTOUT_SPACING);
+    let pages = placements.iter().map(|p| p.page + 1).max().unwrap_or(1);
 
-    let mut cur_h = START_H;
-    let mut cur_page_number = 1;
-    for chamber in dungeon.chambers() {
-        let next_h = cur_h + chamber.bbox().size().y * scale + 12.0;
-        if next_h > END_H {
-            finalize_page(&ctx, cur_page_number);
-            cur_page_number += 1;
-            // start new page
-            ctx.show_page().unwrap();
-            cur_h = START_H;
+    let mut page_number = 1;
+    for sheet in 0..pages {
+        if sheet > 0 {
+            doc.show_page();
+        }
+        let mut backs = vec![];
+        for (i, placement) in placements.iter().enumerate() {
+            if placement.page != sheet {
+                continue;
+            }
+            let cutout = &cutouts[i];
+            let lh = label_height(cutout);
+            let pos = Vec2 {
+                x: page.left() + placement.pos.x,
+                y: page.start_h() + placement.pos.y,
+            };
+            let size = match placement.turned {
+                true => pieces[i].turned.unwrap(),
+                false => pieces[i].size,
+            };
+            backs.push((cutout, pos, size));
+
+            ctx.set_source_rgb(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b);
+            ctx.move_to(pos.x, pos.y);
+            show_layout(ctx, &cutout.label);
+
+            ctx.save().unwrap();
+            ctx.rectangle(pos.x, pos.y + lh, size.x, size.y - lh);
+            ctx.clip();
+            ctx.new_path();
+            if placement.turned {
+                ctx.translate(pos.x + size.x, pos.y + lh);
+                ctx.rotate(f64::consts::FRAC_PI_2);
+            } else {
+                ctx.translate(pos.x, pos.y + lh);
+            }
+            ctx.scale(scale, scale);
+            ctx.translate(-cutout.bbox.min.x, -cutout.bbox.min.y);
+            let prims = chamber_primitives(dungeon, &style, cutout.chamber, false);
+            draw_themed(ctx, &style, &prims, &cutout.bbox, &[cutout.chamber.shape()]);
+            draw_door_tabs(ctx, &style, dungeon, cutout.chamber);
+            ctx.restore().unwrap();
+        }
+        finalize_page(&page, ctx, page_number);
+        page_number += 1;
+
+        if settings.cutout_backs && !backs.is_empty() {
+            doc.show_page();
+            draw_cutout_backs(&page, ctx, &backs);
+            finalize_page(&page, ctx, page_number);
+            page_number += 1;
         }
-        cur_h += draw_chamber(
-            dungeon,
-            chamber,
-            cur_h,
-            scale * chamber.bbox().size(),
-            &ctx,
-            false,
-        ) + 12.0;
     }
-    // add page number to last page
-    finalize_page(&ctx, cur_page_number);
 }
 
-pub fn to_full_player_map_pdf(dungeon: &Dungeon, path: String) {
+pub fn to_full_player_map_pdf(dungeon: &Dungeon, settings: &ExportSettings, target: Target) {
     // Draw entire dungeon
-    let all_prims = dungeon_to_primitives(dungeon, false);
+    let all_prims = dungeon_to_primitives(dungeon, &settings.theme.style(), false, None);
     // early abort of dungeon is empty (nothing to draw)
     if all_prims.len() == 0 {
         return;
@@ -688,80 +1860,305 @@ pub fn to_full_player_map_pdf(dungeon: &Dungeon, path: String) {
     }
 
     let size = bbox.max - bbox.min;
-    // determine if page should be horizontal or vertical
-    let vertical = size.y > size.x;
-    let (pdf, max_scale_x, max_scale_y) = if vertical {
-        (
-            gtk::cairo::PdfSurface::new(PAGE_W, PAGE_H, path).unwrap(),
-            (PAGE_W - (2. * EDGE_SPACING)) / size.x,
-            (PAGE_H - (2. * EDGE_SPACING)) / size.y,
-        )
-    } else {
-        (
-            gtk::cairo::PdfSurface::new(PAGE_H, PAGE_W, path).unwrap(),
-            (PAGE_H - (2. * EDGE_SPACING)) / size.x,
-            (PAGE_W - (2. * EDGE_SPACING)) / size.y,
-        )
-    };
+    // the page is turned to fit the dungeon, unless the orientation is set
+    let page = Page::new(settings, settings.page_size_for(Some(size)));
+    let doc = Document::new(target, page.size());
+    let ctx = &doc.ctx;
+    let max_scale_x = (page.width - (2. * page.margin)) / size.x;
+    let max_scale_y = (page.height - (2. * page.margin)) / size.y;
     let scale = f64::min(max_scale_x, max_scale_y);
 
-    let ctx = Context::new(pdf).unwrap();
+    ctx.translate(
+        -bbox.min.x * scale + page.margin,
+        -bbox.min.y * scale + page.margin,
+    );
+    ctx.scale(scale, scale);
 
-    if vertical {
-        ctx.translate(
-            -bbox.min.x * scale + EDGE_SPACING,
-            -bbox.min.y * scale + EDGE_SPACING,
-        );
-    } else {
-        ctx.translate(
-            -bbox.min.x * scale + EDGE_SPACING,
-            -bbox.min.y * scale + EDGE_SPACING,
-        );
+    draw_map(dungeon, ctx, &page.style, &all_prims, &bbox, false);
+
+    ctx.reset_clip();
+    ctx.identity_matrix();
+}
+
+/// Name of a poster page, rows are lettered and columns numbered like "B3"
+fn tile_name(row: usize, col: usize) -> String {
+    let mut letters = vec![];
+    let mut n = row + 1;
+    while n > 0 {
+        letters.insert(0, (b'A' + ((n - 1) % 26) as u8) as char);
+        n = (n - 1) / 26;
+    }
+    format!("{}{}", letters.into_iter().collect::<String>(), col + 1)
+}
+
+/// Layout of the battle map poster, sizes in points
+struct Poster {
+    page: Page,
+    /// points per world unit
+    scale: f64,
+    tiles: Vec2<usize>,
+    printable: Vec2<f64>,
+    overlap: f64,
+    bbox: BBox,
+}
+
+impl Poster {
+    /// top left corner of a page in world coordinates
+    fn origin(&self, row: usize, col: usize) -> Vec2<f64> {
+        Vec2 {
+            x: self.bbox.min.x + col as f64 * (self.printable.x - self.overlap) / self.scale,
+            y: self.bbox.min.y + row as f64 * (self.printable.y - self.overlap) / self.scale,
+        }
     }
+}
+
+/// First page of the poster, showing which part of the map is printed on which page
+fn draw_poster_overview(
+    poster: &Poster,
+    dungeon: &Dungeon,
+    settings: &ExportSettings,
+    ctx: &Context,
+    prims: &[Box<dyn Primitive>],
+) {
+    let page = &poster.page;
+    let mut cur_h = page.start_h();
+    let (_, hl) = layout_headline(page);
+    hl.set_text(&dungeon.name);
+    ctx.set_source_rgba(HEADLINE_COLOR.r, HEADLINE_COLOR.g, HEADLINE_COLOR.b, 1.0);
+    ctx.move_to(page.left(), cur_h);
+    show_layout(ctx, &hl);
+    cur_h += (hl.extents().0.height() as f64 / PANGO_SCALE as f64) + TEXT_SPACING;
+
+    let (_, tl) = layout_text(page);
+    tl.set_text(&format!(
+        "Battle map with {} mm per grid cell on {} x {} pages. \
+        Cut off the top and left margins of each page outside the first row and column \
+        and lay the page onto its neighbours along their dashed lines, \
+        so that the short marks continue across the edges.",
+        settings.cell_size, poster.tiles.x, poster.tiles.y
+    ));
+    ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
+    ctx.move_to(page.left(), cur_h);
+    show_layout(ctx, &tl);
+    cur_h += (tl.extents().0.height() as f64 / PANGO_SCALE as f64) + TITLE_SPACING;
+
+    // the area covered by all pages
+    let covered = poster.origin(poster.tiles.y - 1, poster.tiles.x - 1)
+        + (1.0 / poster.scale) * poster.printable
+        - poster.bbox.min;
+    let scale = f64::min(
+        page.text_width() / covered.x,
+        (page.end_h() - cur_h) / covered.y,
+    );
+    ctx.save().unwrap();
+    ctx.translate(
+        page.left() - poster.bbox.min.x * scale,
+        cur_h - poster.bbox.min.y * scale,
+    );
     ctx.scale(scale, scale);
+    draw_map(dungeon, ctx, &page.style, prims, &poster.bbox, false);
+    ctx.reset_clip();
 
-    let mut grid = Grid::new();
-    grid.color = Rgb {
-        r: 0.5,
-        g: 0.5,
-        b: 0.5,
-    };
-    grid.width = 1.0;
+    let (_, ll) = page.layout(page.font(14.0, true));
+    ll.set_width(-1);
+    for row in 0..poster.tiles.y {
+        for col in 0..poster.tiles.x {
+            let origin = poster.origin(row, col);
+            let size = (1.0 / poster.scale) * poster.printable;
+            ctx.set_source_rgba(0.8, 0.0, 0.0, 1.0);
+            ctx.set_line_width(1.0 / scale);
+            ctx.rectangle(origin.x, origin.y, size.x, size.y);
+            ctx.stroke().unwrap();
 
-    // set clipping
-    ctx.rectangle(bbox.min.x, bbox.min.y, size.x, size.y);
-    ctx.clip();
-    ctx.new_path();
+            ll.set_text(&tile_name(row, col));
+            let extents = ll.extents().1;
+            let center = origin + 0.5 * size;
+            ctx.save().unwrap();
+            ctx.translate(center.x, center.y);
+            ctx.scale(1.0 / scale, 1.0 / scale);
+            ctx.move_to(
+                -extents.width() as f64 / PANGO_SCALE as f64 / 2.0,
+                -extents.height() as f64 / PANGO_SCALE as f64 / 2.0,
+            );
+            show_layout(ctx, &ll);
+            ctx.restore().unwrap();
+        }
+    }
+    ctx.restore().unwrap();
+}
 
-    // draw grid
-    ctx.set_dash(&vec![10.0, 10.0], 0.0);
-    for prim in grid.draw(bbox.min.into(), bbox.max.into()) {
-        prim.draw(&ctx)
+/// Alignment marks and the name of a poster page, in page coordinates
+fn draw_poster_marks(poster: &Poster, ctx: &Context, row: usize, col: usize) {
+    let page = &poster.page;
+    let (left, top) = (page.margin, page.margin);
+    let (right, bottom) = (left + poster.printable.x, top + poster.printable.y);
+    ctx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+    ctx.set_line_width(0.5);
+
+    // crop marks at the corners of the printed area, within the margin
+    for (x, dx) in [(left, -1.0), (right, 1.0)] {
+        for (y, dy) in [(top, -1.0), (bottom, 1.0)] {
+            ctx.move_to(x, y + dy * 2.0);
+            ctx.line_to(x, y + dy * page.margin);
+            ctx.move_to(x + dx * 2.0, y);
+            ctx.line_to(x + dx * page.margin, y);
+        }
     }
-    ctx.set_dash(&vec![], 0.0);
+    ctx.stroke().unwrap();
 
-    // draw chamber
-    for prim in all_prims.iter() {
-        prim.draw(&ctx)
+    // the next page to the right or below is aligned at the dashed lines
+    ctx.set_dash(&[4.0, 4.0], 0.0);
+    if col + 1 < poster.tiles.x {
+        ctx.move_to(right - poster.overlap, top);
+        ctx.line_to(right - poster.overlap, bottom);
+    }
+    if row + 1 < poster.tiles.y {
+        ctx.move_to(left, bottom - poster.overlap);
+        ctx.line_to(right, bottom - poster.overlap);
+    }
+    ctx.stroke().unwrap();
+    ctx.set_dash(&[], 0.0);
+
+    // short marks continuing across the edge of the page laid onto this one,
+    // at the same spot of the map on both pages
+    let tick = 8.0;
+    let along = |from: f64, length: f64| [from + length / 4.0, from + length * 3.0 / 4.0];
+    for y in along(top, poster.printable.y) {
+        if col > 0 {
+            ctx.move_to(left, y);
+            ctx.line_to(left + tick, y);
+        }
+        if col + 1 < poster.tiles.x {
+            ctx.move_to(right - poster.overlap - tick, y);
+            ctx.line_to(right - poster.overlap, y);
+        }
     }
+    for x in along(left, poster.printable.x) {
+        if row > 0 {
+            ctx.move_to(x, top);
+            ctx.line_to(x, top + tick);
+        }
+        if row + 1 < poster.tiles.y {
+            ctx.move_to(x, bottom - poster.overlap - tick);
+            ctx.line_to(x, bottom - poster.overlap);
+        }
+    }
+    ctx.stroke().unwrap();
+
+    // name of the page on a white box in the top left corner
+    let (_, nl) = layout_page_number(page);
+    nl.set_alignment(pango::Alignment::Left);
+    nl.set_width(-1);
+    nl.set_text(&format!(
+        "{} ({} of {})",
+        tile_name(row, col),
+        row * poster.tiles.x + col + 1,
+        poster.tiles.x * poster.tiles.y
+    ));
+    let extents = nl.extents().1;
+    let padding = 2.0;
+    ctx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+    ctx.rectangle(
+        left,
+        top,
+        extents.width() as f64 / PANGO_SCALE as f64 + 2.0 * padding,
+        extents.height() as f64 / PANGO_SCALE as f64 + 2.0 * padding,
+    );
+    ctx.fill().unwrap();
+    ctx.set_source_rgba(NOTES_COLOR.r, NOTES_COLOR.g, NOTES_COLOR.b, 1.0);
+    ctx.move_to(left + padding, top + padding);
+    show_layout(ctx, &nl);
+}
 
-    ctx.reset_clip();
-    ctx.identity_matrix();
-    ctx.show_page().unwrap();
+/// Player map at true scale, `ExportSettings::cell_size` per grid cell,
+/// tiled across as many pages as needed for assembling a map for miniatures.
+/// The first page shows which part of the map is printed on which page.
+pub fn to_poster_pdf(dungeon: &Dungeon, settings: &ExportSettings, target: Target) {
+    let all_prims = dungeon_to_primitives(dungeon, &settings.theme.style(), false, None);
+    if all_prims.is_empty() {
+        return;
+    }
+    let bbox = prims_to_bbox(&all_prims);
+    if !bbox.is_valid() {
+        return;
+    }
+
+    let scale = settings.poster_scale();
+    let size = scale * (bbox.max - bbox.min);
+    let page = Page::new(settings, settings.page_size_for(Some(size)));
+    let poster = Poster {
+        page,
+        scale,
+        tiles: settings.poster_tiles(size),
+        printable: Vec2 {
+            x: page.width - 2.0 * page.margin,
+            y: page.height - 2.0 * page.margin,
+        },
+        overlap: settings.overlap_pt(),
+        bbox,
+    };
+    let doc = Document::new(target, page.size());
+    let ctx = &doc.ctx;
+
+    draw_poster_overview(&poster, dungeon, settings, ctx, &all_prims);
+
+    for row in 0..poster.tiles.y {
+        for col in 0..poster.tiles.x {
+            doc.show_page();
+            let origin = poster.origin(row, col);
+            ctx.save().unwrap();
+            ctx.rectangle(
+                page.margin,
+                page.margin,
+                poster.printable.x,
+                poster.printable.y,
+            );
+            ctx.clip();
+            ctx.new_path();
+            ctx.translate(
+                page.margin - origin.x * scale,
+                page.margin - origin.y * scale,
+            );
+            ctx.scale(scale, scale);
+            draw_map(dungeon, ctx, &page.style, &all_prims, &poster.bbox, false);
+            ctx.restore().unwrap();
+
+            draw_poster_marks(&poster, ctx, row, col);
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{chamber::Chamber, dungeon::Dungeon};
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use cairo::{Context, Format, ImageSurface, PdfVersion};
+
+    use crate::{
+        chamber::Chamber,
+        common::{Rgb, Vec2},
+        door::Door,
+        dungeon::{tests::add_rect, Dungeon},
+        export_settings::ExportSettings,
+        print_theme::{PrintTheme, PRINT_THEMES},
+        selection::Selection,
+        state::{State, StateCommand},
+        view::primitives::Primitive,
+    };
 
-    use super::{to_full_player_map_pdf, to_pdf, to_player_cutout_pdf};
+    use super::{
+        appendix_sections, draw_notes, dungeon_to_primitives, paginate, player_view_primitives,
+        tile_name, to_full_player_map_pdf, to_handouts_pdf, to_pdf, to_player_cutout_pdf,
+        to_poster_pdf, Document, Page, Target, DEST_DOORS, DEST_HIDDEN, DEST_INDEX,
+    };
 
     #[test]
     fn test_to_full_player_map_pdf_empty() {
         let dungeon = &Dungeon::new();
         to_full_player_map_pdf(
             dungeon,
-            "/tmp/test_to_full_player_map_pdf_empty.pdf".to_string(),
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_full_player_map_pdf_empty.pdf".to_string()),
         )
     }
     #[test]
@@ -771,21 +2168,30 @@ mod test {
         dungeon.add_chamber(chamber);
         to_full_player_map_pdf(
             &dungeon,
-            "/tmp/test_to_full_player_map_pdf_empty_chamber.pdf".to_string(),
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_full_player_map_pdf_empty_chamber.pdf".to_string()),
         )
     }
 
     #[test]
     fn test_to_pdf_empty() {
         let dungeon = &Dungeon::new();
-        to_pdf(dungeon, "/tmp/test_to_pdf_empty.pdf".to_string())
+        to_pdf(
+            dungeon,
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_pdf_empty.pdf".to_string()),
+        )
     }
     #[test]
     fn test_to_pdf_empty_chamber() {
         let mut dungeon = Dungeon::new();
         let chamber = Chamber::new();
         dungeon.add_chamber(chamber);
-        to_pdf(&dungeon, "/tmp/test_to_pdf_empty_chamber.pdf".to_string())
+        to_pdf(
+            &dungeon,
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_pdf_empty_chamber.pdf".to_string()),
+        )
     }
 
     #[test]
@@ -793,7 +2199,8 @@ mod test {
         let dungeon = &Dungeon::new();
         to_player_cutout_pdf(
             dungeon,
-            "/tmp/test_to_player_cutout_pdf_empty.pdf".to_string(),
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_player_cutout_pdf_empty.pdf".to_string()),
         )
     }
     #[test]
@@ -803,7 +2210,345 @@ mod test {
         dungeon.add_chamber(chamber);
         to_player_cutout_pdf(
             &dungeon,
-            "/tmp/test_to_player_cutout_pdf_empty_chamber.pdf".to_string(),
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_player_cutout_pdf_empty_chamber.pdf".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_to_poster_pdf_empty() {
+        let dungeon = &Dungeon::new();
+        to_poster_pdf(
+            dungeon,
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_poster_pdf_empty.pdf".to_string()),
         )
     }
+
+    #[test]
+    fn test_to_handouts_pdf() {
+        let mut state = State::new();
+        let chamber_id = add_rect(&mut state.dungeon, 0, 0, 100, 100);
+        StateCommand::ChangeChamberReadAloud(chamber_id, "A *dark* hall.".to_owned())
+            .execute(&mut state);
+        to_handouts_pdf(
+            &state.dungeon,
+            &ExportSettings::new(),
+            Target::Pdf("/tmp/test_to_handouts_pdf.pdf".to_string()),
+        )
+    }
+
+    /// color of a pixel after drawing the primitives on the paper, offset by 10 pixels
+    fn pixel(prims: &[Box<dyn Primitive>], paper: Rgb, x: i32, y: i32) -> Rgb {
+        let mut surface = ImageSurface::create(Format::Rgb24, 200, 150).unwrap();
+        {
+            let ctx = Context::new(&surface).unwrap();
+            ctx.set_source_rgb(paper.r, paper.g, paper.b);
+            ctx.paint().unwrap();
+            ctx.translate(10.0, 10.0);
+            for prim in prims {
+                prim.draw(&ctx);
+            }
+        }
+        let stride = surface.stride() as usize;
+        let data = surface.data().unwrap();
+        let p = u32::from_ne_bytes(
+            data[(y + 10) as usize * stride + (x + 10) as usize * 4..][..4]
+                .try_into()
+                .unwrap(),
+        );
+        let channel = |shift: u32| ((p >> shift) & 0xff) as f64 / 255.0;
+        Rgb {
+            r: channel(16),
+            g: channel(8),
+            b: channel(0),
+        }
+    }
+
+    #[test]
+    fn test_exports_with_themes() {
+        let mut dungeon = Dungeon::new();
+        add_rect(&mut dungeon, 0, 0, 150, 100);
+        let near = |a: Rgb, b: Rgb| {
+            (a.r - b.r).abs() < 0.02 && (a.g - b.g).abs() < 0.02 && (a.b - b.b).abs() < 0.02
+        };
+        for theme in PRINT_THEMES {
+            // the floor is the fill over the paper, the walls are drawn in ink
+            let style = theme.style();
+            let prims = dungeon_to_primitives(&dungeon, &style, false, None);
+            let paper = style.paper.unwrap_or(Rgb {
+                r: 1.0,
+                g: 1.0,
+                b: 1.0,
+            });
+            let opacity = style.walls.fill_opacity;
+            let floor = Rgb {
+                r: style.walls.fill.r * opacity + paper.r * (1.0 - opacity),
+                g: style.walls.fill.g * opacity + paper.g * (1.0 - opacity),
+                b: style.walls.fill.b * opacity + paper.b * (1.0 - opacity),
+            };
+            assert!(near(pixel(&prims, paper, 20, 20), floor), "{:?}", theme);
+            assert!(near(pixel(&prims, paper, 0, 50), style.ink), "{:?}", theme);
+            if theme == PrintTheme::Editor {
+                // the floor stays light on white paper
+                assert!(near(floor, paper));
+            }
+
+            let mut settings = ExportSettings::new();
+            settings.theme = theme;
+            let name = theme.to_str().replace(' ', "_");
+            to_pdf(
+                &dungeon,
+                &settings,
+                Target::Pdf(format!("/tmp/test_to_pdf_{}.pdf", name)),
+            );
+            to_player_cutout_pdf(
+                &dungeon,
+                &settings,
+                Target::Pdf(format!("/tmp/test_to_player_cutout_pdf_{}.pdf", name)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_player_view_primitives() {
+        let mut state = State::new();
+        add_rect(&mut state.dungeon, 0, 0, 100, 100);
+        add_rect(&mut state.dungeon, 200, 0, 300, 100);
+        StateCommand::AddObject(Vec2 { x: 250, y: 50 }, Some(2)).execute(&mut state);
+        let right_of = |prims: &Vec<Box<dyn Primitive>>, x: f64| {
+            prims.iter().filter(|p| p.bbox().max.x > x).count()
+        };
+
+        // the area of the whole dungeon is kept while nothing is revealed
+        let (prims, bbox) = player_view_primitives(&state.dungeon, &state.session.revealed);
+        assert!(prims.is_empty());
+        assert_eq!(bbox.max.x, 350.0);
+
+        let chamber_1 = Selection {
+            chambers: vec![1],
+            ..Selection::new()
+        };
+        StateCommand::ChangeEntitiesRevealed(chamber_1, true).execute(&mut state);
+        let (prims, _) = player_view_primitives(&state.dungeon, &state.session.revealed);
+        assert!(!prims.is_empty());
+        assert_eq!(right_of(&prims, 150.0), 0);
+
+        // a secret door is shown once the players found it
+        let wall = state.dungeon.chamber(1).unwrap().walls()[0];
+        let mut secret = Door::new(1, None, 50.0, wall.id, 0.5);
+        secret.hidden = true;
+        StateCommand::AddDoor(secret).execute(&mut state);
+        let (chamber_prims, _) = player_view_primitives(&state.dungeon, &state.session.revealed);
+        assert_eq!(chamber_prims.len(), prims.len());
+        let secret_door = Selection {
+            doors: vec![1],
+            ..Selection::new()
+        };
+        StateCommand::ChangeEntitiesRevealed(secret_door, true).execute(&mut state);
+        let (prims, _) = player_view_primitives(&state.dungeon, &state.session.revealed);
+        assert!(prims.len() > chamber_prims.len());
+
+        // objects are shown with their chamber
+        let chamber_2 = Selection {
+            chambers: vec![2],
+            ..Selection::new()
+        };
+        StateCommand::ChangeEntitiesRevealed(chamber_2, true).execute(&mut state);
+        let (all_prims, _) = player_view_primitives(&state.dungeon, &state.session.revealed);
+        StateCommand::ChangeObjectHidden(1, true).execute(&mut state);
+        let (prims, _) = player_view_primitives(&state.dungeon, &state.session.revealed);
+        assert!(right_of(&all_prims, 150.0) > right_of(&prims, 150.0));
+        assert!(right_of(&prims, 150.0) > 0);
+    }
+
+    #[test]
+    fn test_tile_name() {
+        assert_eq!(tile_name(0, 0), "A1");
+        assert_eq!(tile_name(1, 2), "B3");
+        assert_eq!(tile_name(25, 0), "Z1");
+        assert_eq!(tile_name(26, 9), "AA10");
+    }
+
+    #[test]
+    fn test_to_pdf_linked_chambers() {
+        let mut state = State::new();
+        add_rect(&mut state.dungeon, 0, 0, 100, 100);
+        add_rect(&mut state.dungeon, 100, 0, 200, 100);
+        StateCommand::ChangeChamberName(1, "Hall".to_owned()).execute(&mut state);
+        StateCommand::ChangeChamberName(2, "Crypt".to_owned()).execute(&mut state);
+        let wall = state.dungeon.chamber(1).unwrap().walls()[1];
+        StateCommand::AddDoor(Door::new(1, Some(2), 50.0, wall.id, 0.5)).execute(&mut state);
+        let settings = ExportSettings::new();
+        let path = "/tmp/test_to_pdf_linked_chambers.pdf";
+        {
+            let doc = Document::new(Target::Pdf(path.to_owned()), settings.page_size());
+            // the object streams of PDF 1.5 would compress the outline and the links
+            doc.pdf
+                .as_ref()
+                .unwrap()
+                .restrict(PdfVersion::_1_4)
+                .unwrap();
+            draw_notes(&doc, &state.dungeon, &settings);
+        }
+        let pdf = String::from_utf8_lossy(&std::fs::read(path).unwrap()).to_string();
+        let strings = |key: &str| -> Vec<String> {
+            pdf.split(&format!("{} (", key))
+                .skip(1)
+                .map(|s| s[..s.find(')').unwrap()].to_owned())
+                .collect()
+        };
+        // named destinations, one per line
+        let names: Vec<String> = pdf
+            .split("/Names [")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .take_while(|l| l.trim() != "]")
+            .filter_map(|l| l.trim().strip_prefix('('))
+            .map(|l| l[..l.find(')').unwrap()].to_owned())
+            .collect();
+
+        assert_eq!(
+            strings("/Title"),
+            vec!["Map", "Contents", "Dungeon", "1: Hall", "2: Crypt"]
+        );
+        for dest in ["map", "contents", "dungeon", "chamber-1", "chamber-2"] {
+            assert!(names.contains(&dest.to_owned()), "{}", dest);
+        }
+        // every link and outline entry leads to a destination
+        let dests = strings("/Dest");
+        assert!(dests.iter().all(|d| names.contains(d)));
+        // the outline, the contents, the number on the map and the door of the other chamber
+        for chamber in ["chamber-1", "chamber-2"] {
+            assert_eq!(dests.iter().filter(|d| *d == chamber).count(), 4);
+        }
+    }
+
+    #[test]
+    fn test_to_pdf_appendices() {
+        let mut state = State::new();
+        add_rect(&mut state.dungeon, 0, 0, 100, 100);
+        add_rect(&mut state.dungeon, 100, 0, 200, 100);
+        StateCommand::ChangeChamberHidden(2, true).execute(&mut state);
+        let wall = state.dungeon.chamber(1).unwrap().walls()[1];
+        StateCommand::AddDoor(Door::new(1, Some(2), 50.0, wall.id, 0.5)).execute(&mut state);
+        StateCommand::ChangeDoorName(1, "Secret Door".to_owned()).execute(&mut state);
+        StateCommand::ChangeDoorHidden(1, true).execute(&mut state);
+        StateCommand::AddObject(Vec2 { x: 50, y: 50 }, Some(1)).execute(&mut state);
+        StateCommand::ChangeObjectName(1, "Altar".to_owned()).execute(&mut state);
+        let mut settings = ExportSettings::new();
+        let page = Page::new(&settings, settings.page_size());
+        let pages = HashMap::from([(1, 3), (2, 4)]);
+        assert!(appendix_sections(page, &state.dungeon, &settings, &pages).is_empty());
+
+        settings.index_appendix = true;
+        settings.hidden_appendix = true;
+        settings.doors_appendix = true;
+        let sections = appendix_sections(page, &state.dungeon, &settings, &pages);
+        let titles: Vec<(&str, &str)> = sections
+            .iter()
+            .map(|s| (s.title.as_str(), s.dest.as_str()))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("Index of Objects and Doors", DEST_INDEX),
+                ("Hidden Features", DEST_HIDDEN),
+                ("Door Connections", DEST_DOORS),
+            ]
+        );
+        // headline, table header, a row for each entry and the separator
+        let rows: Vec<usize> = sections.iter().map(|s| s.elems.len() - 3).collect();
+        // the altar and the door, the hidden chamber and door, the door
+        assert_eq!(rows, vec![2, 2, 1]);
+
+        settings.index_appendix = false;
+        let sections = appendix_sections(page, &state.dungeon, &settings, &pages);
+        assert_eq!(sections[0].dest, DEST_HIDDEN);
+
+        to_pdf(
+            &state.dungeon,
+            &settings,
+            Target::Pdf("/tmp/test_to_pdf_appendices.pdf".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_to_player_cutout_pdf_packed() {
+        let mut state = State::new();
+        add_rect(&mut state.dungeon, 0, 0, 100, 100);
+        // wider than the page, only fits when turned
+        add_rect(&mut state.dungeon, 100, 0, 550, 100);
+        let wall = state.dungeon.chamber(1).unwrap().walls()[1];
+        StateCommand::AddDoor(Door::new(1, Some(2), 50.0, wall.id, 0.5)).execute(&mut state);
+        let mut settings = ExportSettings::new();
+        let page_count = |settings: &ExportSettings| {
+            let pages = Rc::new(RefCell::new(vec![]));
+            to_player_cutout_pdf(&state.dungeon, settings, Target::Preview(pages.clone()));
+            let count = pages.borrow().len();
+            count
+        };
+        // both cutouts share a page, the wide one is turned
+        assert_eq!(page_count(&settings), 1);
+        // followed by a page for their backs
+        settings.cutout_backs = true;
+        assert_eq!(page_count(&settings), 2);
+        to_player_cutout_pdf(
+            &state.dungeon,
+            &settings,
+            Target::Pdf("/tmp/test_to_player_cutout_pdf_packed.pdf".to_string()),
+        );
+        // unturned the wide cutout gets a page of its own, each with a page for the backs
+        settings.cutout_rotation = false;
+        assert_eq!(page_count(&settings), 4);
+        settings.cutout_backs = false;
+        assert_eq!(page_count(&settings), 2);
+        to_player_cutout_pdf(
+            &state.dungeon,
+            &settings,
+            Target::Pdf("/tmp/test_to_player_cutout_pdf_unturned.pdf".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_preview_pages() {
+        let mut dungeon = Dungeon::new();
+        add_rect(&mut dungeon, 0, 0, 150, 100);
+        let settings = ExportSettings::new();
+        let pages = Rc::new(RefCell::new(vec![]));
+
+        // map, contents and notes
+        to_pdf(&dungeon, &settings, Target::Preview(pages.clone()));
+        assert_eq!(pages.borrow().len(), 3);
+        assert_eq!(
+            pages.borrow()[0].size,
+            Page::new(&settings, settings.page_size()).size()
+        );
+
+        pages.borrow_mut().clear();
+        to_full_player_map_pdf(&dungeon, &settings, Target::Preview(pages.clone()));
+        assert_eq!(pages.borrow().len(), 1);
+
+        // overview and a single tile
+        pages.borrow_mut().clear();
+        to_poster_pdf(&dungeon, &settings, Target::Preview(pages.clone()));
+        assert_eq!(pages.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_paginate() {
+        let page = Page::new(&ExportSettings::new(), ExportSettings::new().page_size());
+        let space = page.end_h() - page.start_h();
+        let positions = paginate(&page, [space / 2.0, space / 2.0, 1.0, space].into_iter(), 3);
+        assert_eq!(
+            positions,
+            vec![
+                (3, page.start_h()),
+                (3, page.start_h() + space / 2.0),
+                (4, page.start_h()),
+                (5, page.start_h()),
+            ]
+        );
+    }
 }
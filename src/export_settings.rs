@@ -0,0 +1,334 @@
+use crate::{
+    chamber::ChamberId, common::Vec2, config::GRID_SIZE, dungeon::Dungeon, print_theme::PrintTheme,
+};
+
+/// points per millimeter, PDF sizes are given in points
+pub const MM: f64 = 72.0 / 25.4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PageFormat {
+    A5,
+    A4,
+    A3,
+    Letter,
+    Legal,
+    Tabloid,
+    /// size given by `ExportSettings::custom_size`
+    Custom,
+}
+
+pub const PAGE_FORMATS: [PageFormat; 7] = [
+    PageFormat::A5,
+    PageFormat::A4,
+    PageFormat::A3,
+    PageFormat::Letter,
+    PageFormat::Legal,
+    PageFormat::Tabloid,
+    PageFormat::Custom,
+];
+
+impl PageFormat {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            PageFormat::A5 => "A5",
+            PageFormat::A4 => "A4",
+            PageFormat::A3 => "A3",
+            PageFormat::Letter => "Letter",
+            PageFormat::Legal => "Legal",
+            PageFormat::Tabloid => "Tabloid",
+            PageFormat::Custom => "Custom",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        PAGE_FORMATS
+            .into_iter()
+            .find(|f| f.to_str() == s)
+            .unwrap_or(PageFormat::A4)
+    }
+
+    /// portrait size in millimeters, `None` for a custom size
+    pub fn size_mm(self) -> Option<Vec2<f64>> {
+        match self {
+            PageFormat::A5 => Some(Vec2 { x: 148.0, y: 210.0 }),
+            PageFormat::A4 => Some(Vec2 { x: 210.0, y: 297.0 }),
+            PageFormat::A3 => Some(Vec2 { x: 297.0, y: 420.0 }),
+            PageFormat::Letter => Some(Vec2 { x: 215.9, y: 279.4 }),
+            PageFormat::Legal => Some(Vec2 { x: 215.9, y: 355.6 }),
+            PageFormat::Tabloid => Some(Vec2 { x: 279.4, y: 431.8 }),
+            PageFormat::Custom => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    /// portrait for text pages, maps are turned to fit the dungeon
+    Auto,
+    Portrait,
+    Landscape,
+}
+
+pub const ORIENTATIONS: [Orientation; 3] = [
+    Orientation::Auto,
+    Orientation::Portrait,
+    Orientation::Landscape,
+];
+
+impl Orientation {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Orientation::Auto => "Auto",
+            Orientation::Portrait => "Portrait",
+            Orientation::Landscape => "Landscape",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        ORIENTATIONS
+            .into_iter()
+            .find(|o| o.to_str() == s)
+            .unwrap_or(Orientation::Auto)
+    }
+}
+
+/// Page setup of the PDF exports, stored with the dungeon
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExportSettings {
+    pub format: PageFormat,
+    /// portrait page size in millimeters, used for `PageFormat::Custom`
+    pub custom_size: Vec2<f64>,
+    pub orientation: Orientation,
+    /// margin on all sides in millimeters
+    pub margin: f64,
+    /// font size of notes in points, titles and headlines are scaled along
+    pub font_size: f64,
+    /// size of a grid cell on the battle map poster and the cutouts in millimeters
+    pub cell_size: f64,
+    /// overlap of neighbouring poster pages in millimeters
+    pub overlap: f64,
+    /// look of the printed maps and font of the text
+    pub theme: PrintTheme,
+    /// cutouts may be turned by a quarter to fit more on a page
+    pub cutout_rotation: bool,
+    /// the chamber numbers are printed on the back of the cutouts, for duplex printing
+    pub cutout_backs: bool,
+    /// appendix of the GM PDF listing the named objects and doors
+    pub index_appendix: bool,
+    /// appendix of the GM PDF listing the hidden chambers, doors and objects
+    pub hidden_appendix: bool,
+    /// appendix of the GM PDF listing the doors and the chambers they connect
+    pub doors_appendix: bool,
+}
+
+impl ExportSettings {
+    pub fn new() -> Self {
+        Self {
+            format: PageFormat::A4,
+            custom_size: Vec2 { x: 210.0, y: 297.0 },
+            orientation: Orientation::Auto,
+            margin: 5.0,
+            font_size: 10.0,
+            cell_size: 25.4,
+            overlap: 10.0,
+            theme: PrintTheme::Editor,
+            cutout_rotation: true,
+            cutout_backs: false,
+            index_appendix: false,
+            hidden_appendix: false,
+            doors_appendix: false,
+        }
+    }
+
+    /// page size in points for text pages
+    pub fn page_size(&self) -> Vec2<f64> {
+        self.page_size_for(None)
+    }
+
+    /// Page size in points for a map of the given size.
+    /// With automatic orientation wide maps are printed in landscape.
+    pub fn page_size_for(&self, content: Option<Vec2<f64>>) -> Vec2<f64> {
+        let size = self.format.size_mm().unwrap_or(self.custom_size);
+        let (short, long) = (size.x.min(size.y) * MM, size.x.max(size.y) * MM);
+        let landscape = match self.orientation {
+            Orientation::Auto => content.is_some_and(|c| c.x > c.y),
+            Orientation::Portrait => false,
+            Orientation::Landscape => true,
+        };
+        match landscape {
+            true => Vec2 { x: long, y: short },
+            false => Vec2 { x: short, y: long },
+        }
+    }
+
+    pub fn margin_pt(&self) -> f64 {
+        self.margin * MM
+    }
+
+    /// points per world unit on the battle map poster and the cutouts
+    pub fn poster_scale(&self) -> f64 {
+        self.cell_size * MM / GRID_SIZE as f64
+    }
+
+    /// Largest overlap of poster pages in millimeters: half of the printable width of the short
+    /// page side, so every page adds to the poster in both orientations
+    pub fn max_overlap(&self) -> f64 {
+        let size = self.format.size_mm().unwrap_or(self.custom_size);
+        (0.5 * (size.x.min(size.y) - 2.0 * self.margin)).max(0.0)
+    }
+
+    /// overlap of poster pages in points, limited to `max_overlap`
+    pub fn overlap_pt(&self) -> f64 {
+        self.overlap.min(self.max_overlap()) * MM
+    }
+
+    /// Number of pages in x and y needed to print a map of `size` points on the poster.
+    /// Each page shows the printable area of a page, neighbouring pages overlap.
+    pub fn poster_tiles(&self, size: Vec2<f64>) -> Vec2<usize> {
+        let page = self.page_size_for(Some(size));
+        let overlap = self.overlap_pt();
+        let tiles = |length: f64, page_length: f64| -> usize {
+            let printable = page_length - 2.0 * self.margin_pt();
+            let step = printable - overlap;
+            if length <= printable || step <= 0.0 {
+                return 1;
+            }
+            1 + ((length - printable) / step).ceil() as usize
+        };
+        Vec2 {
+            x: tiles(size.x, page.x),
+            y: tiles(size.y, page.y),
+        }
+    }
+}
+
+/// Chambers included in an export, chosen for each export and not saved with the dungeon
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChamberFilter {
+    All,
+    /// the selected chambers
+    Chambers(Vec<ChamberId>),
+    /// chambers with the text in their name, ignoring case
+    Name(String),
+    /// chambers reachable from the chamber passing at most this many doors or corridors
+    Reachable(ChamberId, u32),
+}
+
+impl ChamberFilter {
+    /// The dungeon with only the chambers of the filter, see `Dungeon::subset`
+    pub fn apply(&self, dungeon: &Dungeon) -> Dungeon {
+        let ids = match self {
+            ChamberFilter::All => return dungeon.clone(),
+            ChamberFilter::Chambers(ids) => ids.clone(),
+            ChamberFilter::Name(name) => {
+                let name = name.to_lowercase();
+                dungeon
+                    .chambers()
+                    .iter()
+                    .filter(|c| c.name.to_lowercase().contains(&name))
+                    .map(|c| c.id)
+                    .collect()
+            }
+            ChamberFilter::Reachable(start, steps) => dungeon.reachable_chambers(*start, *steps),
+        };
+        dungeon.subset(&ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chamber::Chamber, common::Vec2, dungeon::Dungeon};
+
+    use super::{ChamberFilter, ExportSettings, Orientation, PageFormat, MM};
+
+    #[test]
+    fn page_sizes() {
+        let mut settings = ExportSettings::new();
+        let a4 = settings.page_size();
+        assert!((a4.x - 595.3).abs() < 0.1 && (a4.y - 841.9).abs() < 0.1);
+
+        // wide maps are printed in landscape
+        let wide = Some(Vec2 { x: 2.0, y: 1.0 });
+        assert_eq!(settings.page_size_for(wide), Vec2 { x: a4.y, y: a4.x });
+
+        settings.format = PageFormat::Letter;
+        settings.orientation = Orientation::Portrait;
+        let letter = settings.page_size_for(wide);
+        assert!((letter.x - 612.0).abs() < 0.01 && (letter.y - 792.0).abs() < 0.01);
+
+        settings.format = PageFormat::Custom;
+        settings.custom_size = Vec2 { x: 300.0, y: 100.0 };
+        let custom = settings.page_size();
+        assert!(custom.x < custom.y);
+    }
+
+    #[test]
+    fn poster_tiles() {
+        let mut settings = ExportSettings::new();
+        settings.orientation = Orientation::Portrait;
+        // one inch per cell
+        assert!((settings.poster_scale() - 72.0 / 50.0).abs() < 1e-9);
+
+        // A4 with 5mm margins prints 200mm x 287mm per page,
+        // each further page adds its printable size minus the overlap
+        let one = Vec2 {
+            x: 199.0 * MM,
+            y: 286.0 * MM,
+        };
+        assert_eq!(settings.poster_tiles(one), Vec2 { x: 1, y: 1 });
+        let two_by_three = Vec2 {
+            x: (199.0 + 190.0) * MM,
+            y: (286.0 + 2.0 * 277.0) * MM,
+        };
+        assert_eq!(settings.poster_tiles(two_by_three), Vec2 { x: 2, y: 3 });
+        let bigger = two_by_three
+            + Vec2 {
+                x: 2.0 * MM,
+                y: 2.0 * MM,
+            };
+        assert_eq!(settings.poster_tiles(bigger), Vec2 { x: 3, y: 4 });
+    }
+
+    #[test]
+    fn poster_overlap_below_printable_size() {
+        let mut settings = ExportSettings::new();
+        settings.orientation = Orientation::Portrait;
+        // A4 with 5mm margins prints 200mm wide, at most half of it overlaps
+        assert_eq!(settings.max_overlap(), 100.0);
+        let size = Vec2 {
+            x: 1000.0 * MM,
+            y: 1000.0 * MM,
+        };
+        for overlap in [200.0, 300.0] {
+            settings.overlap = overlap;
+            assert!((settings.overlap_pt() - 100.0 * MM).abs() < 1e-9);
+            // each page adds 100mm in x and 187mm in y
+            assert_eq!(settings.poster_tiles(size), Vec2 { x: 9, y: 5 });
+        }
+    }
+
+    #[test]
+    fn chamber_filters() {
+        let mut dungeon = Dungeon::new();
+        for name in ["Great Hall", "Guard Room", "Small hall"] {
+            let mut chamber = Chamber::new();
+            chamber.name = name.to_owned();
+            dungeon.add_chamber(chamber);
+        }
+        let names = |filter: ChamberFilter| -> Vec<String> {
+            filter
+                .apply(&dungeon)
+                .chambers()
+                .iter()
+                .map(|c| c.name.clone())
+                .collect()
+        };
+        assert_eq!(names(ChamberFilter::All).len(), 3);
+        assert_eq!(names(ChamberFilter::Chambers(vec![2])), vec!["Guard Room"]);
+        assert_eq!(
+            names(ChamberFilter::Name("HALL".to_owned())),
+            vec!["Great Hall", "Small hall"]
+        );
+        assert_eq!(names(ChamberFilter::Reachable(3, 2)), vec!["Small hall"]);
+    }
+}
@@ -1,11 +1,24 @@
-use crate::export::{to_full_player_map_pdf, to_pdf, to_player_cutout_pdf};
+use crate::background::BackgroundImage;
+use crate::chamber::ChamberId;
+use crate::common::Vec2;
+use crate::dungeon::Dungeon;
+use crate::export::{
+    to_full_player_map_pdf, to_handouts_pdf, to_pdf, to_player_cutout_pdf, to_poster_pdf, Target,
+};
+use crate::export_settings::{
+    ChamberFilter, ExportSettings, PageFormat, ORIENTATIONS, PAGE_FORMATS,
+};
+use crate::import::Import;
 use crate::observers::HistoryObserver;
-use crate::state::StateController;
+use crate::print_theme::PRINT_THEMES;
+use crate::state::events::StateEvent;
+use crate::state::{EditMode, StateCommand, StateController};
 use crate::storage;
 use cairo::glib::clone;
 use gtk::gio::{ActionEntry, SimpleActionGroup};
-use gtk::{glib, ApplicationWindow, FileFilter, MessageDialog};
+use gtk::{glib, ApplicationWindow, DropDown, Entry, FileFilter, Label, MessageDialog, PolicyType};
 use gtk::{prelude::*, FileChooserDialog};
+use gtk::{CheckButton, ScrolledWindow, SpinButton};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -21,6 +34,432 @@ pub fn pdf_filter() -> FileFilter {
     ff
 }
 
+pub fn image_filter() -> FileFilter {
+    let ff = FileFilter::new();
+    ff.set_name(Some("Images (PNG, JPEG)"));
+    ff.add_mime_type("image/png");
+    ff.add_mime_type("image/jpeg");
+    ff
+}
+
+pub fn background_image_dialog(control: Rc<RefCell<StateController>>) {
+    let file_dialog = FileChooserDialog::builder()
+        .title("Open Background Image ...")
+        .action(gtk::FileChooserAction::Open)
+        .select_multiple(false)
+        .modal(true)
+        .filter(&image_filter())
+        .build();
+    file_dialog.add_button("Open", gtk::ResponseType::Accept);
+    file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    file_dialog.connect_response(clone!(@weak control => move |dialog, r| {
+        match r {
+            gtk::ResponseType::Accept => {
+                let path = dialog.file().unwrap().parse_name().to_string();
+                match std::fs::read(&path) {
+                    Ok(data) => {
+                        if let Some(image) = BackgroundImage::from_data(data) {
+                            control
+                                .borrow_mut()
+                                .apply(StateCommand::SetBackgroundImage(Some(Rc::new(image))));
+                        }
+                    }
+                    Err(e) => println!("Unable to read {}: {}", path, e),
+                }
+                dialog.close();
+            }
+            gtk::ResponseType::Cancel => dialog.close(),
+            gtk::ResponseType::DeleteEvent => (),
+            _ => todo!(),
+        }
+    }));
+    file_dialog.show();
+}
+
+/// loads another dungeon file, its content is placed by clicking on the canvas
+fn import_dialog(control: Rc<RefCell<StateController>>) {
+    let file_dialog = FileChooserDialog::builder()
+        .title("Import Dungeon ...")
+        .action(gtk::FileChooserAction::Open)
+        .select_multiple(false)
+        .modal(true)
+        .filter(&dungeon_file_filter())
+        .build();
+    file_dialog.add_button("Import", gtk::ResponseType::Accept);
+    file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    file_dialog.connect_response(clone!(@weak control => move |dialog, r| {
+        match r {
+            gtk::ResponseType::Accept => {
+                let path = dialog.file().unwrap().parse_name().to_string();
+                match Import::load(path.clone()) {
+                    Some(import) => {
+                        let mut control = control.borrow_mut();
+                        control.state.import = Some(import);
+                        control.apply(StateCommand::ChangeMode(EditMode::PlaceImport));
+                    }
+                    None => println!("Nothing to import in {}", path),
+                }
+                dialog.close();
+            }
+            gtk::ResponseType::Cancel => dialog.close(),
+            gtk::ResponseType::DeleteEvent => (),
+            _ => todo!(),
+        }
+    }));
+    file_dialog.show();
+}
+
+/// compares the dungeon with another version, changes are listed and highlighted on the canvas
+fn compare_dialog(control: Rc<RefCell<StateController>>) {
+    let file_dialog = FileChooserDialog::builder()
+        .title("Compare with Dungeon File ...")
+        .action(gtk::FileChooserAction::Open)
+        .select_multiple(false)
+        .modal(true)
+        .filter(&dungeon_file_filter())
+        .build();
+    file_dialog.add_button("Compare", gtk::ResponseType::Accept);
+    file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    file_dialog.connect_response(clone!(@weak control => move |dialog, r| {
+        match r {
+            gtk::ResponseType::Accept => {
+                let path = dialog.file().unwrap().parse_name().to_string();
+                let old = storage::load_dungeon_model(path.clone());
+                let changes = {
+                    let mut control = control.borrow_mut();
+                    control.state.set_compare(Some(old));
+                    control.notify(StateEvent::CompareChanged);
+                    control.state.changes.clone()
+                };
+                let text = match changes.is_empty() {
+                    true => "No changes".to_owned(),
+                    false => changes
+                        .iter()
+                        .map(|c| c.describe())
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                };
+                dialog.close();
+                changes_dialog(&path, &text);
+            }
+            gtk::ResponseType::Cancel => dialog.close(),
+            gtk::ResponseType::DeleteEvent => (),
+            _ => todo!(),
+        }
+    }));
+    file_dialog.show();
+}
+
+fn changes_dialog(path: &str, text: &str) {
+    let dialog = MessageDialog::builder()
+        .message_type(gtk::MessageType::Info)
+        .buttons(gtk::ButtonsType::Close)
+        .text("Changes")
+        .secondary_text(format!(
+            "Changes since {}, highlighted on the canvas until \"Hide Changes\" is chosen.",
+            path
+        ))
+        .modal(true)
+        .build();
+    let area = dialog.message_area().downcast::<gtk::Box>().unwrap();
+    area.append(
+        &ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .min_content_width(400)
+            .min_content_height(300)
+            .child(
+                &Label::builder()
+                    .label(text)
+                    .xalign(0.0)
+                    .selectable(true)
+                    .wrap(true)
+                    .build(),
+            )
+            .build(),
+    );
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+pub type PdfExport = fn(&Dungeon, &ExportSettings, Target);
+
+/// Which settings besides the page setup an export needs
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportKind {
+    Pages,
+    /// the GM PDF with its appendices
+    Notes,
+    Poster,
+    Cutouts,
+}
+
+/// Asks for the page setup of a PDF export, see `page_setup_dialog`.
+/// The export is written after choosing the file.
+fn export_settings_dialog(
+    control: Rc<RefCell<StateController>>,
+    title: &str,
+    export: PdfExport,
+    kind: ExportKind,
+) {
+    let title = title.to_owned();
+    page_setup_dialog(control, kind, move |control, filter| {
+        pdf_file_dialog(control, &title, export, filter)
+    });
+}
+
+/// Asks for the page setup of a PDF export, which is stored with the dungeon.
+/// The appendices of the GM PDF, the scale and overlap of the battle map poster
+/// and the options of the cutouts are only asked for with their `kind`.
+/// The chambers to export are chosen as well, but not saved, they are passed to `then`.
+pub fn page_setup_dialog(
+    control: Rc<RefCell<StateController>>,
+    kind: ExportKind,
+    then: impl Fn(Rc<RefCell<StateController>>, ChamberFilter) + 'static,
+) {
+    let settings = control.borrow().state.dungeon.export_settings;
+    let (selected, chamber_ids, chamber_names, active) = {
+        let state = &control.borrow().state;
+        // a single chamber is active instead of selected
+        let selected: Vec<ChamberId> = match state.selection.chambers.is_empty() {
+            true => state.active_chamber_id.into_iter().collect(),
+            false => state.selection.chambers.clone(),
+        };
+        let chambers = state.dungeon.chambers();
+        let chamber_ids: Vec<ChamberId> = chambers.iter().map(|c| c.id).collect();
+        let chamber_names: Vec<String> = chambers
+            .iter()
+            .map(|c| format!("{}: {}", c.id, c.name))
+            .collect();
+        let active = state
+            .active_chamber_id
+            .and_then(|id| chamber_ids.iter().position(|c| *c == id))
+            .unwrap_or(0);
+        (selected, chamber_ids, chamber_names, active)
+    };
+    let dialog = MessageDialog::builder()
+        .message_type(gtk::MessageType::Question)
+        .buttons(gtk::ButtonsType::OkCancel)
+        .text("Page Setup")
+        .secondary_text("The page setup is saved with the dungeon.")
+        .modal(true)
+        .build();
+
+    let format = DropDown::from_strings(&PAGE_FORMATS.map(|f| f.to_str()));
+    format.set_selected(
+        PAGE_FORMATS
+            .iter()
+            .position(|f| *f == settings.format)
+            .unwrap_or(0) as u32,
+    );
+    let width = SpinButton::with_range(50.0, 2000.0, 1.0);
+    width.set_value(settings.custom_size.x);
+    let height = SpinButton::with_range(50.0, 2000.0, 1.0);
+    height.set_value(settings.custom_size.y);
+    let custom = settings.format == PageFormat::Custom;
+    width.set_sensitive(custom);
+    height.set_sensitive(custom);
+    format.connect_selected_notify(clone!(@strong width, @strong height => move |format| {
+        let custom = PAGE_FORMATS[format.selected() as usize] == PageFormat::Custom;
+        width.set_sensitive(custom);
+        height.set_sensitive(custom);
+    }));
+    let orientation = DropDown::from_strings(&ORIENTATIONS.map(|o| o.to_str()));
+    orientation.set_selected(
+        ORIENTATIONS
+            .iter()
+            .position(|o| *o == settings.orientation)
+            .unwrap_or(0) as u32,
+    );
+    let margin = SpinButton::with_range(0.0, 50.0, 0.5);
+    margin.set_digits(1);
+    margin.set_value(settings.margin);
+    let font_size = SpinButton::with_range(6.0, 24.0, 0.5);
+    font_size.set_digits(1);
+    font_size.set_value(settings.font_size);
+    let theme = DropDown::from_strings(&PRINT_THEMES.map(|t| t.to_str()));
+    theme.set_selected(
+        PRINT_THEMES
+            .iter()
+            .position(|t| *t == settings.theme)
+            .unwrap_or(0) as u32,
+    );
+    let cell_size = SpinButton::with_range(5.0, 100.0, 0.1);
+    cell_size.set_digits(1);
+    cell_size.set_value(settings.cell_size);
+    let overlap = SpinButton::with_range(0.0, settings.max_overlap(), 0.5);
+    overlap.set_digits(1);
+    overlap.set_value(settings.overlap);
+    // the overlap has to stay below the printable size of the chosen page
+    let update_overlap = clone!(@strong format, @strong width, @strong height, @strong margin,
+        @strong overlap => move || {
+        let page = ExportSettings {
+            format: PAGE_FORMATS[format.selected() as usize],
+            custom_size: Vec2 {
+                x: width.value(),
+                y: height.value(),
+            },
+            margin: margin.value(),
+            ..settings
+        };
+        overlap.set_range(0.0, page.max_overlap());
+    });
+    format.connect_selected_notify(clone!(@strong update_overlap => move |_| update_overlap()));
+    for spin in [&width, &height, &margin] {
+        spin.connect_value_changed(clone!(@strong update_overlap => move |_| update_overlap()));
+    }
+    let rotation = CheckButton::with_label("Rotate to fit");
+    rotation.set_active(settings.cutout_rotation);
+    let backs = CheckButton::with_label("Print chamber numbers on back pages (duplex)");
+    backs.set_active(settings.cutout_backs);
+    let index = CheckButton::with_label("Appendix: index of objects and doors");
+    index.set_active(settings.index_appendix);
+    let hidden = CheckButton::with_label("Appendix: checklist of hidden features");
+    hidden.set_active(settings.hidden_appendix);
+    let doors = CheckButton::with_label("Appendix: door connections");
+    doors.set_active(settings.doors_appendix);
+
+    let filter = DropDown::from_strings(&[
+        "All Chambers",
+        "Selected Chambers",
+        "Chambers Named",
+        "Chambers Reachable From",
+    ]);
+    let name = Entry::new();
+    name.set_placeholder_text(Some("Part of the name"));
+    let start = DropDown::from_strings(
+        &chamber_names
+            .iter()
+            .map(|n| n.as_str())
+            .collect::<Vec<&str>>(),
+    );
+    start.set_selected(active as u32);
+    let steps = SpinButton::with_range(0.0, 20.0, 1.0);
+    steps.set_value(1.0);
+    let update_filter = clone!(@strong name, @strong start, @strong steps => move |filter: &DropDown| {
+        name.set_sensitive(filter.selected() == 2);
+        start.set_sensitive(filter.selected() == 3);
+        steps.set_sensitive(filter.selected() == 3);
+    });
+    update_filter(&filter);
+    filter.connect_selected_notify(update_filter);
+
+    let area = dialog.message_area().downcast::<gtk::Box>().unwrap();
+    area.append(&Label::new(Some("Page Size")));
+    area.append(&format);
+    area.append(&Label::new(Some("Custom Width (mm)")));
+    area.append(&width);
+    area.append(&Label::new(Some("Custom Height (mm)")));
+    area.append(&height);
+    area.append(&Label::new(Some("Orientation")));
+    area.append(&orientation);
+    area.append(&Label::new(Some("Margin (mm)")));
+    area.append(&margin);
+    area.append(&Label::new(Some("Font Size (pt)")));
+    area.append(&font_size);
+    area.append(&Label::new(Some("Print Theme")));
+    area.append(&theme);
+    area.append(&Label::new(Some("Chambers")));
+    area.append(&filter);
+    area.append(&name);
+    area.append(&start);
+    area.append(&Label::new(Some("Doors Away")));
+    area.append(&steps);
+    if kind == ExportKind::Notes {
+        area.append(&index);
+        area.append(&hidden);
+        area.append(&doors);
+    }
+    if kind == ExportKind::Poster || kind == ExportKind::Cutouts {
+        area.append(&Label::new(Some("Grid Cell Size (mm)")));
+        area.append(&cell_size);
+    }
+    if kind == ExportKind::Poster {
+        area.append(&Label::new(Some("Page Overlap (mm)")));
+        area.append(&overlap);
+    }
+    if kind == ExportKind::Cutouts {
+        area.append(&rotation);
+        area.append(&backs);
+    }
+
+    dialog.connect_response(clone!(@strong control => move |dialog, r| {
+        dialog.close();
+        if r != gtk::ResponseType::Ok {
+            return;
+        }
+        let new_settings = ExportSettings {
+            format: PAGE_FORMATS[format.selected() as usize],
+            custom_size: Vec2 {
+                x: width.value(),
+                y: height.value(),
+            },
+            orientation: ORIENTATIONS[orientation.selected() as usize],
+            margin: margin.value(),
+            font_size: font_size.value(),
+            cell_size: cell_size.value(),
+            overlap: overlap.value(),
+            theme: PRINT_THEMES[theme.selected() as usize],
+            cutout_rotation: rotation.is_active(),
+            cutout_backs: backs.is_active(),
+            index_appendix: index.is_active(),
+            hidden_appendix: hidden.is_active(),
+            doors_appendix: doors.is_active(),
+        };
+        if new_settings != settings {
+            control
+                .borrow_mut()
+                .apply(StateCommand::ChangeExportSettings(new_settings));
+        }
+        let filter = match filter.selected() {
+            1 => ChamberFilter::Chambers(selected.clone()),
+            2 => ChamberFilter::Name(name.text().to_string()),
+            3 => match chamber_ids.get(start.selected() as usize) {
+                Some(id) => ChamberFilter::Reachable(*id, steps.value() as u32),
+                None => ChamberFilter::Chambers(vec![]),
+            },
+            _ => ChamberFilter::All,
+        };
+        then(control.clone(), filter);
+    }));
+    dialog.show();
+}
+
+fn pdf_file_dialog(
+    control: Rc<RefCell<StateController>>,
+    title: &str,
+    export: PdfExport,
+    filter: ChamberFilter,
+) {
+    let file_dialog = FileChooserDialog::builder()
+        .title(title)
+        .action(gtk::FileChooserAction::Save)
+        .select_multiple(false)
+        .create_folders(true)
+        .modal(true)
+        .filter(&pdf_filter())
+        .build();
+    file_dialog.add_button("Export", gtk::ResponseType::Accept);
+    file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    file_dialog.connect_response(clone!(@weak control => move |dialog, r| {
+        match r {
+            gtk::ResponseType::Accept => {
+                let file = dialog.file().unwrap();
+                let mut path = file.parse_name().to_string();
+                if !path.ends_with(".pdf") {
+                    path += ".pdf";
+                }
+                let dungeon = filter.apply(&control.borrow().state.dungeon);
+                export(&dungeon, &dungeon.export_settings, Target::Pdf(path));
+                dialog.close();
+            }
+            gtk::ResponseType::Cancel => dialog.close(),
+            gtk::ResponseType::DeleteEvent => (),
+            _ => todo!(),
+        }
+    }));
+    file_dialog.show();
+}
+
 pub fn save_as_dialog<F: Fn() + 'static>(
     title: String,
     control: Rc<RefCell<StateController>>,
@@ -92,7 +531,12 @@ fn open_dialog(
                     for cmd in cmds {
                         control.borrow_mut().apply_silent(cmd);
                     }
+                    let session = storage::load_session(storage::session_path(&path));
+                    control.borrow_mut().state.session = session;
                     control.borrow_mut().reload();
+                    history
+                        .borrow_mut()
+                        .set_session(control.borrow().state.session.clone());
                     let title = format!("Dungeon Planner - {path}");
                     window.set_title(Some(&title));
                     dialog.close();
@@ -223,6 +667,22 @@ pub fn file_actions(
         }))
         .build();
 
+    let action_file_import = ActionEntry::builder("import")
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                import_dialog(control);
+            }),
+        )
+        .build();
+
+    let action_file_compare = ActionEntry::builder("compare")
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                compare_dialog(control);
+            }),
+        )
+        .build();
+
     let action_file_save = ActionEntry::builder("save")
         .activate(clone!( @weak control, @weak history, @strong window => move |_group: &SimpleActionGroup, _, _| {
             let save_file = history.borrow().save_file();
@@ -244,109 +704,57 @@ pub fn file_actions(
         .build();
 
     let action_file_export_pdf = ActionEntry::builder("export_pdf")
-        .activate(clone!( @weak control, @weak history => move |_group: &SimpleActionGroup, _, _| {
-            let file_dialog = FileChooserDialog::builder()
-                .title("Export Dungeon ...")
-                .action(gtk::FileChooserAction::Save)
-                .select_multiple(false)
-                .create_folders(true)
-                .modal(true)
-                .filter(&pdf_filter())
-                .build();
-            file_dialog.add_button("Export", gtk::ResponseType::Accept);
-            file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
-            file_dialog.connect_response(clone!(@weak control, @weak history => move |dialog, r| {
-                match r {
-                    gtk::ResponseType::Accept => {
-                        let file = dialog.file().unwrap();
-                        let mut path = file.parse_name().to_string();
-                        if !path.ends_with(".pdf") {
-                            path += ".pdf";
-                        }
-                        to_pdf(&control.borrow().state.dungeon, path);
-                        dialog.close();
-                    }
-                    gtk::ResponseType::Cancel => dialog.close(),
-                    gtk::ResponseType::DeleteEvent => (),
-                    _ => todo!(),
-                }
-            }));
-            file_dialog.show();
-        }))
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                export_settings_dialog(control, "Export Dungeon ...", to_pdf, ExportKind::Notes);
+            }),
+        )
         .build();
 
     let action_file_export_player_map_pdf = ActionEntry::builder("player_map_pdf")
-        .activate(clone!( @weak control, @weak history => move |_group: &SimpleActionGroup, _, _| {
-            let file_dialog = FileChooserDialog::builder()
-                .title("Export Player Map ...")
-                .action(gtk::FileChooserAction::Save)
-                .select_multiple(false)
-                .create_folders(true)
-                .modal(true)
-                .filter(&pdf_filter())
-                .build();
-            file_dialog.add_button("Export", gtk::ResponseType::Accept);
-            file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
-            file_dialog.connect_response(clone!(@weak control, @weak history => move |dialog, r| {
-                match r {
-                    gtk::ResponseType::Accept => {
-                        let file = dialog.file().unwrap();
-                        let mut path = file.parse_name().to_string();
-                        if !path.ends_with(".pdf") {
-                            path += ".pdf";
-                        }
-                        to_full_player_map_pdf(&control.borrow().state.dungeon, path);
-                        dialog.close();
-                    }
-                    gtk::ResponseType::Cancel => dialog.close(),
-                    gtk::ResponseType::DeleteEvent => (),
-                    _ => todo!(),
-                }
-            }));
-            file_dialog.show();
-        }))
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                export_settings_dialog(control, "Export Player Map ...", to_full_player_map_pdf, ExportKind::Pages);
+            }),
+        )
         .build();
 
     let action_file_export_cutout_pdf = ActionEntry::builder("cutout_pdf")
-        .activate(clone!( @weak control, @weak history => move |_group: &SimpleActionGroup, _, _| {
-            let file_dialog = FileChooserDialog::builder()
-                .title("Export Cutout Map ...")
-                .action(gtk::FileChooserAction::Save)
-                .select_multiple(false)
-                .create_folders(true)
-                .modal(true)
-                .filter(&pdf_filter())
-                .build();
-            file_dialog.add_button("Export", gtk::ResponseType::Accept);
-            file_dialog.add_button("Cancel", gtk::ResponseType::Cancel);
-            file_dialog.connect_response(clone!(@weak control, @weak history => move |dialog, r| {
-                match r {
-                    gtk::ResponseType::Accept => {
-                        let file = dialog.file().unwrap();
-                        let mut path = file.parse_name().to_string();
-                        if !path.ends_with(".pdf") {
-                            path += ".pdf";
-                        }
-                        to_player_cutout_pdf(&control.borrow().state.dungeon, path);
-                        dialog.close();
-                    }
-                    gtk::ResponseType::Cancel => dialog.close(),
-                    gtk::ResponseType::DeleteEvent => (),
-                    _ => todo!(),
-                }
-            }));
-            file_dialog.show();
-        }))
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                export_settings_dialog(control, "Export Cutout Map ...", to_player_cutout_pdf, ExportKind::Cutouts);
+            }),
+        )
+        .build();
+
+    let action_file_export_poster_pdf = ActionEntry::builder("poster_pdf")
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                export_settings_dialog(control, "Export Battle Map Poster ...", to_poster_pdf, ExportKind::Poster);
+            }),
+        )
+        .build();
+
+    let action_file_export_handouts_pdf = ActionEntry::builder("handouts_pdf")
+        .activate(
+            clone!( @weak control => move |_group: &SimpleActionGroup, _, _| {
+                export_settings_dialog(control, "Export Read-Aloud Handouts ...", to_handouts_pdf, ExportKind::Pages);
+            }),
+        )
         .build();
 
     file_actions.add_action_entries([
         action_file_new,
         action_file_open,
+        action_file_import,
+        action_file_compare,
         action_file_save,
         action_file_save_as,
         action_file_export_pdf,
         action_file_export_player_map_pdf,
         action_file_export_cutout_pdf,
+        action_file_export_poster_pdf,
+        action_file_export_handouts_pdf,
     ]);
 
     file_actions
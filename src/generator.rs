@@ -0,0 +1,806 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{
+    chamber::ChamberId, common::Vec2, config::GRID_SIZE, door::Door, dungeon::Dungeon,
+    emitter::Emitter, state::StateCommand,
+};
+
+/// minimal width and height of a BSP leaf in cells, leaves room for a 3x3 room and a margin
+const MIN_LEAF_SIZE: i32 = 5;
+/// minimal number of cells of a cave
+const MIN_CAVE_SIZE: usize = 12;
+/// chance of a cell to start as wall in the cellular automaton
+const CAVE_WALL_CHANCE: f64 = 0.45;
+const CAVE_STEPS: usize = 5;
+/// extra cost of a turn in a corridor in cells, keeps corridors straight
+const TURN_COST: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeneratorStyle {
+    /// rectangular rooms placed by binary space partitioning
+    Rooms,
+    /// natural caves grown by a cellular automaton
+    Caves,
+}
+
+impl GeneratorStyle {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            GeneratorStyle::Rooms => "Rooms",
+            GeneratorStyle::Caves => "Caves",
+        }
+    }
+}
+
+/// Parameters of the generator, the same parameters always yield the same dungeon
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeneratorSettings {
+    pub style: GeneratorStyle,
+    /// size of the generated area in grid cells
+    pub size: Vec2<i32>,
+    /// number of rooms or caves, less are generated if the area is too small
+    pub rooms: u32,
+    pub seed: u64,
+    /// chance of additional corridors creating loops, from 0 to 1
+    pub loopiness: f64,
+}
+
+impl GeneratorSettings {
+    pub fn new() -> Self {
+        Self {
+            style: GeneratorStyle::Rooms,
+            size: Vec2 { x: 40, y: 30 },
+            rooms: 8,
+            seed: 1,
+            loopiness: 0.2,
+        }
+    }
+}
+
+/// SplitMix64, small and stable across platforms and versions
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in [0, 1)
+    fn float(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// uniform in [min, max]
+    fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+        min + (self.next() % (max - min + 1) as u64) as i32
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        self.float() < p
+    }
+}
+
+/// Rectangle of grid cells
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Area {
+    min: Vec2<i32>,
+    size: Vec2<i32>,
+}
+
+/// Room of the generated layout in cell coordinates
+struct Room {
+    /// outer ring of grid vertices
+    outline: Vec<Vec2<i32>>,
+    holes: Vec<Vec<Vec2<i32>>>,
+    cells: HashSet<Vec2<i32>>,
+    /// cell corridors start from
+    anchor: Vec2<i32>,
+}
+
+/// Generates a dungeon layout with its top left corner at `origin`.
+/// The result are regular commands, so the layout can be edited and undone like a drawn one.
+pub fn generate(
+    dungeon: &Dungeon,
+    settings: &GeneratorSettings,
+    origin: Vec2<i32>,
+) -> Vec<StateCommand> {
+    let mut rng = Rng::new(settings.seed);
+    let rooms = match settings.style {
+        GeneratorStyle::Rooms => bsp_rooms(&mut rng, settings),
+        GeneratorStyle::Caves => caves(&mut rng, settings),
+    };
+    let anchors: Vec<Vec2<i32>> = rooms.iter().map(|r| r.anchor).collect();
+    let connections = connections(&mut rng, &anchors, settings.loopiness);
+
+    let vertex = |v: Vec2<i32>| {
+        origin
+            + Vec2 {
+                x: v.x * GRID_SIZE,
+                y: v.y * GRID_SIZE,
+            }
+    };
+    let cell_center = |c: Vec2<i32>| {
+        origin
+            + Vec2 {
+                x: c.x * GRID_SIZE + GRID_SIZE / 2,
+                y: c.y * GRID_SIZE + GRID_SIZE / 2,
+            }
+    };
+
+    let mut emitter = Emitter::new(dungeon);
+
+    let name = match settings.style {
+        GeneratorStyle::Rooms => "Room",
+        GeneratorStyle::Caves => "Cave",
+    };
+    let mut chamber_ids: Vec<ChamberId> = vec![];
+    for (i, room) in rooms.iter().enumerate() {
+        let outline: Vec<Vec2<i32>> = room.outline.iter().map(|v| vertex(*v)).collect();
+        let holes: Vec<Vec<Vec2<i32>>> = room
+            .holes
+            .iter()
+            .map(|h| h.iter().map(|v| vertex(*v)).collect())
+            .collect();
+        let chamber_id = emitter.add_chamber(&outline, &holes);
+        emitter.apply(StateCommand::ChangeChamberName(
+            chamber_id,
+            format!("{} {}", name, i + 1),
+        ));
+        chamber_ids.push(chamber_id);
+    }
+
+    for (from, to) in connections {
+        // corridors go around the other rooms instead of through them
+        let blocked: HashSet<Vec2<i32>> = rooms
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != from && *i != to)
+            .flat_map(|(_, r)| r.cells.iter().copied())
+            .collect();
+        let path = corridor_path(anchors[from], anchors[to], &blocked, settings.size);
+        emitter.apply(StateCommand::AddCorridor);
+        let corridor_id = emitter.state.active_corridor_id.unwrap();
+        for point in path.iter() {
+            emitter.apply(StateCommand::AddVertexToCorridor(
+                corridor_id,
+                cell_center(*point),
+            ));
+        }
+
+        // caves are open, rooms get doors where the corridor passes their walls
+        if settings.style != GeneratorStyle::Rooms {
+            continue;
+        }
+        let cells = path_cells(&path);
+        let exits = [
+            (from, exit(&cells, &rooms[from].cells)),
+            (to, exit(&reversed(&cells), &rooms[to].cells)),
+        ];
+        for (room, exit) in exits {
+            if let Some((inside, outside)) = exit {
+                let a: Vec2<f64> = cell_center(inside).into();
+                let b: Vec2<f64> = cell_center(outside).into();
+                let pos = 0.5 * (a + b);
+                if let Some(wall) = emitter.wall_at(chamber_ids[room], pos) {
+                    emitter.apply(StateCommand::AddDoor(Door::new(
+                        chamber_ids[room],
+                        None,
+                        GRID_SIZE as f64,
+                        wall.id,
+                        wall.nearest_relative_pos(pos),
+                    )));
+                }
+            }
+        }
+    }
+
+    emitter.finish()
+}
+
+/// splits the area into leaves and places a room inside of each leaf
+fn bsp_rooms(rng: &mut Rng, settings: &GeneratorSettings) -> Vec<Room> {
+    let mut leaves = vec![Area {
+        min: Vec2 { x: 0, y: 0 },
+        size: settings.size,
+    }];
+    while leaves.len() < settings.rooms as usize {
+        // split the largest leaf which is big enough
+        let index = leaves
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.size.x.max(l.size.y) >= 2 * MIN_LEAF_SIZE)
+            .max_by_key(|(i, l)| (l.size.x * l.size.y, usize::MAX - i))
+            .map(|(i, _)| i);
+        let leaf = match index {
+            Some(i) => leaves.remove(i),
+            None => break,
+        };
+        let vertical = match (
+            leaf.size.x >= 2 * MIN_LEAF_SIZE,
+            leaf.size.y >= 2 * MIN_LEAF_SIZE,
+        ) {
+            (true, false) => true,
+            (false, true) => false,
+            _ if leaf.size.x * 4 > leaf.size.y * 5 => true,
+            _ if leaf.size.y * 4 > leaf.size.x * 5 => false,
+            _ => rng.chance(0.5),
+        };
+        let (first, second) = match vertical {
+            true => {
+                let at = rng.range(MIN_LEAF_SIZE, leaf.size.x - MIN_LEAF_SIZE);
+                (
+                    Area {
+                        min: leaf.min,
+                        size: Vec2 {
+                            x: at,
+                            y: leaf.size.y,
+                        },
+                    },
+                    Area {
+                        min: Vec2 {
+                            x: leaf.min.x + at,
+                            y: leaf.min.y,
+                        },
+                        size: Vec2 {
+                            x: leaf.size.x - at,
+                            y: leaf.size.y,
+                        },
+                    },
+                )
+            }
+            false => {
+                let at = rng.range(MIN_LEAF_SIZE, leaf.size.y - MIN_LEAF_SIZE);
+                (
+                    Area {
+                        min: leaf.min,
+                        size: Vec2 {
+                            x: leaf.size.x,
+                            y: at,
+                        },
+                    },
+                    Area {
+                        min: Vec2 {
+                            x: leaf.min.x,
+                            y: leaf.min.y + at,
+                        },
+                        size: Vec2 {
+                            x: leaf.size.x,
+                            y: leaf.size.y - at,
+                        },
+                    },
+                )
+            }
+        };
+        leaves.push(first);
+        leaves.push(second);
+    }
+
+    leaves
+        .iter()
+        .filter(|l| l.size.x >= MIN_LEAF_SIZE && l.size.y >= MIN_LEAF_SIZE)
+        .map(|leaf| {
+            let size = Vec2 {
+                x: rng.range(3, leaf.size.x - 2),
+                y: rng.range(3, leaf.size.y - 2),
+            };
+            let min = Vec2 {
+                x: leaf.min.x + rng.range(1, leaf.size.x - size.x - 1),
+                y: leaf.min.y + rng.range(1, leaf.size.y - size.y - 1),
+            };
+            let max = min + size;
+            let mut cells = HashSet::new();
+            for x in min.x..max.x {
+                for y in min.y..max.y {
+                    cells.insert(Vec2 { x, y });
+                }
+            }
+            Room {
+                outline: vec![
+                    min,
+                    Vec2 { x: max.x, y: min.y },
+                    max,
+                    Vec2 { x: min.x, y: max.y },
+                ],
+                holes: vec![],
+                cells,
+                anchor: Vec2 {
+                    x: min.x + size.x / 2,
+                    y: min.y + size.y / 2,
+                },
+            }
+        })
+        .collect()
+}
+
+/// grows caves with a cellular automaton and keeps the largest ones
+fn caves(rng: &mut Rng, settings: &GeneratorSettings) -> Vec<Room> {
+    let size = settings.size;
+    if size.x < 3 || size.y < 3 {
+        return vec![];
+    }
+    let border = |x: i32, y: i32| x == 0 || y == 0 || x == size.x - 1 || y == size.y - 1;
+    let index = |x: i32, y: i32| (y * size.x + x) as usize;
+
+    let mut floor = vec![false; (size.x * size.y) as usize];
+    for y in 0..size.y {
+        for x in 0..size.x {
+            floor[index(x, y)] = !border(x, y) && !rng.chance(CAVE_WALL_CHANCE);
+        }
+    }
+
+    for _ in 0..CAVE_STEPS {
+        let mut next = floor.clone();
+        for y in 1..size.y - 1 {
+            for x in 1..size.x - 1 {
+                let walls = (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .filter(|(dx, dy)| !floor[index(x + dx, y + dy)])
+                    .count();
+                next[index(x, y)] = walls < 5;
+            }
+        }
+        floor = next;
+    }
+
+    // cells touching only at a corner would make the traced outline touch itself
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for y in 0..size.y - 1 {
+            for x in 0..size.x - 1 {
+                let a = floor[index(x, y)];
+                let b = floor[index(x + 1, y)];
+                let c = floor[index(x, y + 1)];
+                let d = floor[index(x + 1, y + 1)];
+                if a && d && !b && !c {
+                    floor[index(x + 1, y)] = true;
+                    changed = true;
+                } else if b && c && !a && !d {
+                    floor[index(x, y)] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // connected regions, largest first
+    let mut visited = vec![false; floor.len()];
+    let mut regions: Vec<Vec<Vec2<i32>>> = vec![];
+    for y in 0..size.y {
+        for x in 0..size.x {
+            if !floor[index(x, y)] || visited[index(x, y)] {
+                continue;
+            }
+            let mut region = vec![];
+            let mut stack = vec![Vec2 { x, y }];
+            visited[index(x, y)] = true;
+            while let Some(c) = stack.pop() {
+                region.push(c);
+                for n in neighbors(c) {
+                    if n.x >= 0
+                        && n.y >= 0
+                        && n.x < size.x
+                        && n.y < size.y
+                        && floor[index(n.x, n.y)]
+                        && !visited[index(n.x, n.y)]
+                    {
+                        visited[index(n.x, n.y)] = true;
+                        stack.push(n);
+                    }
+                }
+            }
+            regions.push(region);
+        }
+    }
+    regions.sort_by_key(|r| std::cmp::Reverse(r.len()));
+
+    regions
+        .into_iter()
+        .filter(|r| r.len() >= MIN_CAVE_SIZE)
+        .take(settings.rooms as usize)
+        .map(|region| {
+            let cells: HashSet<Vec2<i32>> = region.iter().copied().collect();
+            let mut rings = trace(&cells);
+            // the outer ring runs clockwise, holes counterclockwise
+            let outer = rings
+                .iter()
+                .position(|r| signed_area(r) > 0)
+                .expect("region has an outline");
+            let outline = rings.remove(outer);
+            let center = region.iter().fold(Vec2 { x: 0, y: 0 }, |s, c| s + *c);
+            let center = Vec2 {
+                x: center.x / region.len() as i32,
+                y: center.y / region.len() as i32,
+            };
+            let anchor = *region
+                .iter()
+                .min_by_key(|c| (c.x - center.x).abs() + (c.y - center.y).abs())
+                .unwrap();
+            Room {
+                outline,
+                holes: rings,
+                cells,
+                anchor,
+            }
+        })
+        .collect()
+}
+
+fn neighbors(c: Vec2<i32>) -> [Vec2<i32>; 4] {
+    [
+        Vec2 { x: c.x + 1, y: c.y },
+        Vec2 { x: c.x - 1, y: c.y },
+        Vec2 { x: c.x, y: c.y + 1 },
+        Vec2 { x: c.x, y: c.y - 1 },
+    ]
+}
+
+/// Outlines of a set of cells as rings of grid vertices, without vertices in straight walls.
+/// The cells must not touch only at a corner.
+fn trace(cells: &HashSet<Vec2<i32>>) -> Vec<Vec<Vec2<i32>>> {
+    // boundary edges running clockwise around each cell, keyed by their start
+    let mut edges: HashMap<Vec2<i32>, Vec2<i32>> = HashMap::new();
+    for c in cells.iter() {
+        let tl = *c;
+        let tr = Vec2 { x: c.x + 1, y: c.y };
+        let br = Vec2 {
+            x: c.x + 1,
+            y: c.y + 1,
+        };
+        let bl = Vec2 { x: c.x, y: c.y + 1 };
+        let sides = [
+            (Vec2 { x: c.x, y: c.y - 1 }, tl, tr),
+            (Vec2 { x: c.x + 1, y: c.y }, tr, br),
+            (Vec2 { x: c.x, y: c.y + 1 }, br, bl),
+            (Vec2 { x: c.x - 1, y: c.y }, bl, tl),
+        ];
+        for (neighbor, from, to) in sides {
+            if !cells.contains(&neighbor) {
+                edges.insert(from, to);
+            }
+        }
+    }
+
+    let mut rings = vec![];
+    // start at the smallest vertex to get the same rings on every run
+    while let Some(start) = edges.keys().min_by_key(|v| (v.y, v.x)).copied() {
+        let mut ring = vec![start];
+        let mut current = edges.remove(&start).unwrap();
+        while current != start {
+            ring.push(current);
+            current = edges.remove(&current).unwrap();
+        }
+        let n = ring.len();
+        let corners: Vec<Vec2<i32>> = (0..n)
+            .filter(|i| {
+                let prev = ring[(i + n - 1) % n];
+                let next = ring[(i + 1) % n];
+                ring[*i] - prev != next - ring[*i]
+            })
+            .map(|i| ring[i])
+            .collect();
+        rings.push(corners);
+    }
+    rings
+}
+
+/// twice the area of the ring, positive for clockwise rings on screen
+fn signed_area(ring: &[Vec2<i32>]) -> i32 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let a = ring[i];
+            let b = ring[(i + 1) % n];
+            a.x * b.y - b.x * a.y
+        })
+        .sum()
+}
+
+/// Pairs of rooms connected by corridors: a minimal spanning tree, so every room is reachable,
+/// and additional connections to the nearest room depending on `loopiness`.
+fn connections(rng: &mut Rng, anchors: &[Vec2<i32>], loopiness: f64) -> Vec<(usize, usize)> {
+    let distance = |a: usize, b: usize| {
+        (anchors[a].x - anchors[b].x).abs() + (anchors[a].y - anchors[b].y).abs()
+    };
+    let n = anchors.len();
+    let mut result: Vec<(usize, usize)> = vec![];
+    let mut in_tree = vec![false; n];
+    if n == 0 {
+        return result;
+    }
+    in_tree[0] = true;
+    for _ in 1..n {
+        let edge = (0..n)
+            .filter(|i| in_tree[*i])
+            .flat_map(|i| (0..n).filter(|j| !in_tree[*j]).map(move |j| (i, j)))
+            .min_by_key(|(i, j)| distance(*i, *j));
+        if let Some((i, j)) = edge {
+            in_tree[j] = true;
+            result.push((i, j));
+        }
+    }
+
+    let connected = |result: &Vec<(usize, usize)>, a: usize, b: usize| {
+        result.contains(&(a, b)) || result.contains(&(b, a))
+    };
+    for i in 0..n {
+        let nearest = (0..n)
+            .filter(|j| *j != i && !connected(&result, i, *j))
+            .min_by_key(|j| distance(i, *j));
+        if let Some(j) = nearest {
+            if rng.chance(loopiness) {
+                result.push((i, j));
+            }
+        }
+    }
+    result
+}
+
+/// Corners of the shortest path between two cells that does not pass through the cells in
+/// `blocked`, staying within `size` and a margin of one cell around it. Every turn costs
+/// `TURN_COST` steps, so corridors are mostly straight. Falls back to an L shaped path if the
+/// target can not be reached.
+fn corridor_path(
+    from: Vec2<i32>,
+    to: Vec2<i32>,
+    blocked: &HashSet<Vec2<i32>>,
+    size: Vec2<i32>,
+) -> Vec<Vec2<i32>> {
+    let inside = |c: Vec2<i32>| c.x >= -1 && c.y >= -1 && c.x <= size.x && c.y <= size.y;
+    // nodes are a cell and the direction it was entered from, 4 for the start
+    let mut cost: HashMap<(Vec2<i32>, usize), u32> = HashMap::new();
+    let mut previous: HashMap<(Vec2<i32>, usize), (Vec2<i32>, usize)> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    cost.insert((from, 4), 0);
+    queue.push(Reverse((0, from.x, from.y, 4)));
+    let mut end = None;
+    while let Some(Reverse((c, x, y, dir))) = queue.pop() {
+        let cell = Vec2 { x, y };
+        if cost.get(&(cell, dir)).is_some_and(|best| c > *best) {
+            continue;
+        }
+        if cell == to {
+            end = Some((cell, dir));
+            break;
+        }
+        for (next_dir, next) in neighbors(cell).into_iter().enumerate() {
+            if !inside(next) || blocked.contains(&next) {
+                continue;
+            }
+            let turn = if dir != 4 && dir != next_dir {
+                TURN_COST
+            } else {
+                0
+            };
+            let next_cost = c + 1 + turn;
+            if cost
+                .get(&(next, next_dir))
+                .is_some_and(|best| *best <= next_cost)
+            {
+                continue;
+            }
+            cost.insert((next, next_dir), next_cost);
+            previous.insert((next, next_dir), (cell, dir));
+            queue.push(Reverse((next_cost, next.x, next.y, next_dir)));
+        }
+    }
+
+    let mut path = vec![];
+    match end {
+        Some(mut node) => {
+            path.push(node.0);
+            while let Some(p) = previous.get(&node) {
+                // only keep the corners
+                if p.1 != node.1 && p.1 != 4 {
+                    path.push(p.0);
+                }
+                node = *p;
+            }
+            if *path.last().unwrap() != from {
+                path.push(from);
+            }
+            path.reverse();
+        }
+        None => {
+            path.push(from);
+            for p in [Vec2 { x: to.x, y: from.y }, to] {
+                if *path.last().unwrap() != p {
+                    path.push(p);
+                }
+            }
+        }
+    }
+    path
+}
+
+/// all cells along an axis aligned path
+fn path_cells(path: &[Vec2<i32>]) -> Vec<Vec2<i32>> {
+    let mut cells = vec![path[0]];
+    for w in path.windows(2) {
+        let step = Vec2 {
+            x: (w[1].x - w[0].x).signum(),
+            y: (w[1].y - w[0].y).signum(),
+        };
+        let mut c = w[0];
+        while c != w[1] {
+            c += step;
+            cells.push(c);
+        }
+    }
+    cells
+}
+
+fn reversed(cells: &[Vec2<i32>]) -> Vec<Vec2<i32>> {
+    cells.iter().rev().copied().collect()
+}
+
+/// the last cell inside of the room and the first one outside when following the path
+fn exit(cells: &[Vec2<i32>], room: &HashSet<Vec2<i32>>) -> Option<(Vec2<i32>, Vec2<i32>)> {
+    cells
+        .windows(2)
+        .find(|w| room.contains(&w[0]) && !room.contains(&w[1]))
+        .map(|w| (w[0], w[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::common::Vec2;
+    use crate::config::GRID_SIZE;
+    use crate::dungeon::Dungeon;
+    use crate::state::State;
+
+    use super::{
+        corridor_path, generate, path_cells, signed_area, trace, GeneratorSettings, GeneratorStyle,
+    };
+
+    fn apply(settings: &GeneratorSettings) -> State {
+        let mut state = State::new();
+        for cmd in generate(&Dungeon::new(), settings, Vec2 { x: 0, y: 0 }) {
+            cmd.execute(&mut state);
+        }
+        state
+    }
+
+    fn outlines(state: &State) -> Vec<Vec<Vec2<i32>>> {
+        state
+            .dungeon
+            .chambers
+            .iter()
+            .map(|c| c.walls().iter().map(|w| w.p1).collect())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_dungeon() {
+        for style in [GeneratorStyle::Rooms, GeneratorStyle::Caves] {
+            let settings = GeneratorSettings {
+                style,
+                seed: 42,
+                ..GeneratorSettings::new()
+            };
+            let a = apply(&settings);
+            let b = apply(&settings);
+            assert!(!a.dungeon.chambers.is_empty());
+            assert_eq!(outlines(&a), outlines(&b));
+            assert_eq!(a.dungeon.doors.len(), b.dungeon.doors.len());
+
+            let c = apply(&GeneratorSettings {
+                seed: 43,
+                ..settings
+            });
+            assert_ne!(outlines(&a), outlines(&c));
+        }
+    }
+
+    #[test]
+    fn rooms_are_connected_by_doors() {
+        let state = apply(&GeneratorSettings {
+            loopiness: 0.0,
+            ..GeneratorSettings::new()
+        });
+        let rooms = state.dungeon.chambers.len();
+        assert_eq!(rooms, 8);
+        // a spanning tree connects all rooms, each corridor has a door at both ends
+        assert_eq!(state.dungeon.corridors.len(), rooms - 1);
+        assert_eq!(state.dungeon.doors.len(), 2 * (rooms - 1));
+        assert!(state.active_chamber_id.is_none());
+    }
+
+    #[test]
+    fn corridors_go_around_rooms() {
+        for seed in 1..6 {
+            let state = apply(&GeneratorSettings {
+                seed,
+                loopiness: 1.0,
+                ..GeneratorSettings::new()
+            });
+            let chambers = &state.dungeon.chambers;
+            for corridor in state.dungeon.corridors.iter() {
+                let points = corridor.points();
+                let ends: Vec<Vec2<f64>> = vec![
+                    (*points.first().unwrap()).into(),
+                    (*points.last().unwrap()).into(),
+                ];
+                let cells: Vec<Vec2<i32>> = points
+                    .iter()
+                    .map(|p| Vec2 {
+                        x: p.x / GRID_SIZE,
+                        y: p.y / GRID_SIZE,
+                    })
+                    .collect();
+                for cell in path_cells(&cells) {
+                    let p: Vec2<f64> = Vec2 {
+                        x: cell.x * GRID_SIZE + GRID_SIZE / 2,
+                        y: cell.y * GRID_SIZE + GRID_SIZE / 2,
+                    }
+                    .into();
+                    for chamber in chambers.iter().filter(|c| c.contains_point(p)) {
+                        // a corridor only passes the rooms it connects
+                        assert!(ends.iter().any(|e| chamber.contains_point(*e)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corridor_path_avoids_blocked_cells() {
+        let blocked: HashSet<Vec2<i32>> = (-1..=1).map(|y| Vec2 { x: 2, y }).collect();
+        let path = corridor_path(
+            Vec2 { x: 0, y: 0 },
+            Vec2 { x: 4, y: 0 },
+            &blocked,
+            Vec2 { x: 5, y: 5 },
+        );
+        assert_eq!(path.first(), Some(&Vec2 { x: 0, y: 0 }));
+        assert_eq!(path.last(), Some(&Vec2 { x: 4, y: 0 }));
+        assert!(path_cells(&path).iter().all(|c| !blocked.contains(c)));
+        // straight segments between the corners
+        assert!(path
+            .windows(2)
+            .all(|w| w[0].x == w[1].x || w[0].y == w[1].y));
+    }
+
+    #[test]
+    fn trace_cells_with_hole() {
+        let mut cells = HashSet::new();
+        for x in 0..3 {
+            for y in 0..3 {
+                if (x, y) != (1, 1) {
+                    cells.insert(Vec2 { x, y });
+                }
+            }
+        }
+        let rings = trace(&cells);
+        assert_eq!(rings.len(), 2);
+        assert_eq!(
+            rings[0],
+            vec![
+                Vec2 { x: 0, y: 0 },
+                Vec2 { x: 3, y: 0 },
+                Vec2 { x: 3, y: 3 },
+                Vec2 { x: 0, y: 3 }
+            ]
+        );
+        assert!(signed_area(&rings[0]) > 0);
+        assert_eq!(signed_area(&rings[1]), -2);
+    }
+}
@@ -0,0 +1,701 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::common::Vec2;
+
+/// A polygon consisting of an outer ring and optional holes.
+/// The outer ring has a positive signed area, holes have a negative signed area.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shape {
+    pub outer: Vec<Vec2<i32>>,
+    pub holes: Vec<Vec<Vec2<i32>>>,
+}
+
+impl Shape {
+    /// even-odd test if a point is inside the shape and not inside one of its holes
+    pub fn contains(&self, p: Vec2<f64>) -> bool {
+        ring_contains(&self.outer, p) && !self.holes.iter().any(|h| ring_contains(h, p))
+    }
+
+    /// outer ring and holes
+    pub fn rings(&self) -> impl Iterator<Item = &Vec<Vec2<i32>>> {
+        std::iter::once(&self.outer).chain(self.holes.iter())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Difference,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EdgeClass {
+    Inside,
+    Outside,
+    // edge also exists in the other polygon, bool is true if it has the same direction
+    Shared(bool),
+}
+
+type Edge = (Vec2<i32>, Vec2<i32>);
+
+fn cross(a: Vec2<i64>, b: Vec2<i64>) -> i64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn wide(v: Vec2<i32>) -> Vec2<i64> {
+    Vec2 {
+        x: v.x as i64,
+        y: v.y as i64,
+    }
+}
+
+/// signed area of a ring, positive for rings with "positive" orientation
+pub fn signed_area(ring: &[Vec2<i32>]) -> f64 {
+    let mut sum: i64 = 0;
+    for i in 0..ring.len() {
+        let a = wide(ring[i]);
+        let b = wide(ring[(i + 1) % ring.len()]);
+        sum += a.x * b.y - b.x * a.y;
+    }
+    sum as f64 / 2.0
+}
+
+/// even-odd test if a point is inside a ring
+pub fn ring_contains(ring: &[Vec2<i32>], p: Vec2<f64>) -> bool {
+    let mut inside = false;
+    for i in 0..ring.len() {
+        let a: Vec2<f64> = ring[i].into();
+        let b: Vec2<f64> = ring[(i + 1) % ring.len()].into();
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if x > p.x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn on_segment(p: Vec2<i32>, q: Vec2<i32>, x: Vec2<i32>) -> bool {
+    let d = wide(q) - wide(p);
+    let v = wide(x) - wide(p);
+    if cross(d, v) != 0 {
+        return false;
+    }
+    let dot = d.x * v.x + d.y * v.y;
+    dot >= 0 && dot <= d.x * d.x + d.y * d.y
+}
+
+/// intersection point of two segments, rounded to the integer grid.
+/// Parallel segments have no intersection; overlaps are handled by `on_segment`
+fn intersection(p: Vec2<i32>, q: Vec2<i32>, r: Vec2<i32>, s: Vec2<i32>) -> Option<Vec2<i32>> {
+    let d1 = wide(q) - wide(p);
+    let d2 = wide(s) - wide(r);
+    let denom = cross(d1, d2);
+    if denom == 0 {
+        return None;
+    }
+    let rp = wide(r) - wide(p);
+    let t = cross(rp, d2) as f64 / denom as f64;
+    let u = cross(rp, d1) as f64 / denom as f64;
+    if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    Some(Vec2 {
+        x: (p.x as f64 + t * d1.x as f64).round() as i32,
+        y: (p.y as f64 + t * d1.y as f64).round() as i32,
+    })
+}
+
+fn ring_edges(ring: &[Vec2<i32>]) -> Vec<Edge> {
+    (0..ring.len())
+        .map(|i| (ring[i], ring[(i + 1) % ring.len()]))
+        .filter(|(a, b)| a != b)
+        .collect()
+}
+
+/// splits all edges at the points where they touch or cross edges of `other`
+fn split_edges(edges: &[Edge], other: &[Edge]) -> Vec<Edge> {
+    let mut result = vec![];
+    for &(p, q) in edges {
+        let mut points = vec![];
+        for &(r, s) in other {
+            if on_segment(p, q, r) {
+                points.push(r);
+            }
+            if on_segment(p, q, s) {
+                points.push(s);
+            }
+            if let Some(x) = intersection(p, q, r, s) {
+                points.push(x);
+            }
+            if on_segment(r, s, p) {
+                points.push(p);
+            }
+        }
+        let d = wide(q) - wide(p);
+        let param = |x: &Vec2<i32>| {
+            let v = wide(*x) - wide(p);
+            (v.x * d.x + v.y * d.y) as f64 / (d.x * d.x + d.y * d.y) as f64
+        };
+        points.retain(|x| *x != p && *x != q);
+        points.sort_by(|a, b| param(a).total_cmp(&param(b)));
+        points.dedup();
+
+        let mut last = p;
+        for x in points {
+            if x != last {
+                result.push((last, x));
+                last = x;
+            }
+        }
+        if last != q {
+            result.push((last, q));
+        }
+    }
+    result
+}
+
+fn classify(
+    edges: &[Edge],
+    other_edges: &[Edge],
+    other_rings: &[Vec<Vec2<i32>>],
+) -> Vec<EdgeClass> {
+    let other_set: HashSet<Edge> = other_edges.iter().copied().collect();
+    edges
+        .iter()
+        .map(|&(a, b)| {
+            if other_set.contains(&(a, b)) {
+                EdgeClass::Shared(true)
+            } else if other_set.contains(&(b, a)) {
+                EdgeClass::Shared(false)
+            } else {
+                let mid = 0.5 * (Into::<Vec2<f64>>::into(a) + b.into());
+                let inside = other_rings.iter().filter(|r| ring_contains(r, mid)).count() % 2 == 1;
+                match inside {
+                    true => EdgeClass::Inside,
+                    false => EdgeClass::Outside,
+                }
+            }
+        })
+        .collect()
+}
+
+/// connects directed edges into closed rings
+fn chain(edges: Vec<Edge>) -> Vec<Vec<Vec2<i32>>> {
+    let mut outgoing: HashMap<Vec2<i32>, Vec<usize>> = HashMap::new();
+    for (i, e) in edges.iter().enumerate() {
+        outgoing.entry(e.0).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = vec![];
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        let mut ring = vec![edges[start].0];
+        let mut cur = start;
+        let closed = loop {
+            used[cur] = true;
+            let end = edges[cur].1;
+            if end == ring[0] {
+                break true;
+            }
+            match outgoing
+                .get(&end)
+                .and_then(|c| c.iter().find(|i| !used[**i]))
+            {
+                Some(next) => {
+                    ring.push(end);
+                    cur = *next;
+                }
+                None => break false,
+            }
+        };
+        if closed {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+/// removes duplicated and collinear corners
+pub fn simplify(ring: &[Vec2<i32>]) -> Vec<Vec2<i32>> {
+    let mut ring: Vec<Vec2<i32>> = ring.to_vec();
+    ring.dedup();
+    while ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    let mut changed = true;
+    while changed && ring.len() >= 3 {
+        changed = false;
+        for i in 0..ring.len() {
+            let prev = ring[(i + ring.len() - 1) % ring.len()];
+            let next = ring[(i + 1) % ring.len()];
+            if cross(wide(ring[i]) - wide(prev), wide(next) - wide(ring[i])) == 0 {
+                ring.remove(i);
+                changed = true;
+                break;
+            }
+        }
+    }
+    ring
+}
+
+fn oriented(ring: &[Vec2<i32>], positive: bool) -> Vec<Vec2<i32>> {
+    let mut ring = simplify(ring);
+    if (signed_area(&ring) > 0.0) != positive {
+        ring.reverse();
+    }
+    ring
+}
+
+fn shape_rings(shape: &Shape) -> Vec<Vec<Vec2<i32>>> {
+    let mut rings = vec![oriented(&shape.outer, true)];
+    for hole in shape.holes.iter() {
+        rings.push(oriented(hole, false));
+    }
+    rings.retain(|r| r.len() >= 3);
+    rings
+}
+
+/// combines rings into shapes by assigning holes to the smallest outer ring containing them
+fn assemble(rings: Vec<Vec<Vec2<i32>>>) -> Vec<Shape> {
+    let (outers, holes): (Vec<_>, Vec<_>) = rings
+        .into_iter()
+        .map(|r| simplify(&r))
+        .filter(|r| r.len() >= 3 && signed_area(r) != 0.0)
+        .partition(|r| signed_area(r) > 0.0);
+
+    let mut shapes: Vec<Shape> = outers
+        .into_iter()
+        .map(|outer| Shape {
+            outer,
+            holes: vec![],
+        })
+        .collect();
+    for hole in holes {
+        let probe = 0.5 * (Into::<Vec2<f64>>::into(hole[0]) + hole[1].into());
+        let owner = shapes
+            .iter_mut()
+            .filter(|s| ring_contains(&s.outer, probe))
+            .min_by(|a, b| signed_area(&a.outer).total_cmp(&signed_area(&b.outer)));
+        if let Some(shape) = owner {
+            shape.holes.push(hole);
+        }
+    }
+    shapes
+}
+
+/// Applies a boolean operation on two shapes.
+/// The result can consist of any number of shapes (e.g. a difference can cut a shape in two).
+pub fn boolean(a: &Shape, b: &Shape, op: BooleanOp) -> Vec<Shape> {
+    let rings_a = shape_rings(a);
+    let rings_b = shape_rings(b);
+    if rings_a.is_empty() {
+        return match op {
+            BooleanOp::Union => assemble(rings_b),
+            _ => vec![],
+        };
+    }
+    if rings_b.is_empty() {
+        return assemble(rings_a);
+    }
+
+    let edges_a: Vec<Edge> = rings_a.iter().flat_map(|r| ring_edges(r)).collect();
+    let edges_b: Vec<Edge> = rings_b.iter().flat_map(|r| ring_edges(r)).collect();
+    let split_a = split_edges(&edges_a, &edges_b);
+    let split_b = split_edges(&edges_b, &edges_a);
+    let class_a = classify(&split_a, &split_b, &rings_b);
+    let class_b = classify(&split_b, &split_a, &rings_a);
+
+    let mut result: Vec<Edge> = vec![];
+    for (e, c) in split_a.iter().zip(class_a.iter()) {
+        let keep = matches!(
+            (op, c),
+            (BooleanOp::Union, EdgeClass::Outside)
+                | (BooleanOp::Difference, EdgeClass::Outside)
+                | (BooleanOp::Union, EdgeClass::Shared(true))
+                | (BooleanOp::Difference, EdgeClass::Shared(false))
+        );
+        if keep {
+            result.push(*e);
+        }
+    }
+    for (e, c) in split_b.iter().zip(class_b.iter()) {
+        match (op, c) {
+            (BooleanOp::Union, EdgeClass::Outside) => result.push(*e),
+            (BooleanOp::Difference, EdgeClass::Inside) => result.push((e.1, e.0)),
+            _ => (),
+        }
+    }
+
+    assemble(chain(result))
+}
+
+/// Splits a shape in two along the segment from `from` to `to`.
+/// The part of the segment inside the shape has to run from one point of the outer ring to another,
+/// otherwise the shape is not split and `None` is returned.
+pub fn split(shape: &Shape, from: Vec2<i32>, to: Vec2<i32>) -> Option<(Shape, Shape)> {
+    let a: Vec2<f64> = from.into();
+    let b: Vec2<f64> = to.into();
+    let [(t0, t1)] = segment_inside(std::slice::from_ref(shape), a, b)[..] else {
+        return None;
+    };
+    let outer = oriented(&shape.outer, true);
+    let p = a + t0 * (b - a);
+    let q = a + t1 * (b - a);
+    let i = ring_edge_at(&outer, p)?;
+    let j = ring_edge_at(&outer, q)?;
+    if i == j {
+        return None;
+    }
+    let round = |v: Vec2<f64>| Vec2 {
+        x: v.x.round() as i32,
+        y: v.y.round() as i32,
+    };
+    // walk along the outer ring from the cut at `p` to the cut at `q` and back
+    let part = |start: usize, end: usize, first: Vec2<f64>, last: Vec2<f64>| {
+        let mut ring = vec![round(first)];
+        let mut k = start;
+        while k != end {
+            k = (k + 1) % outer.len();
+            ring.push(outer[k]);
+        }
+        ring.push(round(last));
+        oriented(&ring, true)
+    };
+    let mut rings = vec![part(i, j, p, q), part(j, i, q, p)];
+    rings.extend(shape.holes.iter().map(|h| oriented(h, false)));
+    match &assemble(rings)[..] {
+        [first, second] => Some((first.clone(), second.clone())),
+        _ => None,
+    }
+}
+
+/// index of the edge of the ring `p` lies on
+fn ring_edge_at(ring: &[Vec2<i32>], p: Vec2<f64>) -> Option<usize> {
+    (0..ring.len())
+        .map(|i| {
+            let a: Vec2<f64> = ring[i].into();
+            let d = Into::<Vec2<f64>>::into(ring[(i + 1) % ring.len()]) - a;
+            let t = ((p - a).dot(d) / d.sqr_len()).clamp(0.0, 1.0);
+            (i, (a + t * d - p).len())
+        })
+        .filter(|(_, distance)| *distance < 1.0)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// Parameter intervals (0..1) of the segment from `a` to `b` lying inside any of the shapes.
+/// Overlapping intervals are merged.
+pub fn segment_inside(shapes: &[Shape], a: Vec2<f64>, b: Vec2<f64>) -> Vec<(f64, f64)> {
+    let d = b - a;
+    let mut params = vec![0.0, 1.0];
+    for shape in shapes {
+        for ring in shape.rings() {
+            for i in 0..ring.len() {
+                let p: Vec2<f64> = ring[i].into();
+                let q: Vec2<f64> = ring[(i + 1) % ring.len()].into();
+                let e = q - p;
+                let denom = d.x * e.y - d.y * e.x;
+                if denom == 0.0 {
+                    continue;
+                }
+                let ap = p - a;
+                let t = (ap.x * e.y - ap.y * e.x) / denom;
+                let u = (ap.x * d.y - ap.y * d.x) / denom;
+                if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                    params.push(t);
+                }
+            }
+        }
+    }
+    params.sort_by(|a, b| a.total_cmp(b));
+
+    let mut intervals: Vec<(f64, f64)> = vec![];
+    for w in params.windows(2) {
+        if w[1] - w[0] < 1e-9 {
+            continue;
+        }
+        let mid = a + (0.5 * (w[0] + w[1])) * d;
+        if shapes.iter().any(|s| s.contains(mid)) {
+            match intervals.last_mut() {
+                Some(last) if last.1 >= w[0] => last.1 = w[1],
+                _ => intervals.push((w[0], w[1])),
+            }
+        }
+    }
+    intervals
+}
+
+/// Splits a closed ring into open polylines, leaving out the gaps of each edge.
+/// `gaps(i)` returns the sorted parameter intervals to leave out on the edge starting at corner `i`.
+/// A ring without gaps results in a single polyline returning to its first corner.
+pub fn ring_runs(
+    ring: &[Vec2<f64>],
+    gaps: impl Fn(usize) -> Vec<(f64, f64)>,
+) -> Vec<Vec<Vec2<f64>>> {
+    let mut runs: Vec<Vec<Vec2<f64>>> = vec![];
+    let mut current: Vec<Vec2<f64>> = vec![];
+    // true if the first run starts at the first corner and can be joined with the last run
+    let mut starts_closed = false;
+    for i in 0..ring.len() {
+        let a = ring[i];
+        let b = ring[(i + 1) % ring.len()];
+        let at = |t: f64| a + t * (b - a);
+
+        let mut visible = vec![];
+        let mut t = 0.0;
+        for (g0, g1) in gaps(i) {
+            if g0 > t {
+                visible.push((t, g0));
+            }
+            t = f64::max(t, g1);
+        }
+        if t < 1.0 {
+            visible.push((t, 1.0));
+        }
+
+        for (t0, t1) in visible {
+            if t0 == 0.0 && !current.is_empty() {
+                current.push(at(t1));
+            } else {
+                if current.len() > 1 {
+                    runs.push(current);
+                }
+                if i == 0 && t0 == 0.0 {
+                    starts_closed = true;
+                }
+                current = vec![at(t0), at(t1)];
+            }
+            if t1 < 1.0 {
+                runs.push(current);
+                current = vec![];
+            }
+        }
+    }
+    if current.len() > 1 {
+        if starts_closed && !runs.is_empty() {
+            // last run ends at the first corner, continue with the first run
+            let first = runs.remove(0);
+            current.extend(first.into_iter().skip(1));
+        }
+        runs.push(current);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{boolean, ring_runs, segment_inside, signed_area, split, BooleanOp, Shape};
+    use crate::common::Vec2;
+
+    fn rect(x0: i32, y0: i32, x1: i32, y1: i32) -> Shape {
+        Shape {
+            outer: vec![
+                Vec2 { x: x0, y: y0 },
+                Vec2 { x: x1, y: y0 },
+                Vec2 { x: x1, y: y1 },
+                Vec2 { x: x0, y: y1 },
+            ],
+            holes: vec![],
+        }
+    }
+
+    fn area(shapes: &[Shape]) -> f64 {
+        shapes
+            .iter()
+            .map(|s| signed_area(&s.outer) + s.holes.iter().map(|h| signed_area(h)).sum::<f64>())
+            .sum()
+    }
+
+    #[test]
+    fn union_adjacent() {
+        let r = boolean(
+            &rect(0, 0, 100, 100),
+            &rect(100, 0, 200, 100),
+            BooleanOp::Union,
+        );
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].outer.len(), 4);
+        assert_eq!(area(&r), 20000.0);
+    }
+
+    #[test]
+    fn union_overlapping() {
+        let r = boolean(
+            &rect(0, 0, 100, 100),
+            &rect(50, 50, 150, 150),
+            BooleanOp::Union,
+        );
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].outer.len(), 8);
+        assert_eq!(area(&r), 17500.0);
+    }
+
+    #[test]
+    fn union_disjoint() {
+        let r = boolean(
+            &rect(0, 0, 100, 100),
+            &rect(200, 0, 300, 100),
+            BooleanOp::Union,
+        );
+        assert_eq!(r.len(), 2);
+    }
+
+    #[test]
+    fn difference_corner() {
+        let r = boolean(
+            &rect(0, 0, 100, 100),
+            &rect(50, 50, 150, 150),
+            BooleanOp::Difference,
+        );
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].outer.len(), 6);
+        assert_eq!(area(&r), 7500.0);
+    }
+
+    #[test]
+    fn difference_cuts_in_two() {
+        let r = boolean(
+            &rect(0, 0, 300, 100),
+            &rect(100, -50, 200, 150),
+            BooleanOp::Difference,
+        );
+        assert_eq!(r.len(), 2);
+        assert_eq!(area(&r), 20000.0);
+    }
+
+    #[test]
+    fn difference_inner_creates_hole() {
+        let r = boolean(
+            &rect(0, 0, 300, 300),
+            &rect(100, 100, 200, 200),
+            BooleanOp::Difference,
+        );
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].holes.len(), 1);
+        assert_eq!(area(&r), 80000.0);
+    }
+
+    #[test]
+    fn difference_shared_wall() {
+        let r = boolean(
+            &rect(0, 0, 200, 100),
+            &rect(100, 0, 200, 100),
+            BooleanOp::Difference,
+        );
+        assert_eq!(r.len(), 1);
+        assert_eq!(r[0].outer.len(), 4);
+        assert_eq!(area(&r), 10000.0);
+    }
+
+    #[test]
+    fn split_rect() {
+        let (a, b) = split(
+            &rect(0, 0, 200, 100),
+            Vec2 { x: 100, y: -50 },
+            Vec2 { x: 100, y: 150 },
+        )
+        .unwrap();
+        assert_eq!(area(&[a]), 10000.0);
+        assert_eq!(area(&[b]), 10000.0);
+    }
+
+    #[test]
+    fn split_miss() {
+        let r = split(
+            &rect(0, 0, 200, 100),
+            Vec2 { x: 300, y: 0 },
+            Vec2 { x: 300, y: 100 },
+        );
+        assert!(r.is_none());
+        // the segment ends inside of the chamber
+        let r = split(
+            &rect(0, 0, 200, 100),
+            Vec2 { x: 100, y: 0 },
+            Vec2 { x: 100, y: 50 },
+        );
+        assert!(r.is_none());
+    }
+
+    #[test]
+    fn split_concave_in_two() {
+        // U shape open to the top
+        let u = Shape {
+            outer: vec![
+                Vec2 { x: 0, y: 0 },
+                Vec2 { x: 100, y: 0 },
+                Vec2 { x: 100, y: 200 },
+                Vec2 { x: 200, y: 200 },
+                Vec2 { x: 200, y: 0 },
+                Vec2 { x: 300, y: 0 },
+                Vec2 { x: 300, y: 300 },
+                Vec2 { x: 0, y: 300 },
+            ],
+            holes: vec![],
+        };
+        // only the left arm is cut off
+        let (a, b) = split(&u, Vec2 { x: 0, y: 100 }, Vec2 { x: 100, y: 100 }).unwrap();
+        let mut areas = [area(&[a]), area(&[b])];
+        areas.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!(areas, [10000.0, 60000.0]);
+        // a segment through both arms would cut the shape in three
+        assert!(split(&u, Vec2 { x: 0, y: 100 }, Vec2 { x: 300, y: 100 }).is_none());
+    }
+
+    #[test]
+    fn split_keeps_hole() {
+        let mut shape = rect(0, 0, 300, 300);
+        shape.holes.push(rect(100, 100, 200, 200).outer);
+        let (a, b) = split(&shape, Vec2 { x: 0, y: 50 }, Vec2 { x: 300, y: 50 }).unwrap();
+        assert_eq!(a.holes.len() + b.holes.len(), 1);
+        assert_eq!(area(&[a, b]), 80000.0);
+        // a segment ending at the hole does not split the shape
+        assert!(split(&shape, Vec2 { x: 0, y: 150 }, Vec2 { x: 100, y: 150 }).is_none());
+    }
+
+    #[test]
+    fn segment_inside_rect() {
+        let shapes = vec![rect(100, 0, 200, 100), rect(150, 0, 300, 100)];
+        let r = segment_inside(
+            &shapes,
+            Vec2 { x: 0.0, y: 50.0 },
+            Vec2 { x: 400.0, y: 50.0 },
+        );
+        assert_eq!(r, vec![(0.25, 0.75)]);
+    }
+
+    #[test]
+    fn runs_without_gaps() {
+        let ring = vec![
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 1.0, y: 0.0 },
+            Vec2 { x: 1.0, y: 1.0 },
+        ];
+        let runs = ring_runs(&ring, |_| vec![]);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].len(), 4);
+        assert_eq!(runs[0][0], runs[0][3]);
+    }
+
+    #[test]
+    fn runs_with_gap() {
+        let ring = vec![
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 4.0, y: 0.0 },
+            Vec2 { x: 4.0, y: 4.0 },
+            Vec2 { x: 0.0, y: 4.0 },
+        ];
+        let runs = ring_runs(&ring, |i| match i {
+            1 => vec![(0.25, 0.5)],
+            _ => vec![],
+        });
+        // the gap cuts the ring into a single open polyline
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].first(), Some(&Vec2 { x: 4.0, y: 2.0 }));
+        assert_eq!(runs[0].last(), Some(&Vec2 { x: 4.0, y: 1.0 }));
+        assert_eq!(runs[0].len(), 6);
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use crate::{
+    chamber::ChamberId, common::Vec2, config::GRID_SIZE, door::Door, dungeon::Dungeon,
+    emitter::Emitter, geometry::Shape, state::StateCommand, storage,
+};
+
+/// Content of another dungeon file, placed into the current dungeon.
+/// Entities get new ids on placement, so they do not collide with existing ones.
+pub struct Import {
+    dungeon: Dungeon,
+    /// top left corner of the content, aligned to the grid
+    origin: Vec2<i32>,
+    /// computed on load, the preview follows every mouse motion
+    outlines: Vec<Shape>,
+    size: Vec2<i32>,
+}
+
+impl Import {
+    /// loads a dungeon file by replaying its commands, `None` if it has no chambers or corridors
+    pub fn load(path: String) -> Option<Self> {
+        Self::from_dungeon(storage::load_dungeon_model(path))
+    }
+
+    pub fn from_dungeon(dungeon: Dungeon) -> Option<Self> {
+        let points: Vec<Vec2<i32>> = dungeon
+            .chambers
+            .iter()
+            .flat_map(|c| c.vertices())
+            .chain(
+                dungeon
+                    .corridors
+                    .iter()
+                    .flat_map(|c| c.points().iter().copied()),
+            )
+            .collect();
+        let min = Vec2 {
+            x: points.iter().map(|v| v.x).min()?,
+            y: points.iter().map(|v| v.y).min()?,
+        };
+        let origin = Vec2 {
+            x: min.x.div_euclid(GRID_SIZE) * GRID_SIZE,
+            y: min.y.div_euclid(GRID_SIZE) * GRID_SIZE,
+        };
+
+        let relative = |ring: &Vec<Vec2<i32>>| -> Vec<Vec2<i32>> {
+            ring.iter().map(|v| *v - origin).collect()
+        };
+        let outlines: Vec<Shape> = dungeon
+            .chambers
+            .iter()
+            .map(|c| c.shape())
+            .chain(
+                dungeon
+                    .corridors
+                    .iter()
+                    .flat_map(|c| c.outline().iter().cloned()),
+            )
+            .map(|s| Shape {
+                outer: relative(&s.outer),
+                holes: s.holes.iter().map(relative).collect(),
+            })
+            .collect();
+        let points = || outlines.iter().flat_map(|s| s.outer.iter());
+        let size = Vec2 {
+            x: points().map(|v| v.x).max().unwrap_or(0),
+            y: points().map(|v| v.y).max().unwrap_or(0),
+        };
+
+        Some(Self {
+            dungeon,
+            origin,
+            outlines,
+            size,
+        })
+    }
+
+    /// outlines of chambers and corridors, relative to the top left corner
+    pub fn outlines(&self) -> &Vec<Shape> {
+        &self.outlines
+    }
+
+    /// size of the bounding box
+    pub fn size(&self) -> Vec2<i32> {
+        self.size
+    }
+
+    /// Commands placing the content with its top left corner at `pos`.
+    /// Ids are remapped, doors are moved to the walls of the new chambers.
+    /// The name, notes and background of the imported dungeon are not taken over.
+    pub fn place(&self, dungeon: &Dungeon, pos: Vec2<i32>) -> Vec<StateCommand> {
+        let offset = pos - self.origin;
+        let offset_f: Vec2<f64> = offset.into();
+        let mut emitter = Emitter::new(dungeon);
+
+        // chambers first, doors may lead to any of them
+        let mut chambers: HashMap<ChamberId, ChamberId> = HashMap::new();
+        for chamber in self.dungeon.chambers.iter() {
+            let shape = chamber.shape();
+            if shape.outer.is_empty() {
+                continue;
+            }
+            let outer: Vec<Vec2<i32>> = shape.outer.iter().map(|v| *v + offset).collect();
+            let holes: Vec<Vec<Vec2<i32>>> = shape
+                .holes
+                .iter()
+                .map(|h| h.iter().map(|v| *v + offset).collect())
+                .collect();
+            let chamber_id = emitter.add_chamber(&outer, &holes);
+            chambers.insert(chamber.id, chamber_id);
+            emitter.apply(StateCommand::ChangeChamberName(
+                chamber_id,
+                chamber.name.clone(),
+            ));
+            if !chamber.notes.is_empty() {
+                emitter.apply(StateCommand::ChangeChamberNotes(
+                    chamber_id,
+                    chamber.notes.clone(),
+                ));
+            }
+            if !chamber.read_aloud.is_empty() {
+                emitter.apply(StateCommand::ChangeChamberReadAloud(
+                    chamber_id,
+                    chamber.read_aloud.clone(),
+                ));
+            }
+            if chamber.hidden {
+                emitter.apply(StateCommand::ChangeChamberHidden(chamber_id, true));
+            }
+        }
+
+        for door in self.dungeon.doors.iter() {
+            let part_of = match chambers.get(&door.part_of) {
+                Some(id) => *id,
+                None => continue,
+            };
+            let door_pos = self.dungeon.door_world_pos(door) + offset_f;
+            if let Some(wall) = emitter.wall_at(part_of, door_pos) {
+                let mut new_door = Door::new(
+                    part_of,
+                    door.leads_to.and_then(|id| chambers.get(&id).copied()),
+                    door.width,
+                    wall.id,
+                    wall.nearest_relative_pos(door_pos),
+                );
+                new_door.name = door.name.clone();
+                new_door.notes = door.notes.clone();
+                new_door.read_aloud = door.read_aloud.clone();
+                new_door.hidden = door.hidden;
+                emitter.add_door(new_door);
+            }
+        }
+
+        for object in self.dungeon.objects.iter() {
+            let mut new_object = object.clone();
+            new_object.pos = object.pos + offset;
+            new_object.part_of = object.part_of.and_then(|id| chambers.get(&id).copied());
+            emitter.add_object(&new_object);
+        }
+
+        for corridor in self.dungeon.corridors.iter() {
+            emitter.apply(StateCommand::AddCorridor);
+            let corridor_id = emitter.state.active_corridor_id.unwrap();
+            for point in corridor.points() {
+                emitter.apply(StateCommand::AddVertexToCorridor(
+                    corridor_id,
+                    *point + offset,
+                ));
+            }
+            emitter.apply(StateCommand::ChangeCorridorName(
+                corridor_id,
+                corridor.name.clone(),
+            ));
+            if corridor.width() != 1 {
+                emitter.apply(StateCommand::ChangeCorridorWidth(
+                    corridor_id,
+                    corridor.width(),
+                ));
+            }
+            if !corridor.notes.is_empty() {
+                emitter.apply(StateCommand::ChangeCorridorNotes(
+                    corridor_id,
+                    corridor.notes.clone(),
+                ));
+            }
+            if corridor.hidden {
+                emitter.apply(StateCommand::ChangeCorridorHidden(corridor_id, true));
+            }
+            if corridor.numbered {
+                emitter.apply(StateCommand::ChangeCorridorNumbered(corridor_id, true));
+            }
+        }
+
+        emitter.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::Vec2;
+    use crate::door::Door;
+    use crate::dungeon::tests::add_rect;
+    use crate::state::{State, StateCommand};
+    use crate::storage::{load_dungeon, save_to_file};
+
+    use super::Import;
+
+    /// two chambers connected by a door, with an object and a corridor
+    fn other_dungeon() -> State {
+        let mut state = State::new();
+        add_rect(&mut state.dungeon, 100, 100, 200, 200);
+        add_rect(&mut state.dungeon, 200, 100, 300, 200);
+        let wall = state.dungeon.chamber(1).unwrap().walls()[1];
+        StateCommand::AddDoor(Door::new(1, Some(2), 50.0, wall.id, 0.5)).execute(&mut state);
+        StateCommand::ChangeDoorName(1, "Gate".to_owned()).execute(&mut state);
+        StateCommand::ChangeDoorNotes(1, "Barred".to_owned()).execute(&mut state);
+        StateCommand::ChangeDoorHidden(1, true).execute(&mut state);
+        StateCommand::AddObject(Vec2 { x: 250, y: 150 }, Some(2)).execute(&mut state);
+        StateCommand::AddCorridor.execute(&mut state);
+        for (x, y) in [(325, 125), (425, 125)] {
+            StateCommand::AddVertexToCorridor(1, Vec2 { x, y }).execute(&mut state);
+        }
+        state
+    }
+
+    #[test]
+    fn import_remaps_ids() {
+        let imported = Import::from_dungeon(other_dungeon().dungeon).unwrap();
+        assert_eq!(imported.size(), Vec2 { x: 350, y: 100 });
+
+        // the current dungeon already uses the ids of the imported one
+        let mut state = other_dungeon();
+        let cmds = imported.place(&state.dungeon, Vec2 { x: 1000, y: 500 });
+        // the placed dungeon survives saving and loading
+        let path = "/tmp/test_import_remaps_ids.dungeon".to_owned();
+        save_to_file(path.clone(), &cmds);
+        for cmd in load_dungeon(path) {
+            cmd.execute(&mut state);
+        }
+        let dungeon = &state.dungeon;
+        assert_eq!(dungeon.chambers.len(), 4);
+        assert_eq!(dungeon.corridors.len(), 2);
+        assert_eq!(
+            dungeon.chamber(3).unwrap().bbox().min,
+            Vec2 {
+                x: 1000.0,
+                y: 500.0
+            }
+        );
+
+        let door = &dungeon.doors[1];
+        assert_eq!((door.part_of, door.leads_to), (3, Some(4)));
+        assert_eq!(door.name, "Gate");
+        assert_eq!(door.notes, "Barred");
+        assert!(door.hidden);
+        assert_eq!(
+            dungeon.door_world_pos(door),
+            Vec2 {
+                x: 1100.0,
+                y: 550.0
+            }
+        );
+        let object = &dungeon.objects[1];
+        assert_eq!(object.pos, Vec2 { x: 1150, y: 550 });
+        assert_eq!(object.part_of, Some(4));
+        assert_eq!(dungeon.corridors[1].points()[0], Vec2 { x: 1225, y: 525 });
+    }
+}
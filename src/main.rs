@@ -1,15 +1,30 @@
+mod background;
 mod chamber;
 mod common;
 mod config;
+mod corridor;
+mod diff;
 mod door;
 mod dungeon;
 mod edit_actions;
+mod emitter;
 mod export;
+mod export_settings;
 mod file_actions;
+mod generator;
+mod geometry;
+mod import;
+mod markdown;
 mod object;
 pub mod observers;
+mod packing;
+mod print_theme;
+mod selection;
+mod session;
 mod state;
 mod storage;
+mod symbol;
+mod template;
 mod view;
 
 use cairo::glib::{clone, Propagation};
@@ -22,20 +37,32 @@ use observers::HistoryObserver;
 use state::StateController;
 use std::cell::RefCell;
 use std::rc::Rc;
-use view::buttons::{AddChamberButton, EditModeButton};
+use view::buttons::{AddChamberButton, AddCorridorButton, EditModeButton};
 use view::canvas::Canvas;
 use view::chamber_edit::ChamberEdit;
 use view::chamber_list::ChamberList;
+use view::corridor_edit::CorridorEdit;
+use view::corridor_list::CorridorList;
 use view::door_edit::DoorEdit;
 use view::door_list::DoorList;
 use view::dungeon_edit::DungeonEdit;
 use view::entity_tabs::EntityTabs;
 use view::object_edit::ObjectEdit;
 use view::object_list::ObjectList;
+use view::player_view::PlayerView;
+use view::print_preview::PrintPreview;
+use view::session_edit::SessionEdit;
+use view::template_panel::TemplatePanel;
 
 const APP_ID: &str = "org.rerere.DungeonPlanner";
 
 fn main() -> glib::ExitCode {
+    // `dungeon_planner --diff OLD NEW` prints the changes between two dungeon files
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 4 && args[1] == "--diff" {
+        return print_diff(&args[2], &args[3]);
+    }
+
     // Create a new application
     let app = adw::Application::builder().application_id(APP_ID).build();
 
@@ -55,6 +82,9 @@ fn main() -> glib::ExitCode {
     app.set_accels_for_action("edit.delete_selected", &["<Ctrl><Alt>X"]);
     app.set_accels_for_action("edit.undo", &["<Ctrl>Z"]);
     app.set_accels_for_action("edit.add_chamber", &["<Alt>C"]);
+    app.set_accels_for_action("edit.add_corridor", &["<Alt>K"]);
+    app.set_accels_for_action("edit.reveal_selected", &["<Ctrl>R"]);
+    app.set_accels_for_action("edit.unreveal_selected", &["<Ctrl><Shift>R"]);
 
     // modes
     app.set_accels_for_action("edit.mode_select", &["<Alt>S"]);
@@ -68,6 +98,26 @@ fn main() -> glib::ExitCode {
     app.run()
 }
 
+fn print_diff(old: &str, new: &str) -> glib::ExitCode {
+    for path in [old, new] {
+        if !std::path::Path::new(path).is_file() {
+            eprintln!("Unable to read {}", path);
+            return glib::ExitCode::FAILURE;
+        }
+    }
+    let changes = diff::diff(
+        &storage::load_dungeon_model(old.to_owned()),
+        &storage::load_dungeon_model(new.to_owned()),
+    );
+    if changes.is_empty() {
+        println!("No changes");
+    }
+    for change in changes {
+        println!("{}", change.describe());
+    }
+    glib::ExitCode::SUCCESS
+}
+
 fn load_css() {
     // Load the CSS file and add it to the provider
     let provider = CssProvider::new();
@@ -107,6 +157,7 @@ fn build_ui(app: &adw::Application) {
 
     let canvas = Canvas::new(control.clone());
     let add_chamber_button = AddChamberButton::new(control.clone());
+    let add_corridor_button = AddCorridorButton::new(control.clone());
     let select_chamber_button = EditModeButton::new(
         control.clone(),
         state::EditMode::Select,
@@ -146,13 +197,38 @@ fn build_ui(app: &adw::Application) {
         "Insert Object",
     );
 
+    let merge_chambers_button = EditModeButton::new(
+        control.clone(),
+        state::EditMode::MergeChamber,
+        include_bytes!("../assets/icons/merge_chambers.png").to_vec(),
+        "Merge Chambers",
+    );
+
+    let subtract_chamber_button = EditModeButton::new(
+        control.clone(),
+        state::EditMode::SubtractChamber,
+        include_bytes!("../assets/icons/subtract_chamber.png").to_vec(),
+        "Cut out Chamber",
+    );
+
+    let split_chamber_button = EditModeButton::new(
+        control.clone(),
+        state::EditMode::SplitChamber,
+        include_bytes!("../assets/icons/split_chamber.png").to_vec(),
+        "Split Chamber",
+    );
+
     tool_box.append(&add_chamber_button.widget);
+    tool_box.append(&add_corridor_button.widget);
     tool_box.append(&select_chamber_button.borrow().widget);
     tool_box.append(&append_verts_button.borrow().widget);
     tool_box.append(&split_edge_button.borrow().widget);
     tool_box.append(&delete_corner_button.borrow().widget);
     tool_box.append(&add_door_button.borrow().widget);
     tool_box.append(&add_object_button.borrow().widget);
+    tool_box.append(&merge_chambers_button.borrow().widget);
+    tool_box.append(&subtract_chamber_button.borrow().widget);
+    tool_box.append(&split_chamber_button.borrow().widget);
     side_box.append(&tool_box);
 
     let dungeon_tab = gtk::Box::builder()
@@ -185,12 +261,35 @@ fn build_ui(app: &adw::Application) {
     object_tab.append(&object_list.borrow().scrolled_window);
     object_tab.append(&object_edit.borrow().widget);
 
+    let corridor_tab = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    let corridor_list = CorridorList::new(control.clone());
+    let corridor_edit = CorridorEdit::new(control.clone());
+    corridor_tab.append(&corridor_list.borrow().scrolled_window);
+    corridor_tab.append(&corridor_edit.borrow().widget);
+
+    let session_tab = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    let session_edit = SessionEdit::new(control.clone());
+    session_tab.append(&session_edit.borrow().widget);
+
+    let template_tab = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .build();
+    let template_panel = TemplatePanel::new(control.clone());
+    template_tab.append(&template_panel.borrow().widget);
+
     let object_tabs = EntityTabs::new(
         control.clone(),
         dungeon_tab,
         chamber_tab,
         door_tab,
         object_tab,
+        corridor_tab,
+        session_tab,
+        template_tab,
     );
     side_box.append(&object_tabs.borrow().widget);
 
@@ -202,6 +301,14 @@ fn build_ui(app: &adw::Application) {
     let file_menu = Menu::new();
     file_menu.insert_item(0, &MenuItem::new(Some("New Dungeon"), Some("file.new")));
     file_menu.insert_item(5, &MenuItem::new(Some("Open ..."), Some("file.open")));
+    file_menu.insert_item(
+        7,
+        &MenuItem::new(Some("Import Dungeon ..."), Some("file.import")),
+    );
+    file_menu.insert_item(
+        8,
+        &MenuItem::new(Some("Compare with File ..."), Some("file.compare")),
+    );
     file_menu.insert_item(10, &MenuItem::new(Some("Save ..."), Some("file.save")));
     file_menu.insert_item(
         11,
@@ -219,6 +326,24 @@ fn build_ui(app: &adw::Application) {
         22,
         &MenuItem::new(Some("Export Cutout Map ..."), Some("file.cutout_pdf")),
     );
+    file_menu.insert_item(
+        23,
+        &MenuItem::new(
+            Some("Export Battle Map Poster ..."),
+            Some("file.poster_pdf"),
+        ),
+    );
+    file_menu.insert_item(
+        24,
+        &MenuItem::new(
+            Some("Export Read-Aloud Handouts ..."),
+            Some("file.handouts_pdf"),
+        ),
+    );
+    file_menu.insert_item(
+        25,
+        &MenuItem::new(Some("Print Preview ..."), Some("win.print_preview")),
+    );
 
     let edit_menu = Menu::new();
     edit_menu.insert_item(0, &MenuItem::new(Some("Undo"), Some("edit.undo")));
@@ -226,6 +351,36 @@ fn build_ui(app: &adw::Application) {
         10,
         &MenuItem::new(Some("Add new Chamber"), Some("edit.add_chamber")),
     );
+    edit_menu.insert_item(
+        15,
+        &MenuItem::new(Some("Add new Corridor"), Some("edit.add_corridor")),
+    );
+    edit_menu.insert_item(
+        20,
+        &MenuItem::new(Some("Generate Dungeon ..."), Some("edit.generate")),
+    );
+    edit_menu.insert_item(
+        30,
+        &MenuItem::new(
+            Some("Edit Shared Walls Together"),
+            Some("edit.link_shared_walls"),
+        ),
+    );
+    let selection_menu = Menu::new();
+    selection_menu.insert_item(
+        0,
+        &MenuItem::new(Some("Delete"), Some("edit.delete_selected")),
+    );
+    selection_menu.insert_item(10, &MenuItem::new(Some("Hide"), Some("edit.hide_selected")));
+    selection_menu.insert_item(
+        20,
+        &MenuItem::new(Some("Unhide"), Some("edit.show_selected")),
+    );
+    selection_menu.insert_item(
+        30,
+        &MenuItem::new(Some("Rename ..."), Some("edit.rename_selected")),
+    );
+    edit_menu.insert_submenu(25, Some("Selection"), &selection_menu);
     let mode_menu = Menu::new();
     mode_menu.insert_item(0, &MenuItem::new(Some("Select"), Some("edit.mode_select")));
     mode_menu.insert_item(
@@ -240,11 +395,50 @@ fn build_ui(app: &adw::Application) {
         0,
         &MenuItem::new(Some("Add Door"), Some("edit.mode_add_door")),
     );
+    mode_menu.insert_item(
+        0,
+        &MenuItem::new(Some("Merge Chambers"), Some("edit.mode_merge_chamber")),
+    );
+    mode_menu.insert_item(
+        0,
+        &MenuItem::new(Some("Cut out Chamber"), Some("edit.mode_subtract_chamber")),
+    );
+    mode_menu.insert_item(
+        0,
+        &MenuItem::new(Some("Split Chamber"), Some("edit.mode_split_chamber")),
+    );
+    mode_menu.insert_item(
+        35,
+        &MenuItem::new(Some("Draw Corridor"), Some("edit.mode_append_corridor")),
+    );
     edit_menu.insert_submenu(20, Some("Change Mode"), &mode_menu);
 
+    let view_menu = Menu::new();
+    view_menu.insert_item(
+        0,
+        &MenuItem::new(Some("Player Window"), Some("win.player_view")),
+    );
+    view_menu.insert_item(
+        10,
+        &MenuItem::new(Some("Reveal Selection"), Some("edit.reveal_selected")),
+    );
+    view_menu.insert_item(
+        20,
+        &MenuItem::new(Some("Unreveal Selection"), Some("edit.unreveal_selected")),
+    );
+    view_menu.insert_item(
+        30,
+        &MenuItem::new(Some("Session View"), Some("edit.session_view")),
+    );
+    view_menu.insert_item(
+        40,
+        &MenuItem::new(Some("Hide Changes"), Some("edit.hide_changes")),
+    );
+
     let menu = Menu::new();
     menu.insert_submenu(0, Some("File"), &file_menu);
     menu.insert_submenu(1, Some("Edit"), &edit_menu);
+    menu.insert_submenu(2, Some("View"), &view_menu);
     let menu_model: MenuModel = menu.into();
 
     let menubar = PopoverMenuBar::from_model(Some(&menu_model));
@@ -276,7 +470,30 @@ fn build_ui(app: &adw::Application) {
             window.close();
         })
         .build();
-    window.add_action_entries([action_close]);
+    let player_view = PlayerView::new(control.clone());
+    let action_player_view = ActionEntry::builder("player_view")
+        .activate(
+            clone!(@strong player_view => move |_: &ApplicationWindow, _, _| {
+                player_view.borrow().window.present();
+            }),
+        )
+        .build();
+    let print_preview = PrintPreview::new(control.clone());
+    print_preview
+        .borrow()
+        .window
+        .set_transient_for(Some(&window));
+    print_preview.borrow().window.set_destroy_with_parent(true);
+    let action_print_preview = ActionEntry::builder("print_preview")
+        .activate(
+            clone!(@strong print_preview, @strong control => move |_: &ApplicationWindow, _, _| {
+                let preview = print_preview.borrow();
+                preview.update(&control.borrow().state);
+                preview.window.present();
+            }),
+        )
+        .build();
+    window.add_action_entries([action_close, action_player_view, action_print_preview]);
     let force_close = Rc::new(RefCell::new(false));
     window.connect_close_request(clone!(@strong control, @strong history, @strong force_close => move |window| {
         if history.borrow().unsaved_state() {
@@ -319,6 +536,12 @@ fn build_ui(app: &adw::Application) {
             Propagation::Proceed
         }
     }));
+    // not transient for the editor, so it can be moved to a projector on its own,
+    // closed with the editor once the changes are saved or discarded
+    window.connect_close_request(clone!(@strong player_view => move |_| {
+        player_view.borrow().window.destroy();
+        Propagation::Proceed
+    }));
 
     #[cfg(debug_assertions)]
     {
@@ -0,0 +1,357 @@
+/// Block of a note in the Markdown subset supported in notes
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    /// `#` to `###`, with the level from 1 to 3
+    Heading(usize, String),
+    /// lines of text, line breaks are kept as written
+    Paragraph(String),
+    /// empty line between blocks
+    Blank,
+    /// item of a bullet list `- item` or a numbered list `1. item`,
+    /// nested by two spaces of indentation per level
+    ListItem {
+        number: Option<u32>,
+        depth: usize,
+        text: String,
+    },
+    /// `> text`, e.g. read-aloud text, consecutive lines are joined
+    Quote(String),
+    /// `| a | b |` rows, the header is only set if the second row is a separator like `|---|---|`
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+fn heading(line: &str) -> Option<Block> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    match (level, line[level..].strip_prefix(' ')) {
+        (1..=3, Some(text)) => Some(Block::Heading(level, text.trim().to_owned())),
+        _ => None,
+    }
+}
+
+fn list_item(line: &str) -> Option<Block> {
+    let indent = line.chars().take_while(|c| *c == ' ').count();
+    let rest = &line[indent..];
+    let depth = indent / 2;
+    if let Some(text) = rest.strip_prefix("- ").or(rest.strip_prefix("* ")) {
+        return Some(Block::ListItem {
+            number: None,
+            depth,
+            text: text.trim().to_owned(),
+        });
+    }
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    match rest[digits..].strip_prefix(". ") {
+        Some(text) if digits > 0 => Some(Block::ListItem {
+            number: rest[..digits].parse().ok(),
+            depth,
+            text: text.trim().to_owned(),
+        }),
+        _ => None,
+    }
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(|c| c.trim().to_owned()).collect()
+}
+
+fn is_separator_row(cells: &[String]) -> bool {
+    cells
+        .iter()
+        .all(|c| !c.is_empty() && c.chars().all(|ch| ch == '-' || ch == ':'))
+}
+
+/// escapes text for pango markup
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Splits a note into blocks
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = vec![];
+    let mut table: Vec<Vec<String>> = vec![];
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('|') {
+            table.push(table_cells(trimmed));
+            continue;
+        }
+        if !table.is_empty() {
+            blocks.push(to_table(std::mem::take(&mut table)));
+        }
+
+        let block = if trimmed.is_empty() {
+            Block::Blank
+        } else if let Some(quoted) = trimmed.strip_prefix('>') {
+            let quoted = quoted.trim();
+            if let Some(Block::Quote(text)) = blocks.last_mut() {
+                text.push('\n');
+                text.push_str(quoted);
+                continue;
+            }
+            Block::Quote(quoted.to_owned())
+        } else if let Some(block) = heading(trimmed).or_else(|| list_item(line)) {
+            block
+        } else {
+            if let Some(Block::Paragraph(text)) = blocks.last_mut() {
+                text.push('\n');
+                text.push_str(line.trim_end());
+                continue;
+            }
+            Block::Paragraph(line.trim_end().to_owned())
+        };
+        blocks.push(block);
+    }
+    if !table.is_empty() {
+        blocks.push(to_table(table));
+    }
+    blocks
+}
+
+fn to_table(mut rows: Vec<Vec<String>>) -> Block {
+    let header = match rows.get(1).is_some_and(|r| is_separator_row(r)) {
+        true => {
+            rows.remove(1);
+            rows.remove(0)
+        }
+        false => vec![],
+    };
+    Block::Table { header, rows }
+}
+
+/// Converts `**bold**`, `*italic*` and `_italic_` to pango markup, escaping everything else.
+/// Markers without a matching closing marker are kept as written, so tags are always nested.
+pub fn inline_markup(text: &str) -> String {
+    // pieces of the output, markers are replaced by tags once they are closed
+    let mut pieces: Vec<String> = vec![];
+    let mut open: Vec<(&str, usize)> = vec![];
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let marker = match chars[i] {
+            // an open italic is closed first, e.g. in `**bold *both***`
+            '*' if chars.get(i + 1) == Some(&'*') && open.last().map(|(m, _)| *m) != Some("*") => {
+                Some("**")
+            }
+            '*' => Some("*"),
+            '_' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1);
+                let closes = open.last().is_some_and(|(m, _)| *m == "_")
+                    && !after.is_some_and(|c| c.is_alphanumeric());
+                let opens = !before.is_some_and(|c| c.is_alphanumeric())
+                    && after.is_some_and(|c| !c.is_whitespace());
+                (closes || opens).then_some("_")
+            }
+            _ => None,
+        };
+        match marker {
+            Some(marker) => {
+                let tag = match marker {
+                    "**" => "b",
+                    _ => "i",
+                };
+                if open.last().is_some_and(|(m, _)| *m == marker) {
+                    let (_, at) = open.pop().unwrap();
+                    pieces[at] = format!("<{}>", tag);
+                    pieces.push(format!("</{}>", tag));
+                } else {
+                    open.push((marker, pieces.len()));
+                    pieces.push(marker.to_owned());
+                }
+                i += marker.len();
+            }
+            None => {
+                pieces.push(escape(&chars[i].to_string()));
+                i += 1;
+            }
+        }
+    }
+    pieces.concat()
+}
+
+/// text without the inline markers, e.g. to measure table columns
+fn plain_text(text: &str) -> String {
+    let markup = inline_markup(text);
+    let mut plain = String::new();
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => (),
+        }
+    }
+    plain
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Text shown in front of a list item
+pub fn list_marker(number: Option<u32>) -> String {
+    match number {
+        Some(n) => format!("{}.", n),
+        None => "•".to_owned(),
+    }
+}
+
+/// Pango markup of a note, used for the preview in the note editors
+pub fn to_markup(text: &str) -> String {
+    let lines: Vec<String> = parse(text)
+        .into_iter()
+        .map(|block| match block {
+            Block::Heading(level, text) => {
+                let size = match level {
+                    1 => "x-large",
+                    2 => "large",
+                    _ => "medium",
+                };
+                format!(
+                    "<span size=\"{}\" weight=\"bold\">{}</span>",
+                    size,
+                    inline_markup(&text)
+                )
+            }
+            Block::Paragraph(text) => inline_markup(&text),
+            Block::Blank => String::new(),
+            Block::ListItem {
+                number,
+                depth,
+                text,
+            } => format!(
+                "{}{} {}",
+                "    ".repeat(depth + 1),
+                list_marker(number),
+                inline_markup(&text)
+            ),
+            Block::Quote(text) => text
+                .lines()
+                .map(|l| format!("┃ <i>{}</i>", inline_markup(l)))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Block::Table { header, rows } => {
+                let columns = header
+                    .len()
+                    .max(rows.iter().map(|r| r.len()).max().unwrap_or(0));
+                let width = |col: usize| -> usize {
+                    std::iter::once(&header)
+                        .chain(rows.iter())
+                        .filter_map(|r| r.get(col))
+                        .map(|c| plain_text(c).chars().count())
+                        .max()
+                        .unwrap_or(0)
+                };
+                let widths: Vec<usize> = (0..columns).map(width).collect();
+                let row = |cells: &Vec<String>| -> String {
+                    let cells: Vec<String> = (0..columns)
+                        .map(|col| {
+                            let cell = cells.get(col).map(|c| plain_text(c)).unwrap_or_default();
+                            let padding = widths[col] - cell.chars().count();
+                            format!("{}{}", escape(&cell), " ".repeat(padding))
+                        })
+                        .collect();
+                    format!("<tt>{}</tt>", cells.join(" │ "))
+                };
+                let mut lines = vec![];
+                if !header.is_empty() {
+                    lines.push(format!("<b>{}</b>", row(&header)));
+                }
+                lines.extend(rows.iter().map(row));
+                lines.join("\n")
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inline_markup, parse, plain_text, to_markup, Block};
+
+    #[test]
+    fn parse_blocks() {
+        let note = "# Crypt\n\
+            Dusty air.\n\
+            Cold walls.\n\
+            \n\
+            > You enter a\n\
+            > dark room.\n\
+            - coffin\n  \
+            - skeleton\n\
+            2. lever\n\
+            | Roll | Result |\n\
+            |------|--------|\n\
+            | 1 | *rats* |\n\
+            ## Exits";
+        assert_eq!(
+            parse(note),
+            vec![
+                Block::Heading(1, "Crypt".to_owned()),
+                Block::Paragraph("Dusty air.\nCold walls.".to_owned()),
+                Block::Blank,
+                Block::Quote("You enter a\ndark room.".to_owned()),
+                Block::ListItem {
+                    number: None,
+                    depth: 0,
+                    text: "coffin".to_owned()
+                },
+                Block::ListItem {
+                    number: None,
+                    depth: 1,
+                    text: "skeleton".to_owned()
+                },
+                Block::ListItem {
+                    number: Some(2),
+                    depth: 0,
+                    text: "lever".to_owned()
+                },
+                Block::Table {
+                    header: vec!["Roll".to_owned(), "Result".to_owned()],
+                    rows: vec![vec!["1".to_owned(), "*rats*".to_owned()]],
+                },
+                Block::Heading(2, "Exits".to_owned()),
+            ]
+        );
+        // plain notes stay paragraphs
+        assert_eq!(
+            parse("#1 is a trap\n-5 gold"),
+            vec![Block::Paragraph("#1 is a trap\n-5 gold".to_owned())]
+        );
+    }
+
+    #[test]
+    fn inline_formatting() {
+        assert_eq!(
+            inline_markup("**Trap** in *room* <3 & _hall_"),
+            "<b>Trap</b> in <i>room</i> &lt;3 &amp; <i>hall</i>"
+        );
+        assert_eq!(
+            inline_markup("a * b and trap_door_2"),
+            "a * b and trap_door_2"
+        );
+        assert_eq!(inline_markup("**bold *both***"), "<b>bold <i>both</i></b>");
+        assert_eq!(inline_markup("**open *x"), "**open *x");
+        assert_eq!(plain_text("**Trap** & *x*"), "Trap & x");
+    }
+
+    #[test]
+    fn preview_markup() {
+        assert_eq!(
+            to_markup("## Loot\n- 5 *gp*\n> Hush"),
+            "<span size=\"large\" weight=\"bold\">Loot</span>\n    • 5 <i>gp</i>\n┃ <i>Hush</i>"
+        );
+    }
+}
@@ -1,17 +1,22 @@
+use std::rc::Rc;
+
 use crate::{
     chamber::ChamberId,
-    common::{Rgb, Vec2},
+    common::{BBox, Rgb, Vec2},
     config::{GRID_SIZE, WALL_WIDTH},
+    symbol::Symbol,
     view::primitives::{Circle, Line, Polygon, Primitive},
 };
 
 pub type ObjectId = u32;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum ObjectStyle {
     Blocker,
     Stairs,
     Round,
+    /// built-in or user defined symbol, see `SymbolLibrary`
+    Symbol(Rc<Symbol>),
 }
 
 impl ObjectStyle {
@@ -20,15 +25,23 @@ impl ObjectStyle {
             ObjectStyle::Blocker => "Blocker".to_owned(),
             ObjectStyle::Stairs => "Stairs".to_owned(),
             ObjectStyle::Round => "Round".to_owned(),
+            ObjectStyle::Symbol(symbol) => symbol.name.clone(),
         }
     }
 
+    /// style by name, unknown names fall back to `Blocker`
     pub fn from_str(s: &str) -> Self {
         match s {
             "Blocker" => ObjectStyle::Blocker,
             "Stairs" => ObjectStyle::Stairs,
             "Round" => ObjectStyle::Round,
-            _ => todo!(),
+            _ => match Symbol::builtin(s) {
+                Some(symbol) => ObjectStyle::Symbol(symbol),
+                None => {
+                    println!("Unknown object style {}", s);
+                    ObjectStyle::Blocker
+                }
+            },
         }
     }
 }
@@ -48,10 +61,17 @@ pub struct Object {
     pub id: ObjectId,
     pub name: String,
     pub notes: String,
+    /// boxed text read to the players, separate from the GM notes
+    pub read_aloud: String,
     pub hidden: bool,
     pub style: ObjectStyle,
+    /// top left corner of the footprint, before rotation
     pub pos: Vec2<i32>,
     pub part_of: Option<ChamberId>,
+    /// footprint in grid cells
+    pub size: Vec2<i32>,
+    /// rotation around the center of the footprint in degrees, clockwise
+    pub rotation: f64,
 }
 
 impl Object {
@@ -60,10 +80,13 @@ impl Object {
             id: 1,
             name: "".to_owned(),
             notes: "".to_owned(),
+            read_aloud: "".to_owned(),
             style: ObjectStyle::Blocker,
             pos: pos,
             hidden: false,
             part_of: part_of,
+            size: Vec2 { x: 1, y: 1 },
+            rotation: 0.0,
         }
     }
 
@@ -78,186 +101,224 @@ impl Object {
             ObjectStyle::Blocker => self.draw_blocker(color),
             ObjectStyle::Stairs => self.draw_stairs(color),
             ObjectStyle::Round => self.draw_round(color),
+            ObjectStyle::Symbol(ref symbol) => self.draw_symbol(symbol, color),
+        }
+    }
+
+    /// size of the footprint in world units
+    fn extent(&self) -> Vec2<f64> {
+        Vec2 {
+            x: (self.size.x * GRID_SIZE) as f64,
+            y: (self.size.y * GRID_SIZE) as f64,
         }
     }
 
+    pub fn center(&self) -> Vec2<f64> {
+        Into::<Vec2<f64>>::into(self.pos) + 0.5 * self.extent()
+    }
+
+    /// transforms a point relative to the unrotated footprint into world coordinates
+    fn to_world(&self, local: Vec2<f64>) -> Vec2<f64> {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let d = local - 0.5 * self.extent();
+        self.center()
+            + Vec2 {
+                x: d.x * cos - d.y * sin,
+                y: d.x * sin + d.y * cos,
+            }
+    }
+
+    /// transforms a point in world coordinates into the unrotated footprint
+    fn to_local(&self, world: Vec2<f64>) -> Vec2<f64> {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let d = world - self.center();
+        0.5 * self.extent()
+            + Vec2 {
+                x: d.x * cos + d.y * sin,
+                y: -d.x * sin + d.y * cos,
+            }
+    }
+
+    /// corners of the rotated footprint
+    pub fn footprint(&self) -> Vec<Vec2<f64>> {
+        let e = self.extent();
+        vec![
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: e.x, y: 0.0 },
+            e,
+            Vec2 { x: 0.0, y: e.y },
+        ]
+        .into_iter()
+        .map(|p| self.to_world(p))
+        .collect()
+    }
+
+    pub fn bbox(&self) -> BBox {
+        let mut bbox = BBox::new();
+        for p in self.footprint() {
+            bbox += p;
+        }
+        bbox
+    }
+
     pub fn contains(&self, pos: Vec2<f64>) -> bool {
-        let s: f64 = GRID_SIZE as f64;
-        let obj_pos: Vec2<f64> = self.pos.into();
-        pos.x >= obj_pos.x
-            && pos.y >= obj_pos.y
-            && pos.x <= (obj_pos.x + s)
-            && pos.y <= (obj_pos.y + s)
+        let e = self.extent();
+        let p = self.to_local(pos);
+        p.x >= 0.0 && p.y >= 0.0 && p.x <= e.x && p.y <= e.y
+    }
+
+    /// line between two points relative to the footprint
+    fn local_line(&self, from: Vec2<f64>, to: Vec2<f64>, color: Rgb, dashed: bool) -> Box<Line> {
+        Box::new(Line {
+            color,
+            dashed,
+            from: self.to_world(from),
+            to: self.to_world(to),
+            width: WALL_WIDTH,
+        })
+    }
+
+    fn draw_footprint(&self, color: Rgb) -> Box<Polygon> {
+        Box::new(Polygon {
+            holes: vec![],
+            dashed: self.hidden,
+            fill_color: color,
+            fill_opacity: 0.0,
+            stroke_color: color,
+            stroke_width: WALL_WIDTH,
+            points: self.footprint(),
+        })
     }
 
     fn draw_blocker(&self, color: Rgb) -> Vec<Box<dyn Primitive>> {
+        let e = self.extent();
         vec![
             // draw box
-            Box::new(Polygon {
-                dashed: self.hidden,
-                fill_color: color,
-                fill_opacity: 0.0,
-                stroke_color: color,
-                stroke_width: WALL_WIDTH,
-                points: vec![
-                    Vec2 {
-                        x: self.pos.x as f64,
-                        y: self.pos.y as f64,
-                    },
-                    Vec2 {
-                        x: (self.pos.x + GRID_SIZE) as f64,
-                        y: self.pos.y as f64,
-                    },
-                    Vec2 {
-                        x: (self.pos.x + GRID_SIZE) as f64,
-                        y: (self.pos.y + GRID_SIZE) as f64,
-                    },
-                    Vec2 {
-                        x: self.pos.x as f64,
-                        y: (self.pos.y + GRID_SIZE) as f64,
-                    },
-                ],
-            }),
+            self.draw_footprint(color),
             // draw x in box
-            Box::new(Line {
-                color: color,
-                dashed: self.hidden,
-                from: Vec2 {
-                    x: self.pos.x as f64,
-                    y: self.pos.y as f64,
-                },
-                to: Vec2 {
-                    x: (self.pos.x + GRID_SIZE) as f64,
-                    y: (self.pos.y + GRID_SIZE) as f64,
-                },
-                width: WALL_WIDTH,
-            }),
-            Box::new(Line {
-                color: color,
-                dashed: self.hidden,
-                from: Vec2 {
-                    x: (self.pos.x + GRID_SIZE) as f64,
-                    y: self.pos.y as f64,
-                },
-                to: Vec2 {
-                    x: self.pos.x as f64,
-                    y: (self.pos.y + GRID_SIZE) as f64,
-                },
-                width: WALL_WIDTH,
-            }),
+            self.local_line(Vec2 { x: 0.0, y: 0.0 }, e, color, self.hidden),
+            self.local_line(
+                Vec2 { x: e.x, y: 0.0 },
+                Vec2 { x: 0.0, y: e.y },
+                color,
+                self.hidden,
+            ),
         ]
     }
 
     fn draw_stairs(&self, color: Rgb) -> Vec<Box<dyn Primitive>> {
-        let s: f64 = GRID_SIZE as f64;
-        let c: f64 = GRID_SIZE as f64 / 2.0;
+        let e = self.extent();
+        let c = e.x / 2.0;
 
-        vec![
-            // draw box
-            Box::new(Polygon {
-                dashed: self.hidden,
-                fill_color: color,
-                fill_opacity: 0.0,
-                stroke_color: color,
-                stroke_width: WALL_WIDTH,
-                points: vec![
-                    Vec2 {
-                        x: self.pos.x as f64,
-                        y: self.pos.y as f64,
-                    },
-                    Vec2 {
-                        x: (self.pos.x + GRID_SIZE) as f64,
-                        y: self.pos.y as f64,
-                    },
-                    Vec2 {
-                        x: (self.pos.x + GRID_SIZE) as f64,
-                        y: (self.pos.y + GRID_SIZE) as f64,
-                    },
-                    Vec2 {
-                        x: self.pos.x as f64,
-                        y: (self.pos.y + GRID_SIZE) as f64,
-                    },
-                ],
-            }),
-            Box::new(Line {
-                color: color,
-                dashed: false,
-                from: Vec2 {
-                    x: self.pos.x as f64 + c - 0.8 * c,
-                    y: self.pos.y as f64 + 0.2 * s,
+        let mut prims: Vec<Box<dyn Primitive>> = vec![self.draw_footprint(color)];
+        // steps getting narrower towards the bottom
+        for (y, w) in [(0.2, 0.8), (0.4, 0.6), (0.6, 0.4), (0.8, 0.2)] {
+            prims.push(self.local_line(
+                Vec2 {
+                    x: c - w * c,
+                    y: y * e.y,
                 },
-                to: Vec2 {
-                    x: self.pos.x as f64 + c + 0.8 * c,
-                    y: self.pos.y as f64 + 0.2 * s,
+                Vec2 {
+                    x: c + w * c,
+                    y: y * e.y,
                 },
-                width: WALL_WIDTH,
-            }),
-            Box::new(Line {
-                color: color,
-                dashed: false,
-                from: Vec2 {
-                    x: self.pos.x as f64 + c - 0.6 * c,
-                    y: self.pos.y as f64 + 0.4 * s,
-                },
-                to: Vec2 {
-                    x: self.pos.x as f64 + c + 0.6 * c,
-                    y: self.pos.y as f64 + 0.4 * s,
-                },
-                width: WALL_WIDTH,
-            }),
-            Box::new(Line {
-                color: color,
-                dashed: false,
-                from: Vec2 {
-                    x: self.pos.x as f64 + c - 0.4 * c,
-                    y: self.pos.y as f64 + 0.6 * s,
-                },
-                to: Vec2 {
-                    x: self.pos.x as f64 + c + 0.4 * c,
-                    y: self.pos.y as f64 + 0.6 * s,
-                },
-                width: WALL_WIDTH,
-            }),
-            Box::new(Line {
-                color: color,
-                dashed: false,
-                from: Vec2 {
-                    x: self.pos.x as f64 + c - 0.2 * c,
-                    y: self.pos.y as f64 + 0.8 * s,
-                },
-                to: Vec2 {
-                    x: self.pos.x as f64 + c + 0.2 * c,
-                    y: self.pos.y as f64 + 0.8 * s,
-                },
-                width: WALL_WIDTH,
-            }),
-        ]
+                color,
+                false,
+            ));
+        }
+        prims
+    }
+
+    fn draw_symbol(&self, symbol: &Symbol, color: Rgb) -> Vec<Box<dyn Primitive>> {
+        let e = self.extent();
+        let mut prims: Vec<Box<dyn Primitive>> = vec![];
+        for path in symbol.paths.iter() {
+            // symbols are stretched to the footprint
+            let points: Vec<Vec2<f64>> = path
+                .points
+                .iter()
+                .map(|p| {
+                    self.to_world(Vec2 {
+                        x: p.x * e.x,
+                        y: p.y * e.y,
+                    })
+                })
+                .collect();
+            if path.closed {
+                prims.push(Box::new(Polygon {
+                    holes: vec![],
+                    dashed: self.hidden,
+                    fill_color: color,
+                    fill_opacity: 0.0,
+                    stroke_color: color,
+                    stroke_width: WALL_WIDTH,
+                    points,
+                }));
+            } else {
+                for w in points.windows(2) {
+                    prims.push(Box::new(Line {
+                        color,
+                        dashed: self.hidden,
+                        from: w[0],
+                        to: w[1],
+                        width: WALL_WIDTH,
+                    }));
+                }
+            }
+        }
+        prims
     }
 
     fn draw_round(&self, color: Rgb) -> Vec<Box<dyn Primitive>> {
-        let pos: Vec2<f64> = self.pos.into();
+        let e = self.extent();
+        if self.size.x == self.size.y {
+            return vec![
+                Box::new(Circle {
+                    at: self.center(),
+                    radius: e.x / 2.0,
+                    width: WALL_WIDTH,
+                    color,
+                    dashed: self.hidden,
+                }),
+                Box::new(Circle {
+                    at: self.center(),
+                    radius: e.x / 4.0,
+                    width: WALL_WIDTH,
+                    color,
+                    dashed: false,
+                }),
+            ];
+        }
+        // ellipses for non square footprints
+        let ellipse = |scale: f64| -> Vec<Vec2<f64>> {
+            (0..48)
+                .map(|i| {
+                    let a = i as f64 / 48.0 * std::f64::consts::TAU;
+                    self.to_world(Vec2 {
+                        x: e.x / 2.0 * (1.0 + scale * a.cos()),
+                        y: e.y / 2.0 * (1.0 + scale * a.sin()),
+                    })
+                })
+                .collect()
+        };
         vec![
-            Box::new(Circle {
-                at: pos
-                    + Vec2::<f64> {
-                        x: GRID_SIZE as f64 / 2.0,
-                        y: GRID_SIZE as f64 / 2.0,
-                    },
-                radius: GRID_SIZE as f64 / 2.0,
-                width: WALL_WIDTH,
-                color: color,
+            Box::new(Polygon {
+                holes: vec![],
                 dashed: self.hidden,
+                fill_color: color,
+                fill_opacity: 0.0,
+                stroke_color: color,
+                stroke_width: WALL_WIDTH,
+                points: ellipse(1.0),
             }),
-            Box::new(Circle {
-                at: pos
-                    + Vec2::<f64> {
-                        x: GRID_SIZE as f64 / 2.0,
-                        y: GRID_SIZE as f64 / 2.0,
-                    },
-                radius: GRID_SIZE as f64 / 4.0,
-                width: WALL_WIDTH,
-                color: color,
+            Box::new(Polygon {
+                holes: vec![],
                 dashed: false,
+                fill_color: color,
+                fill_opacity: 0.0,
+                stroke_color: color,
+                stroke_width: WALL_WIDTH,
+                points: ellipse(0.5),
             }),
         ]
     }
@@ -283,4 +344,22 @@ mod test {
         assert_eq!(obj.contains(Vec2 { x: -20.0, y: -20.0 }), false);
         assert_eq!(obj.contains(Vec2 { x: 51.0, y: 51.0 }), false);
     }
+
+    #[test]
+    fn test_contains_size_and_rotation() {
+        let mut obj = Object::new(Vec2 { x: 0, y: 0 }, None);
+        obj.size = Vec2 { x: 2, y: 1 };
+
+        assert_eq!(obj.contains(Vec2 { x: 90.0, y: 25.0 }), true);
+        assert_eq!(obj.contains(Vec2 { x: 50.0, y: 60.0 }), false);
+
+        // rotated by 90 degrees the footprint is 1 cell wide and 2 cells high
+        obj.rotation = 90.0;
+        assert_eq!(obj.contains(Vec2 { x: 90.0, y: 25.0 }), false);
+        assert_eq!(obj.contains(Vec2 { x: 50.0, y: 60.0 }), true);
+
+        let bbox = obj.bbox();
+        assert!((bbox.min.x - 25.0).abs() < 1e-9);
+        assert!((bbox.max.y - 75.0).abs() < 1e-9);
+    }
 }
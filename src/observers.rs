@@ -1,8 +1,9 @@
+use crate::session::Session;
 use crate::state::{
     State, StateCommand, StateCommandSubscriber, StateController, StateEventSubscriber,
 };
 use crate::storage;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, ops::Range, rc::Rc};
 pub struct DebugObserver {}
 
 const MAX_EDIT_DISTANCE: i64 = 12;
@@ -30,6 +31,11 @@ pub struct HistoryObserver {
     save_file: Option<String>,
     cmds: Vec<StateCommand>,
     unsaved_state: bool,
+    /// saved next to the dungeon file, session commands are not part of the history
+    session: Session,
+    /// commands undone together, e.g. a generated dungeon
+    groups: Vec<Range<usize>>,
+    group_start: Option<usize>,
 }
 
 impl HistoryObserver {
@@ -41,6 +47,9 @@ impl HistoryObserver {
             save_file: save_file,
             cmds: vec![],
             unsaved_state: false,
+            session: Session::new(),
+            groups: vec![],
+            group_start: None,
         }));
 
         state.borrow_mut().subscribe_cmds(obs.clone());
@@ -54,10 +63,24 @@ impl HistoryObserver {
         self.save_file = None;
         self.unsaved_state = false;
         self.cmds = vec![];
+        self.session = Session::new();
+        self.groups = vec![];
+    }
+
+    pub fn set_session(&mut self, session: Session) {
+        self.session = session;
     }
 
     pub fn undo(&mut self) {
-        self.cmds.pop();
+        match self.groups.last() {
+            Some(group) if group.end == self.cmds.len() => {
+                self.cmds.truncate(group.start);
+                self.groups.pop();
+            }
+            _ => {
+                self.cmds.pop();
+            }
+        }
     }
 
     pub fn get_stack(&self) -> Vec<StateCommand> {
@@ -82,6 +105,9 @@ impl HistoryObserver {
         match &self.save_file {
             Some(f) => {
                 storage::save_to_file(f.to_string(), &self.cmds);
+                if let Err(e) = storage::save_session(storage::session_path(f), &self.session) {
+                    println!("Unable to save session: {}", e);
+                }
                 self.unsaved_state = false;
             }
             None => todo!(),
@@ -90,33 +116,70 @@ impl HistoryObserver {
 }
 
 impl StateCommandSubscriber for HistoryObserver {
-    fn on_cmd_event(&mut self, _state: &mut crate::state::State, cmd: StateCommand) {
+    fn on_cmd_event(&mut self, state: &mut crate::state::State, cmd: StateCommand) {
+        // deleting entities removes them from the session as well
+        self.session = state.session.clone();
         match cmd {
+            x if x.is_session() => (),
             // compressing text changes into reasonable chunks
             StateCommand::ChangeChamberNotes(id, s) => {
                 crate::txt_cmd!(StateCommand::ChangeChamberNotes, id, s, self.cmds);
             }
+            StateCommand::ChangeChamberReadAloud(id, s) => {
+                crate::txt_cmd!(StateCommand::ChangeChamberReadAloud, id, s, self.cmds);
+            }
             StateCommand::ChangeChamberName(id, s) => {
                 crate::txt_cmd!(StateCommand::ChangeChamberName, id, s, self.cmds);
             }
             StateCommand::ChangeDoorNotes(id, s) => {
                 crate::txt_cmd!(StateCommand::ChangeDoorNotes, id, s, self.cmds);
             }
+            StateCommand::ChangeDoorReadAloud(id, s) => {
+                crate::txt_cmd!(StateCommand::ChangeDoorReadAloud, id, s, self.cmds);
+            }
             StateCommand::ChangeDoorName(id, s) => {
                 crate::txt_cmd!(StateCommand::ChangeDoorName, id, s, self.cmds);
             }
+            StateCommand::ChangeObjectReadAloud(id, s) => {
+                crate::txt_cmd!(StateCommand::ChangeObjectReadAloud, id, s, self.cmds);
+            }
+            StateCommand::ChangeCorridorNotes(id, s) => {
+                crate::txt_cmd!(StateCommand::ChangeCorridorNotes, id, s, self.cmds);
+            }
+            StateCommand::ChangeCorridorName(id, s) => {
+                crate::txt_cmd!(StateCommand::ChangeCorridorName, id, s, self.cmds);
+            }
             StateCommand::ChangeDungeonName(s) => {
                 crate::txt_cmd_dungeon!(StateCommand::ChangeDungeonName, s, self.cmds);
             }
             StateCommand::ChangeDungeonNotes(s) => {
                 crate::txt_cmd_dungeon!(StateCommand::ChangeDungeonNotes, s, self.cmds);
             }
+            // dragging a spin button changes the background many times, undone in one step
+            StateCommand::ChangeBackgroundSettings(settings) => match self.cmds.last_mut() {
+                Some(StateCommand::ChangeBackgroundSettings(last)) => *last = settings,
+                _ => self
+                    .cmds
+                    .push(StateCommand::ChangeBackgroundSettings(settings)),
+            },
             x => {
                 self.cmds.push(x);
             }
         }
         self.unsaved_state = true;
     }
+
+    fn on_group_start(&mut self) {
+        self.group_start = Some(self.cmds.len());
+    }
+
+    fn on_group_end(&mut self) {
+        if let Some(start) = self.group_start.take() {
+            if self.cmds.len() > start + 1 {
+                self.groups.push(start..self.cmds.len());
+            }
+        }
+    }
 }
 
 #[macro_export]
@@ -174,3 +237,65 @@ macro_rules! txt_cmd_dungeon {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::background::BackgroundSettings;
+    use crate::common::Vec2;
+    use crate::dungeon::tests::add_rect;
+    use crate::state::{State, StateCommand, StateCommandSubscriber, StateController};
+
+    use super::HistoryObserver;
+
+    #[test]
+    fn background_changes_in_one_step() {
+        let control = Rc::new(RefCell::new(StateController::new()));
+        let history = HistoryObserver::new(control, None);
+        let mut settings = BackgroundSettings::new();
+        history.borrow_mut().on_cmd_event(
+            &mut State::new(),
+            StateCommand::ChangeDungeonName("Crypt".to_owned()),
+        );
+        for x in 1..=10 {
+            settings.pos.x = x as f64;
+            history.borrow_mut().on_cmd_event(
+                &mut State::new(),
+                StateCommand::ChangeBackgroundSettings(settings),
+            );
+        }
+        let stack = history.borrow().get_stack();
+        assert_eq!(stack.len(), 2);
+        assert!(matches!(stack[1], StateCommand::ChangeBackgroundSettings(s) if s.pos.x == 10.0));
+
+        history.borrow_mut().undo();
+        assert_eq!(history.borrow().get_stack().len(), 1);
+    }
+
+    #[test]
+    fn linked_split_in_one_step() {
+        let control = Rc::new(RefCell::new(StateController::new()));
+        let history = HistoryObserver::new(control.clone(), None);
+        let a = add_rect(&mut control.borrow_mut().state.dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut control.borrow_mut().state.dungeon, 100, 0, 200, 100);
+        control
+            .borrow_mut()
+            .apply(StateCommand::ChangeDungeonName("Crypt".to_owned()));
+
+        // the shared wall is split in both chambers by one click
+        let pos = Vec2 { x: 100, y: 50 };
+        let wall_a = control.borrow().state.dungeon.chamber(a).unwrap().walls()[1].id;
+        let wall_b = control.borrow().state.dungeon.chamber(b).unwrap().walls()[3].id;
+        control.borrow_mut().apply_group(vec![
+            StateCommand::SplitWall(a, wall_a, pos),
+            StateCommand::SplitWall(b, wall_b, pos),
+        ]);
+        assert_eq!(history.borrow().get_stack().len(), 3);
+
+        history.borrow_mut().undo();
+        let stack = history.borrow().get_stack();
+        assert_eq!(stack.len(), 1);
+        assert!(matches!(stack[0], StateCommand::ChangeDungeonName(_)));
+    }
+}
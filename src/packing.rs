@@ -0,0 +1,243 @@
+use crate::common::Vec2;
+
+/// A rectangle to place, with its size when turned by a quarter if it may be turned
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Piece {
+    pub size: Vec2<f64>,
+    pub turned: Option<Vec2<f64>>,
+}
+
+/// Where a piece is placed, `pos` is its top left corner relative to the area
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    pub page: usize,
+    pub pos: Vec2<f64>,
+    pub turned: bool,
+}
+
+/// row of pieces on a page
+struct Shelf {
+    y: f64,
+    height: f64,
+    /// x of the next piece
+    used: f64,
+}
+
+struct Sheet {
+    shelves: Vec<Shelf>,
+    /// y of the next shelf
+    used: f64,
+}
+
+fn fits(size: Vec2<f64>, area: Vec2<f64>) -> bool {
+    size.x <= area.x && size.y <= area.y
+}
+
+/// Packs the pieces onto as few pages of `area` as possible, keeping `gap` between them.
+/// Pieces are sorted by height and placed on rows, first fit, turned where it makes them flatter.
+/// A piece larger than the area is placed alone on a page.
+/// The placements are returned in the order of the pieces.
+pub fn pack(pieces: &[Piece], area: Vec2<f64>, gap: f64) -> Vec<Placement> {
+    // orientation of each piece, flat pieces leave less space on their row
+    let oriented: Vec<(Vec2<f64>, bool)> = pieces
+        .iter()
+        .map(|p| match p.turned {
+            Some(t) if fits(t, area) && (!fits(p.size, area) || t.y < p.size.y) => (t, true),
+            _ => (p.size, false),
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..pieces.len()).collect();
+    order.sort_by(|a, b| oriented[*b].0.y.total_cmp(&oriented[*a].0.y));
+
+    let mut sheets: Vec<Sheet> = vec![];
+    let mut placements = vec![None; pieces.len()];
+    for i in order {
+        let (size, turned) = oriented[i];
+        if !fits(size, area) {
+            sheets.push(Sheet {
+                shelves: vec![],
+                used: f64::INFINITY,
+            });
+            placements[i] = Some(Placement {
+                page: sheets.len() - 1,
+                pos: Vec2 { x: 0.0, y: 0.0 },
+                turned,
+            });
+            continue;
+        }
+
+        let mut placed = None;
+        for (page, sheet) in sheets.iter_mut().enumerate() {
+            if let Some(shelf) = sheet
+                .shelves
+                .iter_mut()
+                .find(|s| s.used + size.x <= area.x && size.y <= s.height)
+            {
+                placed = Some((
+                    page,
+                    Vec2 {
+                        x: shelf.used,
+                        y: shelf.y,
+                    },
+                ));
+                shelf.used += size.x + gap;
+                break;
+            }
+            if sheet.used + size.y <= area.y {
+                placed = Some((
+                    page,
+                    Vec2 {
+                        x: 0.0,
+                        y: sheet.used,
+                    },
+                ));
+                sheet.shelves.push(Shelf {
+                    y: sheet.used,
+                    height: size.y,
+                    used: size.x + gap,
+                });
+                sheet.used += size.y + gap;
+                break;
+            }
+        }
+        let (page, pos) = placed.unwrap_or_else(|| {
+            sheets.push(Sheet {
+                shelves: vec![Shelf {
+                    y: 0.0,
+                    height: size.y,
+                    used: size.x + gap,
+                }],
+                used: size.y + gap,
+            });
+            (sheets.len() - 1, Vec2 { x: 0.0, y: 0.0 })
+        });
+        placements[i] = Some(Placement { page, pos, turned });
+    }
+    placements.into_iter().map(|p| p.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::Vec2;
+
+    use super::{pack, Piece, Placement};
+
+    fn piece(x: f64, y: f64, turnable: bool) -> Piece {
+        Piece {
+            size: Vec2 { x, y },
+            turned: turnable.then_some(Vec2 { x: y, y: x }),
+        }
+    }
+
+    #[test]
+    fn pack_rows() {
+        let area = Vec2 { x: 100.0, y: 100.0 };
+        let placements = pack(
+            &[
+                piece(40.0, 30.0, false),
+                piece(50.0, 50.0, false),
+                piece(40.0, 40.0, false),
+                piece(100.0, 40.0, false),
+            ],
+            area,
+            0.0,
+        );
+        // highest first: 50x50 and 40x40 share the first row, 100x40 fills the second row
+        // and 40x30 is left for the next page
+        assert_eq!(placements[1].pos, Vec2 { x: 0.0, y: 0.0 });
+        assert_eq!(placements[2].pos, Vec2 { x: 50.0, y: 0.0 });
+        assert_eq!(placements[3].pos, Vec2 { x: 0.0, y: 50.0 });
+        assert_eq!(placements[3].page, 0);
+        assert_eq!(
+            placements[0],
+            Placement {
+                page: 1,
+                pos: Vec2 { x: 0.0, y: 0.0 },
+                turned: false
+            }
+        );
+    }
+
+    #[test]
+    fn pack_turned() {
+        let area = Vec2 { x: 100.0, y: 100.0 };
+        // tall pieces are turned to share a row
+        let placements = pack(
+            &[piece(30.0, 90.0, true), piece(30.0, 90.0, true)],
+            area,
+            5.0,
+        );
+        assert!(placements.iter().all(|p| p.turned && p.page == 0));
+        assert_eq!(placements[1].pos, Vec2 { x: 0.0, y: 35.0 });
+
+        // only fits when turned
+        let placements = pack(
+            &[piece(50.0, 150.0, true)],
+            Vec2 { x: 200.0, y: 100.0 },
+            0.0,
+        );
+        assert!(placements[0].turned);
+    }
+
+    #[test]
+    fn pack_oversized() {
+        let area = Vec2 { x: 100.0, y: 100.0 };
+        let placements = pack(
+            &[piece(20.0, 20.0, false), piece(200.0, 150.0, true)],
+            area,
+            0.0,
+        );
+        // the large piece gets a page of its own
+        assert_eq!(placements[1].page, 0);
+        assert!(!placements[1].turned);
+        assert_eq!(placements[0].page, 1);
+    }
+
+    #[test]
+    fn pack_inside_without_overlaps() {
+        let area = Vec2 { x: 100.0, y: 150.0 };
+        let gap = 5.0;
+        let pieces: Vec<Piece> = (0..40)
+            .map(|i| {
+                piece(
+                    10.0 + (i * 37 % 60) as f64,
+                    10.0 + (i * 53 % 90) as f64,
+                    i % 3 != 0,
+                )
+            })
+            .collect();
+        let placements = pack(&pieces, area, gap);
+        let rects: Vec<(usize, Vec2<f64>, Vec2<f64>)> = pieces
+            .iter()
+            .zip(placements.iter())
+            .map(|(piece, p)| {
+                let size = match p.turned {
+                    true => piece.turned.unwrap(),
+                    false => piece.size,
+                };
+                (p.page, p.pos, size)
+            })
+            .collect();
+        for (i, (page, pos, size)) in rects.iter().enumerate() {
+            assert!(pos.x >= 0.0 && pos.y >= 0.0);
+            assert!(pos.x + size.x <= area.x && pos.y + size.y <= area.y);
+            for (other_page, other_pos, other_size) in rects[i + 1..].iter() {
+                let apart = pos.x + size.x + gap <= other_pos.x
+                    || other_pos.x + other_size.x + gap <= pos.x
+                    || pos.y + size.y + gap <= other_pos.y
+                    || other_pos.y + other_size.y + gap <= pos.y;
+                assert!(page != other_page || apart);
+            }
+        }
+        // no page is left empty and the pieces would not fit on less pages
+        let pages = placements.iter().map(|p| p.page + 1).max().unwrap();
+        assert!((0..pages).all(|page| placements.iter().any(|p| p.page == page)));
+        let used: f64 = rects.iter().map(|(_, _, s)| s.x * s.y).sum();
+        assert!(pages as f64 >= used / (area.x * area.y));
+
+        // two rows of two pieces on each page
+        let placements = pack(&[piece(45.0, 70.0, false); 6], area, gap);
+        assert_eq!(placements.iter().map(|p| p.page).max(), Some(1));
+        assert_eq!(placements[3].pos, Vec2 { x: 50.0, y: 75.0 });
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{common::Rgb, config::WALL_WIDTH};
+
+/// Grid of the printed maps
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GridStyle {
+    Dashed,
+    Lines,
+    /// a dot at every grid intersection
+    Dots,
+    None,
+}
+
+/// Walls and floor of printed chambers and corridors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WallStyle {
+    pub width: f64,
+    /// walls of hidden chambers and corridors are dashed
+    pub dashed_hidden: bool,
+    pub fill: Rgb,
+    pub fill_opacity: f64,
+}
+
+/// Look of the printed maps of a `PrintTheme`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeStyle {
+    /// walls, doors, objects and numbers
+    pub ink: Rgb,
+    pub walls: WallStyle,
+    /// color of the map area, e.g. the rock around the chambers
+    pub paper: Option<Rgb>,
+    pub grid: GridStyle,
+    pub grid_color: Rgb,
+    /// the grid is drawn over the floors of chambers and corridors only, so it shows on opaque fills
+    pub grid_on_floors: bool,
+    /// cross-hatching along the outside of the walls
+    pub hatching: bool,
+    /// font family of the map numbers and the text of the PDF exports
+    pub font: &'static str,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrintTheme {
+    /// the look of the editor
+    Editor,
+    /// old-school blue maps with white chambers
+    ClassicBlue,
+    /// black outlines without fills
+    InkSaver,
+    Parchment,
+    CrossHatched,
+}
+
+pub const PRINT_THEMES: [PrintTheme; 5] = [
+    PrintTheme::Editor,
+    PrintTheme::ClassicBlue,
+    PrintTheme::InkSaver,
+    PrintTheme::Parchment,
+    PrintTheme::CrossHatched,
+];
+
+const BLACK: Rgb = Rgb {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+};
+const WHITE: Rgb = Rgb {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+};
+const GRAY: Rgb = Rgb {
+    r: 0.5,
+    g: 0.5,
+    b: 0.5,
+};
+const CLASSIC_BLUE: Rgb = Rgb {
+    r: 0.42,
+    g: 0.66,
+    b: 0.87,
+};
+const DARK_BLUE: Rgb = Rgb {
+    r: 0.12,
+    g: 0.35,
+    b: 0.62,
+};
+const PARCHMENT: Rgb = Rgb {
+    r: 0.95,
+    g: 0.89,
+    b: 0.76,
+};
+const SEPIA: Rgb = Rgb {
+    r: 0.36,
+    g: 0.22,
+    b: 0.1,
+};
+
+impl PrintTheme {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            PrintTheme::Editor => "Editor",
+            PrintTheme::ClassicBlue => "Classic Blue",
+            PrintTheme::InkSaver => "Ink Saver",
+            PrintTheme::Parchment => "Parchment",
+            PrintTheme::CrossHatched => "Cross-Hatched",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        PRINT_THEMES
+            .into_iter()
+            .find(|t| t.to_str() == s)
+            .unwrap_or(PrintTheme::Editor)
+    }
+
+    pub fn style(self) -> ThemeStyle {
+        match self {
+            PrintTheme::Editor => ThemeStyle {
+                ink: BLACK,
+                walls: WallStyle {
+                    width: WALL_WIDTH,
+                    dashed_hidden: true,
+                    fill: WHITE,
+                    fill_opacity: 0.3,
+                },
+                paper: None,
+                grid: GridStyle::Dashed,
+                grid_color: GRAY,
+                grid_on_floors: false,
+                hatching: false,
+                font: "Sans",
+            },
+            PrintTheme::ClassicBlue => ThemeStyle {
+                ink: DARK_BLUE,
+                walls: WallStyle {
+                    width: WALL_WIDTH,
+                    dashed_hidden: true,
+                    fill: WHITE,
+                    fill_opacity: 1.0,
+                },
+                paper: Some(CLASSIC_BLUE),
+                grid: GridStyle::Lines,
+                grid_color: CLASSIC_BLUE,
+                grid_on_floors: true,
+                hatching: false,
+                font: "Sans",
+            },
+            PrintTheme::InkSaver => ThemeStyle {
+                ink: BLACK,
+                walls: WallStyle {
+                    width: WALL_WIDTH * 0.6,
+                    dashed_hidden: true,
+                    fill: WHITE,
+                    fill_opacity: 0.0,
+                },
+                paper: None,
+                grid: GridStyle::Dots,
+                grid_color: GRAY,
+                grid_on_floors: false,
+                hatching: false,
+                font: "Sans",
+            },
+            PrintTheme::Parchment => ThemeStyle {
+                ink: SEPIA,
+                walls: WallStyle {
+                    width: WALL_WIDTH,
+                    dashed_hidden: true,
+                    fill: WHITE,
+                    fill_opacity: 0.4,
+                },
+                paper: Some(PARCHMENT),
+                grid: GridStyle::Lines,
+                grid_color: Rgb {
+                    r: 0.78,
+                    g: 0.66,
+                    b: 0.5,
+                },
+                grid_on_floors: false,
+                hatching: false,
+                font: "Serif",
+            },
+            PrintTheme::CrossHatched => ThemeStyle {
+                ink: BLACK,
+                walls: WallStyle {
+                    width: WALL_WIDTH * 1.5,
+                    dashed_hidden: true,
+                    fill: WHITE,
+                    fill_opacity: 1.0,
+                },
+                paper: None,
+                grid: GridStyle::Lines,
+                grid_color: Rgb {
+                    r: 0.7,
+                    g: 0.7,
+                    b: 0.7,
+                },
+                grid_on_floors: true,
+                hatching: true,
+                font: "Serif",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PrintTheme, PRINT_THEMES};
+
+    #[test]
+    fn theme_names() {
+        for theme in PRINT_THEMES {
+            assert_eq!(PrintTheme::from_str(theme.to_str()), theme);
+        }
+        assert_eq!(PrintTheme::from_str("unknown"), PrintTheme::Editor);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{chamber::ChamberId, corridor::CorridorId, door::DoorId, object::ObjectId};
+
+/// A set of entities selected at the same time.
+/// Bulk operations (delete, hide, move, rename) are applied to all entities of a selection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    pub chambers: Vec<ChamberId>,
+    pub doors: Vec<DoorId>,
+    pub objects: Vec<ObjectId>,
+    pub corridors: Vec<CorridorId>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chambers.len() + self.doors.len() + self.objects.len() + self.corridors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// adds all entities of `other`, which are not yet selected
+    pub fn extend(&mut self, other: &Selection) {
+        extend_unique(&mut self.chambers, &other.chambers);
+        extend_unique(&mut self.doors, &other.doors);
+        extend_unique(&mut self.objects, &other.objects);
+        extend_unique(&mut self.corridors, &other.corridors);
+    }
+
+    /// adds entities of `other` not in the selection and removes entities already selected
+    pub fn toggle(&mut self, other: &Selection) {
+        toggle(&mut self.chambers, &other.chambers);
+        toggle(&mut self.doors, &other.doors);
+        toggle(&mut self.objects, &other.objects);
+        toggle(&mut self.corridors, &other.corridors);
+    }
+
+    /// checks if any entity of `other` is part of the selection
+    pub fn intersects(&self, other: &Selection) -> bool {
+        other.chambers.iter().any(|id| self.chambers.contains(id))
+            || other.doors.iter().any(|id| self.doors.contains(id))
+            || other.objects.iter().any(|id| self.objects.contains(id))
+            || other.corridors.iter().any(|id| self.corridors.contains(id))
+    }
+}
+
+fn extend_unique(ids: &mut Vec<u32>, other: &[u32]) {
+    for id in other {
+        if !ids.contains(id) {
+            ids.push(*id);
+        }
+    }
+}
+
+fn toggle(ids: &mut Vec<u32>, other: &[u32]) {
+    for id in other {
+        match ids.iter().position(|i| i == id) {
+            Some(idx) => {
+                ids.remove(idx);
+            }
+            None => ids.push(*id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selection;
+
+    #[test]
+    fn toggle_adds_and_removes() {
+        let mut s = Selection {
+            chambers: vec![1, 2],
+            ..Selection::new()
+        };
+        s.toggle(&Selection {
+            chambers: vec![2],
+            doors: vec![4],
+            ..Selection::new()
+        });
+        assert_eq!(s.chambers, vec![1]);
+        assert_eq!(s.doors, vec![4]);
+        assert_eq!(s.len(), 2);
+    }
+
+    #[test]
+    fn extend_keeps_ids_unique() {
+        let mut s = Selection {
+            objects: vec![1],
+            ..Selection::new()
+        };
+        s.extend(&Selection {
+            objects: vec![1, 3],
+            ..Selection::new()
+        });
+        assert_eq!(s.objects, vec![1, 3]);
+        assert!(s.intersects(&Selection {
+            objects: vec![3],
+            ..Selection::new()
+        }));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{
+    chamber::ChamberId, door::DoorId, dungeon::Dungeon, object::ObjectId, selection::Selection,
+};
+
+/// Progress of a play session tracked for an entity.
+/// Explored and cleared apply to chambers, opened and broken to doors and looted to objects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionFlag {
+    Explored,
+    Cleared,
+    Opened,
+    Broken,
+    Looted,
+}
+
+impl SessionFlag {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            SessionFlag::Explored => "Explored",
+            SessionFlag::Cleared => "Cleared",
+            SessionFlag::Opened => "Opened",
+            SessionFlag::Broken => "Broken",
+            SessionFlag::Looted => "Looted",
+        }
+    }
+}
+
+pub const SESSION_FLAGS: [SessionFlag; 5] = [
+    SessionFlag::Explored,
+    SessionFlag::Cleared,
+    SessionFlag::Opened,
+    SessionFlag::Broken,
+    SessionFlag::Looted,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionNote {
+    /// seconds since the unix epoch
+    pub time: i64,
+    pub text: String,
+}
+
+/// State of a play session, kept apart from the design of the dungeon.
+/// It is stored in its own file next to the dungeon file and not part of the undo history.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    pub explored: Vec<ChamberId>,
+    pub cleared: Vec<ChamberId>,
+    pub opened: Vec<DoorId>,
+    pub broken: Vec<DoorId>,
+    pub looted: Vec<ObjectId>,
+    /// chambers, doors and corridors shown in the player window
+    pub revealed: Selection,
+    pub notes: Vec<SessionNote>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ids of all entities with the flag set
+    pub fn ids(&self, flag: SessionFlag) -> &Vec<u32> {
+        match flag {
+            SessionFlag::Explored => &self.explored,
+            SessionFlag::Cleared => &self.cleared,
+            SessionFlag::Opened => &self.opened,
+            SessionFlag::Broken => &self.broken,
+            SessionFlag::Looted => &self.looted,
+        }
+    }
+
+    pub fn ids_mut(&mut self, flag: SessionFlag) -> &mut Vec<u32> {
+        match flag {
+            SessionFlag::Explored => &mut self.explored,
+            SessionFlag::Cleared => &mut self.cleared,
+            SessionFlag::Opened => &mut self.opened,
+            SessionFlag::Broken => &mut self.broken,
+            SessionFlag::Looted => &mut self.looted,
+        }
+    }
+
+    pub fn is_set(&self, flag: SessionFlag, id: u32) -> bool {
+        self.ids(flag).contains(&id)
+    }
+
+    /// Reveals or covers the chambers, doors and corridors of the selection in the player window.
+    /// Doors of chambers are changed with them, hidden doors are only revealed on their own.
+    pub fn reveal(&mut self, dungeon: &Dungeon, selection: &Selection, value: bool) {
+        let mut targets = Selection {
+            objects: vec![],
+            ..selection.clone()
+        };
+        for door in dungeon.doors.iter() {
+            if selection.chambers.contains(&door.part_of) && !(value && door.hidden) {
+                targets.doors.push(door.id);
+            }
+        }
+        if value {
+            self.revealed.extend(&targets);
+        } else {
+            self.revealed
+                .chambers
+                .retain(|id| !targets.chambers.contains(id));
+            self.revealed.doors.retain(|id| !targets.doors.contains(id));
+            self.revealed
+                .corridors
+                .retain(|id| !targets.corridors.contains(id));
+        }
+    }
+
+    /// Removes the ids of entities no longer in the dungeon.
+    /// Ids of deleted entities are reused, a new entity must not inherit their progress.
+    pub fn remove_deleted(&mut self, dungeon: &Dungeon) {
+        self.explored.retain(|id| dungeon.chamber(*id).is_some());
+        self.cleared.retain(|id| dungeon.chamber(*id).is_some());
+        self.opened.retain(|id| dungeon.door(*id).is_some());
+        self.broken.retain(|id| dungeon.door(*id).is_some());
+        self.looted.retain(|id| dungeon.object(*id).is_some());
+        self.revealed
+            .chambers
+            .retain(|id| dungeon.chamber(*id).is_some());
+        self.revealed.doors.retain(|id| dungeon.door(*id).is_some());
+        self.revealed
+            .corridors
+            .retain(|id| dungeon.corridor(*id).is_some());
+    }
+
+    /// sets or clears the flag for the entities of the selection it applies to
+    pub fn set(&mut self, flag: SessionFlag, selection: &Selection, value: bool) {
+        let targets = match flag {
+            SessionFlag::Explored | SessionFlag::Cleared => &selection.chambers,
+            SessionFlag::Opened | SessionFlag::Broken => &selection.doors,
+            SessionFlag::Looted => &selection.objects,
+        };
+        let ids = self.ids_mut(flag);
+        for id in targets {
+            match (value, ids.contains(id)) {
+                (true, false) => ids.push(*id),
+                (false, true) => ids.retain(|i| i != id),
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::door::Door;
+    use crate::dungeon::{tests::add_rect, Dungeon};
+    use crate::selection::Selection;
+    use crate::state::{StateCommand, StateController};
+
+    use super::{Session, SessionFlag};
+
+    #[test]
+    fn set_flags_by_entity_kind() {
+        let mut session = Session::new();
+        let selection = Selection {
+            chambers: vec![1, 2],
+            doors: vec![3],
+            ..Selection::new()
+        };
+        session.set(SessionFlag::Explored, &selection, true);
+        session.set(SessionFlag::Opened, &selection, true);
+        session.set(SessionFlag::Looted, &selection, true);
+        assert_eq!(session.explored, vec![1, 2]);
+        assert_eq!(session.opened, vec![3]);
+        assert!(session.looted.is_empty());
+
+        session.set(
+            SessionFlag::Explored,
+            &Selection {
+                chambers: vec![1],
+                ..Selection::new()
+            },
+            false,
+        );
+        assert!(!session.is_set(SessionFlag::Explored, 1));
+        assert!(session.is_set(SessionFlag::Explored, 2));
+    }
+
+    #[test]
+    fn reveal_chambers_with_their_doors() {
+        let mut dungeon = Dungeon::new();
+        let a = add_rect(&mut dungeon, 0, 0, 100, 100);
+        let b = add_rect(&mut dungeon, 100, 0, 200, 100);
+        let door = dungeon.add_door(Door::new(a, Some(b), 50.0, 2, 0.5));
+        let mut secret = Door::new(a, None, 50.0, 1, 0.5);
+        secret.hidden = true;
+        let secret = dungeon.add_door(secret);
+
+        let mut session = Session::new();
+        let chamber_a = Selection {
+            chambers: vec![a],
+            ..Selection::new()
+        };
+        session.reveal(&dungeon, &chamber_a, true);
+        assert_eq!(session.revealed.chambers, vec![a]);
+        assert_eq!(session.revealed.doors, vec![door]);
+
+        // hidden doors are only revealed on their own
+        let secret_door = Selection {
+            doors: vec![secret],
+            ..Selection::new()
+        };
+        session.reveal(&dungeon, &secret_door, true);
+        assert_eq!(session.revealed.doors, vec![door, secret]);
+
+        // hiding a chamber hides all of its doors
+        session.reveal(&dungeon, &chamber_a, false);
+        assert!(session.revealed.is_empty());
+    }
+
+    #[test]
+    fn new_entities_do_not_inherit_the_session_of_deleted_ones() {
+        let mut control = StateController::new();
+        let a = add_rect(&mut control.state.dungeon, 0, 0, 100, 100);
+        let chamber_a = Selection {
+            chambers: vec![a],
+            ..Selection::new()
+        };
+        control.apply(StateCommand::ChangeEntitiesRevealed(
+            chamber_a.clone(),
+            true,
+        ));
+        control.apply(StateCommand::ChangeSessionFlag(
+            chamber_a,
+            SessionFlag::Explored,
+            true,
+        ));
+
+        control.apply(StateCommand::DeleteChamber(a));
+        control.apply(StateCommand::AddChamber);
+        assert_eq!(control.state.active_chamber_id, Some(a));
+        assert!(control.state.session.revealed.is_empty());
+        assert!(!control.state.session.is_set(SessionFlag::Explored, a));
+
+        // e.g. after undoing the creation of a revealed chamber
+        control.state.session.revealed.chambers.push(a + 1);
+        control.reload();
+        assert!(control.state.session.revealed.is_empty());
+    }
+}
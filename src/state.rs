@@ -5,9 +5,16 @@ pub mod events;
 use crate::{
     chamber::{Chamber, ChamberId},
     common::Vec2,
+    config::SNAP_DISTANCE,
+    corridor::{Corridor, CorridorId},
+    diff::{diff, Change},
     door::{Door, DoorId},
-    dungeon::Dungeon,
+    dungeon::{Dungeon, Snap, SnapTarget},
+    import::Import,
     object::Object,
+    selection::Selection,
+    session::Session,
+    template::Template,
     view::{grid::Grid, View},
 };
 pub use commands::StateCommand;
@@ -29,6 +36,23 @@ pub struct State {
     pub active_chamber_id: Option<ChamberId>,
     pub active_door_id: Option<DoorId>,
     pub active_object_id: Option<DoorId>,
+    pub active_corridor_id: Option<CorridorId>,
+    /// entities selected together, empty if at most one entity is selected
+    pub selection: Selection,
+    /// split walls and remove corners of neighboring chambers together with the active chamber
+    pub link_shared_walls: bool,
+    /// progress of the current play session, not part of the dungeon design
+    pub session: Session,
+    /// show the progress of the session instead of the design on the canvas
+    pub session_view: bool,
+    /// rotated template placed by clicking in `EditMode::PlaceTemplate`
+    pub template: Option<Template>,
+    /// content of another dungeon file placed by clicking in `EditMode::PlaceImport`
+    pub import: Option<Import>,
+    /// another version of the dungeon, changes to it are highlighted on the canvas
+    pub compare: Option<Dungeon>,
+    /// changes of the dungeon since `compare`, kept up to date instead of comparing on every redraw
+    pub changes: Vec<Change>,
 }
 pub struct StateController {
     pub state: State,
@@ -43,6 +67,11 @@ pub trait StateEventSubscriber {
 
 pub trait StateCommandSubscriber {
     fn on_cmd_event(&mut self, state: &mut State, cmd: StateCommand);
+
+    /// the following commands belong together until `on_group_end`, see `StateController::apply_group`
+    fn on_group_start(&mut self) {}
+
+    fn on_group_end(&mut self) {}
 }
 
 impl State {
@@ -51,6 +80,15 @@ impl State {
             active_chamber_id: None,
             active_door_id: None,
             active_object_id: None,
+            active_corridor_id: None,
+            selection: Selection::new(),
+            link_shared_walls: false,
+            session: Session::new(),
+            session_view: false,
+            template: None,
+            import: None,
+            compare: None,
+            changes: vec![],
             dungeon: Dungeon::new(),
             grid: Grid::new(),
             view: View::new(),
@@ -61,6 +99,19 @@ impl State {
         }
     }
 
+    /// sets the version of the dungeon changes are highlighted against
+    pub fn set_compare(&mut self, compare: Option<Dungeon>) {
+        self.compare = compare;
+        self.update_changes();
+    }
+
+    pub fn update_changes(&mut self) {
+        self.changes = match &self.compare {
+            Some(old) => diff(old, &self.dungeon),
+            None => vec![],
+        };
+    }
+
     pub fn active_chamber(&self) -> Option<&Chamber> {
         match self.active_chamber_id {
             Some(chamber_id) => self.dungeon.chamber(chamber_id),
@@ -79,12 +130,43 @@ impl State {
         self.cursor.pos + self.view.world_min().into()
     }
 
+    /// Snaps a position to nearby corners and walls of other chambers, falling back to the grid.
+    /// The active chamber is skipped, as it is the one being edited.
+    pub fn snap(&self, pos: Vec2<f64>) -> Snap {
+        self.dungeon
+            .snap_to_walls(pos, SNAP_DISTANCE, self.active_chamber_id)
+            .unwrap_or(Snap {
+                pos: self.grid.snap(pos.into()),
+                target: SnapTarget::Grid,
+            })
+    }
+
+    /// All selected entities, either the multi-selection or the active entity
+    pub fn selected(&self) -> Selection {
+        if !self.selection.is_empty() {
+            return self.selection.clone();
+        }
+        let mut selected = Selection::new();
+        selected.chambers.extend(self.active_chamber_id);
+        selected.doors.extend(self.active_door_id);
+        selected.objects.extend(self.active_object_id);
+        selected.corridors.extend(self.active_corridor_id);
+        selected
+    }
+
     pub(crate) fn active_object(&self) -> Option<&Object> {
         match self.active_object_id {
             Some(object_id) => self.dungeon.object(object_id),
             None => None,
         }
     }
+
+    pub fn active_corridor(&self) -> Option<&Corridor> {
+        match self.active_corridor_id {
+            Some(corridor_id) => self.dungeon.corridor(corridor_id),
+            None => None,
+        }
+    }
 }
 
 impl StateController {
@@ -104,6 +186,12 @@ impl StateController {
     pub fn apply(&mut self, command: StateCommand) {
         {
             let events = command.execute(&mut self.state);
+            if self.state.compare.is_some() && events.iter().any(|e| e.modifies_dungeon()) {
+                self.state.update_changes();
+            }
+            if events.iter().any(|e| e.deletes_entity()) {
+                self.state.session.remove_deleted(&self.state.dungeon);
+            }
             for e in events.iter() {
                 self.notify(e.clone());
             }
@@ -114,6 +202,19 @@ impl StateController {
         }
     }
 
+    /// applies the commands as one step, e.g. to undo them together
+    pub fn apply_group(&mut self, commands: Vec<StateCommand>) {
+        for sub in self.cmd_subscribers.iter() {
+            sub.borrow_mut().on_group_start();
+        }
+        for command in commands {
+            self.apply(command);
+        }
+        for sub in self.cmd_subscribers.iter() {
+            sub.borrow_mut().on_group_end();
+        }
+    }
+
     pub fn apply_silent(&mut self, command: StateCommand) {
         command.execute(&mut self.state);
     }
@@ -155,12 +256,20 @@ impl StateController {
 
     pub fn reset(&mut self) {
         let view = self.state.view.clone();
+        let link_shared_walls = self.state.link_shared_walls;
+        let session_view = self.state.session_view;
+        let compare = self.state.compare.take();
         self.state = State::new();
         self.state.view = view;
+        self.state.link_shared_walls = link_shared_walls;
+        self.state.session_view = session_view;
+        self.state.compare = compare;
         self.notify(StateEvent::Reset);
     }
 
     pub fn reload(&mut self) {
+        self.state.update_changes();
+        self.state.session.remove_deleted(&self.state.dungeon);
         self.notify(StateEvent::Reload);
     }
 }
@@ -1,8 +1,17 @@
+use std::rc::Rc;
+
 use crate::{
+    background::{Background, BackgroundImage, BackgroundSettings},
     chamber::{Chamber, ChamberId, WallId},
     common::Vec2,
+    corridor::{Corridor, CorridorId},
     door::{Door, DoorId},
+    dungeon::ChamberReplacement,
+    export_settings::ExportSettings,
+    geometry::{self, BooleanOp},
     object::{Object, ObjectId, ObjectStyle},
+    selection::Selection,
+    session::{SessionFlag, SessionNote},
 };
 
 use super::{events::StateEvent, EditMode, State};
@@ -15,6 +24,7 @@ pub enum StateCommand {
     AddVertexToChamber(ChamberId, Vec2<i32>),
     ChangeChamberName(ChamberId, String),
     ChangeChamberNotes(ChamberId, String),
+    ChangeChamberReadAloud(ChamberId, String),
     ChangeChamberHidden(ChamberId, bool),
     SplitWall(ChamberId, WallId, Vec2<i32>),
     CollapseWall(ChamberId, WallId),
@@ -23,18 +33,119 @@ pub enum StateCommand {
     SelectDoor(Option<DoorId>),
     ChangeDoorName(DoorId, String),
     ChangeDoorNotes(DoorId, String),
+    ChangeDoorReadAloud(DoorId, String),
     ChangeDoorLeadsTo(DoorId, Option<ChamberId>),
     ChangeDoorHidden(DoorId, bool),
     DeleteDoor(DoorId),
     ChangeDungeonName(String),
     ChangeDungeonNotes(String),
+    /// replaces or removes the background image, the placement is kept
+    SetBackgroundImage(Option<Rc<BackgroundImage>>),
+    ChangeBackgroundSettings(BackgroundSettings),
+    ChangeExportSettings(ExportSettings),
     AddObject(Vec2<i32>, Option<ChamberId>),
     SelectObject(Option<ObjectId>),
     DeleteObject(ObjectId),
     ChangeObjectName(ObjectId, String),
     ChangeObjectNotes(ObjectId, String),
+    ChangeObjectReadAloud(ObjectId, String),
     ChangeObjectHidden(ObjectId, bool),
     ChangeObjectStyle(ObjectId, ObjectStyle),
+    ChangeObjectSize(ObjectId, Vec2<i32>),
+    ChangeObjectRotation(ObjectId, f64),
+    MergeChambers(ChamberId, ChamberId),
+    SubtractChamber(ChamberId, ChamberId),
+    SplitChamber(ChamberId, Vec2<i32>, Vec2<i32>),
+    AddCorridor,
+    SelectCorridor(Option<CorridorId>),
+    AddVertexToCorridor(CorridorId, Vec2<i32>),
+    ChangeCorridorName(CorridorId, String),
+    ChangeCorridorNotes(CorridorId, String),
+    ChangeCorridorHidden(CorridorId, bool),
+    ChangeCorridorNumbered(CorridorId, bool),
+    ChangeCorridorWidth(CorridorId, i32),
+    DeleteCorridor(CorridorId),
+    ChangeSelection(Selection),
+    DeleteEntities(Selection),
+    ChangeEntitiesHidden(Selection, bool),
+    MoveEntities(Selection, Vec2<i32>),
+    /// renames all entities using a pattern, see `rename_pattern`
+    RenameEntities(Selection, String),
+    /// session commands are not recorded in the dungeon history, see `is_session`
+    ChangeSessionFlag(Selection, SessionFlag, bool),
+    /// reveals or unreveals chambers, doors and corridors in the player window, see `Session::reveal`
+    ChangeEntitiesRevealed(Selection, bool),
+    /// adds a session note with a timestamp in seconds since the unix epoch
+    AddSessionNote(i64, String),
+    DeleteSessionNote(usize),
+}
+
+impl StateCommand {
+    /// commands changing the play session instead of the dungeon
+    pub fn is_session(&self) -> bool {
+        matches!(
+            self,
+            StateCommand::ChangeSessionFlag(_, _, _)
+                | StateCommand::ChangeEntitiesRevealed(_, _)
+                | StateCommand::AddSessionNote(_, _)
+                | StateCommand::DeleteSessionNote(_)
+        )
+    }
+}
+
+/// Creates a name from a rename pattern.
+/// `{n}` is replaced by the running number, `{name}` by the previous name.
+pub fn rename_pattern(pattern: &str, n: usize, name: &str) -> String {
+    pattern
+        .replace("{n}", &n.to_string())
+        .replace("{name}", name)
+}
+
+/// clears a multi-selection, used when a single entity is selected
+fn clear_selection(state: &mut State) -> Vec<StateEvent> {
+    if state.selection.is_empty() {
+        return vec![];
+    }
+    state.selection = Selection::new();
+    vec![StateEvent::SelectionChanged]
+}
+
+/// converts the result of a chamber replacement into events.
+/// Selections of removed entities are cleared.
+fn replacement_events(state: &mut State, r: ChamberReplacement) -> Vec<StateEvent> {
+    let mut events = vec![];
+    events.extend(r.added.iter().map(|id| StateEvent::ChamberAdded(*id)));
+    events.extend(r.modified.iter().map(|id| StateEvent::ChamberModified(*id)));
+    events.extend(
+        r.modified_doors
+            .iter()
+            .map(|id| StateEvent::DoorModified(*id)),
+    );
+    events.extend(
+        r.removed_doors
+            .iter()
+            .map(|id| StateEvent::DoorDeleted(*id)),
+    );
+    events.extend(
+        r.modified_objects
+            .iter()
+            .map(|id| StateEvent::ObjectModified(*id)),
+    );
+    events.extend(r.removed.iter().map(|id| StateEvent::ChamberDeleted(*id)));
+
+    if let Some(door_id) = state.active_door_id {
+        if r.removed_doors.contains(&door_id) {
+            state.active_door_id = None;
+            events.push(StateEvent::ActiveDoorChanged(None));
+        }
+    }
+    if let Some(chamber_id) = state.active_chamber_id {
+        if r.removed.contains(&chamber_id) {
+            state.active_chamber_id = r.modified.first().copied();
+            events.push(StateEvent::ActiveChamberChanged(state.active_chamber_id));
+        }
+    }
+    events
 }
 
 impl StateCommand {
@@ -44,11 +155,13 @@ impl StateCommand {
                 let chamber_id = state.dungeon.add_chamber(Chamber::new());
                 state.active_chamber_id = Some(chamber_id);
                 state.mode = EditMode::AppendChamber;
-                vec![
+                let mut events = vec![
                     StateEvent::ChamberAdded(chamber_id),
                     StateEvent::ActiveChamberChanged(Some(chamber_id)),
                     StateEvent::EditModeChanged(EditMode::AppendChamber),
-                ]
+                ];
+                events.extend(clear_selection(state));
+                events
             }
             StateCommand::ChangeMode(mode) => {
                 state.mode = *mode;
@@ -58,32 +171,58 @@ impl StateCommand {
                 state.active_chamber_id = *chamber_id;
                 state.active_door_id = None;
                 state.active_object_id = None;
-                vec![
+                state.active_corridor_id = None;
+                let mut events = vec![
                     StateEvent::ActiveDoorChanged(None),
                     StateEvent::ActiveObjectChanged(None),
+                    StateEvent::ActiveCorridorChanged(None),
                     StateEvent::ActiveChamberChanged(*chamber_id),
-                ]
+                ];
+                events.extend(clear_selection(state));
+                events
             }
             StateCommand::SelectDoor(door_id) => {
                 state.active_chamber_id = None;
                 state.active_door_id = *door_id;
                 state.active_object_id = None;
-                vec![
+                state.active_corridor_id = None;
+                let mut events = vec![
                     StateEvent::ActiveChamberChanged(None),
                     StateEvent::ActiveObjectChanged(None),
+                    StateEvent::ActiveCorridorChanged(None),
                     StateEvent::ActiveDoorChanged(*door_id),
-                ]
+                ];
+                events.extend(clear_selection(state));
+                events
             }
             StateCommand::SelectObject(obj_id) => {
                 state.active_chamber_id = None;
                 state.active_door_id = None;
                 state.active_object_id = *obj_id;
+                state.active_corridor_id = None;
 
-                vec![
+                let mut events = vec![
                     StateEvent::ActiveChamberChanged(None),
                     StateEvent::ActiveDoorChanged(None),
+                    StateEvent::ActiveCorridorChanged(None),
                     StateEvent::ActiveObjectChanged(*obj_id),
-                ]
+                ];
+                events.extend(clear_selection(state));
+                events
+            }
+            StateCommand::SelectCorridor(corridor_id) => {
+                state.active_chamber_id = None;
+                state.active_door_id = None;
+                state.active_object_id = None;
+                state.active_corridor_id = *corridor_id;
+                let mut events = vec![
+                    StateEvent::ActiveChamberChanged(None),
+                    StateEvent::ActiveDoorChanged(None),
+                    StateEvent::ActiveObjectChanged(None),
+                    StateEvent::ActiveCorridorChanged(*corridor_id),
+                ];
+                events.extend(clear_selection(state));
+                events
             }
             StateCommand::AddVertexToChamber(chamber_id, pos) => {
                 state.dungeon.chamber_mut(*chamber_id).unwrap().append(*pos);
@@ -97,6 +236,10 @@ impl StateCommand {
                 state.dungeon.chamber_mut(*chamber_id).unwrap().notes = notes.clone();
                 vec![StateEvent::ChamberModified(*chamber_id)]
             }
+            StateCommand::ChangeChamberReadAloud(chamber_id, text) => {
+                state.dungeon.chamber_mut(*chamber_id).unwrap().read_aloud = text.clone();
+                vec![StateEvent::ChamberModified(*chamber_id)]
+            }
             StateCommand::ChangeChamberHidden(chamber_id, hidden) => {
                 state.dungeon.chamber_mut(*chamber_id).unwrap().hidden = *hidden;
                 vec![StateEvent::ChamberModified(*chamber_id)]
@@ -120,10 +263,13 @@ impl StateCommand {
                     .dungeon
                     .doors
                     .iter()
-                    .filter(|d| d.on_wall == removed_wall_id)
+                    .filter(|d| d.part_of == *chamber_id && d.on_wall == removed_wall_id)
                     .map(|d| StateEvent::DoorDeleted(d.id))
                     .collect();
-                state.dungeon.doors.retain(|d| d.on_wall != removed_wall_id);
+                state
+                    .dungeon
+                    .doors
+                    .retain(|d| d.part_of != *chamber_id || d.on_wall != removed_wall_id);
 
                 events.push(StateEvent::ChamberModified(*chamber_id));
 
@@ -145,29 +291,10 @@ impl StateCommand {
             }
             StateCommand::AddDoor(door) => {
                 let mut door = door.clone();
-                let wall = state
-                    .dungeon
-                    .chamber(door.part_of)
-                    .unwrap()
-                    .wall(door.on_wall)
-                    .unwrap();
-                let world_pos = wall.rel_to_world(door.position);
-                // check if a "leads_to" can be uniquely determined
-                // iterate all walls
-                let containing_walls = state
-                    .dungeon
-                    .walls()
-                    .iter()
-                    .filter(|w| {
-                        // skip all wals of chamber containing the door
-                        // check if world pos of door lies on wall
-                        w.chamber_id != door.part_of && w.distance(world_pos) < 1e-6
-                    })
-                    .map(|w| w.chamber_id)
-                    .collect::<std::collections::HashSet<_>>();
-
-                if containing_walls.len() == 1 {
-                    door.leads_to = Some(*containing_walls.iter().next().unwrap());
+                // a door on a wall shared with exactly one chamber leads to this chamber
+                let shared_with = state.dungeon.door_shared_with(&door);
+                if shared_with.len() == 1 {
+                    door.leads_to = Some(shared_with[0]);
                 }
                 let door_id = state.dungeon.add_door(door.clone());
 
@@ -184,6 +311,10 @@ impl StateCommand {
                 state.dungeon.door_mut(*door_id).unwrap().notes = notes.clone();
                 vec![StateEvent::DoorModified(*door_id)]
             }
+            StateCommand::ChangeDoorReadAloud(door_id, text) => {
+                state.dungeon.door_mut(*door_id).unwrap().read_aloud = text.clone();
+                vec![StateEvent::DoorModified(*door_id)]
+            }
             StateCommand::ChangeDoorLeadsTo(door_id, chamber_id) => {
                 state.dungeon.door_mut(*door_id).unwrap().leads_to = *chamber_id;
                 vec![StateEvent::DoorModified(*door_id)]
@@ -209,6 +340,26 @@ impl StateCommand {
                 state.dungeon.notes = notes.clone();
                 vec![StateEvent::DungeonModified]
             }
+            StateCommand::SetBackgroundImage(image) => {
+                let settings = match &state.dungeon.background {
+                    Some(background) => background.settings,
+                    None => BackgroundSettings::new(),
+                };
+                state.dungeon.background =
+                    image.clone().map(|image| Background { image, settings });
+                vec![StateEvent::BackgroundChanged]
+            }
+            StateCommand::ChangeBackgroundSettings(settings) => {
+                match state.dungeon.background.as_mut() {
+                    Some(background) => background.settings = *settings,
+                    None => return vec![],
+                }
+                vec![StateEvent::BackgroundChanged]
+            }
+            StateCommand::ChangeExportSettings(settings) => {
+                state.dungeon.export_settings = *settings;
+                vec![StateEvent::DungeonModified]
+            }
             StateCommand::AddObject(pos, part_of) => {
                 let obj_id = state.dungeon.add_object(Object::new(*pos, *part_of));
                 vec![StateEvent::ObjectAdded(obj_id)]
@@ -230,14 +381,268 @@ impl StateCommand {
                 state.dungeon.object_mut(*object_id).unwrap().notes = notes.clone();
                 vec![StateEvent::ObjectModified(*object_id)]
             }
+            StateCommand::ChangeObjectReadAloud(object_id, text) => {
+                state.dungeon.object_mut(*object_id).unwrap().read_aloud = text.clone();
+                vec![StateEvent::ObjectModified(*object_id)]
+            }
             StateCommand::ChangeObjectHidden(object_id, hidden) => {
                 state.dungeon.object_mut(*object_id).unwrap().hidden = *hidden;
                 vec![StateEvent::ObjectModified(*object_id)]
             }
             StateCommand::ChangeObjectStyle(object_id, style) => {
-                state.dungeon.object_mut(*object_id).unwrap().style = *style;
+                state.dungeon.object_mut(*object_id).unwrap().style = style.clone();
+                vec![StateEvent::ObjectModified(*object_id)]
+            }
+            StateCommand::ChangeObjectSize(object_id, size) => {
+                state.dungeon.object_mut(*object_id).unwrap().size = *size;
+                vec![StateEvent::ObjectModified(*object_id)]
+            }
+            StateCommand::ChangeObjectRotation(object_id, rotation) => {
+                state.dungeon.object_mut(*object_id).unwrap().rotation = rotation.rem_euclid(360.0);
                 vec![StateEvent::ObjectModified(*object_id)]
             }
+            StateCommand::MergeChambers(chamber_id, other_id) => {
+                if chamber_id == other_id {
+                    return vec![];
+                }
+                let shapes = geometry::boolean(
+                    &state.dungeon.chamber(*chamber_id).unwrap().shape(),
+                    &state.dungeon.chamber(*other_id).unwrap().shape(),
+                    BooleanOp::Union,
+                );
+                if shapes.len() != 1 {
+                    println!("Chambers do not form a single chamber, unable to merge");
+                    return vec![];
+                }
+                let r = state
+                    .dungeon
+                    .replace_chambers(&[*chamber_id, *other_id], &shapes);
+                replacement_events(state, r)
+            }
+            StateCommand::SubtractChamber(chamber_id, other_id) => {
+                if chamber_id == other_id {
+                    return vec![];
+                }
+                let shapes = geometry::boolean(
+                    &state.dungeon.chamber(*chamber_id).unwrap().shape(),
+                    &state.dungeon.chamber(*other_id).unwrap().shape(),
+                    BooleanOp::Difference,
+                );
+                if shapes.is_empty() {
+                    println!("Chamber can not be cut out of chamber");
+                    return vec![];
+                }
+                let r = state.dungeon.replace_chambers(&[*chamber_id], &shapes);
+                replacement_events(state, r)
+            }
+            StateCommand::SplitChamber(chamber_id, from, to) => {
+                let parts = geometry::split(
+                    &state.dungeon.chamber(*chamber_id).unwrap().shape(),
+                    *from,
+                    *to,
+                );
+                let (first, second) = match parts {
+                    Some(parts) => parts,
+                    None => {
+                        println!("Line does not split the chamber in two");
+                        return vec![];
+                    }
+                };
+                let r = state
+                    .dungeon
+                    .replace_chambers(&[*chamber_id], &[first, second]);
+                replacement_events(state, r)
+            }
+            StateCommand::AddCorridor => {
+                let corridor_id = state.dungeon.add_corridor(Corridor::new());
+                state.active_chamber_id = None;
+                state.active_door_id = None;
+                state.active_object_id = None;
+                state.active_corridor_id = Some(corridor_id);
+                state.mode = EditMode::AppendCorridor;
+                let mut events = vec![
+                    StateEvent::CorridorAdded(corridor_id),
+                    StateEvent::ActiveChamberChanged(None),
+                    StateEvent::ActiveDoorChanged(None),
+                    StateEvent::ActiveObjectChanged(None),
+                    StateEvent::ActiveCorridorChanged(Some(corridor_id)),
+                    StateEvent::EditModeChanged(EditMode::AppendCorridor),
+                ];
+                events.extend(clear_selection(state));
+                events
+            }
+            StateCommand::AddVertexToCorridor(corridor_id, pos) => {
+                state
+                    .dungeon
+                    .corridor_mut(*corridor_id)
+                    .unwrap()
+                    .append(*pos);
+                vec![StateEvent::CorridorModified(*corridor_id)]
+            }
+            StateCommand::ChangeCorridorName(corridor_id, name) => {
+                state.dungeon.corridor_mut(*corridor_id).unwrap().name = name.clone();
+                vec![StateEvent::CorridorModified(*corridor_id)]
+            }
+            StateCommand::ChangeCorridorNotes(corridor_id, notes) => {
+                state.dungeon.corridor_mut(*corridor_id).unwrap().notes = notes.clone();
+                vec![StateEvent::CorridorModified(*corridor_id)]
+            }
+            StateCommand::ChangeCorridorHidden(corridor_id, hidden) => {
+                state.dungeon.corridor_mut(*corridor_id).unwrap().hidden = *hidden;
+                vec![StateEvent::CorridorModified(*corridor_id)]
+            }
+            StateCommand::ChangeCorridorNumbered(corridor_id, numbered) => {
+                state.dungeon.corridor_mut(*corridor_id).unwrap().numbered = *numbered;
+                vec![StateEvent::CorridorModified(*corridor_id)]
+            }
+            StateCommand::ChangeCorridorWidth(corridor_id, width) => {
+                state
+                    .dungeon
+                    .corridor_mut(*corridor_id)
+                    .unwrap()
+                    .set_width(*width);
+                vec![StateEvent::CorridorModified(*corridor_id)]
+            }
+            StateCommand::DeleteCorridor(corridor_id) => {
+                state.dungeon.remove_corridor(*corridor_id);
+                let mut events = vec![StateEvent::CorridorDeleted(*corridor_id)];
+                if state.active_corridor_id == Some(*corridor_id) {
+                    state.active_corridor_id = None;
+                    events.push(StateEvent::ActiveCorridorChanged(None));
+                }
+                events
+            }
+            StateCommand::ChangeSelection(selection) => {
+                // a single entity becomes the active entity
+                if selection.len() <= 1 {
+                    let cmd = if let Some(id) = selection.doors.first() {
+                        StateCommand::SelectDoor(Some(*id))
+                    } else if let Some(id) = selection.objects.first() {
+                        StateCommand::SelectObject(Some(*id))
+                    } else if let Some(id) = selection.corridors.first() {
+                        StateCommand::SelectCorridor(Some(*id))
+                    } else {
+                        StateCommand::SelectChamber(selection.chambers.first().copied())
+                    };
+                    return cmd.execute(state);
+                }
+                state.active_chamber_id = None;
+                state.active_door_id = None;
+                state.active_object_id = None;
+                state.active_corridor_id = None;
+                state.selection = selection.clone();
+                vec![
+                    StateEvent::ActiveChamberChanged(None),
+                    StateEvent::ActiveDoorChanged(None),
+                    StateEvent::ActiveObjectChanged(None),
+                    StateEvent::ActiveCorridorChanged(None),
+                    StateEvent::SelectionChanged,
+                ]
+            }
+            StateCommand::DeleteEntities(selection) => {
+                let mut events = vec![];
+                for id in selection.doors.iter() {
+                    events.extend(StateCommand::DeleteDoor(*id).execute(state));
+                }
+                for id in selection.objects.iter() {
+                    events.extend(StateCommand::DeleteObject(*id).execute(state));
+                }
+                for id in selection.corridors.iter() {
+                    events.extend(StateCommand::DeleteCorridor(*id).execute(state));
+                }
+                for id in selection.chambers.iter() {
+                    events.extend(StateCommand::DeleteChamber(*id).execute(state));
+                }
+                events.extend(clear_selection(state));
+                events
+            }
+            StateCommand::ChangeEntitiesHidden(selection, hidden) => {
+                let mut events = vec![];
+                for id in selection.chambers.iter() {
+                    events.extend(StateCommand::ChangeChamberHidden(*id, *hidden).execute(state));
+                }
+                for id in selection.doors.iter() {
+                    events.extend(StateCommand::ChangeDoorHidden(*id, *hidden).execute(state));
+                }
+                for id in selection.objects.iter() {
+                    events.extend(StateCommand::ChangeObjectHidden(*id, *hidden).execute(state));
+                }
+                for id in selection.corridors.iter() {
+                    events.extend(StateCommand::ChangeCorridorHidden(*id, *hidden).execute(state));
+                }
+                events
+            }
+            StateCommand::ChangeSessionFlag(selection, flag, value) => {
+                state.session.set(*flag, selection, *value);
+                vec![StateEvent::SessionChanged]
+            }
+            StateCommand::ChangeEntitiesRevealed(selection, revealed) => {
+                state.session.reveal(&state.dungeon, selection, *revealed);
+                vec![StateEvent::SessionChanged]
+            }
+            StateCommand::AddSessionNote(time, text) => {
+                state.session.notes.push(SessionNote {
+                    time: *time,
+                    text: text.clone(),
+                });
+                vec![StateEvent::SessionChanged]
+            }
+            StateCommand::DeleteSessionNote(index) => {
+                if *index >= state.session.notes.len() {
+                    return vec![];
+                }
+                state.session.notes.remove(*index);
+                vec![StateEvent::SessionChanged]
+            }
+            StateCommand::MoveEntities(selection, by) => {
+                let moved_objects = state.dungeon.move_entities(selection, *by);
+                let mut events: Vec<StateEvent> = selection
+                    .chambers
+                    .iter()
+                    .map(|id| StateEvent::ChamberModified(*id))
+                    .collect();
+                events.extend(
+                    selection
+                        .corridors
+                        .iter()
+                        .map(|id| StateEvent::CorridorModified(*id)),
+                );
+                events.extend(
+                    moved_objects
+                        .iter()
+                        .map(|id| StateEvent::ObjectModified(*id)),
+                );
+                events
+            }
+            StateCommand::RenameEntities(selection, pattern) => {
+                let mut events = vec![];
+                let mut n = 0;
+                for id in selection.chambers.iter() {
+                    n += 1;
+                    let old = &state.dungeon.chamber(*id).unwrap().name;
+                    let name = rename_pattern(pattern, n, old);
+                    events.extend(StateCommand::ChangeChamberName(*id, name).execute(state));
+                }
+                for id in selection.corridors.iter() {
+                    n += 1;
+                    let old = &state.dungeon.corridor(*id).unwrap().name;
+                    let name = rename_pattern(pattern, n, old);
+                    events.extend(StateCommand::ChangeCorridorName(*id, name).execute(state));
+                }
+                for id in selection.doors.iter() {
+                    n += 1;
+                    let old = &state.dungeon.door(*id).unwrap().name;
+                    let name = rename_pattern(pattern, n, old);
+                    events.extend(StateCommand::ChangeDoorName(*id, name).execute(state));
+                }
+                for id in selection.objects.iter() {
+                    n += 1;
+                    let old = &state.dungeon.object(*id).unwrap().name;
+                    let name = rename_pattern(pattern, n, old);
+                    events.extend(StateCommand::ChangeObjectName(*id, name).execute(state));
+                }
+                events
+            }
         }
     }
 }
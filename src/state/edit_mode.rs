@@ -6,6 +6,12 @@ pub enum EditMode {
     AddDoor,
     AddObject,
     RemoveVertex,
+    MergeChamber,
+    SubtractChamber,
+    SplitChamber,
+    AppendCorridor,
+    PlaceTemplate,
+    PlaceImport,
 }
 
 impl EditMode {
@@ -17,6 +23,12 @@ impl EditMode {
             EditMode::AddDoor => "AddDoor".to_owned(),
             EditMode::AddObject => "AddObject".to_owned(),
             EditMode::RemoveVertex => "RemoveVertex".to_owned(),
+            EditMode::MergeChamber => "MergeChamber".to_owned(),
+            EditMode::SubtractChamber => "SubtractChamber".to_owned(),
+            EditMode::SplitChamber => "SplitChamber".to_owned(),
+            EditMode::AppendCorridor => "AppendCorridor".to_owned(),
+            EditMode::PlaceTemplate => "PlaceTemplate".to_owned(),
+            EditMode::PlaceImport => "PlaceImport".to_owned(),
         }
     }
 
@@ -28,6 +40,12 @@ impl EditMode {
             "AddDoor" => EditMode::AddDoor,
             "AddObject" => EditMode::AddObject,
             "RemoveVertex" => EditMode::RemoveVertex,
+            "MergeChamber" => EditMode::MergeChamber,
+            "SubtractChamber" => EditMode::SubtractChamber,
+            "SplitChamber" => EditMode::SplitChamber,
+            "AppendCorridor" => EditMode::AppendCorridor,
+            "PlaceTemplate" => EditMode::PlaceTemplate,
+            "PlaceImport" => EditMode::PlaceImport,
             _ => todo!(),
         }
     }
@@ -1,4 +1,4 @@
-use crate::{chamber::ChamberId, door::DoorId, object::ObjectId};
+use crate::{chamber::ChamberId, corridor::CorridorId, door::DoorId, object::ObjectId};
 use std::hash::Hash;
 
 use super::EditMode;
@@ -11,6 +11,7 @@ pub enum StateEvent {
     ActiveChamberChanged(Option<ChamberId>),
     ActiveDoorChanged(Option<DoorId>),
     ActiveObjectChanged(Option<ObjectId>),
+    ActiveCorridorChanged(Option<CorridorId>),
     EditModeChanged(EditMode),
     DoorAdded(DoorId),
     DoorModified(DoorId),
@@ -18,11 +19,50 @@ pub enum StateEvent {
     ObjectAdded(ObjectId),
     ObjectDeleted(ObjectId),
     ObjectModified(ObjectId),
+    CorridorAdded(CorridorId),
+    CorridorModified(CorridorId),
+    CorridorDeleted(CorridorId),
     DungeonModified,
+    BackgroundChanged,
+    SelectionChanged,
+    SessionChanged,
+    CompareChanged,
     Reset,
     Reload,
 }
 
+impl StateEvent {
+    /// the content of the dungeon changed, as opposed to the editor or the session
+    pub fn modifies_dungeon(&self) -> bool {
+        matches!(
+            self,
+            StateEvent::ChamberAdded(_)
+                | StateEvent::ChamberModified(_)
+                | StateEvent::ChamberDeleted(_)
+                | StateEvent::DoorAdded(_)
+                | StateEvent::DoorModified(_)
+                | StateEvent::DoorDeleted(_)
+                | StateEvent::ObjectAdded(_)
+                | StateEvent::ObjectDeleted(_)
+                | StateEvent::ObjectModified(_)
+                | StateEvent::CorridorAdded(_)
+                | StateEvent::CorridorModified(_)
+                | StateEvent::CorridorDeleted(_)
+                | StateEvent::DungeonModified
+        )
+    }
+
+    pub fn deletes_entity(&self) -> bool {
+        matches!(
+            self,
+            StateEvent::ChamberDeleted(_)
+                | StateEvent::DoorDeleted(_)
+                | StateEvent::ObjectDeleted(_)
+                | StateEvent::CorridorDeleted(_)
+        )
+    }
+}
+
 impl PartialEq for StateEvent {
     fn eq(&self, other: &Self) -> bool {
         std::mem::discriminant(self) == std::mem::discriminant(other)
@@ -1,13 +1,52 @@
+use crate::background::{BackgroundImage, BackgroundSettings};
 use crate::chamber::{ChamberId, WallId};
 use crate::common::Vec2;
+use crate::corridor::CorridorId;
 use crate::door::{Door, DoorId};
+use crate::dungeon::Dungeon;
+use crate::export_settings::{ExportSettings, Orientation, PageFormat};
 use crate::object::{ObjectId, ObjectStyle};
-use crate::state::{EditMode, StateCommand};
+use crate::print_theme::PrintTheme;
+use crate::selection::Selection;
+use crate::session::{Session, SessionNote, SESSION_FLAGS};
+use crate::state::{EditMode, State, StateCommand};
+use crate::symbol::Symbol;
+use gtk::glib;
 use serde_json::json;
 use serde_json::Value;
 use std::fs::File;
 use std::fs::{read_to_string, OpenOptions};
 use std::io::prelude::*;
+use std::rc::Rc;
+
+fn selection_to_json(selection: &Selection) -> Value {
+    json!({
+        "chambers": selection.chambers,
+        "doors": selection.doors,
+        "objects": selection.objects,
+        "corridors": selection.corridors,
+    })
+}
+
+fn json_to_selection(v: &Value) -> Selection {
+    let ids = |key: &str| -> Vec<u32> {
+        v[key]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|id| id.as_u64())
+                    .map(|id| id as u32)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    Selection {
+        chambers: ids("chambers"),
+        doors: ids("doors"),
+        objects: ids("objects"),
+        corridors: ids("corridors"),
+    }
+}
 
 fn line_to_command(l: &String) -> Option<StateCommand> {
     match l.split_once(" >> ") {
@@ -61,6 +100,13 @@ fn line_to_command(l: &String) -> Option<StateCommand> {
                     v["notes"].as_str().unwrap().to_owned(),
                 ))
             }
+            "ChangeChamberReadAloud" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeChamberReadAloud(
+                    v["chamber_id"].as_u64().unwrap() as ChamberId,
+                    v["read_aloud"].as_str().unwrap().to_owned(),
+                ))
+            }
             "ChangeChamberHidden" => {
                 let v: Value = serde_json::from_str(data).unwrap();
                 Some(StateCommand::ChangeChamberHidden(
@@ -122,6 +168,13 @@ fn line_to_command(l: &String) -> Option<StateCommand> {
                     v["notes"].as_str().unwrap().to_owned(),
                 ))
             }
+            "ChangeDoorReadAloud" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeDoorReadAloud(
+                    v["door_id"].as_u64().unwrap() as DoorId,
+                    v["read_aloud"].as_str().unwrap().to_owned(),
+                ))
+            }
             "ChangeDoorLeadsTo" => {
                 let v: Value = serde_json::from_str(data).unwrap();
                 Some(StateCommand::ChangeDoorLeadsTo(
@@ -170,6 +223,50 @@ fn line_to_command(l: &String) -> Option<StateCommand> {
                     v["notes"].as_str().unwrap().to_owned(),
                 ))
             }
+            "SetBackgroundImage" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::SetBackgroundImage(
+                    v["image"]
+                        .as_str()
+                        .and_then(|image| BackgroundImage::from_data(glib::base64_decode(image)))
+                        .map(Rc::new),
+                ))
+            }
+            "ChangeBackgroundSettings" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeBackgroundSettings(BackgroundSettings {
+                    pos: Vec2 {
+                        x: v["x"].as_f64().unwrap(),
+                        y: v["y"].as_f64().unwrap(),
+                    },
+                    scale: v["scale"].as_f64().unwrap(),
+                    rotation: v["rotation"].as_f64().unwrap(),
+                    opacity: v["opacity"].as_f64().unwrap(),
+                    in_gm_export: v["in_gm_export"].as_bool().unwrap(),
+                    in_player_export: v["in_player_export"].as_bool().unwrap(),
+                }))
+            }
+            "ChangeExportSettings" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeExportSettings(ExportSettings {
+                    format: PageFormat::from_str(v["format"].as_str().unwrap()),
+                    custom_size: Vec2 {
+                        x: v["custom_width"].as_f64().unwrap(),
+                        y: v["custom_height"].as_f64().unwrap(),
+                    },
+                    orientation: Orientation::from_str(v["orientation"].as_str().unwrap()),
+                    margin: v["margin"].as_f64().unwrap(),
+                    font_size: v["font_size"].as_f64().unwrap(),
+                    cell_size: v["cell_size"].as_f64().unwrap_or(25.4),
+                    overlap: v["overlap"].as_f64().unwrap_or(10.0),
+                    theme: PrintTheme::from_str(v["theme"].as_str().unwrap_or("")),
+                    cutout_rotation: v["cutout_rotation"].as_bool().unwrap_or(true),
+                    cutout_backs: v["cutout_backs"].as_bool().unwrap_or(false),
+                    index_appendix: v["index_appendix"].as_bool().unwrap_or(false),
+                    hidden_appendix: v["hidden_appendix"].as_bool().unwrap_or(false),
+                    doors_appendix: v["doors_appendix"].as_bool().unwrap_or(false),
+                }))
+            }
             "DeleteObject" => {
                 let v: Value = serde_json::from_str(data).unwrap();
                 Some(StateCommand::DeleteObject(
@@ -190,6 +287,13 @@ fn line_to_command(l: &String) -> Option<StateCommand> {
                     v["notes"].as_str().unwrap().to_owned(),
                 ))
             }
+            "ChangeObjectReadAloud" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeObjectReadAloud(
+                    v["object_id"].as_u64().unwrap() as ObjectId,
+                    v["read_aloud"].as_str().unwrap().to_owned(),
+                ))
+            }
             "ChangeObjectHidden" => {
                 let v: Value = serde_json::from_str(data).unwrap();
                 Some(StateCommand::ChangeObjectHidden(
@@ -199,9 +303,154 @@ fn line_to_command(l: &String) -> Option<StateCommand> {
             }
             "ChangeObjectStyle" => {
                 let v: Value = serde_json::from_str(data).unwrap();
+                let name = v["style"].as_str().unwrap();
+                // user defined symbols are embedded in the file
+                let style = match v["svg"].as_str() {
+                    Some(svg) => ObjectStyle::Symbol(Rc::new(Symbol::parse(name, svg))),
+                    None => ObjectStyle::from_str(name),
+                };
                 Some(StateCommand::ChangeObjectStyle(
                     v["object_id"].as_u64().unwrap() as ObjectId,
-                    ObjectStyle::from_str(v["style"].as_str().unwrap()),
+                    style,
+                ))
+            }
+            "ChangeObjectSize" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeObjectSize(
+                    v["object_id"].as_u64().unwrap() as ObjectId,
+                    Vec2 {
+                        x: v["width"].as_i64().unwrap() as i32,
+                        y: v["height"].as_i64().unwrap() as i32,
+                    },
+                ))
+            }
+            "ChangeObjectRotation" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeObjectRotation(
+                    v["object_id"].as_u64().unwrap() as ObjectId,
+                    v["rotation"].as_f64().unwrap(),
+                ))
+            }
+            "MergeChambers" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::MergeChambers(
+                    v["chamber_id"].as_u64().unwrap() as ChamberId,
+                    v["other_id"].as_u64().unwrap() as ChamberId,
+                ))
+            }
+            "SubtractChamber" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::SubtractChamber(
+                    v["chamber_id"].as_u64().unwrap() as ChamberId,
+                    v["other_id"].as_u64().unwrap() as ChamberId,
+                ))
+            }
+            "SplitChamber" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::SplitChamber(
+                    v["chamber_id"].as_u64().unwrap() as ChamberId,
+                    Vec2 {
+                        x: v["from_x"].as_i64().unwrap() as i32,
+                        y: v["from_y"].as_i64().unwrap() as i32,
+                    },
+                    Vec2 {
+                        x: v["to_x"].as_i64().unwrap() as i32,
+                        y: v["to_y"].as_i64().unwrap() as i32,
+                    },
+                ))
+            }
+            "AddCorridor" => Some(StateCommand::AddCorridor),
+            "SelectCorridor" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::SelectCorridor(
+                    v["corridor_id"].as_u64().map(|x| x as CorridorId),
+                ))
+            }
+            "AddVertexToCorridor" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::AddVertexToCorridor(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                    Vec2 {
+                        x: v["x"].as_i64().unwrap() as i32,
+                        y: v["y"].as_i64().unwrap() as i32,
+                    },
+                ))
+            }
+            "ChangeCorridorName" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeCorridorName(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                    v["name"].as_str().unwrap().to_owned(),
+                ))
+            }
+            "ChangeCorridorNotes" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeCorridorNotes(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                    v["notes"].as_str().unwrap().to_owned(),
+                ))
+            }
+            "ChangeCorridorHidden" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeCorridorHidden(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                    v["hidden"].as_bool().unwrap(),
+                ))
+            }
+            "ChangeCorridorNumbered" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeCorridorNumbered(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                    v["numbered"].as_bool().unwrap(),
+                ))
+            }
+            "ChangeCorridorWidth" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeCorridorWidth(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                    v["width"].as_i64().unwrap() as i32,
+                ))
+            }
+            "DeleteCorridor" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::DeleteCorridor(
+                    v["corridor_id"].as_u64().unwrap() as CorridorId,
+                ))
+            }
+            "ChangeSelection" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeSelection(json_to_selection(
+                    &v["selection"],
+                )))
+            }
+            "DeleteEntities" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::DeleteEntities(json_to_selection(
+                    &v["selection"],
+                )))
+            }
+            "ChangeEntitiesHidden" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::ChangeEntitiesHidden(
+                    json_to_selection(&v["selection"]),
+                    v["hidden"].as_bool().unwrap(),
+                ))
+            }
+            "MoveEntities" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::MoveEntities(
+                    json_to_selection(&v["selection"]),
+                    Vec2 {
+                        x: v["x"].as_i64().unwrap() as i32,
+                        y: v["y"].as_i64().unwrap() as i32,
+                    },
+                ))
+            }
+            "RenameEntities" => {
+                let v: Value = serde_json::from_str(data).unwrap();
+                Some(StateCommand::RenameEntities(
+                    json_to_selection(&v["selection"]),
+                    v["pattern"].as_str().unwrap().to_owned(),
                 ))
             }
 
@@ -233,6 +482,15 @@ pub fn load_dungeon(path: String) -> Vec<StateCommand> {
     }
 }
 
+/// the dungeon of a file, built by replaying its commands
+pub fn load_dungeon_model(path: String) -> Dungeon {
+    let mut state = State::new();
+    for cmd in load_dungeon(path) {
+        cmd.execute(&mut state);
+    }
+    state.dungeon
+}
+
 pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
     let mut file = OpenOptions::new()
         .write(true)
@@ -252,6 +510,7 @@ pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
             StateCommand::AddVertexToChamber(_, _) => "AddVertexToChamber".to_owned(),
             StateCommand::ChangeChamberName(_, _) => "ChangeChamberName".to_owned(),
             StateCommand::ChangeChamberNotes(_, _) => "ChangeChamberNotes".to_owned(),
+            StateCommand::ChangeChamberReadAloud(_, _) => "ChangeChamberReadAloud".to_owned(),
             StateCommand::ChangeChamberHidden(_, _) => "ChangeChamberHidden".to_owned(),
             StateCommand::ChangeMode(_) => "ChangeMode".to_owned(),
             StateCommand::SplitWall(_, _, _) => "SplitWall".to_owned(),
@@ -260,17 +519,46 @@ pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
             StateCommand::AddDoor(_) => "AddDoor".to_owned(),
             StateCommand::ChangeDoorName(_, _) => "ChangeDoorName".to_owned(),
             StateCommand::ChangeDoorNotes(_, _) => "ChangeDoorNotes".to_owned(),
+            StateCommand::ChangeDoorReadAloud(_, _) => "ChangeDoorReadAloud".to_owned(),
             StateCommand::ChangeDoorLeadsTo(_, _) => "ChangeDoorLeadsTo".to_owned(),
             StateCommand::ChangeDoorHidden(_, _) => "ChangeDoorHidden".to_owned(),
             StateCommand::DeleteDoor(_) => "DeleteDoor".to_owned(),
             StateCommand::ChangeDungeonName(_) => "ChangeDungeonName".to_owned(),
             StateCommand::ChangeDungeonNotes(_) => "ChangeDungeonNotes".to_owned(),
+            StateCommand::SetBackgroundImage(_) => "SetBackgroundImage".to_owned(),
+            StateCommand::ChangeBackgroundSettings(_) => "ChangeBackgroundSettings".to_owned(),
+            StateCommand::ChangeExportSettings(_) => "ChangeExportSettings".to_owned(),
             StateCommand::AddObject(_, _) => "AddObject".to_owned(),
             StateCommand::DeleteObject(_) => "DeleteObject".to_owned(),
             StateCommand::ChangeObjectName(_, _) => "ChangeObjectName".to_owned(),
             StateCommand::ChangeObjectNotes(_, _) => "ChangeObjectNotes".to_owned(),
+            StateCommand::ChangeObjectReadAloud(_, _) => "ChangeObjectReadAloud".to_owned(),
             StateCommand::ChangeObjectHidden(_, _) => "ChangeObjectHidden".to_owned(),
             StateCommand::ChangeObjectStyle(_, _) => "ChangeObjectStyle".to_owned(),
+            StateCommand::ChangeObjectSize(_, _) => "ChangeObjectSize".to_owned(),
+            StateCommand::ChangeObjectRotation(_, _) => "ChangeObjectRotation".to_owned(),
+            StateCommand::MergeChambers(_, _) => "MergeChambers".to_owned(),
+            StateCommand::SubtractChamber(_, _) => "SubtractChamber".to_owned(),
+            StateCommand::SplitChamber(_, _, _) => "SplitChamber".to_owned(),
+            StateCommand::AddCorridor => "AddCorridor".to_owned(),
+            StateCommand::SelectCorridor(_) => "SelectCorridor".to_owned(),
+            StateCommand::AddVertexToCorridor(_, _) => "AddVertexToCorridor".to_owned(),
+            StateCommand::ChangeCorridorName(_, _) => "ChangeCorridorName".to_owned(),
+            StateCommand::ChangeCorridorNotes(_, _) => "ChangeCorridorNotes".to_owned(),
+            StateCommand::ChangeCorridorHidden(_, _) => "ChangeCorridorHidden".to_owned(),
+            StateCommand::ChangeCorridorNumbered(_, _) => "ChangeCorridorNumbered".to_owned(),
+            StateCommand::ChangeCorridorWidth(_, _) => "ChangeCorridorWidth".to_owned(),
+            StateCommand::DeleteCorridor(_) => "DeleteCorridor".to_owned(),
+            StateCommand::ChangeSelection(_) => "ChangeSelection".to_owned(),
+            StateCommand::DeleteEntities(_) => "DeleteEntities".to_owned(),
+            StateCommand::ChangeEntitiesHidden(_, _) => "ChangeEntitiesHidden".to_owned(),
+            StateCommand::MoveEntities(_, _) => "MoveEntities".to_owned(),
+            StateCommand::RenameEntities(_, _) => "RenameEntities".to_owned(),
+            // stored in the session file, see `save_session`
+            StateCommand::ChangeSessionFlag(_, _, _)
+            | StateCommand::ChangeEntitiesRevealed(_, _)
+            | StateCommand::AddSessionNote(_, _)
+            | StateCommand::DeleteSessionNote(_) => continue,
         };
         let data = match cmd {
             StateCommand::AddChamber => serde_json::Value::Null,
@@ -290,6 +578,10 @@ pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
                 "chamber_id": chamber_id,
                 "notes": notes,
             }),
+            StateCommand::ChangeChamberReadAloud(chamber_id, text) => json!({
+                "chamber_id": chamber_id,
+                "read_aloud": text,
+            }),
             StateCommand::ChangeChamberHidden(chamber_id, hidden) => json!({
                 "chamber_id": chamber_id,
                 "hidden": hidden,
@@ -322,6 +614,10 @@ pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
                 "door_id": door_id,
                 "notes": notes,
             }),
+            StateCommand::ChangeDoorReadAloud(door_id, text) => json!({
+                "door_id": door_id,
+                "read_aloud": text,
+            }),
             StateCommand::ChangeDoorLeadsTo(door_id, chamber_id) => json!({
                 "door_id": door_id,
                 "chamber_id": chamber_id,
@@ -337,6 +633,34 @@ pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
             StateCommand::ChangeDungeonNotes(notes) => json!({
                 "notes": notes,
             }),
+            StateCommand::SetBackgroundImage(image) => json!({
+                "image": image.as_ref().map(|image| glib::base64_encode(&image.data).to_string()),
+            }),
+            StateCommand::ChangeBackgroundSettings(settings) => json!({
+                "x": settings.pos.x,
+                "y": settings.pos.y,
+                "scale": settings.scale,
+                "rotation": settings.rotation,
+                "opacity": settings.opacity,
+                "in_gm_export": settings.in_gm_export,
+                "in_player_export": settings.in_player_export,
+            }),
+            StateCommand::ChangeExportSettings(settings) => json!({
+                "format": settings.format.to_str(),
+                "custom_width": settings.custom_size.x,
+                "custom_height": settings.custom_size.y,
+                "orientation": settings.orientation.to_str(),
+                "margin": settings.margin,
+                "font_size": settings.font_size,
+                "cell_size": settings.cell_size,
+                "overlap": settings.overlap,
+                "theme": settings.theme.to_str(),
+                "cutout_rotation": settings.cutout_rotation,
+                "cutout_backs": settings.cutout_backs,
+                "index_appendix": settings.index_appendix,
+                "hidden_appendix": settings.hidden_appendix,
+                "doors_appendix": settings.doors_appendix,
+            }),
             StateCommand::AddObject(pos, part_of) => json!({
                 "x": pos.x,
                 "y": pos.y,
@@ -351,17 +675,227 @@ pub fn save_to_file(save_file: String, cmds: &Vec<StateCommand>) {
                 "object_id": object_id,
                 "notes": notes,
             }),
+            StateCommand::ChangeObjectReadAloud(object_id, text) => json!({
+                "object_id": object_id,
+                "read_aloud": text,
+            }),
             StateCommand::ChangeObjectHidden(object_id, hidden) => json!({
                 "object_id": object_id,
                 "hidden": hidden,
             }),
-            StateCommand::ChangeObjectStyle(object_id, style) => json!({
+            StateCommand::ChangeObjectStyle(object_id, style) => match style {
+                ObjectStyle::Symbol(symbol) if !symbol.builtin => json!({
+                    "object_id": object_id,
+                    "style": style.to_str(),
+                    "svg": symbol.svg,
+                }),
+                _ => json!({
+                    "object_id": object_id,
+                    "style": style.to_str(),
+                }),
+            },
+            StateCommand::ChangeObjectSize(object_id, size) => json!({
+                "object_id": object_id,
+                "width": size.x,
+                "height": size.y,
+            }),
+            StateCommand::ChangeObjectRotation(object_id, rotation) => json!({
                 "object_id": object_id,
-                "style": style.to_str(),
+                "rotation": rotation,
+            }),
+            StateCommand::MergeChambers(chamber_id, other_id) => json!({
+                "chamber_id": chamber_id,
+                "other_id": other_id,
+            }),
+            StateCommand::SubtractChamber(chamber_id, other_id) => json!({
+                "chamber_id": chamber_id,
+                "other_id": other_id,
+            }),
+            StateCommand::SplitChamber(chamber_id, from, to) => json!({
+                "chamber_id": chamber_id,
+                "from_x": from.x,
+                "from_y": from.y,
+                "to_x": to.x,
+                "to_y": to.y,
+            }),
+            StateCommand::AddCorridor => serde_json::Value::Null,
+            StateCommand::SelectCorridor(corridor_id) => json!({ "corridor_id": corridor_id }),
+            StateCommand::AddVertexToCorridor(corridor_id, pos) => json!({
+                "corridor_id": corridor_id,
+                "x": pos.x,
+                "y": pos.y
+            }),
+            StateCommand::ChangeCorridorName(corridor_id, name) => json!({
+                "corridor_id": corridor_id,
+                "name": name,
+            }),
+            StateCommand::ChangeCorridorNotes(corridor_id, notes) => json!({
+                "corridor_id": corridor_id,
+                "notes": notes,
             }),
+            StateCommand::ChangeCorridorHidden(corridor_id, hidden) => json!({
+                "corridor_id": corridor_id,
+                "hidden": hidden,
+            }),
+            StateCommand::ChangeCorridorNumbered(corridor_id, numbered) => json!({
+                "corridor_id": corridor_id,
+                "numbered": numbered,
+            }),
+            StateCommand::ChangeCorridorWidth(corridor_id, width) => json!({
+                "corridor_id": corridor_id,
+                "width": width,
+            }),
+            StateCommand::DeleteCorridor(corridor_id) => json!({ "corridor_id": corridor_id }),
+            StateCommand::ChangeSelection(selection) => json!({
+                "selection": selection_to_json(selection),
+            }),
+            StateCommand::DeleteEntities(selection) => json!({
+                "selection": selection_to_json(selection),
+            }),
+            StateCommand::ChangeEntitiesHidden(selection, hidden) => json!({
+                "selection": selection_to_json(selection),
+                "hidden": hidden,
+            }),
+            StateCommand::MoveEntities(selection, by) => json!({
+                "selection": selection_to_json(selection),
+                "x": by.x,
+                "y": by.y,
+            }),
+            StateCommand::RenameEntities(selection, pattern) => json!({
+                "selection": selection_to_json(selection),
+                "pattern": pattern,
+            }),
+            StateCommand::ChangeSessionFlag(_, _, _)
+            | StateCommand::ChangeEntitiesRevealed(_, _)
+            | StateCommand::AddSessionNote(_, _)
+            | StateCommand::DeleteSessionNote(_) => continue,
         };
         data_str += format!("{} >> {}\n", name, data).as_str();
     }
     file.write(data_str.as_bytes()).unwrap();
     file.flush().unwrap();
 }
+
+/// path of the session file belonging to a dungeon file
+pub fn session_path(dungeon_path: &str) -> String {
+    match dungeon_path.strip_suffix(".dungeon") {
+        Some(base) => format!("{base}.session"),
+        None => format!("{dungeon_path}.session"),
+    }
+}
+
+fn session_to_json(session: &Session) -> Value {
+    let mut flags = serde_json::Map::new();
+    for flag in SESSION_FLAGS {
+        flags.insert(flag.to_str().to_owned(), json!(session.ids(flag)));
+    }
+    json!({
+        "flags": flags,
+        "revealed": selection_to_json(&session.revealed),
+        "notes": session
+            .notes
+            .iter()
+            .map(|n| json!({ "time": n.time, "text": n.text }))
+            .collect::<Vec<Value>>(),
+    })
+}
+
+fn json_to_session(v: &Value) -> Session {
+    let mut session = Session::new();
+    for flag in SESSION_FLAGS {
+        *session.ids_mut(flag) = v["flags"][flag.to_str()]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|id| id.as_u64())
+                    .map(|id| id as u32)
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    session.revealed = json_to_selection(&v["revealed"]);
+    if let Some(notes) = v["notes"].as_array() {
+        session.notes = notes
+            .iter()
+            .map(|n| SessionNote {
+                time: n["time"].as_i64().unwrap_or(0),
+                text: n["text"].as_str().unwrap_or("").to_owned(),
+            })
+            .collect();
+    }
+    session
+}
+
+/// loads a session file, a missing file is an empty session
+pub fn load_session(path: String) -> Session {
+    match read_to_string(path) {
+        Ok(data) => match serde_json::from_str(&data) {
+            Ok(v) => json_to_session(&v),
+            Err(e) => {
+                println!("Unable to read session: {}", e);
+                Session::new()
+            }
+        },
+        Err(_) => Session::new(),
+    }
+}
+
+/// saves the session next to the dungeon, nothing is written for a dungeon never played
+pub fn save_session(path: String, session: &Session) -> std::io::Result<()> {
+    // no file for dungeons never played
+    if *session == Session::new() && File::open(path.clone()).is_err() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.write_all(session_to_json(session).to_string().as_bytes())?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::selection::Selection;
+    use crate::session::{Session, SessionNote};
+
+    use super::{json_to_session, save_session, session_path, session_to_json};
+
+    #[test]
+    fn session_next_to_dungeon_file() {
+        assert_eq!(session_path("/tmp/crypt.dungeon"), "/tmp/crypt.session");
+        assert_eq!(session_path("/tmp/crypt"), "/tmp/crypt.session");
+    }
+
+    #[test]
+    fn session_json_roundtrip() {
+        let session = Session {
+            explored: vec![1, 2],
+            broken: vec![4],
+            looted: vec![7],
+            revealed: Selection {
+                chambers: vec![1],
+                doors: vec![4],
+                ..Selection::new()
+            },
+            notes: vec![SessionNote {
+                time: 1700000000,
+                text: "Goblins fled north".to_owned(),
+            }],
+            ..Session::new()
+        };
+        assert_eq!(json_to_session(&session_to_json(&session)), session);
+    }
+
+    #[test]
+    fn save_session_to_unwritable_path() {
+        let session = Session {
+            explored: vec![1],
+            ..Session::new()
+        };
+        let path = "/nonexistent/crypt.session";
+        assert!(save_session(path.to_owned(), &session).is_err());
+        assert!(!std::path::Path::new(path).exists());
+    }
+}
@@ -0,0 +1,553 @@
+use std::{fs, path::PathBuf, rc::Rc};
+
+use crate::common::Vec2;
+
+/// number of line segments used to approximate curves and circles
+const CURVE_SEGMENTS: usize = 16;
+
+/// Symbols shipped with the application, name and svg source
+const BUILTIN_SYMBOLS: [(&str, &str); 10] = [
+    ("Chest", include_str!("../assets/symbols/chest.svg")),
+    ("Trap", include_str!("../assets/symbols/trap.svg")),
+    ("Altar", include_str!("../assets/symbols/altar.svg")),
+    ("Statue", include_str!("../assets/symbols/statue.svg")),
+    ("Well", include_str!("../assets/symbols/well.svg")),
+    ("Pillar", include_str!("../assets/symbols/pillar.svg")),
+    ("Bed", include_str!("../assets/symbols/bed.svg")),
+    ("Table", include_str!("../assets/symbols/table.svg")),
+    ("Ladder", include_str!("../assets/symbols/ladder.svg")),
+    ("Throne", include_str!("../assets/symbols/throne.svg")),
+];
+
+/// A single stroke of a symbol in unit coordinates (0.0 to 1.0)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolPath {
+    pub points: Vec<Vec2<f64>>,
+    pub closed: bool,
+}
+
+/// Object symbol created from a svg file.
+/// Only the outlines of basic shapes and paths are used, fills and transforms are ignored.
+#[derive(Debug)]
+pub struct Symbol {
+    pub name: String,
+    /// svg source, stored in saved files for user defined symbols
+    pub svg: String,
+    pub builtin: bool,
+    pub paths: Vec<SymbolPath>,
+}
+
+impl Symbol {
+    pub fn parse(name: &str, svg: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            svg: svg.to_owned(),
+            builtin: false,
+            paths: normalize(parse_svg(svg), view_box(svg)),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Rc<Self>> {
+        BUILTIN_SYMBOLS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(n, svg)| {
+                Rc::new(Self {
+                    builtin: true,
+                    ..Self::parse(n, svg)
+                })
+            })
+    }
+}
+
+/// All symbols available for objects, built-in symbols first
+pub struct SymbolLibrary {
+    pub symbols: Vec<Rc<Symbol>>,
+}
+
+impl SymbolLibrary {
+    /// loads the built-in symbols and all svg files in the user symbol directory
+    pub fn load() -> Self {
+        let mut symbols: Vec<Rc<Symbol>> = BUILTIN_SYMBOLS
+            .iter()
+            .filter_map(|(name, _)| Symbol::builtin(name))
+            .collect();
+
+        let mut files: Vec<PathBuf> = match fs::read_dir(Self::user_dir()) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "svg"))
+                .collect(),
+            Err(_) => vec![],
+        };
+        files.sort();
+        for file in files {
+            let name = match file.file_stem() {
+                Some(stem) => stem.to_string_lossy().to_string(),
+                None => continue,
+            };
+            match fs::read_to_string(&file) {
+                Ok(svg) => symbols.push(Rc::new(Symbol::parse(&name, &svg))),
+                Err(e) => println!("Unable to read symbol {}: {}", file.display(), e),
+            }
+        }
+        Self { symbols }
+    }
+
+    /// directory for user defined symbols, `<config dir>/dungeon_planner/symbols`
+    pub fn user_dir() -> PathBuf {
+        gtk::glib::user_config_dir()
+            .join("dungeon_planner")
+            .join("symbols")
+    }
+}
+
+/// scales the paths from the view box into unit coordinates, keeping the aspect ratio
+fn normalize(paths: Vec<SymbolPath>, view_box: Option<(Vec2<f64>, Vec2<f64>)>) -> Vec<SymbolPath> {
+    let (min, size) = match view_box {
+        Some(vb) => vb,
+        None => {
+            let points = paths.iter().flat_map(|p| p.points.iter());
+            let mut min = Vec2 {
+                x: f64::MAX,
+                y: f64::MAX,
+            };
+            let mut max = Vec2 {
+                x: f64::MIN,
+                y: f64::MIN,
+            };
+            for p in points {
+                min = Vec2 {
+                    x: min.x.min(p.x),
+                    y: min.y.min(p.y),
+                };
+                max = Vec2 {
+                    x: max.x.max(p.x),
+                    y: max.y.max(p.y),
+                };
+            }
+            if min.x > max.x {
+                return vec![];
+            }
+            (min, max - min)
+        }
+    };
+    let scale = size.x.max(size.y);
+    if scale <= 0.0 {
+        return vec![];
+    }
+    // center the shorter side
+    let offset = Vec2 {
+        x: (scale - size.x) / 2.0,
+        y: (scale - size.y) / 2.0,
+    };
+    paths
+        .into_iter()
+        .map(|p| SymbolPath {
+            points: p
+                .points
+                .into_iter()
+                .map(|pt| (1.0 / scale) * (pt - min + offset))
+                .collect(),
+            closed: p.closed,
+        })
+        .collect()
+}
+
+/// min and size of the `viewBox` or `width`/`height` of the svg element
+fn view_box(svg: &str) -> Option<(Vec2<f64>, Vec2<f64>)> {
+    let (_, attrs) = elements(svg).into_iter().find(|(tag, _)| tag == "svg")?;
+    if let Some(vb) = attr(&attrs, "viewBox") {
+        let n = numbers(vb);
+        if n.len() == 4 {
+            return Some((Vec2 { x: n[0], y: n[1] }, Vec2 { x: n[2], y: n[3] }));
+        }
+    }
+    let w = numbers(attr(&attrs, "width")?);
+    let h = numbers(attr(&attrs, "height")?);
+    Some((
+        Vec2 { x: 0.0, y: 0.0 },
+        Vec2 {
+            x: *w.first()?,
+            y: *h.first()?,
+        },
+    ))
+}
+
+/// outlines of all supported elements in svg coordinates
+fn parse_svg(svg: &str) -> Vec<SymbolPath> {
+    let mut paths = vec![];
+    for (tag, attrs) in elements(svg) {
+        let num = |name: &str| {
+            attr(&attrs, name)
+                .and_then(|v| numbers(v).first().copied())
+                .unwrap_or(0.0)
+        };
+        match tag.as_str() {
+            "path" => paths.extend(parse_path(attr(&attrs, "d").unwrap_or(""))),
+            "line" => paths.push(SymbolPath {
+                points: vec![
+                    Vec2 {
+                        x: num("x1"),
+                        y: num("y1"),
+                    },
+                    Vec2 {
+                        x: num("x2"),
+                        y: num("y2"),
+                    },
+                ],
+                closed: false,
+            }),
+            "rect" => {
+                let (x, y, w, h) = (num("x"), num("y"), num("width"), num("height"));
+                paths.push(SymbolPath {
+                    points: vec![
+                        Vec2 { x, y },
+                        Vec2 { x: x + w, y },
+                        Vec2 { x: x + w, y: y + h },
+                        Vec2 { x, y: y + h },
+                    ],
+                    closed: true,
+                })
+            }
+            "circle" | "ellipse" => {
+                let center = Vec2 {
+                    x: num("cx"),
+                    y: num("cy"),
+                };
+                let (rx, ry) = match tag.as_str() {
+                    "circle" => (num("r"), num("r")),
+                    _ => (num("rx"), num("ry")),
+                };
+                paths.push(SymbolPath {
+                    points: (0..CURVE_SEGMENTS * 2)
+                        .map(|i| {
+                            let a = i as f64 / (CURVE_SEGMENTS * 2) as f64 * std::f64::consts::TAU;
+                            center
+                                + Vec2 {
+                                    x: rx * a.cos(),
+                                    y: ry * a.sin(),
+                                }
+                        })
+                        .collect(),
+                    closed: true,
+                })
+            }
+            "polyline" | "polygon" => {
+                let n = numbers(attr(&attrs, "points").unwrap_or(""));
+                paths.push(SymbolPath {
+                    points: n
+                        .chunks_exact(2)
+                        .map(|c| Vec2 { x: c[0], y: c[1] })
+                        .collect(),
+                    closed: tag == "polygon",
+                })
+            }
+            _ => (),
+        }
+    }
+    paths.retain(|p| p.points.len() >= 2);
+    paths
+}
+
+/// start tags with their attribute text, e.g. `("circle", "cx=\"5\" r=\"2\"")`
+fn elements(svg: &str) -> Vec<(String, String)> {
+    svg.split('<')
+        .skip(1)
+        .filter(|part| !part.starts_with(['/', '!', '?']))
+        .filter_map(|part| {
+            let part = &part[..part.find('>')?];
+            let part = part.trim_end_matches('/');
+            let end = part.find(|c: char| c.is_whitespace()).unwrap_or(part.len());
+            Some((part[..end].to_owned(), part[end..].to_owned()))
+        })
+        .collect()
+}
+
+/// value of a single attribute, `None` for values not enclosed in quotes
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+    loop {
+        let eq = rest.find('=')?;
+        let key = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &after[1..];
+        let value_end = value.find(quote)?;
+        if key == name {
+            return Some(&value[..value_end]);
+        }
+        rest = &value[value_end + 1..];
+    }
+}
+
+/// all numbers in a list separated by commas or whitespace, or directly by signs
+fn numbers(s: &str) -> Vec<f64> {
+    let mut tokens = PathTokens::new(s);
+    let mut result = vec![];
+    while let Some(n) = tokens.number() {
+        result.push(n);
+    }
+    result
+}
+
+struct PathTokens<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> PathTokens<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            chars: s.chars().peekable(),
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .chars
+            .peek()
+            .is_some_and(|c| c.is_whitespace() || *c == ',')
+        {
+            self.chars.next();
+        }
+    }
+
+    fn command(&mut self) -> Option<char> {
+        self.skip_separators();
+        match self.chars.peek() {
+            Some(c) if c.is_ascii_alphabetic() && *c != 'e' && *c != 'E' => self.chars.next(),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        self.skip_separators();
+        let mut s = String::new();
+        if let Some(c) = self.chars.peek().copied() {
+            if c == '-' || c == '+' {
+                s.push(c);
+                self.chars.next();
+            }
+        }
+        let mut dot = false;
+        while let Some(c) = self.chars.peek().copied() {
+            match c {
+                '0'..='9' => s.push(c),
+                '.' if !dot => {
+                    dot = true;
+                    s.push(c)
+                }
+                'e' | 'E' => {
+                    s.push(c);
+                    self.chars.next();
+                    if let Some(sign) = self
+                        .chars
+                        .peek()
+                        .copied()
+                        .filter(|c| *c == '-' || *c == '+')
+                    {
+                        s.push(sign);
+                        self.chars.next();
+                    }
+                    continue;
+                }
+                _ => break,
+            }
+            self.chars.next();
+        }
+        s.parse().ok()
+    }
+
+    fn point(&mut self) -> Option<Vec2<f64>> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Some(Vec2 { x, y })
+    }
+}
+
+/// outlines of a path `d` attribute, curves are approximated by lines
+fn parse_path(d: &str) -> Vec<SymbolPath> {
+    let mut tokens = PathTokens::new(d);
+    let mut paths = vec![];
+    let mut current: Vec<Vec2<f64>> = vec![];
+    let mut pos = Vec2 { x: 0.0, y: 0.0 };
+    let mut start = pos;
+    let mut cmd = 'M';
+
+    let finish = |current: &mut Vec<Vec2<f64>>, paths: &mut Vec<SymbolPath>, closed: bool| {
+        if current.len() >= 2 {
+            paths.push(SymbolPath {
+                points: std::mem::take(current),
+                closed,
+            });
+        }
+        current.clear();
+    };
+
+    loop {
+        if let Some(c) = tokens.command() {
+            cmd = c;
+        }
+        let relative = cmd.is_ascii_lowercase();
+        let base = if relative {
+            pos
+        } else {
+            Vec2 { x: 0.0, y: 0.0 }
+        };
+        match cmd.to_ascii_uppercase() {
+            'Z' => {
+                finish(&mut current, &mut paths, true);
+                pos = start;
+                current.push(pos);
+                // a new command is required after closing
+                match tokens.command() {
+                    Some(c) => {
+                        cmd = c;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            'M' => {
+                let p = match tokens.point() {
+                    Some(p) => base + p,
+                    None => break,
+                };
+                finish(&mut current, &mut paths, false);
+                pos = p;
+                start = p;
+                current.push(p);
+                // following coordinate pairs are line commands
+                cmd = if relative { 'l' } else { 'L' };
+            }
+            'L' => match tokens.point() {
+                Some(p) => {
+                    pos = base + p;
+                    current.push(pos);
+                }
+                None => break,
+            },
+            'H' => match tokens.number() {
+                Some(x) => {
+                    pos.x = base.x + x;
+                    current.push(pos);
+                }
+                None => break,
+            },
+            'V' => match tokens.number() {
+                Some(y) => {
+                    pos.y = base.y + y;
+                    current.push(pos);
+                }
+                None => break,
+            },
+            'Q' => {
+                let (c, p) = match (tokens.point(), tokens.point()) {
+                    (Some(c), Some(p)) => (base + c, base + p),
+                    _ => break,
+                };
+                for i in 1..=CURVE_SEGMENTS {
+                    let t = i as f64 / CURVE_SEGMENTS as f64;
+                    current.push(
+                        ((1.0 - t) * (1.0 - t)) * pos + (2.0 * (1.0 - t) * t) * c + (t * t) * p,
+                    );
+                }
+                pos = p;
+            }
+            'C' => {
+                let (c1, c2, p) = match (tokens.point(), tokens.point(), tokens.point()) {
+                    (Some(c1), Some(c2), Some(p)) => (base + c1, base + c2, base + p),
+                    _ => break,
+                };
+                for i in 1..=CURVE_SEGMENTS {
+                    let t = i as f64 / CURVE_SEGMENTS as f64;
+                    let u = 1.0 - t;
+                    current.push(
+                        (u * u * u) * pos
+                            + (3.0 * u * u * t) * c1
+                            + (3.0 * u * t * t) * c2
+                            + (t * t * t) * p,
+                    );
+                }
+                pos = p;
+            }
+            'A' => {
+                // arcs are replaced by a straight line to their end point
+                let n: Vec<f64> = (0..7).filter_map(|_| tokens.number()).collect();
+                if n.len() != 7 {
+                    break;
+                }
+                pos = base + Vec2 { x: n[5], y: n[6] };
+                current.push(pos);
+            }
+            _ => {
+                println!("Unsupported svg path command {}", cmd);
+                break;
+            }
+        }
+        if current.is_empty() {
+            current.push(pos);
+        }
+    }
+    finish(&mut current, &mut paths, false);
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::Vec2;
+
+    use super::{attr, parse_path, Symbol, BUILTIN_SYMBOLS};
+
+    #[test]
+    fn parse_relative_path() {
+        let paths = parse_path("m10,10 h20 v20 h-20 z M0 0 L5-5");
+        assert_eq!(paths.len(), 2);
+        assert!(paths[0].closed);
+        assert_eq!(
+            paths[0].points,
+            vec![
+                Vec2 { x: 10.0, y: 10.0 },
+                Vec2 { x: 30.0, y: 10.0 },
+                Vec2 { x: 30.0, y: 30.0 },
+                Vec2 { x: 10.0, y: 30.0 },
+            ]
+        );
+        assert!(!paths[1].closed);
+        assert_eq!(paths[1].points[1], Vec2 { x: 5.0, y: -5.0 });
+    }
+
+    #[test]
+    fn attribute_values() {
+        assert_eq!(attr(r#"id="a" d='M0 0'"#, "d"), Some("M0 0"));
+        assert_eq!(attr(r#"id="ä" d="M0 0""#, "d"), Some("M0 0"));
+        // malformed values without quotes
+        assert_eq!(attr("d=äM0 0ä", "d"), None);
+        assert_eq!(attr("d=", "d"), None);
+    }
+
+    #[test]
+    fn normalize_to_view_box() {
+        let symbol = Symbol::parse(
+            "box",
+            r#"<svg viewBox="0 0 200 100"><rect x="0" y="0" width="200" height="100"/></svg>"#,
+        );
+        assert_eq!(symbol.paths.len(), 1);
+        assert_eq!(symbol.paths[0].points[0], Vec2 { x: 0.0, y: 0.25 });
+        assert_eq!(symbol.paths[0].points[2], Vec2 { x: 1.0, y: 0.75 });
+    }
+
+    #[test]
+    fn builtin_symbols_have_outlines() {
+        for (name, _) in BUILTIN_SYMBOLS {
+            let symbol = Symbol::builtin(name).unwrap();
+            assert!(!symbol.paths.is_empty(), "{} has no outline", name);
+            assert!(symbol
+                .paths
+                .iter()
+                .flat_map(|p| p.points.iter())
+                .all(|p| p.x >= 0.0 && p.x <= 1.0 && p.y >= 0.0 && p.y <= 1.0));
+        }
+    }
+}
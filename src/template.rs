@@ -0,0 +1,532 @@
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde_json::{json, Value};
+
+use crate::{
+    chamber::ChamberId,
+    common::Vec2,
+    config::GRID_SIZE,
+    door::Door,
+    dungeon::Dungeon,
+    emitter::Emitter,
+    object::{Object, ObjectStyle},
+    state::StateCommand,
+    symbol::Symbol,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateDoor {
+    pub pos: Vec2<f64>,
+    pub width: f64,
+    pub name: String,
+    pub notes: String,
+    pub read_aloud: String,
+    pub hidden: bool,
+}
+
+#[derive(Clone)]
+pub struct TemplateObject {
+    /// top left corner of the footprint, before rotation
+    pub pos: Vec2<i32>,
+    pub size: Vec2<i32>,
+    pub rotation: f64,
+    pub style: ObjectStyle,
+    pub name: String,
+    pub notes: String,
+    pub read_aloud: String,
+    pub hidden: bool,
+}
+
+/// A chamber with its doors and objects, saved to be placed in other dungeons.
+/// Coordinates are relative to the top left corner of the chamber, aligned to the grid.
+#[derive(Clone)]
+pub struct Template {
+    pub name: String,
+    pub chamber_name: String,
+    pub notes: String,
+    pub read_aloud: String,
+    pub hidden: bool,
+    pub outer: Vec<Vec2<i32>>,
+    pub holes: Vec<Vec<Vec2<i32>>>,
+    pub doors: Vec<TemplateDoor>,
+    pub objects: Vec<TemplateObject>,
+}
+
+impl Template {
+    pub fn from_chamber(dungeon: &Dungeon, chamber_id: ChamberId, name: &str) -> Option<Self> {
+        let chamber = dungeon.chamber(chamber_id)?;
+        let shape = chamber.shape();
+        if shape.outer.is_empty() {
+            return None;
+        }
+        let min = Vec2 {
+            x: shape.outer.iter().map(|v| v.x).min().unwrap(),
+            y: shape.outer.iter().map(|v| v.y).min().unwrap(),
+        };
+        let origin = Vec2 {
+            x: min.x.div_euclid(GRID_SIZE) * GRID_SIZE,
+            y: min.y.div_euclid(GRID_SIZE) * GRID_SIZE,
+        };
+        let origin_f: Vec2<f64> = origin.into();
+
+        Some(Self {
+            name: name.to_owned(),
+            chamber_name: chamber.name.clone(),
+            notes: chamber.notes.clone(),
+            read_aloud: chamber.read_aloud.clone(),
+            hidden: chamber.hidden,
+            outer: shape.outer.iter().map(|v| *v - origin).collect(),
+            holes: shape
+                .holes
+                .iter()
+                .map(|h| h.iter().map(|v| *v - origin).collect())
+                .collect(),
+            doors: dungeon
+                .chamber_doors(chamber_id)
+                .iter()
+                .map(|d| TemplateDoor {
+                    pos: dungeon.door_world_pos(d) - origin_f,
+                    width: d.width,
+                    name: d.name.clone(),
+                    notes: d.notes.clone(),
+                    read_aloud: d.read_aloud.clone(),
+                    hidden: d.hidden,
+                })
+                .collect(),
+            objects: dungeon
+                .chamber_objects(chamber_id)
+                .iter()
+                .map(|o| TemplateObject {
+                    pos: o.pos - origin,
+                    size: o.size,
+                    rotation: o.rotation,
+                    style: o.style.clone(),
+                    name: o.name.clone(),
+                    notes: o.notes.clone(),
+                    read_aloud: o.read_aloud.clone(),
+                    hidden: o.hidden,
+                })
+                .collect(),
+        })
+    }
+
+    /// the template turned clockwise by quarter turns, moved back to the origin
+    pub fn rotated(&self, quarter_turns: u32) -> Self {
+        let mut t = self.clone();
+        for _ in 0..quarter_turns % 4 {
+            t = t.rotated_once();
+        }
+        t
+    }
+
+    fn rotated_once(&self) -> Self {
+        // (x, y) -> (-y, x) turns clockwise on screen, the height is added to stay positive
+        let height = self.outer.iter().map(|v| v.y).max().unwrap_or(0);
+        let turn = |v: Vec2<i32>| Vec2 {
+            x: height - v.y,
+            y: v.x,
+        };
+        let turn_f = |v: Vec2<f64>| Vec2 {
+            x: height as f64 - v.y,
+            y: v.x,
+        };
+        Self {
+            outer: self.outer.iter().map(|v| turn(*v)).collect(),
+            holes: self
+                .holes
+                .iter()
+                .map(|h| h.iter().map(|v| turn(*v)).collect())
+                .collect(),
+            doors: self
+                .doors
+                .iter()
+                .map(|d| TemplateDoor {
+                    pos: turn_f(d.pos),
+                    ..d.clone()
+                })
+                .collect(),
+            objects: self
+                .objects
+                .iter()
+                .map(|o| {
+                    // objects rotate around the center of their footprint
+                    let half = Vec2 {
+                        x: o.size.x * GRID_SIZE / 2,
+                        y: o.size.y * GRID_SIZE / 2,
+                    };
+                    TemplateObject {
+                        pos: turn(o.pos + half) - half,
+                        rotation: (o.rotation + 90.0) % 360.0,
+                        ..o.clone()
+                    }
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// size of the bounding box
+    pub fn size(&self) -> Vec2<i32> {
+        Vec2 {
+            x: self.outer.iter().map(|v| v.x).max().unwrap_or(0),
+            y: self.outer.iter().map(|v| v.y).max().unwrap_or(0),
+        }
+    }
+
+    /// Commands placing the template with its top left corner at `pos`.
+    /// The chamber, doors and objects are created like drawn ones.
+    pub fn stamp(&self, dungeon: &Dungeon, pos: Vec2<i32>) -> Vec<StateCommand> {
+        let pos_f: Vec2<f64> = pos.into();
+        let mut emitter = Emitter::new(dungeon);
+        let outer: Vec<Vec2<i32>> = self.outer.iter().map(|v| *v + pos).collect();
+        let holes: Vec<Vec<Vec2<i32>>> = self
+            .holes
+            .iter()
+            .map(|h| h.iter().map(|v| *v + pos).collect())
+            .collect();
+        let chamber_id = emitter.add_chamber(&outer, &holes);
+        emitter.apply(StateCommand::ChangeChamberName(
+            chamber_id,
+            self.chamber_name.clone(),
+        ));
+        if !self.notes.is_empty() {
+            emitter.apply(StateCommand::ChangeChamberNotes(
+                chamber_id,
+                self.notes.clone(),
+            ));
+        }
+        if !self.read_aloud.is_empty() {
+            emitter.apply(StateCommand::ChangeChamberReadAloud(
+                chamber_id,
+                self.read_aloud.clone(),
+            ));
+        }
+        if self.hidden {
+            emitter.apply(StateCommand::ChangeChamberHidden(chamber_id, true));
+        }
+
+        for d in self.doors.iter() {
+            let door_pos = d.pos + pos_f;
+            if let Some(wall) = emitter.wall_at(chamber_id, door_pos) {
+                let mut door = Door::new(
+                    chamber_id,
+                    None,
+                    d.width,
+                    wall.id,
+                    wall.nearest_relative_pos(door_pos),
+                );
+                door.name = d.name.clone();
+                door.notes = d.notes.clone();
+                door.read_aloud = d.read_aloud.clone();
+                door.hidden = d.hidden;
+                emitter.add_door(door);
+            }
+        }
+
+        for o in self.objects.iter() {
+            let mut object = Object::new(o.pos + pos, Some(chamber_id));
+            object.style = o.style.clone();
+            object.size = o.size;
+            object.rotation = o.rotation;
+            object.name = o.name.clone();
+            object.notes = o.notes.clone();
+            object.read_aloud = o.read_aloud.clone();
+            object.hidden = o.hidden;
+            emitter.add_object(&object);
+        }
+
+        emitter.finish()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let points = |ps: &Vec<Vec2<i32>>| -> Vec<Value> {
+            ps.iter().map(|p| json!({"x": p.x, "y": p.y})).collect()
+        };
+        json!({
+            "name": self.name,
+            "chamber_name": self.chamber_name,
+            "notes": self.notes,
+            "read_aloud": self.read_aloud,
+            "hidden": self.hidden,
+            "outer": points(&self.outer),
+            "holes": self.holes.iter().map(points).collect::<Vec<_>>(),
+            "doors": self.doors.iter().map(|d| json!({
+                "x": d.pos.x,
+                "y": d.pos.y,
+                "width": d.width,
+                "name": d.name,
+                "notes": d.notes,
+                "read_aloud": d.read_aloud,
+                "hidden": d.hidden,
+            })).collect::<Vec<_>>(),
+            "objects": self.objects.iter().map(|o| {
+                let mut v = json!({
+                    "x": o.pos.x,
+                    "y": o.pos.y,
+                    "width": o.size.x,
+                    "height": o.size.y,
+                    "rotation": o.rotation,
+                    "style": o.style.to_str(),
+                    "name": o.name,
+                    "notes": o.notes,
+                    "read_aloud": o.read_aloud,
+                    "hidden": o.hidden,
+                });
+                // user symbols are embedded, the template may be used where the symbol is missing
+                if let ObjectStyle::Symbol(symbol) = &o.style {
+                    if !symbol.builtin {
+                        v["svg"] = json!(symbol.svg);
+                    }
+                }
+                v
+            }).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn from_json(v: &Value) -> Option<Self> {
+        let point = |p: &Value| -> Option<Vec2<i32>> {
+            Some(Vec2 {
+                x: p["x"].as_i64()? as i32,
+                y: p["y"].as_i64()? as i32,
+            })
+        };
+        let points =
+            |ps: &Value| -> Option<Vec<Vec2<i32>>> { ps.as_array()?.iter().map(point).collect() };
+        let text = |v: &Value, key: &str| v[key].as_str().unwrap_or("").to_owned();
+        let outer = points(&v["outer"])?;
+        if outer.len() < 3 {
+            return None;
+        }
+        Some(Self {
+            name: text(v, "name"),
+            chamber_name: text(v, "chamber_name"),
+            notes: text(v, "notes"),
+            read_aloud: text(v, "read_aloud"),
+            hidden: v["hidden"].as_bool().unwrap_or(false),
+            outer,
+            holes: v["holes"]
+                .as_array()
+                .map(|hs| hs.iter().filter_map(points).collect())
+                .unwrap_or_default(),
+            doors: v["doors"]
+                .as_array()
+                .map(|ds| {
+                    ds.iter()
+                        .filter_map(|d| {
+                            Some(TemplateDoor {
+                                pos: Vec2 {
+                                    x: d["x"].as_f64()?,
+                                    y: d["y"].as_f64()?,
+                                },
+                                width: d["width"].as_f64().unwrap_or(GRID_SIZE as f64),
+                                name: text(d, "name"),
+                                notes: text(d, "notes"),
+                                read_aloud: text(d, "read_aloud"),
+                                hidden: d["hidden"].as_bool().unwrap_or(false),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            objects: v["objects"]
+                .as_array()
+                .map(|os| {
+                    os.iter()
+                        .filter_map(|o| {
+                            let style_name = o["style"].as_str().unwrap_or("Blocker");
+                            Some(TemplateObject {
+                                pos: point(o)?,
+                                size: Vec2 {
+                                    x: o["width"].as_i64().unwrap_or(1) as i32,
+                                    y: o["height"].as_i64().unwrap_or(1) as i32,
+                                },
+                                rotation: o["rotation"].as_f64().unwrap_or(0.0),
+                                style: match o["svg"].as_str() {
+                                    Some(svg) => {
+                                        ObjectStyle::Symbol(Rc::new(Symbol::parse(style_name, svg)))
+                                    }
+                                    None => ObjectStyle::from_str(style_name),
+                                },
+                                name: text(o, "name"),
+                                notes: text(o, "notes"),
+                                read_aloud: text(o, "read_aloud"),
+                                hidden: o["hidden"].as_bool().unwrap_or(false),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// All templates saved in the user template directory, sorted by name
+pub struct TemplateLibrary {
+    pub templates: Vec<Template>,
+}
+
+impl TemplateLibrary {
+    pub fn load() -> Self {
+        let mut files: Vec<PathBuf> = match fs::read_dir(Self::user_dir()) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(_) => vec![],
+        };
+        files.sort();
+        let mut templates = vec![];
+        for file in files {
+            let template = fs::read_to_string(&file)
+                .map_err(|e| e.to_string())
+                .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+                .map(|v: Value| Template::from_json(&v));
+            match template {
+                Ok(Some(template)) => templates.push(template),
+                Ok(None) => println!("Invalid template {}", file.display()),
+                Err(e) => println!("Unable to read template {}: {}", file.display(), e),
+            }
+        }
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { templates }
+    }
+
+    /// Saves the template, replacing a template stored in the same file.
+    /// Names only differing in characters not allowed in file names, e.g. "a/b" and "a_b", share a file.
+    pub fn save(&mut self, template: Template) {
+        let dir = Self::user_dir();
+        let file = file_name(&template.name);
+        let result = fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(dir.join(&file), template.to_json().to_string()));
+        if let Err(e) = result {
+            println!("Unable to save template {}: {}", template.name, e);
+            return;
+        }
+        self.templates.retain(|t| file_name(&t.name) != file);
+        self.templates.push(template);
+        self.templates.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn delete(&mut self, name: &str) {
+        let file = file_name(name);
+        if let Err(e) = fs::remove_file(Self::user_dir().join(&file)) {
+            println!("Unable to delete template {}: {}", name, e);
+        }
+        self.templates.retain(|t| file_name(&t.name) != file);
+    }
+
+    /// directory for templates, `<config dir>/dungeon_planner/templates`
+    pub fn user_dir() -> PathBuf {
+        gtk::glib::user_config_dir()
+            .join("dungeon_planner")
+            .join("templates")
+    }
+}
+
+/// file name of a template, characters not allowed in file names are replaced
+fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.json", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::Vec2;
+    use crate::door::Door;
+    use crate::dungeon::{tests::add_rect, Dungeon};
+    use crate::state::{State, StateCommand};
+    use crate::storage::{load_dungeon, save_to_file};
+
+    use super::{file_name, Template};
+
+    /// 3x2 cells chamber with a door in the top wall and an object in the top left cell
+    fn shrine() -> State {
+        let mut state = State::new();
+        add_rect(&mut state.dungeon, 100, 50, 250, 150);
+        StateCommand::ChangeChamberNotes(1, "Altar".to_owned()).execute(&mut state);
+        StateCommand::ChangeChamberReadAloud(1, "Candles flicker.".to_owned()).execute(&mut state);
+        let wall = state.dungeon.chamber(1).unwrap().walls()[0];
+        StateCommand::AddDoor(Door::new(1, None, 50.0, wall.id, 0.5)).execute(&mut state);
+        StateCommand::ChangeDoorName(1, "Portcullis".to_owned()).execute(&mut state);
+        StateCommand::ChangeDoorNotes(1, "Rusty".to_owned()).execute(&mut state);
+        StateCommand::ChangeDoorHidden(1, true).execute(&mut state);
+        StateCommand::ChangeDoorReadAloud(1, "Rusty bars.".to_owned()).execute(&mut state);
+        StateCommand::AddObject(Vec2 { x: 100, y: 50 }, Some(1)).execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn template_from_chamber() {
+        let state = shrine();
+        let t = Template::from_chamber(&state.dungeon, 1, "Shrine").unwrap();
+        assert_eq!(t.outer[0], Vec2 { x: 0, y: 0 });
+        assert_eq!(t.size(), Vec2 { x: 150, y: 100 });
+        assert_eq!(t.doors[0].pos, Vec2 { x: 75.0, y: 0.0 });
+        assert_eq!(t.objects[0].pos, Vec2 { x: 0, y: 0 });
+        assert_eq!(t.notes, "Altar");
+
+        let json = t.to_json();
+        let loaded = Template::from_json(&json).unwrap();
+        assert_eq!(loaded.outer, t.outer);
+        assert_eq!(loaded.read_aloud, "Candles flicker.");
+        assert_eq!(loaded.doors, t.doors);
+        assert_eq!(loaded.objects[0].pos, t.objects[0].pos);
+    }
+
+    #[test]
+    fn rotated_template() {
+        let state = shrine();
+        let t = Template::from_chamber(&state.dungeon, 1, "Shrine").unwrap();
+        let r = t.rotated(1);
+        assert_eq!(r.size(), Vec2 { x: 100, y: 150 });
+        // the door in the top wall is now in the right wall
+        assert_eq!(r.doors[0].pos, Vec2 { x: 100.0, y: 75.0 });
+        assert_eq!(r.objects[0].pos, Vec2 { x: 50, y: 0 });
+        assert_eq!(r.objects[0].rotation, 90.0);
+        assert_eq!(t.rotated(4).outer, t.outer);
+    }
+
+    #[test]
+    fn stamp_template() {
+        let state = shrine();
+        let t = Template::from_chamber(&state.dungeon, 1, "Shrine").unwrap();
+        let cmds = t.stamp(&Dungeon::new(), Vec2 { x: 500, y: 500 });
+        // the stamped dungeon survives saving and loading
+        let path = "/tmp/test_stamp_template.dungeon".to_owned();
+        save_to_file(path.clone(), &cmds);
+        let mut target = State::new();
+        for cmd in load_dungeon(path) {
+            cmd.execute(&mut target);
+        }
+        let chamber = &target.dungeon.chambers[0];
+        assert_eq!(chamber.notes, "Altar");
+        assert_eq!(chamber.read_aloud, "Candles flicker.");
+        assert_eq!(chamber.bbox().min, Vec2 { x: 500.0, y: 500.0 });
+        assert_eq!(target.dungeon.doors.len(), 1);
+        assert_eq!(target.dungeon.doors[0].name, "Portcullis");
+        assert_eq!(target.dungeon.doors[0].notes, "Rusty");
+        assert!(target.dungeon.doors[0].hidden);
+        assert_eq!(target.dungeon.doors[0].read_aloud, "Rusty bars.");
+        assert_eq!(
+            target.dungeon.door_world_pos(&target.dungeon.doors[0]),
+            Vec2 { x: 575.0, y: 500.0 }
+        );
+        assert_eq!(target.dungeon.objects[0].pos, Vec2 { x: 500, y: 500 });
+        assert_eq!(target.dungeon.objects[0].part_of, Some(chamber.id));
+    }
+
+    #[test]
+    fn template_file_name() {
+        assert_eq!(file_name("Guard Post"), "Guard Post.json");
+        assert_eq!(file_name("a/b"), "a_b.json");
+    }
+}
@@ -4,16 +4,24 @@ pub mod chamber_edit;
 pub mod chamber_list;
 pub mod chamber_list_entry;
 pub mod chamber_list_object;
+pub mod corridor_edit;
+pub mod corridor_list;
+pub mod corridor_list_entry;
 pub mod door_edit;
 pub mod door_list;
 pub mod door_list_entry;
 pub mod dungeon_edit;
 pub mod entity_tabs;
 pub mod grid;
+pub mod notes_preview;
 pub mod object_edit;
 pub mod object_list;
 pub mod object_list_entry;
+pub mod player_view;
 pub mod primitives;
+pub mod print_preview;
+pub mod session_edit;
+pub mod template_panel;
 
 use crate::common::Vec2;
 
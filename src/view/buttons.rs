@@ -17,6 +17,9 @@ use gtk::{Button, ToggleButton};
 pub struct AddChamberButton {
     pub widget: Button,
 }
+pub struct AddCorridorButton {
+    pub widget: Button,
+}
 pub struct EditModeButton {
     pub widget: ToggleButton,
     mode: EditMode,
@@ -45,6 +48,29 @@ impl AddChamberButton {
     }
 }
 
+impl AddCorridorButton {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Self {
+        let button = Button::new();
+        let bytes = include_bytes!("../../assets/icons/add_corridor.png");
+        let bytes = glib::Bytes::from(&bytes.to_vec());
+        let stream = MemoryInputStream::from_bytes(&bytes);
+        let pixbuf = Pixbuf::from_stream(&stream, Cancellable::NONE).unwrap();
+        let texture = Texture::for_pixbuf(&pixbuf);
+        let image = Image::from_paintable(Some(&texture));
+        button.set_child(Some(&image));
+        button.set_tooltip_text(Some("Create new Corridor"));
+        button.set_has_tooltip(true);
+        button.set_size_request(64, 64);
+
+        button.connect_clicked(move |_button| {
+            let control = &mut *control.borrow_mut();
+            control.apply(StateCommand::AddCorridor);
+        });
+
+        AddCorridorButton { widget: button }
+    }
+}
+
 impl EditModeButton {
     pub fn new(
         control: Rc<RefCell<StateController>>,
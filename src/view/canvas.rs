@@ -1,11 +1,17 @@
-use crate::chamber::{ChamberDrawOptions, NextVert, WallId};
-use crate::common::{Rgb, Vec2};
+use crate::chamber::{ChamberDrawOptions, ChamberId, NextVert, WallId};
+use crate::common::{BBox, Rgb, Vec2};
 use crate::config::{
-    BACKGROUND_COLOR, GRID_SIZE, PRIMARY_ACTIVE_COLOR, SECONDARY_ACTIVE_COLOR,
-    TERTIARY_ACTIVE_COLOR,
+    BACKGROUND_COLOR, DIFF_ADDED_COLOR, DIFF_CHANGED_COLOR, DIFF_REMOVED_COLOR, GRID_SIZE,
+    PRIMARY_ACTIVE_COLOR, SECONDARY_ACTIVE_COLOR, SESSION_BROKEN_COLOR, SESSION_CLEARED_COLOR,
+    SESSION_EXPLORED_COLOR, SESSION_LOOTED_COLOR, SESSION_OPENED_COLOR, SESSION_UNEXPLORED_COLOR,
+    TERTIARY_ACTIVE_COLOR, WALL_WIDTH,
 };
+use crate::corridor::CorridorDrawOptions;
+use crate::diff::{ChangeKind, EntityKind};
 use crate::door::{Door, DoorDrawOptions};
+use crate::dungeon::{Snap, SnapTarget};
 use crate::object::ObjectDrawOptions;
+use crate::session::SessionFlag;
 use crate::state::events::StateEvent;
 use crate::state::{EditMode, State, StateCommand, StateController, StateEventSubscriber};
 use cairo::glib::{clone, Propagation};
@@ -16,12 +22,41 @@ use gtk::{DrawingArea, EventControllerMotion};
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use super::primitives::{Line, Point, Primitive};
+use super::primitives::{Circle, Line, Point, Polygon, Primitive};
+
+/// minimal size of a selection rectangle, smaller drags are treated as clicks
+const MIN_SELECT_DRAG: f64 = 5.0;
+
+/// Dragging with the primary button in select mode.
+/// Starting on a selected entity moves the selection, otherwise a selection rectangle is drawn.
+struct SelectDrag {
+    start: Vec2<f64>,
+    offset: Vec2<f64>,
+    /// None until the first update
+    moves: Option<bool>,
+    /// move without snapping to the grid, while control is held
+    free: bool,
+}
+
+impl SelectDrag {
+    /// offset of the moved selection
+    fn move_by(&self, state: &State) -> Vec2<i32> {
+        match self.free {
+            true => Vec2 {
+                x: self.offset.x.round() as i32,
+                y: self.offset.y.round() as i32,
+            },
+            false => state.grid.snap(self.offset.into()),
+        }
+    }
+}
 
 pub struct Canvas {
     pub widget: DrawingArea,
     selected_wall: Option<WallId>,
     last_pos: Option<Vec2<i32>>,
+    split_start: Option<Vec2<i32>>,
+    select_drag: Option<SelectDrag>,
 }
 
 impl Canvas {
@@ -42,6 +77,8 @@ impl Canvas {
             widget: drawing_area.clone(),
             selected_wall: None,
             last_pos: None,
+            split_start: None,
+            select_drag: None,
         }));
 
         drawing_area.set_draw_func(
@@ -60,11 +97,17 @@ impl Canvas {
             .build();
 
         gesture_click.connect_pressed(
-            clone!( @strong canvas, @weak control, @weak drawing_area => move |_, _, _, _| {
+            clone!( @strong canvas, @weak control, @weak drawing_area => move |gesture, _, _, _| {
                 drawing_area.grab_focus();
-                let cmds = canvas.borrow_mut().click(control.clone());
-                for cmd in cmds {
-                    control.borrow_mut().apply(cmd)
+                let shift = gesture
+                    .current_event_state()
+                    .contains(gdk::ModifierType::SHIFT_MASK);
+                let cmds = canvas.borrow_mut().click(control.clone(), shift);
+                // the edits of one click are undone in one step, e.g. a stamped template
+                // or a wall split in all chambers sharing it
+                match cmds.len() {
+                    1 => control.borrow_mut().apply(cmds.into_iter().next().unwrap()),
+                    _ => control.borrow_mut().apply_group(cmds),
                 }
             }),
         );
@@ -78,10 +121,40 @@ impl Canvas {
             canvas.borrow_mut().drag_update(control, x, y);
         }));
 
+        let select_drag = GestureDrag::builder()
+            .button(GDK_BUTTON_PRIMARY as u32)
+            .build();
+        select_drag.connect_drag_begin(clone!(@strong canvas, @weak control => move |_, x, y| {
+            canvas.borrow_mut().select_drag_begin(&control.borrow().state, x, y);
+        }));
+        select_drag.connect_drag_update(
+            clone!(@strong canvas, @weak control => move |gesture, x, y| {
+                let free = gesture
+                    .current_event_state()
+                    .contains(gdk::ModifierType::CONTROL_MASK);
+                canvas.borrow_mut().select_drag_update(&control.borrow().state, x, y, free);
+            }),
+        );
+        select_drag.connect_drag_end(
+            clone!(@strong canvas, @weak control => move |gesture, _, _| {
+                let shift = gesture
+                    .current_event_state()
+                    .contains(gdk::ModifierType::SHIFT_MASK);
+                let cmds = canvas.borrow_mut().select_drag_end(&control.borrow().state, shift);
+                for cmd in cmds {
+                    control.borrow_mut().apply(cmd)
+                }
+            }),
+        );
+
         let key_controller = EventControllerKey::new();
         key_controller.connect_key_pressed(clone!(@strong control => move |_, key, _, _| {
             let mut control = control.borrow_mut();
             match key {
+                gdk::Key::Delete if !control.state.selection.is_empty() => {
+                    let selection = control.state.selection.clone();
+                    control.apply(StateCommand::DeleteEntities(selection))
+                }
                 gdk::Key::Delete => {
                     if let Some(door_id) = control.state.active_door_id {
                         control
@@ -95,6 +168,10 @@ impl Canvas {
                         control
                             .apply(StateCommand::DeleteObject(object_id))
                     }
+                    if let Some(corridor_id) = control.state.active_corridor_id {
+                        control
+                            .apply(StateCommand::DeleteCorridor(corridor_id))
+                    }
                 },
                 _ => (),
             }
@@ -102,6 +179,7 @@ impl Canvas {
         }));
         drawing_area.add_controller(key_controller);
         drawing_area.add_controller(gesture_drag);
+        drawing_area.add_controller(select_drag);
         drawing_area.add_controller(gesture_click);
         drawing_area.add_controller(pos_controller);
 
@@ -161,6 +239,55 @@ impl Canvas {
         }
     }
 
+    /// chamber under the cursor, ignoring the active chamber
+    fn other_chamber_at(&self, state: &State, pos: Vec2<f64>) -> Option<ChamberId> {
+        state
+            .dungeon
+            .chambers()
+            .iter()
+            .find(|c| Some(c.id) != state.active_chamber_id && c.contains_point(pos))
+            .map(|c| c.id)
+    }
+
+    pub fn highlight_other_chamber(&self, state: &State, pos: Vec2<f64>, ctx: &cairo::Context) {
+        if state.active_chamber_id.is_none() {
+            return;
+        }
+        if let Some(chamber_id) = self.other_chamber_at(state, pos) {
+            for wall in state.dungeon.chamber(chamber_id).unwrap().walls() {
+                Line {
+                    from: wall.p1.into(),
+                    to: wall.p2.into(),
+                    color: Rgb {
+                        r: 1.0,
+                        g: 0.0,
+                        b: 0.0,
+                    },
+                    width: 3.0,
+                    dashed: false,
+                }
+                .draw(ctx)
+            }
+        }
+    }
+
+    /// marks corners and walls the cursor snaps to
+    fn draw_snap(&self, snap: Snap, ctx: &cairo::Context) {
+        let dashed = match snap.target {
+            SnapTarget::Grid => return,
+            SnapTarget::Vertex => false,
+            SnapTarget::Wall => true,
+        };
+        Circle {
+            at: snap.pos.into(),
+            width: 2.0,
+            radius: 10.0,
+            color: PRIMARY_ACTIVE_COLOR,
+            dashed,
+        }
+        .draw(ctx)
+    }
+
     fn draw(&self, ctx: &Context, w: i32, h: i32, control: Rc<RefCell<StateController>>) {
         let control = control.borrow();
         // fill with background color
@@ -171,6 +298,11 @@ impl Canvas {
         let world_min = control.state.view.world_min();
         ctx.translate(-world_min.x as f64, -world_min.y as f64);
 
+        // draw background image below the grid
+        if let Some(background) = &control.dungeon().background {
+            background.draw(ctx);
+        }
+
         // draw grid
         let prims = control
             .state
@@ -182,7 +314,14 @@ impl Canvas {
 
         // draw chambers
         let cp = control.state.cursor_world_pos();
-        let next_vert = control.state.grid.snap(cp.into());
+        let snap = control.state.snap(cp);
+        let next_vert = snap.pos;
+        let corridor_outlines = control.dungeon().corridor_outlines(true, None);
+        let selected = control.state.selected();
+        let session = match control.state.session_view {
+            true => Some(&control.state.session),
+            false => None,
+        };
 
         for chamber in control.dungeon().chambers.iter() {
             let active = control.state.active_chamber_id == Some(chamber.id);
@@ -205,18 +344,20 @@ impl Canvas {
             };
             let prims = chamber.draw(
                 vert_opt,
-                match active {
+                match active || selected.chambers.contains(&chamber.id) {
                     false => {
                         if let Some(door) = control.state.active_door() {
                             if door.part_of == chamber.id {
                                 Some(ChamberDrawOptions {
                                     color: Some(SECONDARY_ACTIVE_COLOR),
                                     fill: None,
+                                    wall_style: None,
                                 })
                             } else if door.leads_to == Some(chamber.id) {
                                 Some(ChamberDrawOptions {
                                     color: Some(TERTIARY_ACTIVE_COLOR),
                                     fill: None,
+                                    wall_style: None,
                                 })
                             } else {
                                 None
@@ -226,19 +367,54 @@ impl Canvas {
                                 Some(ChamberDrawOptions {
                                     color: Some(SECONDARY_ACTIVE_COLOR),
                                     fill: None,
+                                    wall_style: None,
                                 })
                             } else {
                                 None
                             }
                         } else {
-                            None
+                            // progress of the session
+                            session.map(|session| ChamberDrawOptions {
+                                color: Some(if session.is_set(SessionFlag::Cleared, chamber.id) {
+                                    SESSION_CLEARED_COLOR
+                                } else if session.is_set(SessionFlag::Explored, chamber.id) {
+                                    SESSION_EXPLORED_COLOR
+                                } else {
+                                    SESSION_UNEXPLORED_COLOR
+                                }),
+                                fill: None,
+                                wall_style: None,
+                            })
                         }
                     }
                     true => Some(ChamberDrawOptions {
                         color: Some(PRIMARY_ACTIVE_COLOR),
                         fill: None,
+                        wall_style: None,
                     }),
                 },
+                &corridor_outlines,
+            );
+            for prim in prims {
+                prim.draw(ctx)
+            }
+        }
+
+        // draw corridors
+        let chamber_shapes = control.dungeon().chamber_shapes(true);
+        for corridor in control.dungeon().corridors.iter() {
+            let prims = corridor.draw(
+                &chamber_shapes,
+                &control.dungeon().corridor_outlines(true, Some(corridor.id)),
+                CorridorDrawOptions {
+                    color: match selected.corridors.contains(&corridor.id) {
+                        true => Some(PRIMARY_ACTIVE_COLOR),
+                        false => None,
+                    },
+                    fill: None,
+                    show_number: true,
+                    wall_style: None,
+                },
             );
             for prim in prims {
                 prim.draw(ctx)
@@ -247,11 +423,19 @@ impl Canvas {
 
         // draw doors
         for door in control.dungeon().doors.iter() {
-            let options = match control.state.active_door_id == Some(door.id) {
+            let options = match selected.doors.contains(&door.id) {
                 true => DoorDrawOptions {
                     color: Some(PRIMARY_ACTIVE_COLOR),
                 },
-                false => DoorDrawOptions::empty(),
+                false => match session {
+                    Some(s) if s.is_set(SessionFlag::Broken, door.id) => DoorDrawOptions {
+                        color: Some(SESSION_BROKEN_COLOR),
+                    },
+                    Some(s) if s.is_set(SessionFlag::Opened, door.id) => DoorDrawOptions {
+                        color: Some(SESSION_OPENED_COLOR),
+                    },
+                    _ => DoorDrawOptions::empty(),
+                },
             };
             let prims = door.draw(
                 control
@@ -270,8 +454,10 @@ impl Canvas {
         // draw objects
         for obj in control.dungeon().objects.iter() {
             let mut options = ObjectDrawOptions::empty();
-            if control.state.active_object_id == Some(obj.id) {
+            if selected.objects.contains(&obj.id) {
                 options.color = Some(PRIMARY_ACTIVE_COLOR)
+            } else if session.is_some_and(|s| s.is_set(SessionFlag::Looted, obj.id)) {
+                options.color = Some(SESSION_LOOTED_COLOR)
             }
             let prims = obj.draw(options);
 
@@ -280,16 +466,19 @@ impl Canvas {
             }
         }
 
+        self.draw_changes(&control.state, ctx);
+
         /*
          * Mode Specific Drawing
          */
         match control.state.mode {
-            EditMode::Select => {}
-            EditMode::AppendChamber => {}
+            EditMode::Select => self.draw_select_drag(&control.state, ctx),
+            EditMode::AppendChamber => self.draw_snap(snap, ctx),
             EditMode::SplitEdge => {
                 // Highlight nearest wall
-                if self.selected_wall() == None {
-                    self.highlighted_nearest_wall(&control.state, cp, ctx);
+                match self.selected_wall() {
+                    None => self.highlighted_nearest_wall(&control.state, cp, ctx),
+                    Some(_) => self.draw_snap(snap, ctx),
                 }
             }
             EditMode::AddDoor => {
@@ -351,6 +540,214 @@ impl Canvas {
                 }
             }
             EditMode::AddObject => {}
+            EditMode::MergeChamber => self.highlight_other_chamber(&control.state, cp, ctx),
+            EditMode::SubtractChamber => self.highlight_other_chamber(&control.state, cp, ctx),
+            EditMode::AppendCorridor => {
+                if let Some(corridor) = control.state.active_corridor() {
+                    let next = corridor.snap(cp);
+                    if let Some(last) = corridor.points().last() {
+                        Line {
+                            dashed: true,
+                            from: (*last).into(),
+                            to: next.into(),
+                            color: PRIMARY_ACTIVE_COLOR,
+                            width: WALL_WIDTH,
+                        }
+                        .draw(ctx);
+                    }
+                    Point {
+                        at: next.into(),
+                        color: PRIMARY_ACTIVE_COLOR,
+                    }
+                    .draw(ctx)
+                }
+            }
+            EditMode::PlaceTemplate => self.draw_template(&control.state, ctx),
+            EditMode::PlaceImport => self.draw_import(&control.state, ctx),
+            EditMode::SplitChamber => {
+                if control.state.active_chamber().is_some() {
+                    self.draw_snap(snap, ctx);
+                    let color = Rgb {
+                        r: 1.0,
+                        g: 0.0,
+                        b: 0.0,
+                    };
+                    if let Some(start) = self.split_start {
+                        Line {
+                            dashed: true,
+                            from: start.into(),
+                            to: next_vert.into(),
+                            color,
+                            width: 3.0,
+                        }
+                        .draw(ctx);
+                        Point {
+                            at: start.into(),
+                            color,
+                        }
+                        .draw(ctx);
+                    }
+                    Point {
+                        at: next_vert.into(),
+                        color,
+                    }
+                    .draw(ctx)
+                }
+            }
+        }
+    }
+
+    /// top left corner of a placed template or import, centered at the cursor and aligned to the grid
+    fn placement_pos(&self, state: &State, size: Vec2<i32>) -> Vec2<i32> {
+        let size: Vec2<f64> = size.into();
+        let half_cell = GRID_SIZE as f64 / 2.0;
+        let pos = state.cursor_world_pos() - 0.5 * size
+            + Vec2 {
+                x: half_cell,
+                y: half_cell,
+            };
+        state.grid.cell(pos.into())
+    }
+
+    fn draw_template(&self, state: &State, ctx: &cairo::Context) {
+        if let Some(template) = &state.template {
+            let pos: Vec2<f64> = self.placement_pos(state, template.size()).into();
+            let ring = |r: &Vec<Vec2<i32>>| -> Vec<Vec2<f64>> {
+                r.iter()
+                    .map(|v| Into::<Vec2<f64>>::into(*v) + pos)
+                    .collect()
+            };
+            Polygon {
+                points: ring(&template.outer),
+                holes: template.holes.iter().map(ring).collect(),
+                fill_color: PRIMARY_ACTIVE_COLOR,
+                fill_opacity: 0.2,
+                stroke_color: PRIMARY_ACTIVE_COLOR,
+                stroke_width: WALL_WIDTH,
+                dashed: true,
+            }
+            .draw(ctx);
+        }
+    }
+
+    fn click_place_template(&mut self, control: &mut StateController) -> Vec<StateCommand> {
+        match &control.state.template {
+            Some(template) => {
+                let pos = self.placement_pos(&control.state, template.size());
+                template.stamp(&control.state.dungeon, pos)
+            }
+            None => vec![],
+        }
+    }
+
+    /// highlights changes to the compared version of the dungeon, removed entities are dashed
+    fn draw_changes(&self, state: &State, ctx: &cairo::Context) {
+        let old = match &state.compare {
+            Some(old) => old,
+            None => return,
+        };
+        let ring =
+            |r: &Vec<Vec2<i32>>| -> Vec<Vec2<f64>> { r.iter().map(|v| (*v).into()).collect() };
+        let mut drawn: Vec<(EntityKind, u32)> = vec![];
+        for change in state.changes.iter() {
+            // an entity may have multiple changes, it is highlighted once
+            if drawn.contains(&(change.entity, change.id)) {
+                continue;
+            }
+            drawn.push((change.entity, change.id));
+            let (dungeon, color) = match change.kind {
+                ChangeKind::Added => (&state.dungeon, DIFF_ADDED_COLOR),
+                ChangeKind::Removed => (old, DIFF_REMOVED_COLOR),
+                _ => (&state.dungeon, DIFF_CHANGED_COLOR),
+            };
+            let polygon = |points: Vec<Vec2<f64>>, holes: Vec<Vec<Vec2<f64>>>| Polygon {
+                points,
+                holes,
+                fill_color: color,
+                fill_opacity: 0.3,
+                stroke_color: color,
+                stroke_width: WALL_WIDTH,
+                dashed: change.kind == ChangeKind::Removed,
+            };
+            let polygons: Vec<Polygon> = match change.entity {
+                EntityKind::Dungeon => vec![],
+                EntityKind::Chamber => dungeon
+                    .chamber(change.id)
+                    .map(|c| c.shape())
+                    .map(|s| polygon(ring(&s.outer), s.holes.iter().map(ring).collect()))
+                    .into_iter()
+                    .collect(),
+                EntityKind::Corridor => dungeon
+                    .corridor(change.id)
+                    .map(|c| c.outline().clone())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|s| polygon(ring(&s.outer), s.holes.iter().map(ring).collect()))
+                    .collect(),
+                EntityKind::Door => dungeon
+                    .door(change.id)
+                    .map(|d| {
+                        let center = dungeon.door_world_pos(d);
+                        let half = d.width / 2.0;
+                        let corner = |x: f64, y: f64| Vec2 {
+                            x: center.x + x * half,
+                            y: center.y + y * half,
+                        };
+                        polygon(
+                            vec![
+                                corner(-1.0, -1.0),
+                                corner(1.0, -1.0),
+                                corner(1.0, 1.0),
+                                corner(-1.0, 1.0),
+                            ],
+                            vec![],
+                        )
+                    })
+                    .into_iter()
+                    .collect(),
+                EntityKind::Object => dungeon
+                    .object(change.id)
+                    .map(|o| polygon(o.footprint(), vec![]))
+                    .into_iter()
+                    .collect(),
+            };
+            for p in polygons {
+                p.draw(ctx);
+            }
+        }
+    }
+
+    fn draw_import(&self, state: &State, ctx: &cairo::Context) {
+        if let Some(import) = &state.import {
+            let pos: Vec2<f64> = self.placement_pos(state, import.size()).into();
+            let ring = |r: &Vec<Vec2<i32>>| -> Vec<Vec2<f64>> {
+                r.iter()
+                    .map(|v| Into::<Vec2<f64>>::into(*v) + pos)
+                    .collect()
+            };
+            for outline in import.outlines() {
+                Polygon {
+                    points: ring(&outline.outer),
+                    holes: outline.holes.iter().map(ring).collect(),
+                    fill_color: PRIMARY_ACTIVE_COLOR,
+                    fill_opacity: 0.2,
+                    stroke_color: PRIMARY_ACTIVE_COLOR,
+                    stroke_width: WALL_WIDTH,
+                    dashed: true,
+                }
+                .draw(ctx);
+            }
+        }
+    }
+
+    /// places the import once, further clicks select again
+    fn click_place_import(&mut self, control: &mut StateController) -> Vec<StateCommand> {
+        match control.state.import.take() {
+            Some(import) => {
+                let pos = self.placement_pos(&control.state, import.size());
+                import.place(&control.state.dungeon, pos)
+            }
+            None => vec![],
         }
     }
 
@@ -360,27 +757,31 @@ impl Canvas {
         self.update();
     }
 
-    fn click_select(&mut self, control: &mut StateController) -> Vec<StateCommand> {
-        let object_id = control
+    fn click_select(&mut self, control: &mut StateController, shift: bool) -> Vec<StateCommand> {
+        let hit = control
             .state
             .dungeon
-            .object_at(control.state.cursor_world_pos());
-        if let Some(id) = object_id {
-            vec![StateCommand::SelectObject(Some(id))]
-        } else {
-            let door_id = control
-                .state
-                .dungeon
-                .door_at(control.state.cursor_world_pos());
-            if let Some(id) = door_id {
-                vec![StateCommand::SelectDoor(Some(id))]
-            } else {
-                let chamber_id = control
-                    .state
-                    .dungeon
-                    .chamber_at(control.state.cursor_world_pos());
-                vec![StateCommand::SelectChamber(chamber_id)]
+            .entity_at(control.state.cursor_world_pos());
+        if shift {
+            if hit.is_empty() {
+                return vec![];
             }
+            let mut selection = control.state.selected();
+            selection.toggle(&hit);
+            return vec![StateCommand::ChangeSelection(selection)];
+        }
+        // keep the selection when clicking on a selected entity, so it can be moved
+        if control.state.selection.intersects(&hit) {
+            return vec![];
+        }
+        if let Some(id) = hit.objects.first() {
+            vec![StateCommand::SelectObject(Some(*id))]
+        } else if let Some(id) = hit.doors.first() {
+            vec![StateCommand::SelectDoor(Some(*id))]
+        } else if let Some(id) = hit.corridors.first() {
+            vec![StateCommand::SelectCorridor(Some(*id))]
+        } else {
+            vec![StateCommand::SelectChamber(hit.chambers.first().copied())]
         }
     }
 
@@ -390,16 +791,23 @@ impl Canvas {
                 let chamber_id = chamber.id;
                 return vec![StateCommand::AddVertexToChamber(
                     chamber_id,
-                    control
-                        .state
-                        .grid
-                        .snap((control.state.cursor_world_pos()).into()),
+                    control.state.snap(control.state.cursor_world_pos()).pos,
                 )];
             }
         }
         vec![]
     }
 
+    fn click_append_corridor(&mut self, control: &mut StateController) -> Vec<StateCommand> {
+        if let Some(corridor) = control.state.active_corridor() {
+            return vec![StateCommand::AddVertexToCorridor(
+                corridor.id,
+                corridor.snap(control.state.cursor_world_pos()),
+            )];
+        }
+        vec![]
+    }
+
     fn click_split_edge(&mut self, control: &mut StateController) -> Vec<StateCommand> {
         let selected_wall = self.selected_wall();
         match selected_wall {
@@ -408,14 +816,18 @@ impl Canvas {
                     if let Some(chamber) = control.state.dungeon.chamber_mut(active_chamber_id) {
                         let chamber_id = chamber.id;
                         self.set_selected_wall(None);
-                        return vec![StateCommand::SplitWall(
-                            chamber_id,
-                            wall_id,
-                            control
+                        let pos = control.state.snap(control.state.cursor_world_pos()).pos;
+                        let mut cmds = vec![StateCommand::SplitWall(chamber_id, wall_id, pos)];
+                        if control.state.link_shared_walls {
+                            for (other_id, other_wall_id) in control
                                 .state
-                                .grid
-                                .snap(control.state.cursor_world_pos().into()),
-                        )];
+                                .dungeon
+                                .linked_splits(chamber_id, wall_id, pos)
+                            {
+                                cmds.push(StateCommand::SplitWall(other_id, other_wall_id, pos));
+                            }
+                        }
+                        return cmds;
                     }
                 }
             }
@@ -452,7 +864,17 @@ impl Canvas {
     fn click_remove_vertex(&mut self, control: &mut StateController) -> Vec<StateCommand> {
         if let Some(chamber) = control.state.active_chamber() {
             match chamber.nearest_corner(control.state.cursor_world_pos()) {
-                Some((w1, _)) => return vec![StateCommand::CollapseWall(chamber.id, w1.id)],
+                Some((w1, _)) => {
+                    let mut cmds = vec![StateCommand::CollapseWall(chamber.id, w1.id)];
+                    if control.state.link_shared_walls {
+                        for (other_id, other_wall_id) in
+                            control.state.dungeon.linked_corners(chamber.id, w1.p2)
+                        {
+                            cmds.push(StateCommand::CollapseWall(other_id, other_wall_id));
+                        }
+                    }
+                    return cmds;
+                }
                 None => {}
             };
         }
@@ -474,20 +896,183 @@ impl Canvas {
         )];
     }
 
-    fn click(&mut self, control: Rc<RefCell<StateController>>) -> Vec<StateCommand> {
+    fn click_merge_chamber(&mut self, control: &mut StateController) -> Vec<StateCommand> {
+        if let Some(chamber_id) = control.state.active_chamber_id {
+            let pos = control.state.cursor_world_pos();
+            if let Some(other_id) = self.other_chamber_at(&control.state, pos) {
+                return vec![StateCommand::MergeChambers(chamber_id, other_id)];
+            }
+        }
+        vec![]
+    }
+
+    fn click_subtract_chamber(&mut self, control: &mut StateController) -> Vec<StateCommand> {
+        if let Some(chamber_id) = control.state.active_chamber_id {
+            let pos = control.state.cursor_world_pos();
+            if let Some(other_id) = self.other_chamber_at(&control.state, pos) {
+                return vec![StateCommand::SubtractChamber(chamber_id, other_id)];
+            }
+        }
+        vec![]
+    }
+
+    fn click_split_chamber(&mut self, control: &mut StateController) -> Vec<StateCommand> {
+        if let Some(chamber_id) = control.state.active_chamber_id {
+            let pos = control.state.snap(control.state.cursor_world_pos()).pos;
+            match self.split_start {
+                None => self.split_start = Some(pos),
+                Some(start) => {
+                    self.split_start = None;
+                    return vec![StateCommand::SplitChamber(chamber_id, start, pos)];
+                }
+            }
+        }
+        vec![]
+    }
+
+    fn click(&mut self, control: Rc<RefCell<StateController>>, shift: bool) -> Vec<StateCommand> {
         let control = &mut *control.borrow_mut();
         let commands = match control.state.mode {
-            EditMode::Select => self.click_select(control),
+            EditMode::Select => self.click_select(control, shift),
             EditMode::AppendChamber => self.click_append_chamber(control),
             EditMode::SplitEdge => self.click_split_edge(control),
             EditMode::AddDoor => self.click_add_door(control),
             EditMode::RemoveVertex => self.click_remove_vertex(control),
             EditMode::AddObject => self.click_add_object(control),
+            EditMode::MergeChamber => self.click_merge_chamber(control),
+            EditMode::SubtractChamber => self.click_subtract_chamber(control),
+            EditMode::SplitChamber => self.click_split_chamber(control),
+            EditMode::PlaceTemplate => self.click_place_template(control),
+            EditMode::PlaceImport => self.click_place_import(control),
+            EditMode::AppendCorridor => self.click_append_corridor(control),
         };
         self.update();
         commands
     }
 
+    fn select_drag_begin(&mut self, state: &State, x: f64, y: f64) {
+        self.select_drag = match state.mode {
+            EditMode::Select => Some(SelectDrag {
+                start: Vec2 { x, y } + state.view.world_min().into(),
+                offset: Vec2 { x: 0.0, y: 0.0 },
+                moves: None,
+                free: false,
+            }),
+            _ => None,
+        };
+    }
+
+    fn select_drag_update(&mut self, state: &State, x: f64, y: f64, free: bool) {
+        if let Some(drag) = self.select_drag.as_mut() {
+            drag.offset = Vec2 { x, y };
+            drag.free = free;
+            // decided on the first update, after the click has changed the selection
+            if drag.moves.is_none() {
+                let hit = state.dungeon.entity_at(drag.start);
+                drag.moves = Some(!hit.is_empty() && state.selected().intersects(&hit));
+            }
+            self.update();
+        }
+    }
+
+    fn select_drag_end(&mut self, state: &State, shift: bool) -> Vec<StateCommand> {
+        let drag = match self.select_drag.take() {
+            Some(drag) => drag,
+            None => return vec![],
+        };
+        self.update();
+        match drag.moves {
+            None => vec![],
+            Some(true) => {
+                let by = drag.move_by(state);
+                if by == (Vec2 { x: 0, y: 0 }) {
+                    return vec![];
+                }
+                vec![StateCommand::MoveEntities(state.selected(), by)]
+            }
+            Some(false) => {
+                if drag.offset.x.abs() < MIN_SELECT_DRAG && drag.offset.y.abs() < MIN_SELECT_DRAG {
+                    return vec![];
+                }
+                let mut area = BBox::new();
+                area += drag.start;
+                area += drag.start + drag.offset;
+                let found = state.dungeon.entities_in(area);
+                let selection = match shift {
+                    true => {
+                        let mut selection = state.selected();
+                        selection.extend(&found);
+                        selection
+                    }
+                    false => found,
+                };
+                vec![StateCommand::ChangeSelection(selection)]
+            }
+        }
+    }
+
+    /// draws the selection rectangle or the moved selection while dragging
+    fn draw_select_drag(&self, state: &State, ctx: &cairo::Context) {
+        let drag = match &self.select_drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        match drag.moves {
+            None => (),
+            Some(true) => {
+                let by: Vec2<f64> = drag.move_by(state).into();
+                let selection = state.selected();
+                let mut lines = vec![];
+                for chamber in state.dungeon.chambers.iter() {
+                    if selection.chambers.contains(&chamber.id) {
+                        lines.extend(chamber.walls().iter().map(|w| (w.p1, w.p2)));
+                    }
+                }
+                for corridor in state.dungeon.corridors.iter() {
+                    if selection.corridors.contains(&corridor.id) {
+                        lines.extend(corridor.points().windows(2).map(|w| (w[0], w[1])));
+                    }
+                }
+                let mut lines: Vec<(Vec2<f64>, Vec2<f64>)> = lines
+                    .into_iter()
+                    .map(|(from, to)| (from.into(), to.into()))
+                    .collect();
+                for object in state.dungeon.objects.iter() {
+                    if selection.objects.contains(&object.id) {
+                        let footprint = object.footprint();
+                        for i in 0..footprint.len() {
+                            lines.push((footprint[i], footprint[(i + 1) % footprint.len()]));
+                        }
+                    }
+                }
+                for (from, to) in lines {
+                    Line {
+                        from: from + by,
+                        to: to + by,
+                        color: PRIMARY_ACTIVE_COLOR,
+                        width: 2.0,
+                        dashed: true,
+                    }
+                    .draw(ctx)
+                }
+            }
+            Some(false) => {
+                let a = drag.start;
+                let b = drag.start + drag.offset;
+                Polygon {
+                    points: vec![a, Vec2 { x: b.x, y: a.y }, b, Vec2 { x: a.x, y: b.y }],
+                    holes: vec![],
+                    fill_color: PRIMARY_ACTIVE_COLOR,
+                    fill_opacity: 0.1,
+                    stroke_color: PRIMARY_ACTIVE_COLOR,
+                    stroke_width: 1.0,
+                    dashed: true,
+                }
+                .draw(ctx)
+            }
+        }
+    }
+
     fn drag_begin(&mut self) {
         self.set_last_pos(None)
     }
@@ -516,6 +1101,7 @@ impl StateEventSubscriber for Canvas {
             StateEvent::EditModeChanged(_) => {
                 // reset internal state when the edit mode changes
                 self.selected_wall = None;
+                self.split_start = None;
             }
             _ => {}
         }
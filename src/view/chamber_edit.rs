@@ -5,14 +5,22 @@ use gtk::glib::clone;
 use gtk::{prelude::*, CheckButton, Label, PolicyType, ScrolledWindow, TextView};
 use gtk::{Box, Entry};
 
+use crate::selection::Selection;
+use crate::session::SessionFlag;
 use crate::state::events::StateEvent;
 use crate::state::{StateCommand, StateController, StateEventSubscriber};
 
+use super::notes_preview::notes_preview;
+
 pub struct ChamberEdit {
     pub widget: Box,
     name_input: Entry,
     notes_input: TextView,
+    read_aloud_input: TextView,
     hidden_input: CheckButton,
+    revealed_input: CheckButton,
+    explored_input: CheckButton,
+    cleared_input: CheckButton,
 }
 
 impl ChamberEdit {
@@ -24,10 +32,24 @@ impl ChamberEdit {
             .left_margin(10)
             .right_margin(10)
             .build();
+        let read_aloud_i = TextView::builder()
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .left_margin(10)
+            .right_margin(10)
+            .build();
         let hidden_i = CheckButton::builder()
             .label("Hidden")
             .css_classes(vec!["form-input"])
             .build();
+        let revealed_i = CheckButton::builder()
+            .label("Revealed to players")
+            .css_classes(vec!["form-input"])
+            .build();
+        let explored_i = CheckButton::builder().label("Explored").build();
+        let cleared_i = CheckButton::builder()
+            .label("Cleared")
+            .css_classes(vec!["form-input"])
+            .build();
 
         name_i.connect_changed(clone!(@strong control => move |field| {
             let name = field.text().to_string();
@@ -58,6 +80,25 @@ impl ChamberEdit {
                 }
             }));
 
+        read_aloud_i
+            .buffer()
+            .connect_changed(clone!(@strong control => move |buffer| {
+                let (start, end) = buffer.bounds();
+                let text = buffer.text(&start, &end, true).to_string();
+                if let Ok(mut control) = control.try_borrow_mut() {
+                    match control.state.active_chamber_id {
+                        None => (),
+                        Some(chamber_id) => {
+                            if let Some(chamber) = control.state.dungeon.chamber(chamber_id) {
+                                if chamber.read_aloud != text {
+                                    control.apply(StateCommand::ChangeChamberReadAloud(chamber_id, text))
+                                };
+                            }
+                        }
+                    }
+                }
+            }));
+
         hidden_i.connect_toggled(
             clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
                 match control.state.active_chamber_id {
@@ -67,6 +108,42 @@ impl ChamberEdit {
             }),
         );
 
+        revealed_i.connect_toggled(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_chamber_id {
+                    None => (),
+                    Some(chamber_id) => control.apply(StateCommand::ChangeEntitiesRevealed(
+                        Selection {
+                            chambers: vec![chamber_id],
+                            ..Selection::new()
+                        },
+                        w.is_active(),
+                    )),
+                }
+            }),
+        );
+
+        for (input, flag) in [
+            (&explored_i, SessionFlag::Explored),
+            (&cleared_i, SessionFlag::Cleared),
+        ] {
+            input.connect_toggled(
+                clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                    match control.state.active_chamber_id {
+                        None => (),
+                        Some(chamber_id) => control.apply(StateCommand::ChangeSessionFlag(
+                            Selection {
+                                chambers: vec![chamber_id],
+                                ..Selection::new()
+                            },
+                            flag,
+                            w.is_active(),
+                        )),
+                    }
+                }),
+            );
+        }
+
         let b = Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .build();
@@ -74,6 +151,21 @@ impl ChamberEdit {
         b.append(&Label::new(Some("Name")));
         b.append(&name_i);
         b.append(&hidden_i);
+        b.append(&revealed_i);
+        b.append(&Label::new(Some("Session")));
+        b.append(&explored_i);
+        b.append(&cleared_i);
+        b.append(&Label::new(Some("Read-Aloud Text")));
+        b.append(
+            &ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .height_request(150)
+                .child(&read_aloud_i)
+                .css_classes(vec!["form-input"])
+                .build(),
+        );
+        b.append(&notes_preview(&read_aloud_i));
         b.append(&Label::new(Some("Notes")));
         b.append(
             &ScrolledWindow::builder()
@@ -84,13 +176,18 @@ impl ChamberEdit {
                 .css_classes(vec!["form-input"])
                 .build(),
         );
+        b.append(&notes_preview(&notes_i));
         b.set_visible(false);
 
         let re = Rc::new(RefCell::new(ChamberEdit {
             widget: b,
             name_input: name_i,
             notes_input: notes_i,
+            read_aloud_input: read_aloud_i,
             hidden_input: hidden_i,
+            revealed_input: revealed_i,
+            explored_input: explored_i,
+            cleared_input: cleared_i,
         }));
 
         control.borrow_mut().subscribe_any(re.clone());
@@ -103,17 +200,32 @@ impl ChamberEdit {
             let chamber = state.dungeon.chamber(chamber.id).unwrap();
             self.name_input.set_text(&chamber.name);
             self.notes_input.buffer().set_text(&chamber.notes);
+            self.read_aloud_input.buffer().set_text(&chamber.read_aloud);
             self.hidden_input.set_active(chamber.hidden);
+            self.show_session(state);
             self.widget.set_visible(true);
         } else {
             self.widget.set_visible(false)
         }
     }
+
+    fn show_session(&self, state: &crate::state::State) {
+        if let Some(chamber_id) = state.active_chamber_id {
+            let session = &state.session;
+            self.explored_input
+                .set_active(session.is_set(SessionFlag::Explored, chamber_id));
+            self.cleared_input
+                .set_active(session.is_set(SessionFlag::Cleared, chamber_id));
+            self.revealed_input
+                .set_active(session.revealed.chambers.contains(&chamber_id));
+        }
+    }
 }
 
 impl StateEventSubscriber for ChamberEdit {
     fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
         match event {
+            StateEvent::SessionChanged => self.show_session(state),
             StateEvent::ActiveChamberChanged(None) => self.show_chamber(state),
             StateEvent::ActiveChamberChanged(Some(_)) => self.show_chamber(state),
             StateEvent::Reset => self.show_chamber(state),
@@ -1,3 +1,4 @@
+use crate::selection::Selection;
 use crate::state::{
     events::StateEvent, State, StateCommand, StateController, StateEventSubscriber,
 };
@@ -16,7 +17,7 @@ pub struct ChamberList {
 impl ChamberList {
     pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
         let list_box = ListBox::builder()
-            .selection_mode(gtk::SelectionMode::Single)
+            .selection_mode(gtk::SelectionMode::Multiple)
             .focusable(true)
             .focus_on_click(true)
             .build();
@@ -31,7 +32,11 @@ impl ChamberList {
             rows: vec![],
         }));
 
-        list_box.connect_row_activated(clone!(@strong control => move |_, row| {
+        list_box.connect_row_activated(clone!(@strong control => move |list_box, row| {
+            // part of a multi-selection, handled by connect_selected_rows_changed
+            if list_box.selected_rows().len() > 1 {
+                return;
+            }
             let chamber_id = row
                 .clone()
                 .dynamic_cast::<ChamberListEntry>()
@@ -42,10 +47,30 @@ impl ChamberList {
                 .apply(StateCommand::SelectChamber(Some(chamber_id)));
         }));
 
+        list_box.connect_selected_rows_changed(clone!(@strong control => move |list_box| {
+            let rows = list_box.selected_rows();
+            if rows.len() < 2 {
+                return;
+            }
+            let mut selection = Selection::new();
+            selection.chambers = rows
+                .iter()
+                .map(|r| r.clone().dynamic_cast::<ChamberListEntry>().unwrap().chamber_id())
+                .collect();
+            if let Ok(mut control) = control.try_borrow_mut() {
+                control.apply(StateCommand::ChangeSelection(selection));
+            }
+        }));
+
         let key_controller = EventControllerKey::new();
         key_controller.connect_key_pressed(
             clone!(@strong control, @strong list_box => move |_, key, _, _| {
                 match key {
+                    gdk::Key::Delete if list_box.selected_rows().len() > 1 => {
+                        let mut control = control.borrow_mut();
+                        let selection = control.state.selection.clone();
+                        control.apply(StateCommand::DeleteEntities(selection));
+                    },
                     gdk::Key::Delete => {
                         if let Some(row) = list_box.selected_row() {
                             let chamber_id = row
@@ -80,11 +105,17 @@ impl ChamberList {
             self.rows.push(chamber_label);
             self.list_box.append(self.rows.last().unwrap());
         }
-        match state.active_chamber_id {
-            None => self.list_box.unselect_all(),
-            Some(chamber_id) => self
-                .list_box
-                .select_row(self.rows.iter().find(|r| r.chamber_id() == chamber_id)),
+        self.sync_selection(state);
+    }
+
+    /// selects the rows of all selected chambers
+    fn sync_selection(&self, state: &State) {
+        let selected = state.selected();
+        self.list_box.unselect_all();
+        for row in self.rows.iter() {
+            if selected.chambers.contains(&row.chamber_id()) {
+                self.list_box.select_row(Some(row));
+            }
         }
     }
 }
@@ -102,12 +133,8 @@ impl StateEventSubscriber for ChamberList {
                     .filter(|r| r.chamber_id() == chamber_id)
                     .for_each(|w| w.update(chamber));
             }
-            StateEvent::ActiveChamberChanged(chamber_id) => match chamber_id {
-                None => self.list_box.unselect_all(),
-                Some(chamber_id) => self
-                    .list_box
-                    .select_row(self.rows.iter().find(|r| r.chamber_id() == chamber_id)),
-            },
+            StateEvent::ActiveChamberChanged(_) => self.sync_selection(state),
+            StateEvent::SelectionChanged => self.sync_selection(state),
             StateEvent::Reset => self.rebuild_list(state),
             StateEvent::Reload => self.rebuild_list(state),
             StateEvent::ChamberDeleted(_) => self.rebuild_list(state),
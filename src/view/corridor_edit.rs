@@ -0,0 +1,194 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::glib::clone;
+use gtk::{prelude::*, CheckButton, Label, PolicyType, ScrolledWindow, SpinButton, TextView};
+use gtk::{Box, Entry};
+
+use crate::selection::Selection;
+use crate::state::events::StateEvent;
+use crate::state::{StateCommand, StateController, StateEventSubscriber};
+
+use super::notes_preview::notes_preview;
+
+pub struct CorridorEdit {
+    pub widget: Box,
+    name_input: Entry,
+    notes_input: TextView,
+    hidden_input: CheckButton,
+    revealed_input: CheckButton,
+    numbered_input: CheckButton,
+    width_input: SpinButton,
+}
+
+impl CorridorEdit {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
+        let name_i = Entry::builder().css_classes(vec!["form-input"]).build();
+        let notes_i = TextView::builder()
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .editable(true)
+            .left_margin(10)
+            .right_margin(10)
+            .build();
+        let hidden_i = CheckButton::builder()
+            .label("Hidden")
+            .css_classes(vec!["form-input"])
+            .build();
+        let revealed_i = CheckButton::builder()
+            .label("Revealed to players")
+            .css_classes(vec!["form-input"])
+            .build();
+        let numbered_i = CheckButton::builder()
+            .label("Numbered")
+            .tooltip_text("Label the corridor and list it in the PDF export")
+            .css_classes(vec!["form-input"])
+            .build();
+        let width_i = SpinButton::with_range(1.0, 10.0, 1.0);
+        width_i.add_css_class("form-input");
+
+        name_i.connect_changed(clone!(@strong control => move |field| {
+            let name = field.text().to_string();
+            if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_corridor_id {
+                    None => (),
+                    Some(corridor_id) => control.apply(StateCommand::ChangeCorridorName(corridor_id, name)),
+                }
+            }
+        }));
+
+        notes_i
+            .buffer()
+            .connect_changed(clone!(@strong control => move |buffer| {
+                let (start, end) = buffer.bounds();
+                let notes = buffer.text(&start, &end, true).to_string();
+                if let Ok(mut control) = control.try_borrow_mut() {
+                    match control.state.active_corridor_id {
+                        None => (),
+                        Some(corridor_id) => {
+                            if let Some(corridor) = control.state.dungeon.corridor(corridor_id) {
+                                if corridor.notes != notes {
+                                    control.apply(StateCommand::ChangeCorridorNotes(corridor_id, notes))
+                                };
+                            }
+                        }
+                    }
+                }
+            }));
+
+        hidden_i.connect_toggled(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_corridor_id {
+                    None => (),
+                    Some(corridor_id) => control.apply(StateCommand::ChangeCorridorHidden(corridor_id, w.is_active())),
+                }
+            }),
+        );
+
+        revealed_i.connect_toggled(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_corridor_id {
+                    None => (),
+                    Some(corridor_id) => control.apply(StateCommand::ChangeEntitiesRevealed(
+                        Selection {
+                            corridors: vec![corridor_id],
+                            ..Selection::new()
+                        },
+                        w.is_active(),
+                    )),
+                }
+            }),
+        );
+
+        numbered_i.connect_toggled(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_corridor_id {
+                    None => (),
+                    Some(corridor_id) => control.apply(StateCommand::ChangeCorridorNumbered(corridor_id, w.is_active())),
+                }
+            }),
+        );
+
+        width_i.connect_value_changed(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_corridor() {
+                    Some(corridor) if corridor.width() != w.value_as_int() => {
+                        let corridor_id = corridor.id;
+                        control.apply(StateCommand::ChangeCorridorWidth(corridor_id, w.value_as_int()))
+                    }
+                    _ => (),
+                }
+            }),
+        );
+
+        let b = Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+
+        b.append(&Label::new(Some("Name")));
+        b.append(&name_i);
+        b.append(&hidden_i);
+        b.append(&revealed_i);
+        b.append(&numbered_i);
+        b.append(&Label::new(Some("Width (cells)")));
+        b.append(&width_i);
+        b.append(&Label::new(Some("Notes")));
+        b.append(
+            &ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .height_request(300)
+                .child(&notes_i)
+                .css_classes(vec!["form-input"])
+                .build(),
+        );
+        b.append(&notes_preview(&notes_i));
+        b.set_visible(false);
+
+        let re = Rc::new(RefCell::new(CorridorEdit {
+            widget: b,
+            name_input: name_i,
+            notes_input: notes_i,
+            hidden_input: hidden_i,
+            revealed_input: revealed_i,
+            numbered_input: numbered_i,
+            width_input: width_i,
+        }));
+
+        control.borrow_mut().subscribe_any(re.clone());
+
+        re
+    }
+
+    fn show_corridor(&mut self, state: &crate::state::State) {
+        if let Some(corridor) = state.active_corridor() {
+            self.name_input.set_text(&corridor.name);
+            self.notes_input.buffer().set_text(&corridor.notes);
+            self.hidden_input.set_active(corridor.hidden);
+            self.revealed_input
+                .set_active(state.session.revealed.corridors.contains(&corridor.id));
+            self.numbered_input.set_active(corridor.numbered);
+            self.width_input.set_value(corridor.width() as f64);
+            self.widget.set_visible(true);
+        } else {
+            self.widget.set_visible(false)
+        }
+    }
+}
+
+impl StateEventSubscriber for CorridorEdit {
+    fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
+        match event {
+            StateEvent::SessionChanged => {
+                if let Some(corridor_id) = state.active_corridor_id {
+                    self.revealed_input
+                        .set_active(state.session.revealed.corridors.contains(&corridor_id));
+                }
+            }
+            StateEvent::ActiveCorridorChanged(None) => self.show_corridor(state),
+            StateEvent::ActiveCorridorChanged(Some(_)) => self.show_corridor(state),
+            StateEvent::Reset => self.show_corridor(state),
+            StateEvent::Reload => self.show_corridor(state),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::selection::Selection;
+use crate::state::{
+    events::StateEvent, State, StateCommand, StateController, StateEventSubscriber,
+};
+use crate::view::corridor_list_entry::CorridorListEntry;
+use cairo::glib::{clone, Propagation};
+use gtk::{gdk, prelude::*, EventControllerKey};
+use gtk::{ListBox, PolicyType, ScrolledWindow};
+use std::{cell::RefCell, rc::Rc};
+
+pub struct CorridorList {
+    pub list_box: ListBox,
+    pub scrolled_window: ScrolledWindow,
+    pub rows: Vec<CorridorListEntry>,
+}
+
+impl CorridorList {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
+        let list_box = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Multiple)
+            .build();
+        let corridor_list = Rc::new(RefCell::new(CorridorList {
+            list_box: list_box.clone(),
+            scrolled_window: ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .height_request(300)
+                .child(&list_box)
+                .build(),
+            rows: vec![],
+        }));
+
+        list_box.connect_row_activated(clone!(@strong control => move |list_box, row| {
+            // part of a multi-selection, handled by connect_selected_rows_changed
+            if list_box.selected_rows().len() > 1 {
+                return;
+            }
+            let corridor_id = row
+                .clone()
+                .dynamic_cast::<CorridorListEntry>()
+                .unwrap()
+                .corridor_id();
+            control
+                .borrow_mut()
+                .apply(StateCommand::SelectCorridor(Some(corridor_id)));
+        }));
+
+        list_box.connect_selected_rows_changed(clone!(@strong control => move |list_box| {
+            let rows = list_box.selected_rows();
+            if rows.len() < 2 {
+                return;
+            }
+            let mut selection = Selection::new();
+            selection.corridors = rows
+                .iter()
+                .map(|r| r.clone().dynamic_cast::<CorridorListEntry>().unwrap().corridor_id())
+                .collect();
+            if let Ok(mut control) = control.try_borrow_mut() {
+                control.apply(StateCommand::ChangeSelection(selection));
+            }
+        }));
+
+        let key_controller = EventControllerKey::new();
+        key_controller.connect_key_pressed(
+            clone!(@strong control, @strong list_box => move |_, key, _, _| {
+                if key == gdk::Key::Delete && list_box.selected_rows().len() > 1 {
+                    let mut control = control.borrow_mut();
+                    let selection = control.state.selection.clone();
+                    control.apply(StateCommand::DeleteEntities(selection));
+                } else if key == gdk::Key::Delete {
+                    if let Some(row) = list_box.selected_row() {
+                        let corridor_id = row
+                            .clone()
+                            .dynamic_cast::<CorridorListEntry>()
+                            .unwrap()
+                            .corridor_id();
+                        control
+                            .borrow_mut()
+                            .apply(StateCommand::DeleteCorridor(corridor_id))
+                    }
+                }
+                Propagation::Proceed
+            }),
+        );
+        list_box.add_controller(key_controller);
+
+        let mut state = control.borrow_mut();
+        state.subscribe_any(corridor_list.clone());
+        corridor_list
+    }
+
+    fn rebuild_list(&mut self, state: &State) {
+        for row in &self.rows {
+            self.list_box.remove(row)
+        }
+        self.rows = vec![];
+        for corridor in &state.dungeon.corridors {
+            let corridor_label = CorridorListEntry::new(&corridor.clone());
+            self.rows.push(corridor_label);
+            self.list_box.append(self.rows.last().unwrap());
+        }
+        self.sync_selection(state);
+    }
+
+    /// selects the rows of all selected corridors
+    fn sync_selection(&self, state: &State) {
+        let selected = state.selected();
+        self.list_box.unselect_all();
+        for row in self.rows.iter() {
+            if selected.corridors.contains(&row.corridor_id()) {
+                self.list_box.select_row(Some(row));
+            }
+        }
+    }
+}
+
+impl StateEventSubscriber for CorridorList {
+    fn on_state_event(&mut self, state: &State, event: StateEvent) {
+        match event {
+            StateEvent::CorridorAdded(_) => {
+                self.rebuild_list(state);
+            }
+            StateEvent::CorridorModified(corridor_id) => {
+                let corridor = state.dungeon.corridor(corridor_id).unwrap();
+                self.rows
+                    .iter_mut()
+                    .filter(|r| r.corridor_id() == corridor_id)
+                    .for_each(|w| w.update(corridor));
+            }
+            StateEvent::ActiveCorridorChanged(_) => self.sync_selection(state),
+            StateEvent::SelectionChanged => self.sync_selection(state),
+            StateEvent::Reset => self.rebuild_list(state),
+            StateEvent::Reload => self.rebuild_list(state),
+            StateEvent::CorridorDeleted(_) => self.rebuild_list(state),
+            _ => (),
+        }
+    }
+}
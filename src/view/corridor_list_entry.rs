@@ -0,0 +1,79 @@
+use glib::Object;
+use gtk::{
+    glib,
+    subclass::prelude::{ObjectSubclassExt, ObjectSubclassIsExt},
+    Label,
+};
+
+use crate::corridor::{Corridor, CorridorId};
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use gtk::subclass::prelude::*;
+    use gtk::{glib, Label};
+
+    use crate::corridor::CorridorId;
+
+    // Object holding the state
+    #[derive(Default)]
+    pub struct CorridorListEntry {
+        pub corridor_id: Cell<CorridorId>,
+        pub label: RefCell<Label>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for CorridorListEntry {
+        const NAME: &'static str = "DungeonPlannerCorridorListEntry";
+        type Type = super::CorridorListEntry;
+        type ParentType = gtk::ListBoxRow;
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for CorridorListEntry {}
+
+    // Trait shared by all widgets
+    impl WidgetImpl for CorridorListEntry {}
+
+    // Trait shared by all labels
+    impl ListBoxRowImpl for CorridorListEntry {}
+}
+
+glib::wrapper! {
+    pub struct CorridorListEntry(ObjectSubclass<imp::CorridorListEntry>)
+        @extends gtk::ListBoxRow, gtk::Widget,
+        @implements gtk::Accessible, gtk::Actionable, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl CorridorListEntry {
+    fn corridor_to_label(corridor: &Corridor) -> String {
+        if corridor.name.is_empty() {
+            format!("{}) {}", corridor.id, "Corridor")
+        } else {
+            format!("{}) {}", corridor.id, &corridor.name)
+        }
+    }
+
+    pub fn new(corridor: &Corridor) -> Self {
+        let corridor_id = corridor.id;
+
+        let label = Label::new(Some(&CorridorListEntry::corridor_to_label(corridor)));
+        label.set_xalign(0.01);
+        let o: Self = Object::builder().property("child", label.clone()).build();
+        let imp = o.imp();
+        imp.corridor_id.set(corridor_id);
+        imp.label.replace(label.clone());
+        o
+    }
+
+    pub fn corridor_id(&self) -> CorridorId {
+        let imp = imp::CorridorListEntry::from_obj(self);
+        imp.corridor_id.get()
+    }
+
+    pub fn update(&mut self, corridor: &Corridor) {
+        let name = CorridorListEntry::corridor_to_label(corridor);
+        self.imp().label.borrow_mut().set_label(&name);
+    }
+}
@@ -10,18 +10,25 @@ use gtk::{prelude::*, Label, TextView};
 use gtk::{Box, Entry};
 
 use crate::chamber::ChamberId;
+use crate::selection::Selection;
+use crate::session::SessionFlag;
 use crate::state::events::StateEvent;
 use crate::state::{StateCommand, StateController, StateEventSubscriber};
 
 use super::chamber_list_object::ChamberObject;
+use super::notes_preview::notes_preview;
 
 pub struct DoorEdit {
     pub widget: Box,
     part_of_label: Label,
     name_input: Entry,
     notes_input: TextView,
+    read_aloud_input: TextView,
     leads_to_input: DropDown,
     hidden_input: CheckButton,
+    revealed_input: CheckButton,
+    opened_input: CheckButton,
+    broken_input: CheckButton,
     chambers_model: gio::ListStore,
 }
 
@@ -33,10 +40,24 @@ impl DoorEdit {
             .left_margin(10)
             .right_margin(10)
             .build();
+        let read_aloud_i = TextView::builder()
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .left_margin(10)
+            .right_margin(10)
+            .build();
         let hidden_i = CheckButton::builder()
             .css_classes(vec!["form-input"])
             .label("Hidden")
             .build();
+        let revealed_i = CheckButton::builder()
+            .label("Revealed to players")
+            .css_classes(vec!["form-input"])
+            .build();
+        let opened_i = CheckButton::builder().label("Opened").build();
+        let broken_i = CheckButton::builder()
+            .label("Broken")
+            .css_classes(vec!["form-input"])
+            .build();
 
         let chamber_vec: Vec<ChamberObject> =
             vec![ChamberObject::new(None, "-- No Chamber --".to_owned())];
@@ -123,6 +144,25 @@ impl DoorEdit {
                 }
             }));
 
+        read_aloud_i
+            .buffer()
+            .connect_changed(clone!(@strong control => move |buffer| {
+                let (start, end) = buffer.bounds();
+                let text = buffer.text(&start, &end, true).to_string();
+                if let Ok(mut control) = control.try_borrow_mut() {
+                    match control.state.active_door_id {
+                        None => (),
+                        Some(door_id) => {
+                            if let Some(door) = control.state.dungeon.door(door_id) {
+                                if door.read_aloud != text {
+                                    control.apply(StateCommand::ChangeDoorReadAloud(door_id, text))
+                                };
+                            }
+                        }
+                    }
+                }
+            }));
+
         hidden_i.connect_toggled(
             clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
                 match control.state.active_door_id {
@@ -132,6 +172,42 @@ impl DoorEdit {
             }),
         );
 
+        revealed_i.connect_toggled(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_door_id {
+                    None => (),
+                    Some(door_id) => control.apply(StateCommand::ChangeEntitiesRevealed(
+                        Selection {
+                            doors: vec![door_id],
+                            ..Selection::new()
+                        },
+                        w.is_active(),
+                    )),
+                }
+            }),
+        );
+
+        for (input, flag) in [
+            (&opened_i, SessionFlag::Opened),
+            (&broken_i, SessionFlag::Broken),
+        ] {
+            input.connect_toggled(
+                clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                    match control.state.active_door_id {
+                        None => (),
+                        Some(door_id) => control.apply(StateCommand::ChangeSessionFlag(
+                            Selection {
+                                doors: vec![door_id],
+                                ..Selection::new()
+                            },
+                            flag,
+                            w.is_active(),
+                        )),
+                    }
+                }),
+            );
+        }
+
         let b = Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .build();
@@ -144,8 +220,23 @@ impl DoorEdit {
         b.append(&Label::new(Some("Name")));
         b.append(&name_i);
         b.append(&hidden_i);
+        b.append(&revealed_i);
+        b.append(&Label::new(Some("Session")));
+        b.append(&opened_i);
+        b.append(&broken_i);
         b.append(&Label::new(Some("Leads to Chamber:")));
         b.append(&leads_to_i);
+        b.append(&Label::new(Some("Read-Aloud Text")));
+        b.append(
+            &ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .height_request(150)
+                .child(&read_aloud_i)
+                .css_classes(vec!["form-input"])
+                .build(),
+        );
+        b.append(&notes_preview(&read_aloud_i));
         b.append(&Label::new(Some("Notes")));
         b.append(
             &ScrolledWindow::builder()
@@ -156,6 +247,7 @@ impl DoorEdit {
                 .css_classes(vec!["form-input"])
                 .build(),
         );
+        b.append(&notes_preview(&notes_i));
 
         b.set_visible(false);
 
@@ -164,8 +256,12 @@ impl DoorEdit {
             part_of_label: part_of_label,
             name_input: name_i,
             notes_input: notes_i,
+            read_aloud_input: read_aloud_i,
             leads_to_input: leads_to_i,
             hidden_input: hidden_i,
+            revealed_input: revealed_i,
+            opened_input: opened_i,
+            broken_input: broken_i,
             chambers_model: model,
         }));
 
@@ -197,15 +293,29 @@ impl DoorEdit {
 
             self.name_input.set_text(&door.name);
             self.notes_input.buffer().set_text(&door.notes);
+            self.read_aloud_input.buffer().set_text(&door.read_aloud);
             self.leads_to_input
                 .set_selected(self.chamber_object_pos(door.leads_to).unwrap_or(0));
             self.hidden_input.set_active(door.hidden);
+            self.show_session(state);
             self.widget.set_visible(true);
         } else {
             self.widget.set_visible(false);
         }
     }
 
+    fn show_session(&self, state: &crate::state::State) {
+        if let Some(door_id) = state.active_door_id {
+            let session = &state.session;
+            self.opened_input
+                .set_active(session.is_set(SessionFlag::Opened, door_id));
+            self.broken_input
+                .set_active(session.is_set(SessionFlag::Broken, door_id));
+            self.revealed_input
+                .set_active(session.revealed.doors.contains(&door_id));
+        }
+    }
+
     fn rebuild_chamber_list(&mut self, state: &crate::state::State) {
         for chamber in state.dungeon.chambers() {
             match self.chamber_object_pos(Some(chamber.id)) {
@@ -230,6 +340,7 @@ impl DoorEdit {
 impl StateEventSubscriber for DoorEdit {
     fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
         match event {
+            StateEvent::SessionChanged => self.show_session(state),
             StateEvent::ActiveDoorChanged(None) => self.show_door(state),
             StateEvent::ActiveDoorChanged(Some(_)) => self.show_door(state),
             StateEvent::Reset => self.widget.set_visible(false),
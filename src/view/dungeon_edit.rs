@@ -1,17 +1,33 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use gtk::glib::clone;
-use gtk::{prelude::*, Label, PolicyType, ScrolledWindow, TextView};
+use gtk::glib::{self, clone};
+use gtk::{
+    prelude::*, Button, CheckButton, Label, PolicyType, ScrolledWindow, SpinButton, TextView,
+};
 use gtk::{Box, Entry};
 
+use crate::background::BackgroundSettings;
+use crate::common::Vec2;
+use crate::config::GRID_SIZE;
+use crate::file_actions::background_image_dialog;
 use crate::state::events::StateEvent;
 use crate::state::{StateCommand, StateController, StateEventSubscriber};
 
+use super::notes_preview::notes_preview;
+
 pub struct DungeonEdit {
     pub widget: Box,
     name_input: Entry,
     notes_input: TextView,
+    background_settings: Box,
+    background_x: SpinButton,
+    background_y: SpinButton,
+    background_cell_size: SpinButton,
+    background_rotation: SpinButton,
+    background_opacity: SpinButton,
+    background_gm_export: CheckButton,
+    background_player_export: CheckButton,
 }
 
 impl DungeonEdit {
@@ -41,6 +57,48 @@ impl DungeonEdit {
                 }
             }));
 
+        let load_background = Button::builder()
+            .label("Load Image ...")
+            .css_classes(vec!["form-input"])
+            .build();
+        load_background.connect_clicked(clone!(@strong control => move |_| {
+            background_image_dialog(control.clone());
+        }));
+        let remove_background = Button::builder()
+            .label("Remove Image")
+            .css_classes(vec!["form-input"])
+            .build();
+        remove_background.connect_clicked(clone!(@strong control => move |_| {
+            control
+                .borrow_mut()
+                .apply(StateCommand::SetBackgroundImage(None));
+        }));
+
+        let background_x = SpinButton::with_range(-100000.0, 100000.0, 1.0);
+        let background_y = SpinButton::with_range(-100000.0, 100000.0, 1.0);
+        // size of a grid cell in image pixels, used to align the image to the grid
+        let background_cell_size = SpinButton::with_range(1.0, 1000.0, 0.5);
+        background_cell_size.set_digits(1);
+        let background_rotation = SpinButton::with_range(0.0, 359.9, 1.0);
+        background_rotation.set_digits(1);
+        background_rotation.set_wrap(true);
+        let background_opacity = SpinButton::with_range(0.0, 1.0, 0.05);
+        background_opacity.set_digits(2);
+        for input in [
+            &background_x,
+            &background_y,
+            &background_cell_size,
+            &background_rotation,
+            &background_opacity,
+        ] {
+            input.add_css_class("form-input");
+        }
+        let background_gm_export = CheckButton::builder().label("Show in GM export").build();
+        let background_player_export = CheckButton::builder()
+            .label("Show in player export")
+            .css_classes(vec!["form-input"])
+            .build();
+
         let b = Box::builder()
             .orientation(gtk::Orientation::Vertical)
             .build();
@@ -57,21 +115,117 @@ impl DungeonEdit {
                 .css_classes(vec!["form-input"])
                 .build(),
         );
+        b.append(&notes_preview(&notes_i));
+
+        b.append(&Label::new(Some("Background Image")));
+        b.append(&load_background);
+
+        let settings_box = Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .visible(false)
+            .build();
+        settings_box.append(&remove_background);
+        settings_box.append(&Label::new(Some("Position X")));
+        settings_box.append(&background_x);
+        settings_box.append(&Label::new(Some("Position Y")));
+        settings_box.append(&background_y);
+        settings_box.append(&Label::new(Some("Pixels per grid cell")));
+        settings_box.append(&background_cell_size);
+        settings_box.append(&Label::new(Some("Rotation (degrees)")));
+        settings_box.append(&background_rotation);
+        settings_box.append(&Label::new(Some("Opacity")));
+        settings_box.append(&background_opacity);
+        settings_box.append(&background_gm_export);
+        settings_box.append(&background_player_export);
+        b.append(&settings_box);
 
         let re = Rc::new(RefCell::new(DungeonEdit {
             widget: b,
             name_input: name_i,
             notes_input: notes_i,
+            background_settings: settings_box,
+            background_x,
+            background_y,
+            background_cell_size,
+            background_rotation,
+            background_opacity,
+            background_gm_export,
+            background_player_export,
         }));
 
+        {
+            let edit = re.borrow();
+            let settings_changed = clone!(@strong control, @weak re => move || {
+                if let Ok(mut control) = control.try_borrow_mut() {
+                    let settings = re.borrow().background_settings();
+                    match &control.state.dungeon.background {
+                        Some(background) if background.settings != settings => {
+                            control.apply(StateCommand::ChangeBackgroundSettings(settings))
+                        }
+                        _ => (),
+                    }
+                }
+            });
+            for input in [
+                &edit.background_x,
+                &edit.background_y,
+                &edit.background_cell_size,
+                &edit.background_rotation,
+                &edit.background_opacity,
+            ] {
+                input.connect_value_changed(
+                    clone!(@strong settings_changed => move |_| settings_changed()),
+                );
+            }
+            for input in [&edit.background_gm_export, &edit.background_player_export] {
+                input.connect_toggled(
+                    clone!(@strong settings_changed => move |_| settings_changed()),
+                );
+            }
+        }
+
         control.borrow_mut().subscribe_any(re.clone());
 
         re
     }
 
+    /// settings as shown in the inputs
+    fn background_settings(&self) -> BackgroundSettings {
+        BackgroundSettings {
+            pos: Vec2 {
+                x: self.background_x.value(),
+                y: self.background_y.value(),
+            },
+            scale: GRID_SIZE as f64 / self.background_cell_size.value(),
+            rotation: self.background_rotation.value(),
+            opacity: self.background_opacity.value(),
+            in_gm_export: self.background_gm_export.is_active(),
+            in_player_export: self.background_player_export.is_active(),
+        }
+    }
+
     fn sync(&mut self, state: &crate::state::State) {
         self.name_input.set_text(&state.dungeon.name);
         self.notes_input.buffer().set_text(&state.dungeon.notes);
+        self.sync_background(state);
+    }
+
+    fn sync_background(&mut self, state: &crate::state::State) {
+        match &state.dungeon.background {
+            Some(background) => {
+                let s = background.settings;
+                self.background_x.set_value(s.pos.x);
+                self.background_y.set_value(s.pos.y);
+                self.background_cell_size
+                    .set_value(GRID_SIZE as f64 / s.scale);
+                self.background_rotation.set_value(s.rotation);
+                self.background_opacity.set_value(s.opacity);
+                self.background_gm_export.set_active(s.in_gm_export);
+                self.background_player_export.set_active(s.in_player_export);
+                self.background_settings.set_visible(true);
+            }
+            None => self.background_settings.set_visible(false),
+        }
     }
 }
 
@@ -79,6 +233,7 @@ impl StateEventSubscriber for DungeonEdit {
     fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
         match event {
             StateEvent::DungeonModified => self.sync(state),
+            StateEvent::BackgroundChanged => self.sync_background(state),
             StateEvent::Reset => self.sync(state),
             StateEvent::Reload => self.sync(state),
             _ => (),
@@ -15,6 +15,9 @@ impl EntityTabs {
         chamber_tab: gtk::Box,
         door_tab: gtk::Box,
         object_tab: gtk::Box,
+        corridor_tab: gtk::Box,
+        session_tab: gtk::Box,
+        template_tab: gtk::Box,
     ) -> Rc<RefCell<Self>> {
         let notebook = Notebook::builder().build();
 
@@ -22,6 +25,9 @@ impl EntityTabs {
         notebook.append_page(&chamber_tab, Some(&Label::new(Some("Chambers"))));
         notebook.append_page(&door_tab, Some(&Label::new(Some("Doors"))));
         notebook.append_page(&object_tab, Some(&Label::new(Some("Objects"))));
+        notebook.append_page(&corridor_tab, Some(&Label::new(Some("Corridors"))));
+        notebook.append_page(&session_tab, Some(&Label::new(Some("Session"))));
+        notebook.append_page(&template_tab, Some(&Label::new(Some("Templates"))));
 
         let tabs = Rc::new(RefCell::new(EntityTabs { widget: notebook }));
         control.borrow_mut().subscribe_any(tabs.clone());
@@ -42,12 +48,18 @@ impl StateEventSubscriber for EntityTabs {
             StateEvent::ActiveObjectChanged(Some(_)) => {
                 self.widget.set_current_page(Some(3));
             }
+            StateEvent::ActiveCorridorChanged(Some(_)) => {
+                self.widget.set_current_page(Some(4));
+            }
             StateEvent::DoorAdded(_) => {
                 self.widget.set_current_page(Some(2));
             }
             StateEvent::ObjectAdded(_) => {
                 self.widget.set_current_page(Some(3));
             }
+            StateEvent::CorridorAdded(_) => {
+                self.widget.set_current_page(Some(4));
+            }
             _ => {}
         };
     }
@@ -7,6 +7,7 @@ pub struct Grid {
     pub size: Vec2<i32>,
     pub color: Rgb,
     pub width: f64,
+    pub dashed: bool,
 }
 
 impl Grid {
@@ -22,6 +23,7 @@ impl Grid {
                 g: 1.0,
             },
             width: 0.7,
+            dashed: false,
         }
     }
 
@@ -55,7 +57,7 @@ impl Grid {
                 },
                 color: self.color,
                 width: self.width,
-                dashed: false,
+                dashed: self.dashed,
             });
             lines.push(line);
         }
@@ -71,7 +73,7 @@ impl Grid {
                 },
                 color: self.color,
                 width: self.width,
-                dashed: false,
+                dashed: self.dashed,
             });
             lines.push(line);
         }
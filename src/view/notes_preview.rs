@@ -0,0 +1,28 @@
+use gtk::glib::{self, clone};
+use gtk::{prelude::*, Expander, Label, TextView};
+
+use crate::markdown;
+
+/// Collapsible preview of the Markdown formatting of a notes editor,
+/// updated whenever the notes change
+pub fn notes_preview(notes_input: &TextView) -> Expander {
+    let preview = Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .selectable(true)
+        .margin_start(10)
+        .margin_end(10)
+        .build();
+    notes_input
+        .buffer()
+        .connect_changed(clone!(@weak preview => move |buffer| {
+            let (start, end) = buffer.bounds();
+            let notes = buffer.text(&start, &end, true).to_string();
+            preview.set_markup(&markdown::to_markup(&notes));
+        }));
+    Expander::builder()
+        .label("Preview")
+        .child(&preview)
+        .css_classes(vec!["form-input"])
+        .build()
+}
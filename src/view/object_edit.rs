@@ -2,25 +2,41 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use gtk::glib::clone;
-use gtk::{gio, CheckButton, ListItem, PolicyType, ScrolledWindow, SignalListItemFactory};
+use gtk::{
+    gio, CheckButton, DropDown, ListItem, PolicyType, ScrolledWindow, SignalListItemFactory,
+    SpinButton, StringList,
+};
 use gtk::{prelude::*, Label, TextView};
 use gtk::{Box, Entry};
 
+use crate::common::Vec2;
 use crate::object::ObjectStyle;
+use crate::selection::Selection;
+use crate::session::SessionFlag;
 use crate::state::events::StateEvent;
 use crate::state::{StateCommand, StateController, StateEventSubscriber};
+use crate::symbol::SymbolLibrary;
 
 use super::chamber_list_object::ChamberObject;
+use super::notes_preview::notes_preview;
+
+/// styles drawn without a symbol, listed before the symbols
+const BASE_STYLES: [&str; 3] = ["Blocker", "Stairs", "Round"];
 
 pub struct ObjectEdit {
     pub widget: Box,
     part_of_label: Label,
     name_input: Entry,
     notes_input: TextView,
+    read_aloud_input: TextView,
     hidden_input: CheckButton,
-    blocker_style: CheckButton,
-    stair_style: CheckButton,
-    round_style: CheckButton,
+    style_input: DropDown,
+    style_names: StringList,
+    symbols: Rc<RefCell<SymbolLibrary>>,
+    width_input: SpinButton,
+    height_input: SpinButton,
+    rotation_input: SpinButton,
+    looted_input: CheckButton,
 }
 
 impl ObjectEdit {
@@ -31,21 +47,38 @@ impl ObjectEdit {
             .left_margin(10)
             .right_margin(10)
             .build();
+        let read_aloud_i = TextView::builder()
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .left_margin(10)
+            .right_margin(10)
+            .build();
         let hidden_i = CheckButton::builder()
             .css_classes(vec!["form-input"])
             .label("Hidden")
             .build();
-        let blocker_style = CheckButton::builder().label("Blocker").build();
-        let stair_style = CheckButton::builder()
-            .label("Stairs")
-            .group(&blocker_style)
+        let looted_i = CheckButton::builder()
+            .css_classes(vec!["form-input"])
+            .label("Looted")
             .build();
-        let round_style = CheckButton::builder()
+        let symbols = Rc::new(RefCell::new(SymbolLibrary::load()));
+        let style_names = StringList::new(&BASE_STYLES);
+        for symbol in symbols.borrow().symbols.iter() {
+            style_names.append(&symbol.name);
+        }
+        let style_i = DropDown::builder()
+            .model(&style_names)
             .css_classes(vec!["form-input"])
-            .label("Round")
-            .group(&blocker_style)
             .build();
 
+        let width_i = SpinButton::with_range(1.0, 10.0, 1.0);
+        width_i.add_css_class("form-input");
+        let height_i = SpinButton::with_range(1.0, 10.0, 1.0);
+        height_i.add_css_class("form-input");
+        // steps of 90 degrees, other angles can be typed in
+        let rotation_i = SpinButton::with_range(0.0, 359.0, 90.0);
+        rotation_i.set_wrap(true);
+        rotation_i.add_css_class("form-input");
+
         let chamber_vec: Vec<ChamberObject> =
             vec![ChamberObject::new(None, "-- No Chamber --".to_owned())];
         let model = gio::ListStore::new::<ChamberObject>();
@@ -110,6 +143,25 @@ impl ObjectEdit {
                 }
             }));
 
+        read_aloud_i
+            .buffer()
+            .connect_changed(clone!(@strong control => move |buffer| {
+                let (start, end) = buffer.bounds();
+                let text = buffer.text(&start, &end, true).to_string();
+                if let Ok(mut control) = control.try_borrow_mut() {
+                    match control.state.active_object_id {
+                        None => (),
+                        Some(object_id) => {
+                            if let Some(object) = control.state.dungeon.object(object_id) {
+                                if object.read_aloud != text {
+                                    control.apply(StateCommand::ChangeObjectReadAloud(object_id, text))
+                                };
+                            }
+                        }
+                    }
+                }
+            }));
+
         hidden_i.connect_toggled(
             clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
                 match control.state.active_object_id {
@@ -119,29 +171,70 @@ impl ObjectEdit {
             }),
         );
 
-        blocker_style.connect_toggled(
-            clone!(@strong control => move |_w| if let Ok(mut control) = control.try_borrow_mut() {
-                match control.state.active_object_id {
-                    None => (),
-                    Some(object_id) => control.apply(StateCommand::ChangeObjectStyle(object_id, ObjectStyle::Blocker)),
+        style_i.connect_selected_notify(
+            clone!(@strong control, @strong symbols => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                let index = w.selected() as usize;
+                let style = match index {
+                    0 => ObjectStyle::Blocker,
+                    1 => ObjectStyle::Stairs,
+                    2 => ObjectStyle::Round,
+                    _ => match symbols.borrow().symbols.get(index - BASE_STYLES.len()) {
+                        Some(symbol) => ObjectStyle::Symbol(symbol.clone()),
+                        None => return,
+                    },
+                };
+                match control.state.active_object() {
+                    Some(object) if object.style.to_str() != style.to_str() => {
+                        let object_id = object.id;
+                        control.apply(StateCommand::ChangeObjectStyle(object_id, style))
+                    }
+                    _ => (),
                 }
             }),
         );
 
-        stair_style.connect_toggled(
-            clone!(@strong control => move |_w| if let Ok(mut control) = control.try_borrow_mut() {
-                match control.state.active_object_id {
-                    None => (),
-                    Some(object_id) => control.apply(StateCommand::ChangeObjectStyle(object_id, ObjectStyle::Stairs)),
+        let size_changed = clone!(@strong control, @strong width_i, @strong height_i => move |_: &SpinButton| {
+            if let Ok(mut control) = control.try_borrow_mut() {
+                let size = Vec2 {
+                    x: width_i.value_as_int(),
+                    y: height_i.value_as_int(),
+                };
+                match control.state.active_object() {
+                    Some(object) if object.size != size => {
+                        let object_id = object.id;
+                        control.apply(StateCommand::ChangeObjectSize(object_id, size))
+                    }
+                    _ => (),
+                }
+            }
+        });
+        width_i.connect_value_changed(size_changed.clone());
+        height_i.connect_value_changed(size_changed);
+
+        rotation_i.connect_value_changed(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
+                match control.state.active_object() {
+                    Some(object) if object.rotation != w.value() => {
+                        let object_id = object.id;
+                        control.apply(StateCommand::ChangeObjectRotation(object_id, w.value()))
+                    }
+                    _ => (),
                 }
             }),
         );
 
-        round_style.connect_toggled(
-            clone!(@strong control => move |_w| if let Ok(mut control) = control.try_borrow_mut() {
+        looted_i.connect_toggled(
+            clone!(@strong control => move |w| if let Ok(mut control) = control.try_borrow_mut() {
                 match control.state.active_object_id {
                     None => (),
-                    Some(object_id) => control.apply(StateCommand::ChangeObjectStyle(object_id, ObjectStyle::Round)),
+                    Some(object_id) => control.apply(StateCommand::ChangeSessionFlag(
+                        Selection {
+                            objects: vec![object_id],
+                            ..Selection::new()
+                        },
+                        SessionFlag::Looted,
+                        w.is_active(),
+                    )),
                 }
             }),
         );
@@ -158,12 +251,29 @@ impl ObjectEdit {
         b.append(&Label::new(Some("Name")));
         b.append(&name_i);
         b.append(&hidden_i);
+        b.append(&looted_i);
 
         b.append(&Label::new(Some("Style")));
-        b.append(&blocker_style);
-        b.append(&stair_style);
-        b.append(&round_style);
+        b.append(&style_i);
+
+        b.append(&Label::new(Some("Width (cells)")));
+        b.append(&width_i);
+        b.append(&Label::new(Some("Height (cells)")));
+        b.append(&height_i);
+        b.append(&Label::new(Some("Rotation (degrees)")));
+        b.append(&rotation_i);
 
+        b.append(&Label::new(Some("Read-Aloud Text")));
+        b.append(
+            &ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .height_request(150)
+                .child(&read_aloud_i)
+                .css_classes(vec!["form-input"])
+                .build(),
+        );
+        b.append(&notes_preview(&read_aloud_i));
         b.append(&Label::new(Some("Notes")));
         b.append(
             &ScrolledWindow::builder()
@@ -174,6 +284,7 @@ impl ObjectEdit {
                 .css_classes(vec!["form-input"])
                 .build(),
         );
+        b.append(&notes_preview(&notes_i));
 
         b.set_visible(false);
 
@@ -182,10 +293,15 @@ impl ObjectEdit {
             part_of_label: part_of_label,
             name_input: name_i,
             notes_input: notes_i,
+            read_aloud_input: read_aloud_i,
             hidden_input: hidden_i,
-            blocker_style: blocker_style,
-            stair_style: stair_style,
-            round_style: round_style,
+            style_input: style_i,
+            style_names,
+            symbols,
+            width_input: width_i,
+            height_input: height_i,
+            rotation_input: rotation_i,
+            looted_input: looted_i,
         }));
 
         control.borrow_mut().subscribe_any(re.clone());
@@ -208,22 +324,55 @@ impl ObjectEdit {
 
             self.name_input.set_text(&object.name);
             self.notes_input.buffer().set_text(&object.notes);
+            self.read_aloud_input.buffer().set_text(&object.read_aloud);
             self.hidden_input.set_active(object.hidden);
-            match object.style {
-                ObjectStyle::Blocker => self.blocker_style.set_active(true),
-                ObjectStyle::Stairs => self.stair_style.set_active(true),
-                ObjectStyle::Round => self.round_style.set_active(true),
-            };
+            self.show_style(&object.style);
+            self.width_input.set_value(object.size.x as f64);
+            self.height_input.set_value(object.size.y as f64);
+            self.rotation_input.set_value(object.rotation);
+            self.show_session(state);
             self.widget.set_visible(true);
         } else {
             self.widget.set_visible(false);
         }
     }
+
+    fn show_session(&self, state: &crate::state::State) {
+        if let Some(object_id) = state.active_object_id {
+            self.looted_input
+                .set_active(state.session.is_set(SessionFlag::Looted, object_id));
+        }
+    }
+
+    /// selects the style in the drop down, symbols loaded from a file are added to the list
+    fn show_style(&self, style: &ObjectStyle) {
+        let name = style.to_str();
+        if let Some(index) = BASE_STYLES.iter().position(|s| *s == name) {
+            self.style_input.set_selected(index as u32);
+            return;
+        }
+        let mut symbols = self.symbols.borrow_mut();
+        let index = match symbols.symbols.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => match style {
+                ObjectStyle::Symbol(symbol) => {
+                    symbols.symbols.push(symbol.clone());
+                    self.style_names.append(&name);
+                    symbols.symbols.len() - 1
+                }
+                _ => return,
+            },
+        };
+        drop(symbols);
+        self.style_input
+            .set_selected((index + BASE_STYLES.len()) as u32);
+    }
 }
 
 impl StateEventSubscriber for ObjectEdit {
     fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
         match event {
+            StateEvent::SessionChanged => self.show_session(state),
             StateEvent::ActiveObjectChanged(None) => self.show_object(state),
             StateEvent::ActiveObjectChanged(Some(_)) => self.show_object(state),
             StateEvent::Reset => self.widget.set_visible(false),
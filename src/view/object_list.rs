@@ -1,3 +1,4 @@
+use crate::selection::Selection;
 use crate::state::{
     events::StateEvent, State, StateCommand, StateController, StateEventSubscriber,
 };
@@ -16,7 +17,7 @@ pub struct ObjectList {
 impl ObjectList {
     pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
         let list_box = ListBox::builder()
-            .selection_mode(gtk::SelectionMode::Single)
+            .selection_mode(gtk::SelectionMode::Multiple)
             .build();
         let object_list = Rc::new(RefCell::new(ObjectList {
             list_box: list_box.clone(),
@@ -29,7 +30,11 @@ impl ObjectList {
             rows: vec![],
         }));
 
-        list_box.connect_row_activated(clone!(@strong control => move |_, row| {
+        list_box.connect_row_activated(clone!(@strong control => move |list_box, row| {
+            // part of a multi-selection, handled by connect_selected_rows_changed
+            if list_box.selected_rows().len() > 1 {
+                return;
+            }
             let object_id = row
                 .clone()
                 .dynamic_cast::<ObjectListEntry>()
@@ -40,10 +45,30 @@ impl ObjectList {
                 .apply(StateCommand::SelectObject(Some(object_id)));
         }));
 
+        list_box.connect_selected_rows_changed(clone!(@strong control => move |list_box| {
+            let rows = list_box.selected_rows();
+            if rows.len() < 2 {
+                return;
+            }
+            let mut selection = Selection::new();
+            selection.objects = rows
+                .iter()
+                .map(|r| r.clone().dynamic_cast::<ObjectListEntry>().unwrap().object_id())
+                .collect();
+            if let Ok(mut control) = control.try_borrow_mut() {
+                control.apply(StateCommand::ChangeSelection(selection));
+            }
+        }));
+
         let key_controller = EventControllerKey::new();
         key_controller.connect_key_pressed(
             clone!(@strong control, @strong list_box => move |_, key, _, _| {
                 match key {
+                    gdk::Key::Delete if list_box.selected_rows().len() > 1 => {
+                        let mut control = control.borrow_mut();
+                        let selection = control.state.selection.clone();
+                        control.apply(StateCommand::DeleteEntities(selection));
+                    },
                     gdk::Key::Delete => {
                         if let Some(row) = list_box.selected_row() {
                             let object_id = row
@@ -78,11 +103,17 @@ impl ObjectList {
             self.rows.push(object_label);
             self.list_box.append(self.rows.last().unwrap());
         }
-        match state.active_object_id {
-            None => self.list_box.unselect_all(),
-            Some(object_id) => self
-                .list_box
-                .select_row(self.rows.iter().find(|r| r.object_id() == object_id)),
+        self.sync_selection(state);
+    }
+
+    /// selects the rows of all selected objects
+    fn sync_selection(&self, state: &State) {
+        let selected = state.selected();
+        self.list_box.unselect_all();
+        for row in self.rows.iter() {
+            if selected.objects.contains(&row.object_id()) {
+                self.list_box.select_row(Some(row));
+            }
         }
     }
 }
@@ -100,12 +131,8 @@ impl StateEventSubscriber for ObjectList {
                     .filter(|r| r.object_id() == object_id)
                     .for_each(|w| w.update(object));
             }
-            StateEvent::ActiveObjectChanged(object_id) => match object_id {
-                None => self.list_box.unselect_all(),
-                Some(object_id) => self
-                    .list_box
-                    .select_row(self.rows.iter().find(|r| r.object_id() == object_id)),
-            },
+            StateEvent::ActiveObjectChanged(_) => self.sync_selection(state),
+            StateEvent::SelectionChanged => self.sync_selection(state),
             StateEvent::Reset => self.rebuild_list(state),
             StateEvent::Reload => self.rebuild_list(state),
             StateEvent::ObjectDeleted(_) => self.rebuild_list(state),
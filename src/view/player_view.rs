@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::cairo::Context;
+use gtk::gdk::Key;
+use gtk::glib::{self, clone, Propagation};
+use gtk::{prelude::*, DrawingArea, EventControllerKey, Window};
+
+use crate::common::{Rgb, Vec2};
+use crate::dungeon::Dungeon;
+use crate::export::player_view_primitives;
+use crate::selection::Selection;
+use crate::state::events::StateEvent;
+use crate::state::{StateController, StateEventSubscriber};
+
+use super::grid::Grid;
+
+/// color outside of the dungeon map
+const FOG_COLOR: Rgb = Rgb {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+};
+
+/// Second window for a TV or projector.
+/// Shows only the revealed parts of the dungeon, without hidden entities and notes.
+pub struct PlayerView {
+    pub window: Window,
+    area: DrawingArea,
+}
+
+impl PlayerView {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
+        let area = DrawingArea::builder().hexpand(true).vexpand(true).build();
+        area.set_draw_func(clone!(@strong control => move |_area, ctx, w, h| {
+            let state = &control.borrow().state;
+            draw(ctx, w, h, &state.dungeon, &state.session.revealed);
+        }));
+
+        let window = Window::builder()
+            .title("Dungeon Planner - Player View")
+            .default_width(1280)
+            .default_height(720)
+            .hide_on_close(true)
+            .child(&area)
+            .build();
+
+        // F11 toggles fullscreen, e.g. after moving the window to the projector
+        let key_controller = EventControllerKey::new();
+        key_controller.connect_key_pressed(
+            clone!(@weak window => @default-return Propagation::Proceed, move |_, key, _, _| {
+                match key {
+                    Key::F11 => {
+                        window.set_fullscreened(!window.is_fullscreened());
+                        Propagation::Stop
+                    }
+                    Key::Escape if window.is_fullscreened() => {
+                        window.unfullscreen();
+                        Propagation::Stop
+                    }
+                    _ => Propagation::Proceed,
+                }
+            }),
+        );
+        window.add_controller(key_controller);
+
+        let re = Rc::new(RefCell::new(PlayerView { window, area }));
+
+        control.borrow_mut().subscribe_any(re.clone());
+
+        re
+    }
+}
+
+/// draws the revealed dungeon scaled to the window, like the player map export
+fn draw(ctx: &Context, w: i32, h: i32, dungeon: &Dungeon, revealed: &Selection) {
+    ctx.set_source_rgb(FOG_COLOR.r, FOG_COLOR.g, FOG_COLOR.b);
+    ctx.paint().unwrap();
+
+    let (prims, bbox) = player_view_primitives(dungeon, revealed);
+    if !bbox.is_valid() {
+        return;
+    }
+    let size = bbox.max - bbox.min;
+    let scale = f64::min(w as f64 / size.x, h as f64 / size.y);
+    let offset = Vec2 {
+        x: (w as f64 - size.x * scale) / 2.0,
+        y: (h as f64 - size.y * scale) / 2.0,
+    };
+    ctx.translate(
+        -bbox.min.x * scale + offset.x,
+        -bbox.min.y * scale + offset.y,
+    );
+    ctx.scale(scale, scale);
+
+    // paper
+    ctx.rectangle(bbox.min.x, bbox.min.y, size.x, size.y);
+    ctx.clip();
+    ctx.set_source_rgb(1.0, 1.0, 1.0);
+    ctx.paint().unwrap();
+
+    let mut grid = Grid::new();
+    grid.color = Rgb {
+        r: 0.5,
+        g: 0.5,
+        b: 0.5,
+    };
+    grid.width = 1.0;
+    ctx.set_dash(&[10.0, 10.0], 0.0);
+    for prim in grid.draw(bbox.min.into(), bbox.max.into()) {
+        prim.draw(ctx)
+    }
+    ctx.set_dash(&[], 0.0);
+
+    for prim in prims.iter() {
+        prim.draw(ctx)
+    }
+    ctx.reset_clip();
+}
+
+impl StateEventSubscriber for PlayerView {
+    fn on_state_event(&mut self, _state: &crate::state::State, event: StateEvent) {
+        match event {
+            // only the selection changed, nothing visible for the players
+            StateEvent::SelectionChanged
+            | StateEvent::EditModeChanged(_)
+            | StateEvent::ActiveChamberChanged(_)
+            | StateEvent::ActiveDoorChanged(_)
+            | StateEvent::ActiveObjectChanged(_)
+            | StateEvent::ActiveCorridorChanged(_) => (),
+            _ => {
+                if self.window.is_visible() {
+                    self.area.queue_draw()
+                }
+            }
+        }
+    }
+}
@@ -30,6 +30,8 @@ pub struct Line {
 
 pub struct Polygon {
     pub points: Vec<Vec2<f64>>,
+    /// inner rings cut out of the polygon
+    pub holes: Vec<Vec<Vec2<f64>>>,
     pub fill_color: Rgb,
     pub fill_opacity: f64,
     pub stroke_color: Rgb,
@@ -37,6 +39,14 @@ pub struct Polygon {
     pub dashed: bool,
 }
 
+/// open line through multiple points
+pub struct Polyline {
+    pub points: Vec<Vec2<f64>>,
+    pub color: Rgb,
+    pub width: f64,
+    pub dashed: bool,
+}
+
 pub struct Text {
     pub text: String,
     pub color: Rgb,
@@ -114,7 +124,16 @@ impl Primitive for Polygon {
             ctx.line_to(p.x, p.y);
         }
         ctx.close_path();
+        for hole in self.holes.iter().filter(|h| h.len() > 1) {
+            ctx.move_to(hole[0].x, hole[0].y);
+            for p in hole[1..].iter() {
+                ctx.line_to(p.x, p.y);
+            }
+            ctx.close_path();
+        }
+        ctx.set_fill_rule(gtk::cairo::FillRule::EvenOdd);
         ctx.fill_preserve().unwrap();
+        ctx.set_fill_rule(gtk::cairo::FillRule::Winding);
         ctx.set_line_join(gtk::cairo::LineJoin::Bevel);
         ctx.set_line_width(self.stroke_width);
         ctx.set_source_rgb(
@@ -156,6 +175,36 @@ impl Primitive for Polygon {
     }
 }
 
+impl Primitive for Polyline {
+    fn draw(&self, ctx: &gtk::cairo::Context) {
+        if self.points.len() < 2 {
+            return;
+        }
+        if self.dashed {
+            ctx.set_dash(&[20.0, 10.0], 0.0);
+        } else {
+            ctx.set_dash(&[], 0.0);
+        }
+        ctx.set_line_join(gtk::cairo::LineJoin::Bevel);
+        ctx.set_line_width(self.width);
+        ctx.set_source_rgb(self.color.r, self.color.g, self.color.b);
+        ctx.move_to(self.points[0].x, self.points[0].y);
+        for p in self.points[1..].iter() {
+            ctx.line_to(p.x, p.y);
+        }
+        ctx.stroke().unwrap();
+        ctx.set_dash(&[], 0.0);
+    }
+
+    fn bbox(&self) -> BBox {
+        let mut bbox = BBox::new();
+        for p in self.points.iter() {
+            bbox += *p;
+        }
+        bbox
+    }
+}
+
 impl Primitive for Text {
     fn draw(&self, ctx: &gtk::cairo::Context) {
         ctx.set_source_rgb(self.color.r, self.color.g, self.color.b);
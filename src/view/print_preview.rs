@@ -0,0 +1,263 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use gtk::cairo::Context;
+use gtk::glib::{self, clone};
+use gtk::{prelude::*, Button, DrawingArea, DropDown, Label, Orientation, Window};
+
+use crate::common::{Rgb, Vec2};
+use crate::export::{
+    to_full_player_map_pdf, to_handouts_pdf, to_pdf, to_player_cutout_pdf, to_poster_pdf,
+    PreviewPage, Target,
+};
+use crate::export_settings::ChamberFilter;
+use crate::file_actions::{page_setup_dialog, ExportKind, PdfExport};
+use crate::state::events::StateEvent;
+use crate::state::{State, StateController, StateEventSubscriber};
+
+/// color around the pages
+const BACKGROUND_COLOR: Rgb = Rgb {
+    r: 0.35,
+    g: 0.35,
+    b: 0.35,
+};
+
+/// space around the page in pixels
+const PAGE_SPACING: f64 = 16.0;
+
+/// changes within this time are drawn together, e.g. while typing notes
+const UPDATE_DELAY: Duration = Duration::from_millis(300);
+
+/// The exports of the "File" menu with their name in the preview
+const EXPORTS: [(&str, PdfExport, ExportKind); 5] = [
+    ("GM Notes", to_pdf, ExportKind::Notes),
+    ("Player Map", to_full_player_map_pdf, ExportKind::Pages),
+    ("Cutout Map", to_player_cutout_pdf, ExportKind::Cutouts),
+    ("Battle Map Poster", to_poster_pdf, ExportKind::Poster),
+    ("Read-Aloud Handouts", to_handouts_pdf, ExportKind::Pages),
+];
+
+/// Window showing the pages of an export as they are printed.
+/// The pages are drawn again whenever the dungeon or the page setup changes,
+/// changes to the dungeon only while the window is shown.
+pub struct PrintPreview {
+    pub window: Window,
+    area: DrawingArea,
+    export: DropDown,
+    page_label: Label,
+    previous: Button,
+    next: Button,
+    pages: Rc<RefCell<Vec<PreviewPage>>>,
+    /// index of the shown page
+    page: Rc<Cell<usize>>,
+    /// chambers of the last page setup
+    filter: RefCell<ChamberFilter>,
+    /// the pages are drawn again after `UPDATE_DELAY`
+    update_pending: Cell<bool>,
+    this: Weak<RefCell<PrintPreview>>,
+    control: Weak<RefCell<StateController>>,
+}
+
+impl PrintPreview {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
+        let pages: Rc<RefCell<Vec<PreviewPage>>> = Rc::new(RefCell::new(vec![]));
+        let page = Rc::new(Cell::new(0));
+
+        let area = DrawingArea::builder().hexpand(true).vexpand(true).build();
+        area.set_draw_func(
+            clone!(@strong pages, @strong page => move |_area, ctx, w, h| {
+                draw(ctx, w, h, pages.borrow().get(page.get()));
+            }),
+        );
+
+        let export = DropDown::from_strings(&EXPORTS.map(|(name, _, _)| name));
+        let page_setup = Button::with_label("Page Setup ...");
+        let previous = Button::from_icon_name("go-previous-symbolic");
+        let next = Button::from_icon_name("go-next-symbolic");
+        let page_label = Label::new(None);
+        page_label.set_hexpand(true);
+
+        let toolbar = gtk::Box::new(Orientation::Horizontal, 6);
+        toolbar.set_margin_top(6);
+        toolbar.set_margin_bottom(6);
+        toolbar.set_margin_start(6);
+        toolbar.set_margin_end(6);
+        toolbar.append(&export);
+        toolbar.append(&page_setup);
+        toolbar.append(&page_label);
+        toolbar.append(&previous);
+        toolbar.append(&next);
+
+        let content = gtk::Box::new(Orientation::Vertical, 0);
+        content.append(&toolbar);
+        content.append(&area);
+
+        let window = Window::builder()
+            .title("Dungeon Planner - Print Preview")
+            .default_width(800)
+            .default_height(900)
+            .hide_on_close(true)
+            .child(&content)
+            .build();
+
+        let re = Rc::new_cyclic(|this| {
+            RefCell::new(PrintPreview {
+                window,
+                area,
+                export,
+                page_label,
+                previous,
+                next,
+                pages,
+                page,
+                filter: RefCell::new(ChamberFilter::All),
+                update_pending: Cell::new(false),
+                this: this.clone(),
+                control: Rc::downgrade(&control),
+            })
+        });
+
+        let preview = re.borrow();
+        preview
+            .export
+            .connect_selected_notify(clone!(@weak re, @weak control => move |_| {
+                let preview = re.borrow();
+                preview.page.set(0);
+                preview.update(&control.borrow().state);
+            }));
+        page_setup.connect_clicked(clone!(@weak re, @weak control => move |_| {
+            let (_, _, kind) = EXPORTS[re.borrow().export.selected() as usize];
+            page_setup_dialog(control, kind, clone!(@weak re => move |control, filter| {
+                let preview = re.borrow();
+                *preview.filter.borrow_mut() = filter;
+                preview.update(&control.borrow().state);
+            }));
+        }));
+        preview
+            .previous
+            .connect_clicked(clone!(@weak re => move |_| {
+                re.borrow().turn(-1);
+            }));
+        preview.next.connect_clicked(clone!(@weak re => move |_| {
+            re.borrow().turn(1);
+        }));
+        drop(preview);
+
+        control.borrow_mut().subscribe_any(re.clone());
+
+        re
+    }
+
+    /// draws the pages of the chosen export again
+    pub fn update(&self, state: &State) {
+        let (_, export, _) = EXPORTS[self.export.selected() as usize];
+        let dungeon = self.filter.borrow().apply(&state.dungeon);
+        // empty exports do not draw any page
+        self.pages.borrow_mut().clear();
+        export(
+            &dungeon,
+            &dungeon.export_settings,
+            Target::Preview(self.pages.clone()),
+        );
+        let count = self.pages.borrow().len();
+        self.page.set(self.page.get().min(count.saturating_sub(1)));
+        self.update_navigation();
+        self.area.queue_draw();
+    }
+
+    /// draws the pages again after `UPDATE_DELAY` unless already scheduled
+    fn schedule_update(&self) {
+        if self.update_pending.replace(true) {
+            return;
+        }
+        let this = self.this.clone();
+        let control = self.control.clone();
+        glib::timeout_add_local_once(UPDATE_DELAY, move || {
+            let (Some(this), Some(control)) = (this.upgrade(), control.upgrade()) else {
+                return;
+            };
+            let preview = this.borrow();
+            preview.update_pending.set(false);
+            if preview.window.is_visible() {
+                preview.update(&control.borrow().state);
+            }
+        });
+    }
+
+    /// shows the page `by` pages before or after the current one
+    fn turn(&self, by: isize) {
+        let count = self.pages.borrow().len();
+        let page = self.page.get().saturating_add_signed(by);
+        if page < count {
+            self.page.set(page);
+            self.update_navigation();
+            self.area.queue_draw();
+        }
+    }
+
+    fn update_navigation(&self) {
+        let count = self.pages.borrow().len();
+        let page = self.page.get();
+        match count {
+            0 => self.page_label.set_text("Nothing to print"),
+            _ => self
+                .page_label
+                .set_text(&format!("Page {} of {}", page + 1, count)),
+        }
+        self.previous.set_sensitive(page > 0);
+        self.next.set_sensitive(page + 1 < count);
+    }
+}
+
+/// draws the page on white paper, scaled to the window
+fn draw(ctx: &Context, w: i32, h: i32, page: Option<&PreviewPage>) {
+    ctx.set_source_rgb(BACKGROUND_COLOR.r, BACKGROUND_COLOR.g, BACKGROUND_COLOR.b);
+    ctx.paint().unwrap();
+
+    let Some(page) = page else {
+        return;
+    };
+    let scale = f64::min(
+        (w as f64 - 2.0 * PAGE_SPACING) / page.size.x,
+        (h as f64 - 2.0 * PAGE_SPACING) / page.size.y,
+    );
+    if scale <= 0.0 {
+        return;
+    }
+    let offset = Vec2 {
+        x: (w as f64 - page.size.x * scale) / 2.0,
+        y: (h as f64 - page.size.y * scale) / 2.0,
+    };
+    ctx.translate(offset.x, offset.y);
+    ctx.scale(scale, scale);
+
+    ctx.rectangle(0.0, 0.0, page.size.x, page.size.y);
+    ctx.set_source_rgb(1.0, 1.0, 1.0);
+    ctx.fill_preserve().unwrap();
+    ctx.clip();
+    ctx.set_source(&page.pattern).unwrap();
+    ctx.paint().unwrap();
+    ctx.reset_clip();
+}
+
+impl StateEventSubscriber for PrintPreview {
+    fn on_state_event(&mut self, _state: &State, event: StateEvent) {
+        match event {
+            // nothing printed changed
+            StateEvent::SelectionChanged
+            | StateEvent::EditModeChanged(_)
+            | StateEvent::ActiveChamberChanged(_)
+            | StateEvent::ActiveDoorChanged(_)
+            | StateEvent::ActiveObjectChanged(_)
+            | StateEvent::ActiveCorridorChanged(_)
+            | StateEvent::SessionChanged
+            | StateEvent::CompareChanged => (),
+            _ => {
+                if self.window.is_visible() {
+                    self.schedule_update()
+                }
+            }
+        }
+    }
+}
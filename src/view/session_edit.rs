@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gtk::glib::{self, clone, DateTime};
+use gtk::{prelude::*, Button, Label, PolicyType, ScrolledWindow};
+use gtk::{Box, Entry};
+
+use crate::session::SessionFlag;
+use crate::state::events::StateEvent;
+use crate::state::{StateCommand, StateController, StateEventSubscriber};
+
+/// Progress and notes of the play session
+pub struct SessionEdit {
+    pub widget: Box,
+    control: Rc<RefCell<StateController>>,
+    progress_label: Label,
+    notes_list: Box,
+}
+
+impl SessionEdit {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
+        let note_i = Entry::builder()
+            .placeholder_text("What happened?")
+            .css_classes(vec!["form-input"])
+            .build();
+        let add_note = Button::builder()
+            .label("Add Note")
+            .css_classes(vec!["form-input"])
+            .build();
+
+        let add = clone!(@strong control, @weak note_i => move || {
+            let text = note_i.text().to_string();
+            if text.is_empty() {
+                return;
+            }
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            note_i.set_text("");
+            control
+                .borrow_mut()
+                .apply(StateCommand::AddSessionNote(time, text));
+        });
+        add_note.connect_clicked(clone!(@strong add => move |_| add()));
+        note_i.connect_activate(move |_| add());
+
+        let progress_label = Label::builder()
+            .css_classes(vec!["form-input"])
+            .wrap(true)
+            .build();
+        let notes_list = Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+
+        let b = Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        b.append(&Label::new(Some("Progress")));
+        b.append(&progress_label);
+        b.append(&Label::new(Some("Session Notes")));
+        b.append(&note_i);
+        b.append(&add_note);
+        b.append(
+            &ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .vexpand(true)
+                .child(&notes_list)
+                .css_classes(vec!["form-input"])
+                .build(),
+        );
+
+        let re = Rc::new(RefCell::new(SessionEdit {
+            widget: b,
+            control: control.clone(),
+            progress_label,
+            notes_list,
+        }));
+        re.borrow().sync(&control.borrow().state);
+
+        control.borrow_mut().subscribe_any(re.clone());
+
+        re
+    }
+
+    fn sync(&self, state: &crate::state::State) {
+        let session = &state.session;
+        // ids of deleted entities may still be part of the session
+        let chambers = |flag| {
+            session
+                .ids(flag)
+                .iter()
+                .filter(|id| state.dungeon.chamber(**id).is_some())
+                .count()
+        };
+        let looted = session
+            .ids(SessionFlag::Looted)
+            .iter()
+            .filter(|id| state.dungeon.object(**id).is_some())
+            .count();
+        self.progress_label.set_text(&format!(
+            "{} of {} chambers explored, {} cleared\n{} of {} objects looted",
+            chambers(SessionFlag::Explored),
+            state.dungeon.chambers.len(),
+            chambers(SessionFlag::Cleared),
+            looted,
+            state.dungeon.objects.len(),
+        ));
+
+        while let Some(child) = self.notes_list.first_child() {
+            self.notes_list.remove(&child);
+        }
+        // newest note first
+        for (index, note) in session.notes.iter().enumerate().rev() {
+            let time = DateTime::from_unix_local(note.time)
+                .and_then(|t| t.format("%Y-%m-%d %H:%M"))
+                .map(|t| t.to_string())
+                .unwrap_or_default();
+            let text = Label::builder()
+                .label(format!("{}\n{}", time, note.text))
+                .xalign(0.0)
+                .hexpand(true)
+                .wrap(true)
+                .build();
+            let delete = Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text("Delete Note")
+                .build();
+            delete.connect_clicked(clone!(@strong self.control as control => move |_| {
+                control
+                    .borrow_mut()
+                    .apply(StateCommand::DeleteSessionNote(index));
+            }));
+            let row = Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(6)
+                .css_classes(vec!["form-input"])
+                .build();
+            row.append(&text);
+            row.append(&delete);
+            self.notes_list.append(&row);
+        }
+    }
+}
+
+impl StateEventSubscriber for SessionEdit {
+    fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
+        match event {
+            StateEvent::SessionChanged => self.sync(state),
+            StateEvent::ChamberAdded(_) => self.sync(state),
+            StateEvent::ChamberDeleted(_) => self.sync(state),
+            StateEvent::ObjectAdded(_) => self.sync(state),
+            StateEvent::ObjectDeleted(_) => self.sync(state),
+            StateEvent::Reset => self.sync(state),
+            StateEvent::Reload => self.sync(state),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::glib::{self, clone};
+use gtk::{prelude::*, Button, DropDown, Label, ListBox, PolicyType, ScrolledWindow};
+use gtk::{Box, Entry};
+
+use crate::state::events::StateEvent;
+use crate::state::{EditMode, StateCommand, StateController, StateEventSubscriber};
+use crate::template::{Template, TemplateLibrary};
+
+const ROTATIONS: [&str; 4] = ["0°", "90°", "180°", "270°"];
+
+/// Library of chamber templates, saved in the user config directory
+pub struct TemplatePanel {
+    pub widget: Box,
+    library: TemplateLibrary,
+    list: ListBox,
+    name_input: Entry,
+    save_button: Button,
+    rotation_input: DropDown,
+}
+
+impl TemplatePanel {
+    pub fn new(control: Rc<RefCell<StateController>>) -> Rc<RefCell<Self>> {
+        let list = ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Single)
+            .build();
+        let rotation_i = DropDown::from_strings(&ROTATIONS);
+        rotation_i.add_css_class("form-input");
+        let place = Button::builder()
+            .label("Place on Canvas")
+            .css_classes(vec!["form-input"])
+            .build();
+        let delete = Button::builder()
+            .label("Delete Template")
+            .css_classes(vec!["form-input"])
+            .build();
+        let name_i = Entry::builder()
+            .placeholder_text("Template name")
+            .css_classes(vec!["form-input"])
+            .build();
+        let save = Button::builder()
+            .label("Save Active Chamber as Template")
+            .sensitive(false)
+            .css_classes(vec!["form-input"])
+            .build();
+
+        let b = Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+        b.append(&Label::new(Some("Templates")));
+        b.append(
+            &ScrolledWindow::builder()
+                .hscrollbar_policy(PolicyType::Never) // Disable horizontal scrolling
+                .min_content_width(360)
+                .height_request(300)
+                .child(&list)
+                .css_classes(vec!["form-input"])
+                .build(),
+        );
+        b.append(&Label::new(Some("Rotation")));
+        b.append(&rotation_i);
+        b.append(&place);
+        b.append(&delete);
+        b.append(&Label::new(Some("New Template")));
+        b.append(&name_i);
+        b.append(&save);
+
+        let re = Rc::new(RefCell::new(TemplatePanel {
+            widget: b,
+            library: TemplateLibrary::load(),
+            list,
+            name_input: name_i,
+            save_button: save.clone(),
+            rotation_input: rotation_i.clone(),
+        }));
+        re.borrow().rebuild_list();
+
+        place.connect_clicked(clone!(@strong control, @weak re => move |_| {
+            let template = re.borrow().selected_template();
+            if let Some(template) = template {
+                let mut control = control.borrow_mut();
+                control.state.template = Some(template);
+                control.apply(StateCommand::ChangeMode(EditMode::PlaceTemplate));
+            }
+        }));
+
+        // a changed rotation applies to the template currently placed
+        rotation_i.connect_selected_notify(clone!(@strong control, @weak re => move |_| {
+            if let Ok(mut control) = control.try_borrow_mut() {
+                if control.state.mode == EditMode::PlaceTemplate {
+                    control.state.template = re.borrow().selected_template();
+                }
+            }
+        }));
+
+        delete.connect_clicked(clone!(@weak re => move |_| {
+            let mut panel = re.borrow_mut();
+            let name = panel.selected_template().map(|t| t.name);
+            if let Some(name) = name {
+                panel.library.delete(&name);
+                panel.rebuild_list();
+            }
+        }));
+
+        save.connect_clicked(clone!(@strong control, @weak re => move |_| {
+            let mut panel = re.borrow_mut();
+            let name = panel.name_input.text().to_string();
+            let control = control.borrow();
+            let chamber = match control.state.active_chamber() {
+                Some(chamber) => chamber,
+                None => return,
+            };
+            let name = match name.trim() {
+                "" => chamber.name.clone(),
+                name => name.to_owned(),
+            };
+            let template = Template::from_chamber(&control.state.dungeon, chamber.id, &name);
+            if let Some(template) = template {
+                panel.library.save(template);
+                panel.name_input.set_text("");
+                panel.rebuild_list();
+            }
+        }));
+
+        control.borrow_mut().subscribe_any(re.clone());
+
+        re
+    }
+
+    /// the selected template with the selected rotation
+    fn selected_template(&self) -> Option<Template> {
+        let row = self.list.selected_row()?;
+        let template = self.library.templates.get(row.index() as usize)?;
+        Some(template.rotated(self.rotation_input.selected()))
+    }
+
+    fn rebuild_list(&self) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+        for template in self.library.templates.iter() {
+            let label = Label::builder()
+                .label(&template.name)
+                .xalign(0.0)
+                .margin_start(6)
+                .margin_top(3)
+                .margin_bottom(3)
+                .build();
+            self.list.append(&label);
+        }
+    }
+}
+
+impl StateEventSubscriber for TemplatePanel {
+    fn on_state_event(&mut self, state: &crate::state::State, event: StateEvent) {
+        match event {
+            StateEvent::ActiveChamberChanged(_) | StateEvent::Reset | StateEvent::Reload => self
+                .save_button
+                .set_sensitive(state.active_chamber_id.is_some()),
+            _ => (),
+        }
+    }
+}